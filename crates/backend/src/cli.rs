@@ -8,6 +8,10 @@ pub struct CliArgs {
 
     #[clap(long, value_parser, default_value_t = default_host())]
     pub host: String,
+
+    /// List the database migrations which haven't been applied yet and exit.
+    #[clap(long)]
+    pub pending_migrations: bool,
 }
 
 fn default_host() -> String {
@@ -0,0 +1,30 @@
+use rusqlite::Connection;
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // API Token
+    conn.execute(
+        r#"CREATE TABLE "api_token" (
+            "id"             INTEGER NOT NULL UNIQUE,
+
+            "member_id"      INTEGER NOT NULL,
+
+            "name"           TEXT NOT NULL,
+            "prefix"         TEXT NOT NULL,
+            "token_hash"     TEXT NOT NULL UNIQUE,
+            "scope"          INTEGER NOT NULL,
+
+            "expires_at"     TEXT,
+            "last_used_at"   TEXT,
+            "created_at"     TEXT NOT NULL,
+
+            FOREIGN KEY("member_id") REFERENCES members("id") ON DELETE CASCADE,
+
+            PRIMARY KEY("id" AUTOINCREMENT)
+        );"#,
+        [],
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use chrono::Utc;
+use common_local::BookItemCached;
+use rusqlite::{params, Connection};
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // Publisher
+    conn.execute(
+        r#"CREATE TABLE "publisher" (
+            "id"             INTEGER NOT NULL UNIQUE,
+
+            "name"           TEXT NOT NULL COLLATE NOCASE,
+
+            "created_at"     TEXT NOT NULL,
+            "updated_at"     TEXT NOT NULL,
+
+            UNIQUE(name),
+            PRIMARY KEY("id" AUTOINCREMENT)
+        );"#,
+        [],
+    )?;
+
+    // Subject
+    conn.execute(
+        r#"CREATE TABLE "subject" (
+            "id"             INTEGER NOT NULL UNIQUE,
+
+            "name"           TEXT NOT NULL COLLATE NOCASE,
+
+            "created_at"     TEXT NOT NULL,
+
+            UNIQUE(name),
+            PRIMARY KEY("id" AUTOINCREMENT)
+        );"#,
+        [],
+    )?;
+
+    // Book Subject
+    conn.execute(
+        r#"CREATE TABLE "book_subject" (
+            "book_id"       INTEGER NOT NULL,
+            "subject_id"    INTEGER NOT NULL,
+
+            FOREIGN KEY("book_id") REFERENCES book("id") ON DELETE CASCADE,
+            FOREIGN KEY("subject_id") REFERENCES subject("id") ON DELETE CASCADE,
+
+            UNIQUE(book_id, subject_id)
+        );"#,
+        [],
+    )?;
+
+    // Book Identifier
+    conn.execute(
+        r#"CREATE TABLE "book_identifier" (
+            "book_id"       INTEGER NOT NULL,
+
+            "type_of"       INTEGER NOT NULL,
+            "value"         TEXT NOT NULL,
+
+            FOREIGN KEY("book_id") REFERENCES book("id") ON DELETE CASCADE,
+
+            UNIQUE(book_id, type_of, value)
+        );"#,
+        [],
+    )?;
+
+    conn.execute(
+        r#"ALTER TABLE "book" ADD COLUMN "publisher_id" INTEGER REFERENCES publisher("id") ON DELETE SET NULL"#,
+        [],
+    )?;
+
+    conn.execute(r#"ALTER TABLE "book" ADD COLUMN "language" TEXT"#, [])?;
+
+    conn.execute(r#"ALTER TABLE "book" ADD COLUMN "page_count" INTEGER"#, [])?;
+
+    link_cached_publishers(conn)?;
+
+    Ok(())
+}
+
+fn link_cached_publishers(conn: &Connection) -> Result<()> {
+    let books = {
+        let mut stmt =
+            conn.prepare(r#"SELECT id, cached FROM book WHERE cached LIKE '%publisher=%'"#)?;
+
+        let map = stmt.query_map([], |v| {
+            Ok((
+                v.get::<_, usize>(0)?,
+                BookItemCached::from_string(v.get::<_, String>(1)?),
+            ))
+        })?;
+
+        map.collect::<std::result::Result<Vec<_>, _>>()?
+    };
+
+    let now = Utc::now();
+
+    for (book_id, cached) in books {
+        let Some(name) = cached
+            .publisher
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+        else {
+            continue;
+        };
+
+        conn.execute(
+            "INSERT OR IGNORE INTO publisher (name, created_at, updated_at) VALUES (?1, ?2, ?3)",
+            params![name, now, now],
+        )?;
+
+        conn.execute(
+            "UPDATE book SET publisher_id = (SELECT id FROM publisher WHERE name = ?2) WHERE id = ?1",
+            params![book_id, name],
+        )?;
+    }
+
+    Ok(())
+}
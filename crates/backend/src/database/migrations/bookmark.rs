@@ -0,0 +1,36 @@
+use rusqlite::Connection;
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // File Bookmark
+    conn.execute(
+        r#"CREATE TABLE "file_bookmark" (
+            "id"             INTEGER NOT NULL UNIQUE,
+
+            "file_id"        INTEGER NOT NULL,
+            "member_id"      INTEGER NOT NULL,
+
+            "chapter"        INTEGER NOT NULL,
+            "char_pos"       INTEGER NOT NULL,
+
+            "label"          TEXT,
+            "snippet"        TEXT NOT NULL,
+
+            "created_at"     TEXT NOT NULL,
+
+            FOREIGN KEY("file_id") REFERENCES file("id") ON DELETE CASCADE,
+            FOREIGN KEY("member_id") REFERENCES members("id") ON DELETE CASCADE,
+
+            PRIMARY KEY("id" AUTOINCREMENT)
+        );"#,
+        [],
+    )?;
+
+    conn.execute(
+        r#"CREATE INDEX "file_bookmark_member_file" ON "file_bookmark" ("member_id", "file_id");"#,
+        [],
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use chrono::Utc;
+use common_local::TaskStatus;
+use rusqlite::{params, Connection};
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // Every comic book used to get the same hash. Rehash the ones which were already scanned.
+    conn.execute(
+        r#"
+        INSERT INTO task (name, data, is_priority, status, queued_at)
+        SELECT ?1, ?2, 0, ?3, ?4
+        WHERE EXISTS(SELECT id FROM file WHERE file_type = 'cbz')
+    "#,
+        params![
+            "Rehash Files",
+            r#"{"file_type":"cbz"}"#,
+            TaskStatus::Queued,
+            Utc::now()
+        ],
+    )?;
+
+    Ok(())
+}
@@ -1,10 +1,8 @@
-use crate::{database::Database, Result};
+use rusqlite::Connection;
 
-pub async fn init(database: &Database) -> Result<()> {
-    let conn = database.write().await;
-
-    // TODO: Migrations https://github.com/rusqlite/rusqlite/discussions/1117
+use crate::Result;
 
+pub fn init(conn: &Connection) -> Result<()> {
     // Migrations
     conn.execute(
         r#"CREATE TABLE migration (
@@ -0,0 +1,52 @@
+use rusqlite::Connection;
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // KOReader identifies documents by a MD5 of parts of the file. Filled in by the scanner.
+    conn.execute(r#"ALTER TABLE "file" ADD COLUMN "partial_md5" TEXT"#, [])?;
+
+    conn.execute(
+        r#"CREATE INDEX "file_partial_md5" ON "file" ("partial_md5")"#,
+        [],
+    )?;
+
+    // KOReader Key
+    conn.execute(
+        r#"CREATE TABLE "koreader_key" (
+            "member_id"      INTEGER NOT NULL UNIQUE,
+
+            "key_hash"       TEXT NOT NULL,
+
+            "created_at"     TEXT NOT NULL,
+            "updated_at"     TEXT NOT NULL,
+
+            FOREIGN KEY("member_id") REFERENCES members("id") ON DELETE CASCADE
+        );"#,
+        [],
+    )?;
+
+    // KOReader Progress
+    conn.execute(
+        r#"CREATE TABLE "koreader_progress" (
+            "member_id"      INTEGER NOT NULL,
+            "document"       TEXT NOT NULL,
+            "file_id"        INTEGER,
+
+            "progress"       TEXT NOT NULL,
+            "percentage"     REAL NOT NULL,
+            "device"         TEXT NOT NULL,
+            "device_id"      TEXT,
+
+            "updated_at"     TEXT NOT NULL,
+
+            FOREIGN KEY("member_id") REFERENCES members("id") ON DELETE CASCADE,
+            FOREIGN KEY("file_id") REFERENCES file("id") ON DELETE SET NULL,
+
+            UNIQUE(member_id, document)
+        );"#,
+        [],
+    )?;
+
+    Ok(())
+}
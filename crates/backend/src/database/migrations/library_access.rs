@@ -0,0 +1,31 @@
+use common_local::GroupPermissions;
+use rusqlite::{params, Connection};
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // Member Library
+    conn.execute(
+        r#"CREATE TABLE "member_library" (
+            "member_id"      INTEGER NOT NULL,
+            "library_id"     INTEGER NOT NULL,
+
+            FOREIGN KEY("member_id") REFERENCES members("id") ON DELETE CASCADE,
+            FOREIGN KEY("library_id") REFERENCES library("id") ON DELETE CASCADE,
+
+            UNIQUE("member_id", "library_id")
+        );"#,
+        [],
+    )?;
+
+    // Members could download and see every library before the permissions were split up.
+    conn.execute(
+        r#"UPDATE members SET permissions = CAST((CAST(permissions AS INTEGER) | ?1) AS TEXT) WHERE (CAST(permissions AS INTEGER) & ?2) = 0"#,
+        params![
+            (GroupPermissions::DOWNLOAD | GroupPermissions::ALL_LIBRARIES).bits() as i64,
+            GroupPermissions::OWNER.bits() as i64,
+        ],
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use rusqlite::Connection;
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // Book Locked Field
+    conn.execute(
+        r#"CREATE TABLE "book_locked_field" (
+            "book_id"       INTEGER NOT NULL,
+
+            "field"         INTEGER NOT NULL,
+
+            FOREIGN KEY("book_id") REFERENCES book("id") ON DELETE CASCADE,
+
+            UNIQUE(book_id, field)
+        );"#,
+        [],
+    )?;
+
+    // Person Locked Field
+    conn.execute(
+        r#"CREATE TABLE "person_locked_field" (
+            "person_id"     INTEGER NOT NULL,
+
+            "field"         INTEGER NOT NULL,
+
+            FOREIGN KEY("person_id") REFERENCES tag_person("id") ON DELETE CASCADE,
+
+            UNIQUE(person_id, field)
+        );"#,
+        [],
+    )?;
+
+    // Fields which were edited by a user were already protected from refreshes.
+    conn.execute(
+        r#"INSERT INTO book_locked_field (book_id, field) SELECT book_id, field FROM book_field_source WHERE agent = 'user'"#,
+        [],
+    )?;
+
+    Ok(())
+}
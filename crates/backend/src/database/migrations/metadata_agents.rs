@@ -0,0 +1,27 @@
+use rusqlite::Connection;
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // JSON. NULL uses the default order.
+    conn.execute(r#"ALTER TABLE "library" ADD COLUMN "agent_priority" TEXT"#, [])?;
+
+    // Book Field Source
+    conn.execute(
+        r#"CREATE TABLE "book_field_source" (
+            "book_id"       INTEGER NOT NULL,
+
+            "field"         INTEGER NOT NULL,
+            "agent"         TEXT NOT NULL,
+
+            "updated_at"    TEXT NOT NULL,
+
+            FOREIGN KEY("book_id") REFERENCES book("id") ON DELETE CASCADE,
+
+            UNIQUE(book_id, field)
+        );"#,
+        [],
+    )?;
+
+    Ok(())
+}
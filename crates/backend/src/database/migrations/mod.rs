@@ -1,19 +1,177 @@
+use std::time::Instant;
+
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use tracing::info;
+
 use crate::Result;
 
-use super::Database;
+use super::{Database, DatabaseAccess};
 
+mod api_token;
+mod book_details;
+mod bookmark;
+mod comic_hash;
 mod current;
+mod koreader;
+mod library_access;
+mod locked_fields;
+mod metadata_agents;
+mod partial_md5;
+mod series;
+mod task_queue;
+mod text_index;
+
+const BACKUP_DIR: &str = "./app/backup";
+
+pub struct Migration {
+    /// Stored in the migration table. Must be unique and only ever increase.
+    pub id: i64,
+    pub title: &'static str,
+
+    run: fn(&Connection) -> Result<()>,
+}
+
+/// Every migration in the order they're applied. The first one creates the database.
+///
+/// NEVER modify one which was released. Append a new one instead.
+static MIGRATIONS: &[Migration] = &[
+    Migration {
+        id: 1,
+        title: "Initial Schema",
+        run: current::init,
+    },
+    Migration {
+        id: 2,
+        title: "Task Queue",
+        run: task_queue::init,
+    },
+    Migration {
+        id: 3,
+        title: "Series",
+        run: series::init,
+    },
+    Migration {
+        id: 4,
+        title: "Book Details",
+        run: book_details::init,
+    },
+    Migration {
+        id: 5,
+        title: "Metadata Agents",
+        run: metadata_agents::init,
+    },
+    Migration {
+        id: 6,
+        title: "Locked Fields",
+        run: locked_fields::init,
+    },
+    Migration {
+        id: 7,
+        title: "KOReader Sync",
+        run: koreader::init,
+    },
+    Migration {
+        id: 8,
+        title: "API Tokens",
+        run: api_token::init,
+    },
+    Migration {
+        id: 9,
+        title: "Library Access",
+        run: library_access::init,
+    },
+    Migration {
+        id: 10,
+        title: "Bookmarks",
+        run: bookmark::init,
+    },
+    Migration {
+        id: 11,
+        title: "Full-Text Index",
+        run: text_index::init,
+    },
+    Migration {
+        id: 12,
+        title: "Comic Book Hashes",
+        run: comic_hash::init,
+    },
+    Migration {
+        id: 13,
+        title: "KOReader Partial MD5",
+        run: partial_md5::init,
+    },
+];
 
 pub async fn start_initiation(database: &Database) -> Result<()> {
-    if does_migration_table_exist(database).await? {
-        // TODO: Handle Migrations
-    } else {
-        current::init(database).await?;
+    let is_new_database = !does_migration_table_exist(database).await?;
+
+    if !is_new_database && get_applied_migration_ids(database).await?.is_empty() {
+        // Created before migrations were recorded. The tables are from the initial schema.
+        record_migration(
+            &*database.write().await,
+            &MIGRATIONS[0],
+            0,
+            "Database was created before migrations were recorded.",
+        )?;
+    }
+
+    let pending = get_pending_migrations(database).await?;
+
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    if !is_new_database {
+        backup_database(database).await?;
+    }
+
+    for migration in pending {
+        info!(
+            id = migration.id,
+            title = migration.title,
+            "Running Migration"
+        );
+
+        let start = Instant::now();
+
+        let mut trx = database.transaction().await?;
+
+        {
+            let conn = trx.write().await;
+
+            (migration.run)(&conn)?;
+
+            record_migration(&conn, migration, start.elapsed().as_millis() as i64, "")?;
+        }
+
+        trx.commit()?;
     }
 
     Ok(())
 }
 
+/// Migrations which haven't been applied to the database yet.
+pub async fn get_pending_migrations(database: &Database) -> Result<Vec<&'static Migration>> {
+    let applied = if does_migration_table_exist(database).await? {
+        let ids = get_applied_migration_ids(database).await?;
+
+        // Created before migrations were recorded.
+        if ids.is_empty() {
+            vec![MIGRATIONS[0].id]
+        } else {
+            ids
+        }
+    } else {
+        Vec::new()
+    };
+
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|v| !applied.contains(&v.id))
+        .collect())
+}
+
 async fn does_migration_table_exist(database: &Database) -> Result<bool> {
     let read = database.read().await;
 
@@ -24,13 +182,96 @@ async fn does_migration_table_exist(database: &Database) -> Result<bool> {
     )?)
 }
 
-// struct MigrationModel {
-//     id: i32,
-//
-//     duration: i32,
-//
-//     name: String,
-//     notes: String,
-//
-//     created_at: DateTime<Utc>,
-// }
+async fn get_applied_migration_ids(database: &Database) -> Result<Vec<i64>> {
+    let read = database.read().await;
+
+    let mut conn = read.prepare("SELECT id FROM migration")?;
+
+    let map = conn.query_map([], |v| v.get(0))?;
+
+    Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+fn record_migration(
+    conn: &Connection,
+    migration: &Migration,
+    duration: i64,
+    notes: &str,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO migration (id, title, duration, notes, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![migration.id, migration.title, duration, notes, Utc::now()],
+    )?;
+
+    Ok(())
+}
+
+/// Copies the database into the backup folder.
+async fn backup_database(database: &Database) -> Result<()> {
+    tokio::fs::create_dir_all(BACKUP_DIR).await?;
+
+    let path = format!(
+        "{BACKUP_DIR}/database-{}.db",
+        Utc::now().format("%Y%m%d-%H%M%S")
+    );
+
+    info!(path, "Backing up Database before migrating");
+
+    // Unlike copying the file this creates a consistent copy while connections are open.
+    database.write().await.execute("VACUUM INTO ?1", [&path])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::runtime::Runtime;
+
+    fn create_db() -> Result<Database> {
+        // A single connection. Otherwise each one would be a different in-memory database.
+        Database::open(1, || Ok(Connection::open_in_memory()?))
+    }
+
+    #[test]
+    fn new_database() -> Result<()> {
+        Runtime::new().unwrap().block_on(async {
+            let database = create_db()?;
+
+            start_initiation(&database).await?;
+
+            assert!(get_pending_migrations(&database).await?.is_empty());
+            assert_eq!(
+                get_applied_migration_ids(&database).await?.len(),
+                MIGRATIONS.len()
+            );
+
+            // Nothing should happen the second time.
+            start_initiation(&database).await?;
+
+            assert_eq!(
+                get_applied_migration_ids(&database).await?.len(),
+                MIGRATIONS.len()
+            );
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn database_without_recorded_migrations() -> Result<()> {
+        Runtime::new().unwrap().block_on(async {
+            let database = create_db()?;
+
+            current::init(&*database.write().await)?;
+
+            assert_eq!(
+                get_pending_migrations(&database).await?.len(),
+                MIGRATIONS.len() - 1
+            );
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,19 @@
+use chrono::Utc;
+use common_local::TaskStatus;
+use rusqlite::{params, Connection};
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // Files scanned before KOReader Sync don't have one. The scanner only stores it for new or changed files.
+    conn.execute(
+        r#"
+        INSERT INTO task (name, data, is_priority, status, queued_at)
+        SELECT ?1, ?2, 0, ?3, ?4
+        WHERE EXISTS(SELECT id FROM file WHERE partial_md5 IS NULL AND deleted_at IS NULL)
+    "#,
+        params!["Partial MD5", "null", TaskStatus::Queued, Utc::now()],
+    )?;
+
+    Ok(())
+}
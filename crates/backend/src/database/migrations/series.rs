@@ -0,0 +1,87 @@
+use chrono::Utc;
+use common_local::BookItemCached;
+use rusqlite::{params, Connection};
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // Series
+    conn.execute(
+        r#"CREATE TABLE "series" (
+            "id"             INTEGER NOT NULL UNIQUE,
+
+            "name"           TEXT NOT NULL COLLATE NOCASE,
+            "description"    TEXT,
+
+            "thumb_url"      TEXT,
+
+            "created_at"     TEXT NOT NULL,
+            "updated_at"     TEXT NOT NULL,
+
+            UNIQUE(name),
+            PRIMARY KEY("id" AUTOINCREMENT)
+        );"#,
+        [],
+    )?;
+
+    conn.execute(
+        r#"ALTER TABLE "book" ADD COLUMN "series_id" INTEGER REFERENCES series("id") ON DELETE SET NULL"#,
+        [],
+    )?;
+
+    conn.execute(r#"ALTER TABLE "book" ADD COLUMN "series_index" REAL"#, [])?;
+
+    link_cached_series(conn)?;
+
+    Ok(())
+}
+
+/// Comics already stored their series name in the cached column.
+fn link_cached_series(conn: &Connection) -> Result<()> {
+    let books = {
+        let mut stmt =
+            conn.prepare(r#"SELECT id, cached FROM book WHERE cached LIKE '%series=%'"#)?;
+
+        let map = stmt.query_map([], |v| {
+            Ok((
+                v.get::<_, usize>(0)?,
+                BookItemCached::from_string(v.get::<_, String>(1)?),
+            ))
+        })?;
+
+        map.collect::<std::result::Result<Vec<_>, _>>()?
+    };
+
+    let now = Utc::now();
+
+    for (book_id, cached) in books {
+        let Some(name) = cached
+            .series
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+        else {
+            continue;
+        };
+
+        conn.execute(
+            "INSERT OR IGNORE INTO series (name, created_at, updated_at) VALUES (?1, ?2, ?3)",
+            params![name, now, now],
+        )?;
+
+        conn.execute(
+            r#"
+            UPDATE book SET
+                series_id = (SELECT id FROM series WHERE name = ?2),
+                series_index = ?3
+            WHERE id = ?1"#,
+            params![
+                book_id,
+                name,
+                cached.issue.and_then(|v| v.trim().parse::<f64>().ok())
+            ],
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use rusqlite::Connection;
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // Task
+    conn.execute(
+        r#"CREATE TABLE "task" (
+            "id"             INTEGER NOT NULL UNIQUE,
+
+            "name"           TEXT NOT NULL,
+            "data"           TEXT NOT NULL,
+            "is_priority"    INTEGER NOT NULL,
+
+            "status"         INTEGER NOT NULL,
+            "progress"       TEXT,
+            "error"          TEXT,
+
+            "queued_at"      TEXT NOT NULL,
+            "started_at"     TEXT,
+            "finished_at"    TEXT,
+
+            PRIMARY KEY("id" AUTOINCREMENT)
+        );"#,
+        [],
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use rusqlite::Connection;
+
+use crate::Result;
+
+pub fn init(conn: &Connection) -> Result<()> {
+    // File Text. Which version of the file was indexed.
+    conn.execute(
+        r#"CREATE TABLE "file_text" (
+            "file_id"        INTEGER NOT NULL UNIQUE,
+
+            "hash"           TEXT NOT NULL,
+
+            "indexed_at"     TEXT NOT NULL,
+
+            FOREIGN KEY("file_id") REFERENCES file("id") ON DELETE CASCADE,
+
+            PRIMARY KEY("file_id")
+        );"#,
+        [],
+    )?;
+
+    // File Text Chapter
+    conn.execute(
+        r#"CREATE TABLE "file_text_chapter" (
+            "id"             INTEGER NOT NULL UNIQUE,
+
+            "file_id"        INTEGER NOT NULL,
+            "chapter"        INTEGER NOT NULL,
+
+            "content"        TEXT NOT NULL,
+
+            FOREIGN KEY("file_id") REFERENCES file("id") ON DELETE CASCADE,
+
+            PRIMARY KEY("id" AUTOINCREMENT)
+        );"#,
+        [],
+    )?;
+
+    conn.execute(
+        r#"CREATE INDEX "file_text_chapter_file" ON "file_text_chapter" ("file_id");"#,
+        [],
+    )?;
+
+    // The index only stores the tokens. The text is read from the chapter table.
+    conn.execute(
+        r#"CREATE VIRTUAL TABLE "file_text_fts" USING fts5(
+            content,
+            content = 'file_text_chapter',
+            content_rowid = 'id',
+            tokenize = 'unicode61 remove_diacritics 2'
+        );"#,
+        [],
+    )?;
+
+    conn.execute_batch(
+        r#"
+        CREATE TRIGGER "file_text_chapter_insert" AFTER INSERT ON "file_text_chapter" BEGIN
+            INSERT INTO file_text_fts (rowid, content) VALUES (new.id, new.content);
+        END;
+
+        CREATE TRIGGER "file_text_chapter_delete" AFTER DELETE ON "file_text_chapter" BEGIN
+            INSERT INTO file_text_fts (file_text_fts, rowid, content) VALUES ('delete', old.id, old.content);
+        END;
+        "#,
+    )?;
+
+    Ok(())
+}
@@ -30,13 +30,28 @@ impl<'a, V> Deref for Boo<'a, V> {
 }
 
 pub async fn init() -> Result<Database> {
-    let database = Database::open(5, || Ok(Connection::open(DATABASE_PATH)?))?;
+    let database = open()?;
 
     migrations::start_initiation(&database).await?;
 
     Ok(database)
 }
 
+/// Returns the (id, title) of the migrations which will be applied on the next start.
+pub async fn get_pending_migrations() -> Result<Vec<(i64, &'static str)>> {
+    let database = open()?;
+
+    Ok(migrations::get_pending_migrations(&database)
+        .await?
+        .into_iter()
+        .map(|v| (v.id, v.title))
+        .collect())
+}
+
+fn open() -> Result<Database> {
+    Database::open(5, || Ok(Connection::open(DATABASE_PATH)?))
+}
+
 pub struct Database {
     // Using RwLock to engage the r/w locks.
     lock: RwLock<()>,
@@ -16,6 +16,7 @@ use image::ImageError;
 use lettre::address::AddressError;
 use lettre::error::Error as LettreError;
 use lettre::transport::smtp::Error as SmtpError;
+use notify::Error as NotifyError;
 use reqwest::Error as HttpError;
 use rusqlite::Error as RusqliteError;
 use serde::de::value::Error as SerdeValueError;
@@ -25,7 +26,7 @@ use serde_xml_rs::Error as XmlError;
 use toml_edit::{de::Error as TomlDeError, ser::Error as TomlSerError};
 
 use actix_multipart::MultipartError;
-use actix_web::error::{ParseError, PayloadError};
+use actix_web::error::{BlockingError, ParseError, PayloadError};
 use actix_web::Error as ActixError;
 use actix_web::ResponseError;
 
@@ -132,6 +133,10 @@ pub enum Error {
     Rusqlite(#[from] RusqliteError),
     #[error("Bcrypt Error: {0}")]
     Bcrypt(#[from] BcryptError),
+    #[error("Notify Error: {0}")]
+    Notify(#[from] NotifyError),
+    #[error("Blocking Error: {0}")]
+    Blocking(#[from] BlockingError),
 
     #[error(transparent)]
     Common(#[from] CommonError),
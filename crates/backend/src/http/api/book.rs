@@ -12,37 +12,50 @@ use common::{
 };
 use common_local::{
     api::{self, BookPresetListType, BookProgression},
-    DisplayItem, ModifyValuesBy, Poster, SearchFor, SearchForBooksBy, SearchType,
+    DisplayItem, GroupPermissions, MetadataField, ModifyValuesBy, Poster, SearchFor,
+    SearchForBooksBy, SearchType,
 };
 use serde_qs::actix::QsQuery;
 
 use crate::{
-    database::Database,
+    config::get_config,
+    database::{Database, DatabaseAccess},
     http::{JsonResponse, MemberCookie},
     metadata::{self, ActiveAgents},
     model::{
         book::BookModel,
+        book_field_source::BookFieldSourceModel,
+        book_identifier::BookIdentifierModel,
+        book_locked_field::BookLockedFieldModel,
         book_person::BookPersonModel,
         file::FileModel,
+        file_text::{build_match_query, split_snippet, FileTextModel},
         image::{ImageLinkModel, UploadedImageModel},
+        member::MemberModel,
+        member_library::LibraryAccess,
         person::PersonModel,
         progress::FileProgressionModel,
+        subject::SubjectModel,
     },
     queue_task, store_image,
     task::{self, queue_task_priority},
-    Error, WebResult,
+    Error, Result, WebResult,
 };
 
 #[get("/books")]
 pub async fn load_book_list(
     query: QsQuery<api::BookListQuery>,
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<api::ApiGetBookListResponse>> {
     let query = query.into_inner();
 
+    let member = member.fetch_or_error(&db.basic()).await?;
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
+
     let filters = query.filters.unwrap_or_default();
 
-    let count = BookModel::count_search_by(&filters, query.library, &db.basic()).await?;
+    let count = BookModel::count_search_by(&filters, query.library, &access, &db.basic()).await?;
 
     let items = if count == 0 {
         Vec::new()
@@ -50,6 +63,7 @@ pub async fn load_book_list(
         BookModel::search_by(
             &filters,
             query.library,
+            &access,
             query.offset.unwrap_or(0),
             query.limit.unwrap_or(50),
             &db.basic(),
@@ -70,6 +84,55 @@ pub async fn load_book_list(
     )))
 }
 
+#[get("/books/text")]
+pub async fn search_book_text(
+    query: web::Query<api::BookTextSearchQuery>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiGetBookTextSearchResponse>> {
+    let query = query.into_inner();
+
+    if !get_config().scanner.index_text {
+        return Err(ApiErrorResponse::new("Full-text search isn't enabled").into());
+    }
+
+    let Some(search) = build_match_query(&query.query) else {
+        return Err(ApiErrorResponse::new("Search cannot be empty").into());
+    };
+
+    let member = member.fetch_or_error(&db.basic()).await?;
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
+
+    let items = FileTextModel::search(
+        &search,
+        query.library,
+        &access,
+        query.offset.unwrap_or(0),
+        query.limit.unwrap_or(50),
+        &db.basic(),
+    )
+    .await?
+    .into_iter()
+    .map(|item| api::BookTextMatch {
+        book: DisplayItem {
+            id: item.book.id,
+            title: item
+                .book
+                .title
+                .or(item.book.original_title)
+                .unwrap_or_default(),
+            cached: item.book.cached,
+            thumb_path: item.book.thumb_path,
+        },
+        file_id: item.file_id,
+        chapter: item.chapter as usize,
+        snippet: split_snippet(&item.snippet),
+    })
+    .collect();
+
+    Ok(web::Json(WrappingResponse::okay(items)))
+}
+
 // TODO: Place into GET /books
 #[get("/books/preset")]
 pub async fn load_book_preset_list(
@@ -78,6 +141,7 @@ pub async fn load_book_preset_list(
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<api::GetBookPresetListResponse>> {
     let member = member.fetch_or_error(&db.basic()).await?;
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
 
     match query.preset {
         BookPresetListType::Progressing => {
@@ -87,6 +151,10 @@ pub async fn load_book_preset_list(
                 FileProgressionModel::get_member_progression_and_books(member.id, &db.basic())
                     .await?
             {
+                if !access.contains(book.library_id) {
+                    continue;
+                }
+
                 let file = FileModel::find_one_by_id(a.file_id, &db.basic())
                     .await?
                     .unwrap();
@@ -118,14 +186,29 @@ pub async fn update_books(
     member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<&'static str>> {
-    let edit = body.into_inner();
+    let mut edit = body.into_inner();
 
     let member = member.fetch_or_error(&db.basic()).await?;
 
-    if !member.permissions.is_owner() {
-        return Err(ApiErrorResponse::new("Not owner").into());
+    if !member.permissions.has(GroupPermissions::EDIT_METADATA) {
+        return Err(ApiErrorResponse::new("Missing permission to edit metadata").into());
+    }
+
+    // Books in hidden libraries are skipped.
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
+
+    let mut book_ids = Vec::new();
+
+    for book_id in edit.book_ids {
+        if let Some(book) = BookModel::find_one_by_id(book_id, &db.basic()).await? {
+            if access.contains(book.library_id) {
+                book_ids.push(book_id);
+            }
+        }
     }
 
+    edit.book_ids = book_ids;
+
     // TODO: YES, I KNOW! I'm just lazy.
     // People
     match edit.people_list_mod {
@@ -199,15 +282,20 @@ pub async fn load_book_info(
 ) -> WebResult<JsonResponse<api::ApiGetBookByIdResponse>> {
     let book = BookModel::find_one_by_id(*book_id, &db.basic())
         .await?
-        .unwrap();
+        .ok_or_else(|| crate::Error::from(crate::InternalError::ItemMissing))?;
+
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    LibraryAccess::for_member(&member, &db.basic())
+        .await?
+        .check(book.library_id)?;
 
     let mut found_progression = false;
     let (mut media, mut progress) = (Vec::new(), Vec::new());
 
     for file in FileModel::find_by_book_id(book.id, &db.basic()).await? {
         let prog = if !found_progression {
-            let prog =
-                FileProgressionModel::find_one(member.member_id(), file.id, &db.basic()).await?;
+            let prog = FileProgressionModel::find_one(member.id, file.id, &db.basic()).await?;
 
             found_progression = prog.is_some();
 
@@ -221,12 +309,18 @@ pub async fn load_book_info(
     }
 
     let people = PersonModel::find_by_book_id(book.id, &db.basic()).await?;
+    let subjects = SubjectModel::find_by_book_id(book.id, &db.basic()).await?;
+    let identifiers = BookIdentifierModel::find_by_book_id(book.id, &db.basic()).await?;
+    let locked_fields = BookLockedFieldModel::find_by_book_id(book.id, &db.basic()).await?;
 
     Ok(web::Json(WrappingResponse::okay(api::GetBookResponse {
         book: book.into(),
         media,
         progress,
         people: people.into_iter().map(|p| p.into()).collect(),
+        subjects: subjects.into_iter().map(|v| v.name).collect(),
+        identifiers: identifiers.into_iter().map(|v| v.into()).collect(),
+        locked_fields,
     })))
 }
 
@@ -241,10 +335,12 @@ pub async fn update_book_info(
 
     let member = member.fetch_or_error(&db.basic()).await?;
 
-    if !member.permissions.is_owner() {
-        return Err(ApiErrorResponse::new("Not owner").into());
+    if !member.permissions.has(GroupPermissions::EDIT_METADATA) {
+        return Err(ApiErrorResponse::new("Missing permission to edit metadata").into());
     }
 
+    find_visible_book(book_id, &member, &db.basic()).await?;
+
     match body.into_inner() {
         api::PostBookBody::UnMatch => {
             queue_task(task::TaskUpdateInvalidBook::new(
@@ -287,9 +383,22 @@ pub async fn update_book_info(
 #[get("/book/{id}/download")]
 pub async fn download_book(
     book_id: web::Path<BookId>,
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<NamedFile> {
-    let mut files = FileModel::find_by_book_id(*book_id, &db.basic()).await?;
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    if !member.permissions.has(GroupPermissions::DOWNLOAD) {
+        return Err(ApiErrorResponse::new("Missing permission to download").into());
+    }
+
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
+
+    let mut files = FileModel::find_by_book_id(*book_id, &db.basic())
+        .await?
+        .into_iter()
+        .filter(|v| access.contains(v.library_id))
+        .collect::<Vec<_>>();
 
     if files.is_empty() {
         return Err(crate::Error::Internal(crate::InternalError::ItemMissing).into());
@@ -320,11 +429,18 @@ pub async fn download_book(
 #[get("/book/{id}/posters")]
 async fn get_book_posters(
     path: web::Path<BookId>,
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<api::ApiGetPosterByBookIdResponse>> {
     let book = BookModel::find_one_by_id(*path, &db.basic())
         .await?
-        .unwrap();
+        .ok_or_else(|| crate::Error::from(crate::InternalError::ItemMissing))?;
+
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    LibraryAccess::for_member(&member, &db.basic())
+        .await?
+        .check(book.library_id)?;
 
     // TODO: For Open Library we need to go from an Edition to Work.
     // Work is the main book. Usually consisting of more posters.
@@ -359,7 +475,7 @@ async fn get_book_posters(
             book.cached.author.as_deref().unwrap_or_default(),
         ),
         common_local::SearchFor::Book(common_local::SearchForBooksBy::Query),
-        &ActiveAgents::default(),
+        &ActiveAgents::for_library(book.library_id, &db.basic()).await?,
     )
     .await?;
 
@@ -396,13 +512,11 @@ async fn insert_or_update_book_image(
 ) -> WebResult<JsonResponse<&'static str>> {
     let member = member.fetch_or_error(&db.basic()).await?;
 
-    if !member.permissions.is_owner() {
-        return Err(ApiErrorResponse::new("Not owner").into());
+    if !member.permissions.has(GroupPermissions::EDIT_METADATA) {
+        return Err(ApiErrorResponse::new("Missing permission to edit metadata").into());
     }
 
-    let mut book = BookModel::find_one_by_id(*book_id, &db.basic())
-        .await?
-        .unwrap();
+    let mut book = find_visible_book(*book_id, &member, &db.basic()).await?;
 
     match body.into_inner().url_or_id {
         Either::Left(url) => {
@@ -425,7 +539,7 @@ async fn insert_or_update_book_image(
         Either::Right(id) => {
             let poster = UploadedImageModel::get_by_id(id, &db.basic())
                 .await?
-                .unwrap();
+                .ok_or(crate::Error::Internal(crate::InternalError::ItemMissing))?;
 
             if book.thumb_path == poster.path {
                 return Ok(web::Json(WrappingResponse::okay("success")));
@@ -437,6 +551,8 @@ async fn insert_or_update_book_image(
 
     book.update(&db.basic()).await?;
 
+    BookFieldSourceModel::set_user_edited(book.id, &[MetadataField::Cover], &db.basic()).await?;
+
     Ok(web::Json(WrappingResponse::okay("success")))
 }
 
@@ -456,7 +572,15 @@ async fn get_book_progress(
 #[get("/book/search")]
 pub async fn book_search(
     body: web::Query<api::GetBookSearch>,
+    member: MemberCookie,
+    db: web::Data<Database>,
 ) -> WebResult<JsonResponse<api::ApiGetBookSearchResponse>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    if !member.permissions.has(GroupPermissions::EDIT_METADATA) {
+        return Err(ApiErrorResponse::new("Missing permission to edit metadata").into());
+    }
+
     let search = metadata::search_all_agents(
         &body.query,
         match body.search_type {
@@ -528,12 +652,14 @@ async fn insert_book_person(
 
     let member = member.fetch_or_error(&db.basic()).await?;
 
-    if !member.permissions.is_owner() {
+    if !member.permissions.has(GroupPermissions::EDIT_METADATA) {
         return Ok(web::Json(WrappingResponse::error(
             "You cannot do this! No Permissions!",
         )));
     }
 
+    find_visible_book(book_id, &member, &db.basic()).await?;
+
     // If book had no other people referenced we'll update the cached author name.
     if BookPersonModel::find_by(Either::Left(book_id), &db.basic())
         .await?
@@ -552,6 +678,8 @@ async fn insert_book_person(
         .insert_or_ignore(&db.basic())
         .await?;
 
+    BookFieldSourceModel::set_user_edited(book_id, &[MetadataField::Authors], &db.basic()).await?;
+
     Ok(web::Json(WrappingResponse::okay(String::from("success"))))
 }
 
@@ -565,16 +693,20 @@ async fn delete_book_person(
 
     let member = member.fetch_or_error(&db.basic()).await?;
 
-    if !member.permissions.is_owner() {
+    if !member.permissions.has(GroupPermissions::EDIT_METADATA) {
         return Ok(web::Json(WrappingResponse::error(
             "You cannot do this! No Permissions!",
         )));
     }
 
+    find_visible_book(book_id, &member, &db.basic()).await?;
+
     BookPersonModel { book_id, person_id }
         .delete(&db.basic())
         .await?;
 
+    BookFieldSourceModel::set_user_edited(book_id, &[MetadataField::Authors], &db.basic()).await?;
+
     // If book has no other people referenced we'll update the cached author name.
     if BookPersonModel::find_by(Either::Left(book_id), &db.basic())
         .await?
@@ -593,3 +725,20 @@ async fn delete_book_person(
         total: 1,
     })))
 }
+
+/// Books in libraries the member can't see are treated as missing.
+async fn find_visible_book(
+    book_id: BookId,
+    member: &MemberModel,
+    db: &dyn DatabaseAccess,
+) -> Result<BookModel> {
+    let book = BookModel::find_one_by_id(book_id, db)
+        .await?
+        .ok_or(crate::Error::Internal(crate::InternalError::ItemMissing))?;
+
+    LibraryAccess::for_member(member, db)
+        .await?
+        .check(book.library_id)?;
+
+    Ok(book)
+}
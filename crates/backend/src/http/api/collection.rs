@@ -10,6 +10,7 @@ use crate::{
         book::BookModel,
         collection::{CollectionModel, NewCollectionModel},
         collection_item::CollectionItemModel,
+        member_library::LibraryAccess,
     },
     WebResult,
 };
@@ -60,11 +61,16 @@ async fn load_collection_id_books(
         .await?
         .ok_or_else(|| crate::Error::from(crate::InternalError::ItemMissing))?;
 
+    let library_access = LibraryAccess::for_member(&member, &access).await?;
+
     let mut books = Vec::new();
 
     // TODO: Turn into a single SQL Query
     for item in CollectionItemModel::find_by_collection_id(model.id, &access).await? {
-        if let Some(book) = BookModel::find_one_by_id(item.book_id, &access).await? {
+        if let Some(book) = BookModel::find_one_by_id(item.book_id, &access)
+            .await?
+            .filter(|v| library_access.contains(v.library_id))
+        {
             books.push(DisplayItem {
                 id: book.id,
                 title: book.title.or(book.original_title).unwrap_or_default(),
@@ -2,15 +2,22 @@ use actix_files::NamedFile;
 use actix_web::http::header::ContentDisposition;
 use actix_web::{delete, get, post, web, HttpResponse};
 
-use common::api::WrappingResponse;
-use common_local::{api, Chapter, FileId, Progression};
+use chrono::Utc;
+use common::api::{ApiErrorResponse, WrappingResponse};
+use common_local::{
+    api, Bookmark, BookmarkId, Chapter, FileId, GroupPermissions, Notation, NotationId,
+    Progression, TocItem,
+};
 use futures::TryStreamExt;
 use reqwest::header::HeaderValue;
 use tracing::error;
 
-use crate::database::Database;
+use crate::database::{Database, DatabaseAccess};
 use crate::http::{JsonResponse, MemberCookie};
+use crate::model::bookmark::{FileBookmarkModel, NewFileBookmarkModel};
 use crate::model::file::FileModel;
+use crate::model::member_library::LibraryAccess;
+use crate::model::notation::FileNotationModel;
 use crate::model::note::FileNoteModel;
 use crate::model::progress::FileProgressionModel;
 use crate::{Result, WebResult};
@@ -21,13 +28,12 @@ use crate::{Result, WebResult};
 pub async fn load_file_resource(
     path: web::Path<(FileId, String)>,
     res: web::Query<api::LoadResourceQuery>,
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<HttpResponse> {
     let (file_id, resource_path) = path.into_inner();
 
-    let file = FileModel::find_one_by_id(file_id, &db.basic())
-        .await?
-        .unwrap();
+    let file = find_visible_file(file_id, &member, &db.basic()).await?;
 
     let mut book = bookie::load_from_path(&file.path)?.unwrap();
 
@@ -72,13 +78,12 @@ pub async fn load_file_resource(
 #[get("/file/{id}/pages/{pages}")]
 pub async fn load_file_pages(
     path: web::Path<(FileId, String)>,
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<api::ApiGetFilePagesByIdResponse>> {
     let (file_id, chapters) = path.into_inner();
 
-    let file = FileModel::find_one_by_id(file_id, &db.basic())
-        .await?
-        .unwrap();
+    let file = find_visible_file(file_id, &member, &db.basic()).await?;
 
     let mut book = bookie::load_from_path(&file.path)?.unwrap();
 
@@ -124,6 +129,141 @@ pub async fn load_file_pages(
     )))
 }
 
+#[get("/file/{id}/toc")]
+pub async fn load_file_toc(
+    file_id: web::Path<FileId>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiGetFileTocByIdResponse>> {
+    let file = find_visible_file(*file_id, &member, &db.basic()).await?;
+
+    let mut book = bookie::load_from_path(&file.path)?.unwrap();
+
+    Ok(web::Json(WrappingResponse::okay(convert_toc_items(
+        book.get_table_of_contents()?,
+    ))))
+}
+
+const MAX_SEARCH_RESULTS: usize = 100;
+const SEARCH_CONTEXT_LENGTH: usize = 50;
+
+#[get("/file/{id}/search")]
+pub async fn search_file(
+    file_id: web::Path<FileId>,
+    query: web::Query<api::FileSearchQuery>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiGetFileSearchByIdResponse>> {
+    let search = query.query.trim();
+
+    if search.chars().count() < 2 {
+        return Err(ApiErrorResponse::new("Search has to be at least 2 characters long").into());
+    }
+
+    let file = find_visible_file(*file_id, &member, &db.basic()).await?;
+
+    let mut book = bookie::load_from_path(&file.path)?.unwrap();
+
+    let mut items = Vec::new();
+
+    for chapter in 0..book.chapter_count() {
+        book.set_chapter(chapter);
+
+        // Pages which aren't text (eg. comic book images) have nothing to search.
+        let Ok(page) = book.read_page_raw_as_string() else {
+            continue;
+        };
+
+        let text = match bookie::epub::get_body_text(page.as_bytes()) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(file = file.path, chapter, error = ?e, "Unable to read chapter text");
+                continue;
+            }
+        };
+
+        find_text_matches(&text, search, chapter, &mut items);
+
+        if items.len() >= MAX_SEARCH_RESULTS {
+            break;
+        }
+    }
+
+    Ok(web::Json(WrappingResponse::okay(items)))
+}
+
+/// Case insensitive. Positions are in UTF-16 code units since that's what the reader counts in.
+fn find_text_matches(
+    text: &str,
+    search: &str,
+    chapter: usize,
+    found: &mut Vec<api::FileSearchMatch>,
+) {
+    let chars = text.chars().collect::<Vec<_>>();
+    let search = search.chars().collect::<Vec<_>>();
+
+    let mut index = 0;
+    let mut char_pos = 0;
+
+    while index + search.len() <= chars.len() && found.len() < MAX_SEARCH_RESULTS {
+        let is_match = chars[index..index + search.len()]
+            .iter()
+            .zip(&search)
+            .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()));
+
+        if is_match {
+            let end = index + search.len();
+
+            found.push(api::FileSearchMatch {
+                chapter,
+                char_pos,
+                before: collapse_whitespace(
+                    &chars[index.saturating_sub(SEARCH_CONTEXT_LENGTH)..index],
+                ),
+                text: collapse_whitespace(&chars[index..end]),
+                after: collapse_whitespace(
+                    &chars[end..(end + SEARCH_CONTEXT_LENGTH).min(chars.len())],
+                ),
+            });
+
+            char_pos += chars[index..end]
+                .iter()
+                .map(|v| v.len_utf16())
+                .sum::<usize>();
+            index = end;
+        } else {
+            char_pos += chars[index].len_utf16();
+            index += 1;
+        }
+    }
+}
+
+fn collapse_whitespace(value: &[char]) -> String {
+    let mut output = String::new();
+
+    for &c in value {
+        if !c.is_whitespace() {
+            output.push(c);
+        } else if !output.ends_with(' ') {
+            output.push(' ');
+        }
+    }
+
+    output
+}
+
+fn convert_toc_items(items: Vec<bookie::TocItem>) -> Vec<TocItem> {
+    items
+        .into_iter()
+        .map(|item| TocItem {
+            title: item.title,
+            chapter: item.chapter,
+            fragment: item.fragment,
+            children: convert_toc_items(item.children),
+        })
+        .collect()
+}
+
 // TODO: Add body requests for specifics
 #[get("/file/{id}")]
 pub async fn load_file(
@@ -131,8 +271,14 @@ pub async fn load_file(
     file_id: web::Path<FileId>,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<Option<api::GetFileByIdResponse>>> {
+    let access =
+        LibraryAccess::for_member(&member.fetch_or_error(&db.basic()).await?, &db.basic()).await?;
+
     Ok(web::Json(WrappingResponse::okay(
-        if let Some(file) = FileModel::find_one_by_id(*file_id, &db.basic()).await? {
+        if let Some(file) = FileModel::find_one_by_id(*file_id, &db.basic())
+            .await?
+            .filter(|v| access.contains(v.library_id))
+        {
             Some(api::GetFileByIdResponse {
                 progress: FileProgressionModel::find_one(member.member_id(), *file_id, &db.basic())
                     .await?
@@ -149,11 +295,19 @@ pub async fn load_file(
 #[get("/file/{id}/download")]
 pub async fn download_file(
     file_id: web::Path<FileId>,
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<NamedFile> {
-    let file_model = FileModel::find_one_by_id(*file_id, &db.basic())
+    let file_model = find_visible_file(*file_id, &member, &db.basic()).await?;
+
+    if !member
+        .fetch_or_error(&db.basic())
         .await?
-        .ok_or(crate::Error::Internal(crate::InternalError::ItemMissing))?;
+        .permissions
+        .has(GroupPermissions::DOWNLOAD)
+    {
+        return Err(ApiErrorResponse::new("Missing permission to download").into());
+    }
 
     Ok(NamedFile::open_async(file_model.path)
         .await
@@ -170,9 +324,16 @@ pub async fn download_file(
 #[get("/file/{id}/debug/{tail:.*}")]
 pub async fn load_file_debug(
     web_path: web::Path<(FileId, String)>,
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<HttpResponse> {
-    if let Some(file) = FileModel::find_one_by_id(web_path.0, &db.basic()).await? {
+    let access =
+        LibraryAccess::for_member(&member.fetch_or_error(&db.basic()).await?, &db.basic()).await?;
+
+    if let Some(file) = FileModel::find_one_by_id(web_path.0, &db.basic())
+        .await?
+        .filter(|v| access.contains(v.library_id))
+    {
         if web_path.1.is_empty() {
             let book = bookie::load_from_path(&file.path)?.unwrap();
 
@@ -202,9 +363,9 @@ pub async fn progress_file_add(
     member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<&'static str>> {
-    if let Some(book_id) = FileModel::find_one_by_id(*file_id, &db.basic())
+    if let Some(book_id) = find_visible_file(*file_id, &member, &db.basic())
         .await?
-        .and_then(|v| v.book_id)
+        .book_id
     {
         // Check if the book already has progression. Return the progression.
         if let Some(prog) =
@@ -235,6 +396,8 @@ pub async fn progress_file_delete(
     member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<&'static str>> {
+    find_visible_file(*file_id, &member, &db.basic()).await?;
+
     FileProgressionModel::delete_one(member.member_id(), *file_id, &db.basic()).await?;
     Ok(web::Json(WrappingResponse::okay("success")))
 }
@@ -247,6 +410,8 @@ pub async fn notes_file_get(
     member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<api::ApiGetFileNotesByIdResponse>> {
+    find_visible_file(*file_id, &member, &db.basic()).await?;
+
     let v = FileNoteModel::find_one(*file_id, member.member_id(), &db.basic()).await?;
     Ok(web::Json(WrappingResponse::okay(v.map(|v| v.data))))
 }
@@ -265,6 +430,8 @@ pub async fn notes_file_add(
 
     let data = unsafe { String::from_utf8_unchecked(body.to_vec()) };
 
+    find_visible_file(*file_id, &member, &db.basic()).await?;
+
     FileNoteModel::new(*file_id, member.member_id(), data)
         .insert_or_update(&db.basic())
         .await?;
@@ -278,7 +445,261 @@ pub async fn notes_file_delete(
     member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<&'static str>> {
+    find_visible_file(*file_id, &member, &db.basic()).await?;
+
     FileNoteModel::delete_one(*file_id, member.member_id(), &db.basic()).await?;
 
     Ok(web::Json(WrappingResponse::okay("success")))
 }
+
+// Notations
+
+#[get("/file/{id}/notations")]
+pub async fn notations_file_get(
+    file_id: web::Path<FileId>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiGetFileNotationsByIdResponse>> {
+    find_visible_file(*file_id, &member, &db.basic()).await?;
+
+    let mut items =
+        FileNotationModel::find_notations(*file_id, member.member_id(), &db.basic()).await?;
+
+    items.sort_by_key(|v| v.anchor);
+
+    Ok(web::Json(WrappingResponse::okay(items)))
+}
+
+#[post("/file/{id}/notations")]
+pub async fn notations_file_add(
+    file_id: web::Path<FileId>,
+    body: web::Json<api::NewNotationBody>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiPostFileNotationsByIdResponse>> {
+    let body = body.into_inner();
+
+    if body.anchor.start >= body.anchor.end {
+        return Err(ApiErrorResponse::new("The highlight has to contain text").into());
+    }
+
+    find_visible_file(*file_id, &member, &db.basic()).await?;
+
+    let notation =
+        FileNotationModel::update_notations(*file_id, member.member_id(), &db.basic(), |items| {
+            let notation = Notation {
+                id: NotationId::from(items.iter().map(|v| *v.id).max().unwrap_or_default() + 1),
+                anchor: body.anchor,
+                color: body.color,
+                text: body.text,
+                note: body.note.filter(|v| !v.trim().is_empty()),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+
+            items.push(notation.clone());
+
+            Ok(notation)
+        })
+        .await?;
+
+    Ok(web::Json(WrappingResponse::okay(notation)))
+}
+
+#[post("/file/{id}/notations/{notation_id}")]
+pub async fn notations_file_update(
+    path: web::Path<(FileId, NotationId)>,
+    body: web::Json<api::UpdateNotationBody>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiPostFileNotationByIdResponse>> {
+    let (file_id, notation_id) = path.into_inner();
+    let body = body.into_inner();
+
+    find_visible_file(file_id, &member, &db.basic()).await?;
+
+    let notation =
+        FileNotationModel::update_notations(file_id, member.member_id(), &db.basic(), |items| {
+            let notation = items
+                .iter_mut()
+                .find(|v| v.id == notation_id)
+                .ok_or(crate::Error::Internal(crate::InternalError::ItemMissing))?;
+
+            if let Some(color) = body.color {
+                notation.color = color;
+            }
+
+            if let Some(note) = body.note {
+                notation.note = Some(note).filter(|v| !v.trim().is_empty());
+            }
+
+            notation.updated_at = Utc::now();
+
+            Ok(notation.clone())
+        })
+        .await?;
+
+    Ok(web::Json(WrappingResponse::okay(notation)))
+}
+
+#[delete("/file/{id}/notations/{notation_id}")]
+pub async fn notations_file_delete(
+    path: web::Path<(FileId, NotationId)>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiDeleteFileNotationByIdResponse>> {
+    let (file_id, notation_id) = path.into_inner();
+
+    find_visible_file(file_id, &member, &db.basic()).await?;
+
+    FileNotationModel::update_notations(file_id, member.member_id(), &db.basic(), |items| {
+        items.retain(|v| v.id != notation_id);
+
+        Ok(())
+    })
+    .await?;
+
+    Ok(web::Json(WrappingResponse::okay(())))
+}
+
+// Bookmarks
+
+#[get("/file/{id}/bookmarks")]
+pub async fn bookmarks_file_get(
+    file_id: web::Path<FileId>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiGetFileBookmarksByIdResponse>> {
+    find_visible_file(*file_id, &member, &db.basic()).await?;
+
+    let items = FileBookmarkModel::find_by_file_id(member.member_id(), *file_id, &db.basic())
+        .await?
+        .into_iter()
+        .map(Bookmark::from)
+        .collect();
+
+    Ok(web::Json(WrappingResponse::okay(items)))
+}
+
+#[post("/file/{id}/bookmarks")]
+pub async fn bookmarks_file_add(
+    file_id: web::Path<FileId>,
+    body: web::Json<api::NewBookmarkBody>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiPostFileBookmarksByIdResponse>> {
+    let body = body.into_inner();
+
+    let file = find_visible_file(*file_id, &member, &db.basic()).await?;
+
+    if body.chapter >= file.chapter_count as usize {
+        return Err(ApiErrorResponse::new("Chapter is out of bounds").into());
+    }
+
+    let model = NewFileBookmarkModel {
+        file_id: *file_id,
+        member_id: member.member_id(),
+        chapter: body.chapter as i64,
+        char_pos: body.char_pos as i64,
+        label: body.label.filter(|v| !v.trim().is_empty()),
+        snippet: body.snippet,
+        created_at: Utc::now(),
+    }
+    .insert(&db.basic())
+    .await?;
+
+    Ok(web::Json(WrappingResponse::okay(model.into())))
+}
+
+#[post("/file/{id}/bookmarks/{bookmark_id}")]
+pub async fn bookmarks_file_update(
+    path: web::Path<(FileId, BookmarkId)>,
+    body: web::Json<api::UpdateBookmarkBody>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiPostFileBookmarkByIdResponse>> {
+    let (file_id, bookmark_id) = path.into_inner();
+
+    find_visible_file(file_id, &member, &db.basic()).await?;
+
+    let mut model =
+        FileBookmarkModel::find_one(bookmark_id, member.member_id(), file_id, &db.basic())
+            .await?
+            .ok_or(crate::Error::Internal(crate::InternalError::ItemMissing))?;
+
+    model.label = Some(body.into_inner().label).filter(|v| !v.trim().is_empty());
+
+    model.update_label(&db.basic()).await?;
+
+    Ok(web::Json(WrappingResponse::okay(model.into())))
+}
+
+#[delete("/file/{id}/bookmarks/{bookmark_id}")]
+pub async fn bookmarks_file_delete(
+    path: web::Path<(FileId, BookmarkId)>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiDeleteFileBookmarkByIdResponse>> {
+    let (file_id, bookmark_id) = path.into_inner();
+
+    find_visible_file(file_id, &member, &db.basic()).await?;
+
+    FileBookmarkModel::delete(bookmark_id, member.member_id(), file_id, &db.basic()).await?;
+
+    Ok(web::Json(WrappingResponse::okay(())))
+}
+
+/// Files in libraries the member can't see are treated as missing.
+async fn find_visible_file(
+    file_id: FileId,
+    member: &MemberCookie,
+    db: &dyn DatabaseAccess,
+) -> Result<FileModel> {
+    let file = FileModel::find_one_by_id(file_id, db)
+        .await?
+        .ok_or(crate::Error::Internal(crate::InternalError::ItemMissing))?;
+
+    LibraryAccess::for_member(&member.fetch_or_error(db).await?, db)
+        .await?
+        .check(file.library_id)?;
+
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_matches() {
+        let mut found = Vec::new();
+
+        find_text_matches("Le café.  Un Café", "CAFÉ", 3, &mut found);
+
+        assert_eq!(
+            found,
+            vec![
+                api::FileSearchMatch {
+                    chapter: 3,
+                    char_pos: 3,
+                    before: "Le ".to_string(),
+                    text: "café".to_string(),
+                    after: ". Un Café".to_string(),
+                },
+                api::FileSearchMatch {
+                    chapter: 3,
+                    char_pos: 13,
+                    before: "Le café. Un ".to_string(),
+                    text: "Café".to_string(),
+                    after: String::new(),
+                },
+            ]
+        );
+
+        // Characters outside of the BMP take up two UTF-16 code units.
+        found.clear();
+        find_text_matches("😀 ab", "ab", 0, &mut found);
+
+        assert_eq!(found[0].char_pos, 3);
+    }
+}
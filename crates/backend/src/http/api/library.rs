@@ -1,35 +1,44 @@
 use actix_web::{get, post, web};
-use common::api::WrappingResponse;
-use common_local::{api, LibraryColl, LibraryId};
+use common::api::{ApiErrorResponse, WrappingResponse};
+use common_local::{api, AgentPriority, LibraryColl, LibraryId};
 
 use crate::{
     database::Database,
-    http::JsonResponse,
-    model::{directory::DirectoryModel, library::LibraryModel},
+    http::{JsonResponse, MemberCookie},
+    model::{directory::DirectoryModel, library::LibraryModel, member_library::LibraryAccess},
     WebResult,
 };
 
 #[get("/libraries")]
 async fn load_library_list(
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<api::ApiGetLibrariesResponse>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
+
     Ok(web::Json(WrappingResponse::okay(
         api::GetLibrariesResponse {
             items: LibraryModel::get_all(&db.basic())
                 .await?
                 .into_iter()
-                .map(|file| LibraryColl {
-                    id: file.id,
+                .filter(|v| access.contains(v.id))
+                .map(|file| {
+                    Ok(LibraryColl {
+                        id: file.id,
+
+                        agent_priority: file.parse_agent_priority()?,
 
-                    name: file.name,
+                        name: file.name,
 
-                    created_at: file.created_at.timestamp_millis(),
-                    scanned_at: file.scanned_at.timestamp_millis(),
-                    updated_at: file.updated_at.timestamp_millis(),
+                        created_at: file.created_at.timestamp_millis(),
+                        scanned_at: file.scanned_at.timestamp_millis(),
+                        updated_at: file.updated_at.timestamp_millis(),
 
-                    directories: Vec::new(),
+                        directories: Vec::new(),
+                    })
                 })
-                .collect(),
+                .collect::<crate::Result<_>>()?,
         },
     )))
 }
@@ -37,8 +46,15 @@ async fn load_library_list(
 #[get("/library/{id}")]
 async fn load_library_id(
     id: web::Path<LibraryId>,
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<api::ApiGetLibraryIdResponse>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    LibraryAccess::for_member(&member, &db.basic())
+        .await?
+        .check(*id)?;
+
     let model = LibraryModel::find_one_by_id(*id, &db.basic())
         .await?
         .ok_or_else(|| crate::Error::from(crate::InternalError::ItemMissing))?;
@@ -48,6 +64,8 @@ async fn load_library_id(
     let library = LibraryColl {
         id: model.id,
 
+        agent_priority: model.parse_agent_priority()?,
+
         name: model.name,
 
         created_at: model.created_at.timestamp_millis(),
@@ -64,10 +82,17 @@ async fn load_library_id(
 async fn update_library_id(
     id: web::Path<LibraryId>,
     body: web::Json<api::UpdateLibrary>,
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<&'static str>> {
     let body = body.into_inner();
 
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    if !member.permissions.is_owner() {
+        return Err(ApiErrorResponse::new("Not owner").into());
+    }
+
     let mut model = LibraryModel::find_one_by_id(*id, &db.basic())
         .await?
         .ok_or_else(|| crate::Error::from(crate::InternalError::ItemMissing))?;
@@ -82,11 +107,26 @@ async fn update_library_id(
         is_updated = true;
     }
 
+    if let Some(priority) = body.agent_priority {
+        if priority.agents.is_empty() {
+            return Err(ApiErrorResponse::new("At least one agent has to be enabled").into());
+        }
+
+        model.agent_priority = if priority == AgentPriority::default() {
+            None
+        } else {
+            Some(serde_json::to_string(&priority).map_err(crate::Error::from)?)
+        };
+        is_updated = true;
+    }
+
     if !body.remove_directories.is_empty() {
         // TODO: Don't trust that the path is correct. Also remove slashes at the end of path.
         for path in body.remove_directories {
             DirectoryModel::remove_by_path(&path, &db.basic()).await?;
         }
+
+        crate::scanner::watcher::reload_directories();
     }
 
     if !body.add_directories.is_empty() {
@@ -99,6 +139,8 @@ async fn update_library_id(
             .insert(&db.basic())
             .await?;
         }
+
+        crate::scanner::watcher::reload_directories();
     }
 
     if is_updated {
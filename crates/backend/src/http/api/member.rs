@@ -1,14 +1,23 @@
-use actix_web::{get, post, web};
+use actix_web::{delete, get, post, web};
+use chrono::{Duration, Utc};
 use common::api::{ApiErrorResponse, WrappingResponse};
-use common_local::api;
+use common_local::{api, ApiTokenId, GroupPermissions};
 
 use crate::{
     database::Database,
-    http::{JsonResponse, MemberCookie},
-    model::member::{MemberModel, NewMemberModel},
-    WebResult,
+    http::{token, JsonResponse, MemberCookie},
+    model::{
+        api_token::{ApiTokenModel, NewApiTokenModel},
+        koreader::KoreaderKeyModel,
+        member::{MemberModel, NewMemberModel},
+        member_library::MemberLibraryModel,
+    },
+    Error, WebResult,
 };
 
+/// About 10 years.
+const MAX_TOKEN_EXPIRY_DAYS: u32 = 3650;
+
 // TODO: Add body requests for specifics
 #[get("/member")]
 pub async fn load_member_self(
@@ -32,12 +41,20 @@ pub async fn update_member(
 ) -> WebResult<JsonResponse<&'static str>> {
     let member = member.fetch_or_error(&db.basic()).await?;
 
-    if !member.permissions.is_owner() {
-        return Err(ApiErrorResponse::new("Not owner").into());
+    if !member.permissions.has(GroupPermissions::MANAGE_MEMBERS) {
+        return Err(ApiErrorResponse::new("Missing permission to manage members").into());
     }
 
     match update.into_inner() {
         api::UpdateMember::Delete { id } => {
+            let other = MemberModel::find_one_by_id(id, &db.basic())
+                .await?
+                .ok_or_else(|| Error::from(crate::InternalError::ItemMissing))?;
+
+            if other.permissions.is_owner() && !member.permissions.is_owner() {
+                return Err(ApiErrorResponse::new("Only an owner can remove an owner").into());
+            }
+
             MemberModel::delete(id, &db.basic()).await?;
         }
 
@@ -48,11 +65,168 @@ pub async fn update_member(
 
             // TODO: Send an email.
         }
+
+        api::UpdateMember::Permissions {
+            id,
+            permissions,
+            libraries,
+        } => {
+            let mut other = MemberModel::find_one_by_id(id, &db.basic())
+                .await?
+                .ok_or_else(|| Error::from(crate::InternalError::ItemMissing))?;
+
+            // Members who can manage others are still unable to hand out more than they have.
+            if !member.permissions.is_owner()
+                && (other.permissions.is_owner()
+                    || !member.permissions.contains_group(permissions.group))
+            {
+                return Err(ApiErrorResponse::new("Unable to give more than you have").into());
+            }
+
+            if other.id == member.id && other.permissions.is_owner() && !permissions.is_owner() {
+                return Err(ApiErrorResponse::new("Unable to remove your own ownership").into());
+            }
+
+            other.permissions = permissions;
+            other.update(&db.basic()).await?;
+
+            MemberLibraryModel::set_for_member(id, &libraries, &db.basic()).await?;
+        }
+    }
+
+    Ok(web::Json(WrappingResponse::okay("ok")))
+}
+
+#[get("/member/koreader")]
+pub async fn load_member_koreader(
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiGetKoreaderSyncResponse>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    let key = KoreaderKeyModel::find_one_by_member_id(member.id, &db.basic()).await?;
+
+    Ok(web::Json(WrappingResponse::okay(
+        api::GetKoreaderSyncResponse {
+            username: member.email,
+            enabled: key.is_some(),
+        },
+    )))
+}
+
+#[post("/member/koreader")]
+pub async fn update_member_koreader(
+    member: MemberCookie,
+    update: web::Json<api::UpdateKoreaderSync>,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<&'static str>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    match update.into_inner().password.filter(|v| !v.is_empty()) {
+        Some(password) => {
+            // KOReader only ever sends the MD5 of the password.
+            let key = format!("{:x}", md5::compute(password));
+
+            KoreaderKeyModel {
+                member_id: member.id,
+                key_hash: bcrypt::hash(key, bcrypt::DEFAULT_COST).map_err(Error::from)?,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            }
+            .insert_or_replace(&db.basic())
+            .await?;
+        }
+
+        None => {
+            KoreaderKeyModel::delete_by_member_id(member.id, &db.basic()).await?;
+        }
     }
 
     Ok(web::Json(WrappingResponse::okay("ok")))
 }
 
+#[get("/member/tokens")]
+pub async fn load_member_tokens(
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiGetApiTokenListResponse>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    let tokens = ApiTokenModel::find_by_member_id(member.id, &db.basic()).await?;
+
+    Ok(web::Json(WrappingResponse::okay(
+        tokens.into_iter().map(|v| v.into()).collect(),
+    )))
+}
+
+#[post("/member/tokens")]
+pub async fn new_member_token(
+    member: MemberCookie,
+    body: web::Json<api::NewApiTokenBody>,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiPostApiTokenResponse>> {
+    // Otherwise a token could create one with a larger scope.
+    if member.token_scope().is_some_and(|v| !v.is_admin()) {
+        return Err(ApiErrorResponse::new("Tokens can only be created with an admin token").into());
+    }
+
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    let body = body.into_inner();
+
+    if body.name.trim().is_empty() {
+        return Err(ApiErrorResponse::new("A name is required").into());
+    }
+
+    let expires_at = match body.expires_in_days {
+        Some(days) if days > MAX_TOKEN_EXPIRY_DAYS => {
+            return Err(ApiErrorResponse::new("Tokens can't expire in more than 10 years").into());
+        }
+
+        Some(days) => Some(
+            Utc::now()
+                .checked_add_signed(Duration::days(days as i64))
+                .ok_or_else(|| ApiErrorResponse::new("Invalid token expiration"))?,
+        ),
+
+        None => None,
+    };
+
+    let (secret, prefix, token_hash) = token::generate_token();
+
+    let model = NewApiTokenModel {
+        member_id: member.id,
+        name: body.name.trim().to_string(),
+        prefix,
+        token_hash,
+        scope: body.scope,
+        expires_at,
+        created_at: Utc::now(),
+    }
+    .insert(&db.basic())
+    .await?;
+
+    Ok(web::Json(WrappingResponse::okay(
+        api::NewApiTokenResponse {
+            token: model.into(),
+            secret,
+        },
+    )))
+}
+
+#[delete("/member/token/{id}")]
+pub async fn delete_member_token(
+    id: web::Path<ApiTokenId>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<&'static str>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    ApiTokenModel::delete(*id, member.id, &db.basic()).await?;
+
+    Ok(web::Json(WrappingResponse::okay("ok")))
+}
+
 #[get("/members")]
 pub async fn load_members_list(
     member: MemberCookie,
@@ -60,18 +234,28 @@ pub async fn load_members_list(
 ) -> WebResult<JsonResponse<api::ApiGetMembersListResponse>> {
     let member = member.fetch_or_error(&db.basic()).await?;
 
-    if !member.permissions.is_owner() {
-        return Err(ApiErrorResponse::new("Not owner").into());
+    if !member.permissions.has(GroupPermissions::MANAGE_MEMBERS) {
+        return Err(ApiErrorResponse::new("Missing permission to manage members").into());
     }
 
     let count = MemberModel::count(&db.basic()).await?;
 
     let members = MemberModel::get_all(&db.basic()).await?;
 
+    let mut libraries = Vec::new();
+
+    for item in &members {
+        libraries.push((
+            item.id,
+            MemberLibraryModel::find_library_ids_by_member_id(item.id, &db.basic()).await?,
+        ));
+    }
+
     Ok(web::Json(WrappingResponse::okay(
         api::GetMembersListResponse {
             items: members.into_iter().map(|v| v.into()).collect(),
             count,
+            libraries,
         },
     )))
 }
@@ -13,6 +13,7 @@ pub mod library;
 pub mod member;
 pub mod options;
 pub mod person;
+pub mod series;
 pub mod settings;
 pub mod storage;
 pub mod task;
@@ -38,12 +39,22 @@ pub fn api_route() -> Scope<
         .service(file::load_file_debug)
         .service(file::load_file)
         .service(file::load_file_pages)
+        .service(file::load_file_toc)
+        .service(file::search_file)
         .service(file::load_file_resource)
         .service(file::progress_file_add)
         .service(file::progress_file_delete)
         .service(file::notes_file_get)
         .service(file::notes_file_add)
         .service(file::notes_file_delete)
+        .service(file::notations_file_get)
+        .service(file::notations_file_add)
+        .service(file::notations_file_update)
+        .service(file::notations_file_delete)
+        .service(file::bookmarks_file_get)
+        .service(file::bookmarks_file_add)
+        .service(file::bookmarks_file_update)
+        .service(file::bookmarks_file_delete)
         .service(file::download_file)
         // Image
         .service(image::get_local_image)
@@ -51,12 +62,18 @@ pub fn api_route() -> Scope<
         .service(member::load_member_self)
         .service(member::load_members_list)
         .service(member::update_member)
+        .service(member::load_member_koreader)
+        .service(member::update_member_koreader)
+        .service(member::load_member_tokens)
+        .service(member::new_member_token)
+        .service(member::delete_member_token)
         // Preferences
         .service(preferences::get_preferences)
         .service(preferences::post_preferences)
         // Book
         .service(book::load_book_list)
         .service(book::load_book_preset_list)
+        .service(book::search_book_text)
         .service(book::update_books)
         .service(book::update_book_info)
         .service(book::book_search)
@@ -72,6 +89,11 @@ pub fn api_route() -> Scope<
         .service(person::load_author_list)
         .service(person::load_person_thumbnail)
         .service(person::update_person_data)
+        // Series
+        .service(series::load_series_list)
+        .service(series::load_series_id)
+        .service(series::update_series_id)
+        .service(series::load_series_id_books)
         // Options
         .service(options::load_options)
         .service(options::update_options_add)
@@ -90,6 +112,8 @@ pub fn api_route() -> Scope<
         // Task
         .service(task::get_tasks)
         .service(task::run_task)
+        .service(task::cancel_task)
+        .service(task::get_task_history)
         // Storage
         .service(storage::get_directory)
         .default_service(web::route().to(default_handler))
@@ -1,7 +1,7 @@
 use actix_web::{delete, get, post, web};
 use chrono::Utc;
 use common::api::{ApiErrorResponse, WrappingResponse};
-use common_local::{api, util::take_from_and_swap, LibraryColl};
+use common_local::{api, util::take_from_and_swap, CronSchedule, LibraryColl};
 
 use crate::{
     config::{get_config, save_config, update_config},
@@ -10,6 +10,7 @@ use crate::{
     model::{
         directory::DirectoryModel,
         library::{LibraryModel, NewLibraryModel},
+        member_library::LibraryAccess,
     },
     WebResult,
 };
@@ -20,6 +21,7 @@ async fn load_options(
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<api::ApiGetOptionsResponse>> {
     let member = member.fetch_or_error(&db.basic()).await?;
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
 
     let libraries = LibraryModel::get_all(&db.basic()).await?;
     let mut directories = DirectoryModel::get_all(&db.basic()).await?;
@@ -27,18 +29,22 @@ async fn load_options(
     Ok(web::Json(WrappingResponse::okay(api::GetOptionsResponse {
         libraries: libraries
             .into_iter()
-            .map(|lib| LibraryColl {
-                id: lib.id,
-                name: lib.name,
-                scanned_at: lib.scanned_at.timestamp_millis(),
-                created_at: lib.created_at.timestamp_millis(),
-                updated_at: lib.updated_at.timestamp_millis(),
-                directories: take_from_and_swap(&mut directories, |v| v.library_id == lib.id)
-                    .into_iter()
-                    .map(|v| v.path)
-                    .collect(),
+            .filter(|lib| access.contains(lib.id))
+            .map(|lib| {
+                Ok(LibraryColl {
+                    id: lib.id,
+                    agent_priority: lib.parse_agent_priority()?,
+                    name: lib.name,
+                    scanned_at: lib.scanned_at.timestamp_millis(),
+                    created_at: lib.created_at.timestamp_millis(),
+                    updated_at: lib.updated_at.timestamp_millis(),
+                    directories: take_from_and_swap(&mut directories, |v| v.library_id == lib.id)
+                        .into_iter()
+                        .map(|v| v.path)
+                        .collect(),
+                })
             })
-            .collect(),
+            .collect::<crate::Result<_>>()?,
 
         config: member.permissions.is_owner().then(|| {
             let mut config = get_config();
@@ -67,6 +73,7 @@ async fn update_options_add(
     let api::ModifyOptionsBody {
         library,
         libby_public_search,
+        schedule,
     } = modify.into_inner();
 
     if let Some(mut library) = library {
@@ -97,6 +104,8 @@ async fn update_options_add(
                     .insert(&db.basic())
                     .await?;
             }
+
+            crate::scanner::watcher::reload_directories();
         }
     }
 
@@ -110,6 +119,25 @@ async fn update_options_add(
         save_config().await?;
     }
 
+    if let Some(schedule) = schedule {
+        if let Err(e) = schedule.cron.parse::<CronSchedule>() {
+            return Err(ApiErrorResponse {
+                description: format!("Invalid Schedule: {e}"),
+            }
+            .into());
+        }
+
+        update_config(move |config| {
+            config.schedules.push(schedule);
+
+            Ok(())
+        })?;
+
+        save_config().await?;
+
+        crate::task::reload_schedules();
+    }
+
     Ok(web::Json(WrappingResponse::okay("success")))
 }
 
@@ -125,7 +153,9 @@ async fn update_options_remove(
         return Err(ApiErrorResponse::new("Not owner").into());
     }
 
-    let api::ModifyOptionsBody { library, .. } = modify.into_inner();
+    let api::ModifyOptionsBody {
+        library, schedule, ..
+    } = modify.into_inner();
 
     if let Some(library) = library {
         if let Some(id) = library.id {
@@ -137,6 +167,20 @@ async fn update_options_remove(
                 DirectoryModel::remove_by_path(&path, &db.basic()).await?;
             }
         }
+
+        crate::scanner::watcher::reload_directories();
+    }
+
+    if let Some(schedule) = schedule {
+        update_config(move |config| {
+            config.schedules.retain(|v| v != &schedule);
+
+            Ok(())
+        })?;
+
+        save_config().await?;
+
+        crate::task::reload_schedules();
     }
 
     Ok(web::Json(WrappingResponse::okay("success")))
@@ -4,7 +4,7 @@ use common::{
     api::{ApiErrorResponse, WrappingResponse},
     Either, PersonId,
 };
-use common_local::api;
+use common_local::{api, GroupPermissions, PersonField};
 
 use crate::{
     database::Database,
@@ -13,8 +13,10 @@ use crate::{
         book::BookModel,
         book_person::BookPersonModel,
         image::{ImageLinkModel, UploadedImageModel},
+        member_library::LibraryAccess,
         person::PersonModel,
         person_alt::PersonAltModel,
+        person_locked_field::PersonLockedFieldModel,
     },
     queue_task, store_image,
     task::{self, queue_task_priority},
@@ -25,14 +27,18 @@ use crate::{
 #[get("/people")]
 pub async fn load_author_list(
     query: web::Query<api::SimpleListQuery>,
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<api::ApiGetPeopleResponse>> {
     let offset = query.offset.unwrap_or(0);
     let limit = query.offset.unwrap_or(50);
 
+    let member = member.fetch_or_error(&db.basic()).await?;
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
+
     // Return Searched People
     if let Some(query) = query.query.as_deref() {
-        let items = PersonModel::search_by(query, offset, limit, &db.basic())
+        let items = PersonModel::search_by(query, offset, limit, &access, &db.basic())
             .await?
             .into_iter()
             .map(|v| v.into())
@@ -47,7 +53,7 @@ pub async fn load_author_list(
     }
     // Return All People
     else {
-        let items = PersonModel::find(offset, limit, &db.basic())
+        let items = PersonModel::find(offset, limit, &access, &db.basic())
             .await?
             .into_iter()
             .map(|v| v.into())
@@ -56,7 +62,7 @@ pub async fn load_author_list(
         Ok(web::Json(WrappingResponse::okay(api::GetPeopleResponse {
             offset,
             limit,
-            total: PersonModel::count(&db.basic()).await?,
+            total: PersonModel::count(&access, &db.basic()).await?,
             items,
         })))
     }
@@ -88,7 +94,7 @@ async fn post_change_person_thumbnail(
 ) -> WebResult<JsonResponse<&'static str>> {
     let member = member.fetch(&db.basic()).await?.unwrap();
 
-    if !member.permissions.is_owner() {
+    if !member.permissions.has(GroupPermissions::EDIT_METADATA) {
         return Ok(web::Json(WrappingResponse::error(
             "You cannot do this! No Permissions!",
         )));
@@ -131,6 +137,13 @@ async fn post_change_person_thumbnail(
 
     person.update(&db.basic()).await?;
 
+    PersonLockedFieldModel {
+        person_id: person.id,
+        field: PersonField::Cover,
+    }
+    .insert_or_ignore(&db.basic())
+    .await?;
+
     Ok(web::Json(WrappingResponse::okay("success")))
 }
 
@@ -146,8 +159,8 @@ pub async fn update_person_data(
 
     let member = member.fetch_or_error(&db.basic()).await?;
 
-    if !member.permissions.is_owner() {
-        return Err(ApiErrorResponse::new("Not owner").into());
+    if !member.permissions.has(GroupPermissions::EDIT_METADATA) {
+        return Err(ApiErrorResponse::new("Missing permission to edit metadata").into());
     }
 
     match body.into_inner() {
@@ -163,6 +176,16 @@ pub async fn update_person_data(
             ));
         }
 
+        api::PostPersonBody::UpdateLockedFields(fields) => {
+            PersonLockedFieldModel::delete_by_person_id(person_id, &db.basic()).await?;
+
+            for field in fields {
+                PersonLockedFieldModel { person_id, field }
+                    .insert_or_ignore(&db.basic())
+                    .await?;
+            }
+        }
+
         api::PostPersonBody::CombinePersonWith(into_person_id) => {
             // TODO: Tests for this to ensure it's correct.
 
@@ -220,6 +243,7 @@ pub async fn update_person_data(
 
             // Delete Old Person
             PersonModel::delete_by_id(old_person.id, &db.basic()).await?;
+            PersonLockedFieldModel::delete_by_person_id(old_person.id, &db.basic()).await?;
 
             // Update book cache author name cache
             for met_per in trans_book_person_vec {
@@ -241,13 +265,24 @@ pub async fn update_person_data(
 #[get("/person/{id}")]
 async fn load_person(
     person_id: web::Path<PersonId>,
+    member: MemberCookie,
     db: web::Data<Database>,
 ) -> WebResult<JsonResponse<api::GetPersonResponse>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
+
+    if !PersonModel::is_visible(*person_id, &access, &db.basic()).await? {
+        return Err(crate::Error::Internal(crate::InternalError::ItemMissing).into());
+    }
+
     let person = PersonModel::find_one_by_id(*person_id, &db.basic())
         .await?
-        .unwrap();
+        .ok_or(crate::Error::Internal(crate::InternalError::ItemMissing))?;
+
+    let locked_fields = PersonLockedFieldModel::find_by_person_id(person.id, &db.basic()).await?;
 
     Ok(web::Json(WrappingResponse::okay(api::GetPersonResponse {
         person: person.into(),
+        locked_fields,
     })))
 }
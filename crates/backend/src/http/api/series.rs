@@ -0,0 +1,145 @@
+use actix_web::{get, post, web};
+use common::api::{ApiErrorResponse, WrappingResponse};
+use common_local::{
+    api,
+    filter::{FilterContainer, FilterTableType},
+    DisplayItem, GroupPermissions, SeriesId,
+};
+
+use crate::{
+    database::Database,
+    http::{JsonResponse, MemberCookie},
+    model::{book::BookModel, member_library::LibraryAccess, series::SeriesModel},
+    WebResult,
+};
+
+#[get("/series")]
+pub async fn load_series_list(
+    query: web::Query<api::SimpleListQuery>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiGetSeriesListResponse>> {
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(50);
+
+    let member = member.fetch_or_error(&db.basic()).await?;
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
+
+    let (items, total) = if let Some(query) = query.query.as_deref() {
+        let items = SeriesModel::search_by(query, offset, limit, &access, &db.basic()).await?;
+
+        (items, 0) // TODO
+    } else {
+        (
+            SeriesModel::find(offset, limit, &access, &db.basic()).await?,
+            SeriesModel::count(&access, &db.basic()).await?,
+        )
+    };
+
+    Ok(web::Json(WrappingResponse::okay(
+        api::GetSeriesListResponse {
+            offset,
+            limit,
+            total,
+            items: items.into_iter().map(|v| v.into()).collect(),
+        },
+    )))
+}
+
+#[get("/series/{id}")]
+pub async fn load_series_id(
+    id: web::Path<SeriesId>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiGetSeriesByIdResponse>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
+
+    if !SeriesModel::is_visible(*id, &access, &db.basic()).await? {
+        return Err(crate::Error::from(crate::InternalError::ItemMissing).into());
+    }
+
+    let model = SeriesModel::find_one_by_id(*id, &db.basic())
+        .await?
+        .ok_or_else(|| crate::Error::from(crate::InternalError::ItemMissing))?;
+
+    Ok(web::Json(WrappingResponse::okay(model.into())))
+}
+
+#[post("/series/{id}")]
+pub async fn update_series_id(
+    id: web::Path<SeriesId>,
+    web::Json(body): web::Json<api::UpdateSeriesBody>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiPostUpdateSeriesResponse>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    if !member.permissions.has(GroupPermissions::EDIT_METADATA) {
+        return Err(ApiErrorResponse::new("Missing permission to edit metadata").into());
+    }
+
+    let mut model = SeriesModel::find_one_by_id(*id, &db.basic())
+        .await?
+        .ok_or_else(|| crate::Error::from(crate::InternalError::ItemMissing))?;
+
+    if let Some(name) = body
+        .name
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+    {
+        if let Some(existing) = SeriesModel::find_one_by_name(&name, &db.basic()).await? {
+            if existing.id != model.id {
+                return Err(ApiErrorResponse::new("A Series with that name already exists").into());
+            }
+        }
+
+        model.name = name;
+    }
+
+    if let Some(description) = body.description {
+        model.description = Some(description.trim().to_string()).filter(|v| !v.is_empty());
+    }
+
+    model.update(&db.basic()).await?;
+
+    Ok(web::Json(WrappingResponse::okay(())))
+}
+
+#[get("/series/{id}/books")]
+pub async fn load_series_id_books(
+    id: web::Path<SeriesId>,
+    query: web::Query<api::SimpleListQuery>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiGetSeriesBooksByIdResponse>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
+
+    let mut filters = FilterContainer::default().order_by(FilterTableType::Series, false);
+    filters.add_series_filter(*id);
+
+    let count = BookModel::count_search_by(&filters, None, &access, &db.basic()).await?;
+
+    let items = BookModel::search_by(
+        &filters,
+        None,
+        &access,
+        query.offset.unwrap_or(0),
+        query.limit.unwrap_or(50),
+        &db.basic(),
+    )
+    .await?
+    .into_iter()
+    .map(|book| DisplayItem {
+        id: book.id,
+        title: book.title.or(book.original_title).unwrap_or_default(),
+        cached: book.cached,
+        thumb_path: book.thumb_path,
+    })
+    .collect();
+
+    Ok(web::Json(WrappingResponse::okay(
+        api::GetBookListResponse { items, count },
+    )))
+}
@@ -7,7 +7,7 @@ use common::api::{
 };
 use common_local::{
     api,
-    setup::{Config, LibraryConnection, SetupConfig},
+    setup::{Authenticators, Config, LibraryConnection, SetupConfig},
 };
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
@@ -112,6 +112,7 @@ pub async fn save_initial_setup(
         .await?;
 
         crate::task::queue_task(crate::task::TaskLibraryScan { library_id: lib.id });
+        crate::scanner::watcher::reload_directories();
 
         library_count += 1;
     }
@@ -264,7 +265,16 @@ async fn save_setup_config(mut value: SetupConfig) -> Result<()> {
         server: value.server,
         libby: LibraryConnection::default(),
         email: value.email,
-        authenticators: value.authenticators,
+        authenticators: Authenticators {
+            // Can only be set in the config file.
+            oidc: value
+                .authenticators
+                .oidc
+                .or(temp_config.authenticators.oidc),
+            ..value.authenticators
+        },
+        scanner: temp_config.scanner,
+        schedules: temp_config.schedules,
 
         has_admin_account: false,
     };
@@ -1,14 +1,21 @@
-use actix_web::{get, post, web};
+use std::path::Path;
+
+use actix_web::{delete, get, post, web};
 use common::api::{ApiErrorResponse, WrappingResponse};
 use common_local::{
     api,
     ws::{TaskId, TaskInfo},
+    GroupPermissions, Permissions,
 };
 
 use crate::{
+    config::get_config,
     database::Database,
     http::{ws::RUNNING_TASKS, JsonResponse, MemberCookie},
-    queue_task, task, WebResult,
+    model::task::TaskModel,
+    queue_task,
+    scanner::calibre::METADATA_FILE_NAME,
+    task, WebResult,
 };
 
 // TODO: Actually optimize.
@@ -22,14 +29,28 @@ pub async fn run_task(
 
     let member = member.fetch_or_error(&db.basic()).await?;
 
-    if !member.permissions.is_owner() {
-        return Err(ApiErrorResponse::new("Not owner").into());
-    }
+    check_run_task_permissions(member.permissions, &modify)?;
 
     if let Some(library_id) = modify.run_search {
         queue_task(task::TaskLibraryScan { library_id });
     }
 
+    if let Some(path) = modify.import_calibre {
+        let path = path.trim().to_string();
+
+        if tokio::fs::metadata(Path::new(&path).join(METADATA_FILE_NAME))
+            .await
+            .is_err()
+        {
+            return Err(ApiErrorResponse::new("Unable to find the Calibre metadata.db").into());
+        }
+
+        queue_task(task::TaskImportCalibre {
+            path,
+            member_id: member.id,
+        });
+    }
+
     if let Some(library_id) = modify.run_metadata {
         queue_task(task::TaskUpdateInvalidBook::new(
             task::UpdatingBook::UpdateAllWithAgent {
@@ -39,9 +60,37 @@ pub async fn run_task(
         ));
     }
 
+    if let Some(library_id) = modify.index_text {
+        if !get_config().scanner.index_text {
+            return Err(ApiErrorResponse::new("Full-text search isn't enabled").into());
+        }
+
+        queue_task(task::TaskIndexText {
+            library_id: Some(library_id),
+        });
+    }
+
     Ok(web::Json(WrappingResponse::okay("success")))
 }
 
+/// Importing from Calibre adds a directory to a library. Only owners can manage directories.
+fn check_run_task_permissions(
+    permissions: Permissions,
+    body: &api::RunTaskBody,
+) -> std::result::Result<(), ApiErrorResponse> {
+    if !permissions.has(GroupPermissions::RUN_TASKS) {
+        return Err(ApiErrorResponse::new("Missing permission to run tasks"));
+    }
+
+    if body.import_calibre.is_some() && !permissions.is_owner() {
+        return Err(ApiErrorResponse::new(
+            "Only the owner can import a Calibre library",
+        ));
+    }
+
+    Ok(())
+}
+
 #[get("/tasks")]
 pub async fn get_tasks(
     member: MemberCookie,
@@ -49,8 +98,8 @@ pub async fn get_tasks(
 ) -> WebResult<JsonResponse<Vec<(TaskId, TaskInfo)>>> {
     let member = member.fetch_or_error(&db.basic()).await?;
 
-    if !member.permissions.is_owner() {
-        return Err(ApiErrorResponse::new("Not owner").into());
+    if !member.permissions.has(GroupPermissions::RUN_TASKS) {
+        return Err(ApiErrorResponse::new("Missing permission to run tasks").into());
     }
 
     let resp = RUNNING_TASKS
@@ -62,3 +111,85 @@ pub async fn get_tasks(
 
     Ok(web::Json(WrappingResponse::okay(resp)))
 }
+
+#[delete("/task/{id}")]
+pub async fn cancel_task(
+    task_id: web::Path<TaskId>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiDeleteTaskByIdResponse>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    if !member.permissions.has(GroupPermissions::RUN_TASKS) {
+        return Err(ApiErrorResponse::new("Missing permission to run tasks").into());
+    }
+
+    let task_id = *task_id;
+
+    if TaskModel::find_one_by_id(task_id, &db.basic())
+        .await?
+        .is_none()
+    {
+        return Err(ApiErrorResponse::new("Unable to find Task").into());
+    }
+
+    if !task::cancel_task(task_id, &db.basic()).await? {
+        return Err(ApiErrorResponse::new("Task isn't queued or running").into());
+    }
+
+    Ok(web::Json(WrappingResponse::okay(())))
+}
+
+#[get("/tasks/history")]
+pub async fn get_task_history(
+    query: web::Query<api::SimpleListQuery>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<JsonResponse<api::ApiGetTaskHistoryResponse>> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    if !member.permissions.has(GroupPermissions::RUN_TASKS) {
+        return Err(ApiErrorResponse::new("Missing permission to run tasks").into());
+    }
+
+    let items = TaskModel::find_history(
+        query.offset.unwrap_or(0),
+        query.limit.unwrap_or(50),
+        &db.basic(),
+    )
+    .await?
+    .into_iter()
+    .map(|v| v.into())
+    .collect();
+
+    Ok(web::Json(WrappingResponse::okay(items)))
+}
+
+#[cfg(test)]
+mod tests {
+    use common_local::LibraryId;
+
+    use super::*;
+
+    #[test]
+    fn import_calibre_requires_owner() {
+        let run_tasks = Permissions {
+            group: GroupPermissions::BASIC | GroupPermissions::RUN_TASKS,
+        };
+
+        let scan = api::RunTaskBody {
+            run_search: Some(LibraryId::from(1)),
+            ..Default::default()
+        };
+
+        let import = api::RunTaskBody {
+            import_calibre: Some(String::from("/")),
+            ..Default::default()
+        };
+
+        assert!(check_run_task_permissions(run_tasks, &scan).is_ok());
+        assert!(check_run_task_permissions(run_tasks, &import).is_err());
+        assert!(check_run_task_permissions(Permissions::basic(), &scan).is_err());
+        assert!(check_run_task_permissions(Permissions::owner(), &import).is_ok());
+    }
+}
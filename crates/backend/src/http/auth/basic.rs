@@ -0,0 +1,118 @@
+// HTTP Basic Authentication. Used by clients which can't log in through the frontend. (eg. OPDS readers)
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_web::{http::header, web, HttpRequest};
+use common::MemberId;
+use common_local::MemberAuthType;
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+
+use crate::{database::DatabaseAccess, model::member::MemberModel, Result};
+
+pub static BASIC_AUTH_REALM: &str = r#"Basic realm="Books", charset="UTF-8""#;
+
+/// How long a verified password is remembered. Clients send it with every request.
+const VERIFIED_TIMEOUT: Duration = Duration::from_secs(60 * 5);
+
+lazy_static! {
+    /// Passwords which were recently verified. Keyed by the stored bcrypt hash.
+    static ref VERIFIED_PASSWORDS: Mutex<HashMap<String, ([u8; 32], Instant)>> = Mutex::new(HashMap::new());
+}
+
+/// Stored in the request extensions once the credentials were verified.
+#[derive(Debug, Clone, Copy)]
+pub struct BasicAuthMember(pub MemberId);
+
+/// Verifies the email and password in the Authorization header. Only members with a local password can use it.
+pub async fn find_member(
+    req: &HttpRequest,
+    db: &dyn DatabaseAccess,
+) -> Result<Option<MemberModel>> {
+    let Some((email, password)) = parse_credentials(req) else {
+        return Ok(None);
+    };
+
+    let Some(member) = MemberModel::find_one_by_email(email.trim(), db).await? else {
+        return Ok(None);
+    };
+
+    if member.type_of != MemberAuthType::Password {
+        return Ok(None);
+    }
+
+    let Some(hash) = member.password.clone() else {
+        return Ok(None);
+    };
+
+    if verify_cached(password, hash).await? {
+        Ok(Some(member))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Verifies the password against the bcrypt hash. Clients send their credentials with every request
+/// so successful verifications are remembered for a short time.
+pub async fn verify_cached(password: String, hash: String) -> Result<bool> {
+    let digest = password_digest(&hash, &password);
+
+    let is_cached =
+        VERIFIED_PASSWORDS
+            .lock()
+            .unwrap()
+            .get(&hash)
+            .is_some_and(|(cached, verified_at)| {
+                *cached == digest && verified_at.elapsed() < VERIFIED_TIMEOUT
+            });
+
+    if is_cached {
+        return Ok(true);
+    }
+
+    // Bcrypt is slow on purpose. Don't block the worker while it runs.
+    let is_valid = {
+        let hash = hash.clone();
+
+        web::block(move || bcrypt::verify(password, &hash)).await??
+    };
+
+    if is_valid {
+        let mut verified = VERIFIED_PASSWORDS.lock().unwrap();
+
+        verified.retain(|_, (_, verified_at)| verified_at.elapsed() < VERIFIED_TIMEOUT);
+        verified.insert(hash, (digest, Instant::now()));
+    }
+
+    Ok(is_valid)
+}
+
+/// The cache shouldn't contain the password. Salted with the stored hash.
+fn password_digest(hash: &str, password: &str) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(hash)
+        .chain_update(password)
+        .finalize()
+        .into()
+}
+
+/// Returns (Email, Password)
+fn parse_credentials(req: &HttpRequest) -> Option<(String, String)> {
+    let value = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+
+    let (scheme, encoded) = value.split_once(' ')?;
+
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+
+    let decoded = String::from_utf8(base64::decode(encoded.trim()).ok()?).ok()?;
+
+    let (email, password) = decoded.split_once(':')?;
+
+    Some((email.to_string(), password.to_string()))
+}
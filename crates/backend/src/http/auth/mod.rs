@@ -9,10 +9,12 @@ use actix_identity::Identity;
 use actix_web::{
     body::{EitherBody, MessageBody},
     dev::{Extensions, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header,
     FromRequest, HttpRequest, HttpResponse,
 };
 use chrono::Utc;
 use common::{api::ApiErrorResponse, MemberId};
+use common_local::{ApiTokenScope, GroupPermissions};
 use futures::{future::LocalBoxFuture, FutureExt};
 use rand::{rngs::ThreadRng, Rng};
 use serde::{Deserialize, Serialize};
@@ -23,8 +25,11 @@ use crate::{
     InternalError, Result, WebError,
 };
 
+pub mod basic;
+pub mod oidc;
 pub mod password;
 pub mod passwordless;
+pub mod token;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CookieAuth {
@@ -70,7 +75,7 @@ pub fn remember_member_auth(
 }
 
 // Retrieve Member from Identity
-pub struct MemberCookie(CookieAuth);
+pub struct MemberCookie(CookieAuth, Option<ApiTokenScope>);
 
 impl MemberCookie {
     pub fn member_id(&self) -> MemberId {
@@ -81,11 +86,27 @@ impl MemberCookie {
         self.0.token_secret.as_str()
     }
 
+    /// The scope of the API token used instead of a cookie.
+    pub fn token_scope(&self) -> Option<ApiTokenScope> {
+        self.1
+    }
+
     pub async fn fetch(&self, db: &dyn DatabaseAccess) -> Result<Option<MemberModel>> {
         // Not needed now. Checked in the "LoginRequired" Middleware
 
         // if AuthModel::find_by_token(self.token_secret(), db).await?.is_some() {
-        MemberModel::find_one_by_id(self.member_id(), db).await
+        let mut member = MemberModel::find_one_by_id(self.member_id(), db).await?;
+
+        // Tokens are limited by their scope. Only admin tokens can do what the owner can.
+        if let Some((member, scope)) = member.as_mut().zip(self.token_scope()) {
+            if member.permissions.is_owner() {
+                member.permissions.group = GroupPermissions::all();
+            }
+
+            member.permissions.group &= scope.permissions();
+        }
+
+        Ok(member)
         // } else {
         //     Ok(None)
         // }
@@ -105,11 +126,36 @@ impl FromRequest for MemberCookie {
         Pin<Box<dyn std::future::Future<Output = std::result::Result<MemberCookie, Self::Error>>>>;
 
     fn from_request(req: &HttpRequest, pl: &mut Payload) -> Self::Future {
+        // Verified by the "LoginRequired" Middleware.
+        if let Some(basic::BasicAuthMember(member_id)) = req.extensions().get().copied() {
+            return Box::pin(ready(Ok(MemberCookie(
+                CookieAuth {
+                    member_id,
+                    token_secret: String::new(),
+                    last_updated: 0,
+                    stored_since: 0,
+                },
+                None,
+            ))));
+        }
+
+        if let Some(token::TokenAuthMember { member_id, scope }) = req.extensions().get().copied() {
+            return Box::pin(ready(Ok(MemberCookie(
+                CookieAuth {
+                    member_id,
+                    token_secret: String::new(),
+                    last_updated: 0,
+                    stored_since: 0,
+                },
+                Some(scope),
+            ))));
+        }
+
         let fut = Identity::from_request(req, pl);
 
         Box::pin(async move {
             if let Ok(Some(id)) = get_auth_value(&fut.await?) {
-                Ok(MemberCookie(id))
+                Ok(MemberCookie(id, None))
             } else {
                 Err(WebError::ApiResponse(ApiErrorResponse::new("unauthorized")))
             }
@@ -204,7 +250,62 @@ where
                     }
                 }
 
-                Ok(None) => (),
+                Ok(None) => {
+                    let db = actix_web::web::Data::<Database>::from_request(&r, &mut pl).await?;
+
+                    if let Some(model) = token::find_token(&r, &db.basic())
+                        .await
+                        .map_err(WebError::from)?
+                    {
+                        drop(db);
+                        drop(identity);
+
+                        if !token::is_method_allowed(model.scope, r.method()) {
+                            return Ok(ServiceResponse::new(
+                                r,
+                                HttpResponse::Forbidden()
+                                    .json(ApiErrorResponse::new("Token is read only")),
+                            )
+                            .map_into_right_body::<B>());
+                        }
+
+                        r.extensions_mut().insert(token::TokenAuthMember {
+                            member_id: model.member_id,
+                            scope: model.scope,
+                        });
+
+                        return srv
+                            .call(ServiceRequest::from_parts(r, pl))
+                            .await
+                            .map(|res| res.map_into_left_body());
+                    }
+
+                    if let Some(member) = basic::find_member(&r, &db.basic())
+                        .await
+                        .map_err(WebError::from)?
+                    {
+                        drop(db);
+                        drop(identity);
+
+                        r.extensions_mut().insert(basic::BasicAuthMember(member.id));
+
+                        return srv
+                            .call(ServiceRequest::from_parts(r, pl))
+                            .await
+                            .map(|res| res.map_into_left_body());
+                    }
+
+                    // Lets e-readers know to ask for the credentials.
+                    if r.path().starts_with("/opds") {
+                        return Ok(ServiceResponse::new(
+                            r,
+                            HttpResponse::Unauthorized()
+                                .insert_header((header::WWW_AUTHENTICATE, basic::BASIC_AUTH_REALM))
+                                .finish(),
+                        )
+                        .map_into_right_body::<B>());
+                    }
+                }
 
                 Err(_) => {
                     // Logout the person if we've encountered an error.
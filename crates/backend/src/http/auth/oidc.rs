@@ -0,0 +1,653 @@
+// Log in through an OpenID Connect provider. Uses the Authorization Code Flow with PKCE.
+//
+// https://openid.net/specs/openid-connect-core-1_0.html
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_identity::Identity;
+use actix_web::{
+    cookie::{time, Cookie, SameSite},
+    http::header,
+    web, HttpMessage, HttpRequest, HttpResponse,
+};
+use chrono::Utc;
+use common::api::{ApiErrorResponse, WrappingResponse};
+use common_local::{
+    api::{ApiGetOidcProviderResponse, OidcProvider},
+    setup::ConfigOidc,
+    MemberAuthType, Permissions,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::{get_config, is_setup, save_config, update_config},
+    database::{Database, DatabaseAccess},
+    http::JsonResponse,
+    model::{
+        auth::AuthModel,
+        member::{MemberModel, NewMemberModel},
+    },
+    Error, Result, WebError, WebResult,
+};
+
+use super::gen_sample_alphanumeric;
+
+pub static OIDC_PATH: &str = "/auth/oidc";
+pub static OIDC_PATH_CB: &str = "/auth/oidc/callback";
+pub static OIDC_PROVIDER_PATH: &str = "/auth/oidc/provider";
+
+/// Holds the state of the login so the callback has to come from the same browser.
+static STATE_COOKIE_NAME: &str = "bookie-oidc-state";
+
+/// How long the provider has to redirect back to us.
+const PENDING_LOGIN_TIMEOUT: Duration = Duration::from_secs(60 * 10);
+
+lazy_static! {
+    /// Logins waiting on the provider's callback. Keyed by their state.
+    static ref PENDING_LOGINS: Mutex<HashMap<String, PendingLogin>> = Mutex::new(HashMap::new());
+}
+
+pub type Claims = Map<String, Value>;
+
+/// The parts of the discovery document we use.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: Option<String>,
+}
+
+pub struct PendingLogin {
+    pub metadata: ProviderMetadata,
+    pub redirect_uri: String,
+    pub code_verifier: String,
+    pub nonce: String,
+
+    pub created_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    id_token: Option<String>,
+}
+
+/// Used by the login page to show the button.
+pub async fn get_oidc_provider() -> WebResult<JsonResponse<ApiGetOidcProviderResponse>> {
+    Ok(web::Json(WrappingResponse::okay(
+        get_config().authenticators.oidc.map(|oidc| OidcProvider {
+            display_name: oidc.display_name,
+        }),
+    )))
+}
+
+pub async fn get_oidc_login(
+    req: HttpRequest,
+    identity: Option<Identity>,
+) -> WebResult<HttpResponse> {
+    if identity.is_some() || !is_setup() {
+        return Ok(HttpResponse::Found()
+            .append_header((header::LOCATION, "/"))
+            .finish());
+    }
+
+    let config = get_config();
+
+    let Some(oidc) = config.authenticators.oidc else {
+        return Err(ApiErrorResponse::new("OpenID Connect is not configured").into());
+    };
+
+    let Some(host) = req.headers().get("host").and_then(|v| v.to_str().ok()) else {
+        return Err(ApiErrorResponse::new("Unable to get host").into());
+    };
+
+    let proto = if config.server.is_secure {
+        "https"
+    } else {
+        "http"
+    };
+
+    let metadata = discover(&oidc.issuer).await?;
+
+    let (state, pending) = {
+        let mut rng = rand::thread_rng();
+
+        (
+            gen_sample_alphanumeric(32, &mut rng),
+            PendingLogin {
+                metadata,
+                redirect_uri: format!("{proto}://{host}{OIDC_PATH_CB}"),
+                code_verifier: gen_sample_alphanumeric(64, &mut rng),
+                nonce: gen_sample_alphanumeric(32, &mut rng),
+                created_at: Instant::now(),
+            },
+        )
+    };
+
+    let location = authorization_url(&oidc, &pending, &state)?;
+
+    // Lax so it's sent along with the provider's redirect back to us.
+    let cookie = Cookie::build(STATE_COOKIE_NAME, state.clone())
+        .path(OIDC_PATH_CB)
+        .http_only(true)
+        .secure(config.server.is_secure)
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::seconds(
+            PENDING_LOGIN_TIMEOUT.as_secs() as i64
+        ))
+        .finish();
+
+    {
+        let mut pending_logins = PENDING_LOGINS.lock().unwrap();
+
+        pending_logins.retain(|_, v| v.created_at.elapsed() < PENDING_LOGIN_TIMEOUT);
+        pending_logins.insert(state, pending);
+    }
+
+    Ok(HttpResponse::Found()
+        .append_header((header::LOCATION, location))
+        .cookie(cookie)
+        .finish())
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct QueryCallback {
+    pub state: String,
+    pub code: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+pub async fn get_oidc_callback(
+    request: HttpRequest,
+    query: web::Query<QueryCallback>,
+    identity: Option<Identity>,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    if identity.is_some() {
+        return Ok(HttpResponse::Found()
+            .append_header((header::LOCATION, "/"))
+            .finish());
+    }
+
+    let QueryCallback {
+        state,
+        code,
+        error,
+        error_description,
+    } = query.into_inner();
+
+    // The login has to have been started by this browser.
+    if request
+        .cookie(STATE_COOKIE_NAME)
+        .map_or(true, |v| v.value() != state)
+    {
+        return Err(ApiErrorResponse::new("Login expired. Please try again.").into());
+    }
+
+    // The state can only be used once.
+    let Some(pending) = PENDING_LOGINS
+        .lock()
+        .unwrap()
+        .remove(&state)
+        .filter(|v| v.created_at.elapsed() < PENDING_LOGIN_TIMEOUT)
+    else {
+        return Err(ApiErrorResponse::new("Login expired. Please try again.").into());
+    };
+
+    if let Some(error) = error {
+        tracing::error!(
+            "OpenID Connect Provider Error: {error} {}",
+            error_description.unwrap_or_default()
+        );
+
+        return Err(ApiErrorResponse::new("The provider declined the login").into());
+    }
+
+    let (Some(code), Some(oidc)) = (code, get_config().authenticators.oidc) else {
+        return Err(ApiErrorResponse::new("Missing authorization code").into());
+    };
+
+    let claims = exchange_code(&oidc, &pending, &code).await?;
+
+    let member = find_or_create_member(&oidc, &claims, &db.basic()).await?;
+
+    let model = AuthModel::new(Some(member.id));
+
+    model.insert(&db.basic()).await?;
+
+    super::remember_member_auth(&request.extensions(), member.id, model.oauth_token_secret)?;
+
+    let mut cookie = Cookie::named(STATE_COOKIE_NAME);
+    cookie.set_path(OIDC_PATH_CB);
+    cookie.make_removal();
+
+    Ok(HttpResponse::Found()
+        .append_header((header::LOCATION, "/"))
+        .cookie(cookie)
+        .finish())
+}
+
+pub async fn discover(issuer: &str) -> Result<ProviderMetadata> {
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        issuer.trim_end_matches('/')
+    );
+
+    Ok(reqwest::get(url).await?.error_for_status()?.json().await?)
+}
+
+pub fn authorization_url(oidc: &ConfigOidc, pending: &PendingLogin, state: &str) -> Result<String> {
+    let endpoint = &pending.metadata.authorization_endpoint;
+
+    let query = serde_urlencoded::to_string([
+        ("response_type", "code"),
+        ("client_id", oidc.client_id.as_str()),
+        ("redirect_uri", pending.redirect_uri.as_str()),
+        ("scope", oidc.scopes.join(" ").as_str()),
+        ("state", state),
+        ("nonce", pending.nonce.as_str()),
+        (
+            "code_challenge",
+            code_challenge(&pending.code_verifier).as_str(),
+        ),
+        ("code_challenge_method", "S256"),
+    ])?;
+
+    let separator = if endpoint.contains('?') { '&' } else { '?' };
+
+    Ok(format!("{endpoint}{separator}{query}"))
+}
+
+/// PKCE S256 challenge. (RFC 7636)
+pub fn code_challenge(code_verifier: &str) -> String {
+    base64::encode_config(
+        Sha256::digest(code_verifier.as_bytes()),
+        base64::URL_SAFE_NO_PAD,
+    )
+}
+
+/// Exchanges the authorization code and returns the claims of the ID Token and UserInfo.
+pub async fn exchange_code(
+    oidc: &ConfigOidc,
+    pending: &PendingLogin,
+    code: &str,
+) -> WebResult<Claims> {
+    let client = reqwest::Client::new();
+
+    let mut req = client.post(&pending.metadata.token_endpoint).form(&[
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", pending.redirect_uri.as_str()),
+        ("client_id", oidc.client_id.as_str()),
+        ("code_verifier", pending.code_verifier.as_str()),
+    ]);
+
+    if let Some(secret) = oidc.client_secret.as_deref() {
+        req = req.basic_auth(&oidc.client_id, Some(secret));
+    }
+
+    let resp = req.send().await.map_err(Error::from)?;
+
+    if !resp.status().is_success() {
+        tracing::error!(
+            "OpenID Connect Token Error: {}",
+            resp.text().await.unwrap_or_default()
+        );
+
+        return Err(ApiErrorResponse::new("Unable to exchange the authorization code").into());
+    }
+
+    let token: TokenResponse = resp.json().await.map_err(Error::from)?;
+
+    let Some(id_token) = token.id_token else {
+        return Err(ApiErrorResponse::new("The provider didn't return an ID Token").into());
+    };
+
+    let mut claims = verify_id_token(&id_token, oidc, pending)?;
+
+    if let Some(endpoint) = pending.metadata.userinfo_endpoint.as_deref() {
+        let userinfo: Claims = client
+            .get(endpoint)
+            .bearer_auth(&token.access_token)
+            .send()
+            .await
+            .and_then(|v| v.error_for_status())
+            .map_err(Error::from)?
+            .json()
+            .await
+            .map_err(Error::from)?;
+
+        if userinfo.get("sub") != claims.get("sub") {
+            return Err(ApiErrorResponse::new("UserInfo is for a different subject").into());
+        }
+
+        claims.extend(userinfo);
+    }
+
+    Ok(claims)
+}
+
+/// The ID Token is received directly from the token endpoint so its signature isn't checked. (OIDC Core 3.1.3.7)
+pub fn verify_id_token(
+    id_token: &str,
+    oidc: &ConfigOidc,
+    pending: &PendingLogin,
+) -> WebResult<Claims> {
+    let invalid = || WebError::from(ApiErrorResponse::new("Invalid ID Token"));
+
+    let payload = id_token.split('.').nth(1).ok_or_else(invalid)?;
+    let payload = base64::decode_config(payload, base64::URL_SAFE_NO_PAD).map_err(|_| invalid())?;
+
+    let claims: Claims = serde_json::from_slice(&payload).map_err(Error::from)?;
+
+    let is_issuer = claims
+        .get("iss")
+        .and_then(Value::as_str)
+        .map_or(false, |v| {
+            v == pending.metadata.issuer
+                && v.trim_end_matches('/') == oidc.issuer.trim_end_matches('/')
+        });
+
+    let is_audience = match claims.get("aud") {
+        Some(Value::String(v)) => *v == oidc.client_id,
+        Some(Value::Array(v)) => v
+            .iter()
+            .any(|v| v.as_str() == Some(oidc.client_id.as_str())),
+        _ => false,
+    };
+
+    let is_nonce = claims.get("nonce").and_then(Value::as_str) == Some(pending.nonce.as_str());
+
+    let is_expired = claims
+        .get("exp")
+        .and_then(Value::as_i64)
+        .map_or(true, |v| v <= Utc::now().timestamp());
+
+    if is_issuer && is_audience && is_nonce && !is_expired {
+        Ok(claims)
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Returns (Email, Name) using the configured claims.
+pub fn member_details(oidc: &ConfigOidc, claims: &Claims) -> WebResult<(String, String)> {
+    let claim = |name: &str| {
+        claims
+            .get(name)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+
+    let Some(email) = claim(&oidc.claims.email) else {
+        return Err(ApiErrorResponse::new("The provider didn't return an email").into());
+    };
+
+    // Otherwise anyone could sign in as an existing member by using their email.
+    if claims.get("email_verified").and_then(Value::as_bool) != Some(true) {
+        return Err(ApiErrorResponse::new("The provider hasn't verified the email").into());
+    }
+
+    let name = claim(&oidc.claims.name)
+        .or_else(|| email.split_once('@').map(|v| v.0))
+        .unwrap_or(email);
+
+    Ok((email.to_string(), name.to_string()))
+}
+
+/// Members are created on their first login.
+async fn find_or_create_member(
+    oidc: &ConfigOidc,
+    claims: &Claims,
+    db: &dyn DatabaseAccess,
+) -> WebResult<MemberModel> {
+    let (email, name) = member_details(oidc, claims)?;
+
+    if let Some(mut member) = MemberModel::find_one_by_email(&email, db).await? {
+        match member.type_of {
+            // If we were invited update the invite with correct info.
+            MemberAuthType::Invite => {
+                member
+                    .accept_invite(MemberAuthType::External, None, db)
+                    .await?;
+            }
+
+            MemberAuthType::External => (),
+
+            // Otherwise anyone with a provider account using the same email could take it over.
+            MemberAuthType::Passwordless | MemberAuthType::Password => {
+                return Err(ApiErrorResponse::new(
+                    "An account with this email already exists. Log in with it instead.",
+                )
+                .into());
+            }
+        }
+
+        return Ok(member);
+    }
+
+    let has_admin_account = get_config().has_admin_account;
+
+    // The first member is the owner. Same as the other authenticators.
+    let permissions = if !has_admin_account && MemberModel::count(db).await? == 0 {
+        Permissions::owner()
+    } else {
+        oidc.default_permissions
+    };
+
+    let new_member = NewMemberModel {
+        name,
+        email,
+        type_of: MemberAuthType::External,
+        permissions,
+        preferences: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    let member = new_member.insert(db).await?;
+
+    if !has_admin_account {
+        update_config(|config| {
+            config.has_admin_account = true;
+            Ok(())
+        })?;
+
+        save_config().await?;
+    }
+
+    Ok(member)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{App, HttpServer};
+    use serde_json::json;
+
+    use super::*;
+
+    const CLIENT_ID: &str = "bookie";
+    const CODE: &str = "mock-code";
+
+    struct MockIssuer {
+        issuer: String,
+        /// The PKCE challenge from the authorization request.
+        code_challenge: Mutex<String>,
+        nonce: Mutex<String>,
+    }
+
+    async fn mock_discovery(issuer: web::Data<MockIssuer>) -> HttpResponse {
+        HttpResponse::Ok().json(json!({
+            "issuer": issuer.issuer,
+            "authorization_endpoint": format!("{}/authorize", issuer.issuer),
+            "token_endpoint": format!("{}/token", issuer.issuer),
+            "userinfo_endpoint": format!("{}/userinfo", issuer.issuer),
+            "response_types_supported": ["code"],
+        }))
+    }
+
+    async fn mock_token(
+        form: web::Form<HashMap<String, String>>,
+        issuer: web::Data<MockIssuer>,
+    ) -> HttpResponse {
+        let param = |name: &str| form.get(name).map(String::as_str).unwrap_or_default();
+
+        if param("code") != CODE
+            || code_challenge(param("code_verifier")) != *issuer.code_challenge.lock().unwrap()
+        {
+            return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }));
+        }
+
+        let encode =
+            |value: Value| base64::encode_config(value.to_string(), base64::URL_SAFE_NO_PAD);
+
+        let id_token = format!(
+            "{}.{}.",
+            encode(json!({ "alg": "none" })),
+            encode(json!({
+                "iss": issuer.issuer,
+                "sub": "1234",
+                "aud": CLIENT_ID,
+                "exp": Utc::now().timestamp() + 60,
+                "iat": Utc::now().timestamp(),
+                "nonce": *issuer.nonce.lock().unwrap(),
+                "email": "reader@example.com",
+            }))
+        );
+
+        HttpResponse::Ok().json(json!({
+            "access_token": "mock-access-token",
+            "token_type": "Bearer",
+            "id_token": id_token,
+        }))
+    }
+
+    async fn mock_userinfo(req: HttpRequest) -> HttpResponse {
+        let authorization = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok());
+
+        if authorization != Some("Bearer mock-access-token") {
+            return HttpResponse::Unauthorized().finish();
+        }
+
+        HttpResponse::Ok().json(json!({
+            "sub": "1234",
+            "email_verified": true,
+            "preferred_username": "Avid Reader",
+        }))
+    }
+
+    #[actix_web::test]
+    async fn oidc_mock_issuer_login() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let mock = web::Data::new(MockIssuer {
+            issuer: format!("http://{}", listener.local_addr().unwrap()),
+            code_challenge: Mutex::default(),
+            nonce: Mutex::default(),
+        });
+
+        let server = {
+            let mock = mock.clone();
+
+            HttpServer::new(move || {
+                App::new()
+                    .app_data(mock.clone())
+                    .route(
+                        "/.well-known/openid-configuration",
+                        web::get().to(mock_discovery),
+                    )
+                    .route("/token", web::post().to(mock_token))
+                    .route("/userinfo", web::get().to(mock_userinfo))
+            })
+            .listen(listener)
+            .unwrap()
+            .run()
+        };
+
+        actix_web::rt::spawn(server);
+
+        let oidc = ConfigOidc {
+            display_name: String::from("Mock"),
+            issuer: format!("{}/", mock.issuer),
+            client_id: String::from(CLIENT_ID),
+            client_secret: Some(String::from("secret")),
+            scopes: vec![String::from("openid"), String::from("email")],
+            claims: common_local::setup::ConfigOidcClaims {
+                email: String::from("email"),
+                name: String::from("preferred_username"),
+            },
+            default_permissions: Permissions::basic(),
+        };
+
+        let pending = PendingLogin {
+            metadata: discover(&oidc.issuer).await.unwrap(),
+            redirect_uri: String::from("http://localhost/auth/oidc/callback"),
+            code_verifier: gen_sample_alphanumeric(64, &mut rand::thread_rng()),
+            nonce: String::from("mock-nonce"),
+            created_at: Instant::now(),
+        };
+
+        let location = authorization_url(&oidc, &pending, "mock-state").unwrap();
+        let (endpoint, query) = location.split_once('?').unwrap();
+        let query: HashMap<String, String> = serde_urlencoded::from_str(query).unwrap();
+
+        assert_eq!(endpoint, format!("{}/authorize", mock.issuer));
+        assert_eq!(query["state"], "mock-state");
+        assert_eq!(query["scope"], "openid email");
+        assert_eq!(query["code_challenge_method"], "S256");
+
+        *mock.code_challenge.lock().unwrap() = query["code_challenge"].clone();
+        *mock.nonce.lock().unwrap() = query["nonce"].clone();
+
+        let claims = exchange_code(&oidc, &pending, CODE).await.unwrap();
+
+        let (email, name) = member_details(&oidc, &claims).unwrap();
+
+        assert_eq!(email, "reader@example.com");
+        assert_eq!(name, "Avid Reader");
+
+        // Unverified emails can't be used.
+        let mut unverified = claims.clone();
+        unverified.remove("email_verified");
+
+        assert!(member_details(&oidc, &unverified).is_err());
+
+        // Wrong verifier
+        let other = PendingLogin {
+            metadata: pending.metadata.clone(),
+            redirect_uri: pending.redirect_uri.clone(),
+            code_verifier: String::from("wrong"),
+            nonce: pending.nonce.clone(),
+            created_at: Instant::now(),
+        };
+
+        assert!(exchange_code(&oidc, &other, CODE).await.is_err());
+
+        // Replayed ID Token for another login.
+        *mock.nonce.lock().unwrap() = String::from("other-nonce");
+
+        assert!(exchange_code(&oidc, &pending, CODE).await.is_err());
+    }
+
+    #[test]
+    fn pkce_code_challenge() {
+        // RFC 7636 Appendix B
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+}
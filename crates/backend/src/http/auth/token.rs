@@ -0,0 +1,88 @@
+// Personal access tokens. Sent as "Authorization: Bearer {token}" by scripts and other clients.
+
+use actix_web::{
+    http::{header, Method},
+    HttpRequest,
+};
+use chrono::Utc;
+use common::MemberId;
+use common_local::ApiTokenScope;
+use sha2::{Digest, Sha256};
+
+use crate::{database::DatabaseAccess, model::api_token::ApiTokenModel, Result};
+
+use super::gen_sample_alphanumeric;
+
+/// Makes the tokens easy to recognize. (eg. in secret scanners)
+pub static TOKEN_PREFIX: &str = "bk_";
+
+/// How much of the token is stored to tell them apart.
+const DISPLAYED_PREFIX_LENGTH: usize = 8;
+
+/// Stored in the request extensions once the token was verified.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenAuthMember {
+    pub member_id: MemberId,
+    pub scope: ApiTokenScope,
+}
+
+/// Returns (Token, Displayed Prefix, Hash)
+pub fn generate_token() -> (String, String, String) {
+    let token = format!(
+        "{TOKEN_PREFIX}{}",
+        gen_sample_alphanumeric(40, &mut rand::thread_rng())
+    );
+
+    let prefix = token[..DISPLAYED_PREFIX_LENGTH].to_string();
+    let hash = hash_token(&token);
+
+    (token, prefix, hash)
+}
+
+pub fn hash_token(value: &str) -> String {
+    format!("{:x}", Sha256::digest(value.as_bytes()))
+}
+
+/// Finds the unexpired token in the Authorization header and marks it as used.
+pub async fn find_token(
+    req: &HttpRequest,
+    db: &dyn DatabaseAccess,
+) -> Result<Option<ApiTokenModel>> {
+    let Some(value) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return Ok(None);
+    };
+
+    let Some((scheme, token)) = value.split_once(' ') else {
+        return Ok(None);
+    };
+
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return Ok(None);
+    }
+
+    let Some(mut model) =
+        ApiTokenModel::find_one_by_token_hash(&hash_token(token.trim()), db).await?
+    else {
+        return Ok(None);
+    };
+
+    let now = Utc::now();
+
+    ApiTokenModel::update_last_used_at(model.id, now, db).await?;
+
+    model.last_used_at = Some(now);
+
+    Ok(Some(model))
+}
+
+/// Read only tokens can't change anything.
+pub fn is_method_allowed(scope: ApiTokenScope, method: &Method) -> bool {
+    match scope {
+        ApiTokenScope::Read => matches!(*method, Method::GET | Method::HEAD),
+        ApiTokenScope::ReadWrite | ApiTokenScope::Admin => true,
+    }
+}
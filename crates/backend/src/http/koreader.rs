@@ -0,0 +1,318 @@
+// KOReader's progress sync server. Set "{host}/koreader" as the custom sync server in KOReader.
+//
+// https://github.com/koreader/koreader-sync-server
+
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse, Scope};
+use chrono::Utc;
+use common_local::Progression;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    config::get_config,
+    database::{Database, DatabaseAccess},
+    model::{
+        file::FileModel,
+        koreader::{KoreaderKeyModel, KoreaderProgressModel},
+        member::MemberModel,
+        member_library::LibraryAccess,
+        progress::FileProgressionModel,
+    },
+    Result, WebResult,
+};
+
+/// Returned as the device when the web reader has the latest position.
+const WEB_READER_DEVICE_ID: &str = "web-reader";
+
+/// Opened by KOReader's reflowable engine. Their progress is a XPointer instead of a page number.
+const REFLOWABLE_FILE_TYPES: [&str; 5] = ["epub", "mobi", "azw", "azw3", "prc"];
+
+pub fn koreader_route() -> Scope {
+    web::scope("/koreader")
+        .route("/users/create", web::post().to(create_user))
+        .route("/users/auth", web::get().to(authorize))
+        .route("/syncs/progress", web::put().to(update_progress))
+        .route("/syncs/progress/{document}", web::get().to(get_progress))
+}
+
+#[derive(Deserialize)]
+struct ProgressBody {
+    document: Option<String>,
+    progress: Option<String>,
+    percentage: Option<f64>,
+    device: Option<String>,
+    device_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ProgressResponse {
+    document: String,
+    progress: String,
+    percentage: f64,
+    device: String,
+    device_id: Option<String>,
+    timestamp: i64,
+}
+
+impl From<KoreaderProgressModel> for ProgressResponse {
+    fn from(value: KoreaderProgressModel) -> Self {
+        Self {
+            document: value.document,
+            progress: value.progress,
+            percentage: value.percentage,
+            device: value.device,
+            device_id: value.device_id,
+            timestamp: value.updated_at.timestamp(),
+        }
+    }
+}
+
+/// Members are created through the frontend. They set a sync password in their settings instead.
+async fn create_user() -> HttpResponse {
+    error_response(
+        StatusCode::PAYMENT_REQUIRED,
+        2005,
+        "User registration is disabled. Set a KOReader sync password in your member settings.",
+    )
+}
+
+async fn authorize(req: HttpRequest, db: web::Data<Database>) -> WebResult<HttpResponse> {
+    if authenticate(&req, &db.basic()).await?.is_none() {
+        return Ok(unauthorized());
+    }
+
+    Ok(HttpResponse::Ok().json(json!({ "authorized": "OK" })))
+}
+
+async fn get_progress(
+    document: web::Path<String>,
+    req: HttpRequest,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let Some(member) = authenticate(&req, &db.basic()).await? else {
+        return Ok(unauthorized());
+    };
+
+    let document = document.into_inner();
+
+    let stored = KoreaderProgressModel::find_one(member.id, &document, &db.basic()).await?;
+
+    // The web reader's position is returned if it was saved after KOReader's.
+    if let Some(file) = find_visible_file(&document, &member, &db.basic()).await? {
+        if let Some(book_id) = file.book_id {
+            let progression =
+                FileProgressionModel::find_one_by_book_id(member.id, book_id, &db.basic())
+                    .await?
+                    .filter(|v| {
+                        v.file_id == file.id
+                            && stored
+                                .as_ref()
+                                .map_or(true, |stored| v.updated_at > stored.updated_at)
+                    });
+
+            if let Some(progression) = progression {
+                let timestamp = progression.updated_at.timestamp();
+
+                if let Some((progress, percentage)) = from_progression(progression.into(), &file) {
+                    return Ok(HttpResponse::Ok().json(ProgressResponse {
+                        document,
+                        progress,
+                        percentage,
+                        device: get_config().server.name,
+                        device_id: Some(WEB_READER_DEVICE_ID.to_string()),
+                        timestamp,
+                    }));
+                }
+            }
+        }
+    }
+
+    Ok(match stored {
+        Some(stored) => HttpResponse::Ok().json(ProgressResponse::from(stored)),
+        None => HttpResponse::Ok().json(json!({})),
+    })
+}
+
+async fn update_progress(
+    body: web::Json<ProgressBody>,
+    req: HttpRequest,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let Some(member) = authenticate(&req, &db.basic()).await? else {
+        return Ok(unauthorized());
+    };
+
+    let body = body.into_inner();
+
+    let Some(document) = body.document.filter(|v| !v.is_empty()) else {
+        return Ok(error_response(
+            StatusCode::FORBIDDEN,
+            2004,
+            "Field 'document' not provided.",
+        ));
+    };
+
+    let (Some(progress), Some(percentage), Some(device)) =
+        (body.progress, body.percentage, body.device)
+    else {
+        return Ok(error_response(
+            StatusCode::FORBIDDEN,
+            2003,
+            "Invalid request",
+        ));
+    };
+
+    let file = find_visible_file(&document, &member, &db.basic()).await?;
+
+    if let Some(file) = file.as_ref() {
+        if let Some((book_id, progression)) =
+            file.book_id.zip(to_progression(&progress, percentage))
+        {
+            FileProgressionModel::insert_or_update(
+                member.id,
+                book_id,
+                file.id,
+                progression,
+                &db.basic(),
+            )
+            .await?;
+        }
+    }
+
+    // Saved after the web reader's position so it isn't seen as the newer one.
+    let model = KoreaderProgressModel {
+        member_id: member.id,
+        document,
+        file_id: file.map(|v| v.id),
+        progress,
+        percentage,
+        device,
+        device_id: body.device_id,
+        updated_at: Utc::now(),
+    };
+
+    model.insert_or_replace(&db.basic()).await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "document": model.document,
+        "timestamp": model.updated_at.timestamp(),
+    })))
+}
+
+/// KOReader sends the username and the MD5 of the password in headers.
+async fn authenticate(req: &HttpRequest, db: &dyn DatabaseAccess) -> Result<Option<MemberModel>> {
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+
+    let (Some(username), Some(key)) = (header("x-auth-user"), header("x-auth-key")) else {
+        return Ok(None);
+    };
+
+    let Some(member) = MemberModel::find_one_by_email(username.trim(), db).await? else {
+        return Ok(None);
+    };
+
+    let Some(stored) = KoreaderKeyModel::find_one_by_member_id(member.id, db).await? else {
+        return Ok(None);
+    };
+
+    if super::auth::basic::verify_cached(key.to_lowercase(), stored.key_hash).await? {
+        Ok(Some(member))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Files in libraries the member can't see are treated as unknown documents.
+async fn find_visible_file(
+    document: &str,
+    member: &MemberModel,
+    db: &dyn DatabaseAccess,
+) -> Result<Option<FileModel>> {
+    let Some(file) = FileModel::find_one_by_partial_md5(document, db).await? else {
+        return Ok(None);
+    };
+
+    if LibraryAccess::for_member(member, db)
+        .await?
+        .contains(file.library_id)
+    {
+        Ok(Some(file))
+    } else {
+        Ok(None)
+    }
+}
+
+/// KOReader's position as the chapter the web reader uses.
+fn to_progression(progress: &str, percentage: f64) -> Option<Progression> {
+    if percentage >= 1.0 {
+        return Some(Progression::Complete);
+    }
+
+    // Reflowable: "/body/DocFragment[12]/body/div/p[3]/text().0" Paged: "12"
+    let position = match progress.strip_prefix("/body/DocFragment[") {
+        Some(value) => value.split_once(']')?.0,
+        None => progress.trim(),
+    };
+
+    Some(Progression::Ebook {
+        chapter: (position.parse::<i64>().ok()? - 1).max(0),
+        page: 0,
+        char_pos: -1,
+    })
+}
+
+/// The web reader's position as KOReader's progress and percentage. Only accurate to the chapter.
+fn from_progression(progression: Progression, file: &FileModel) -> Option<(String, f64)> {
+    let chapter_count = file.chapter_count.max(1);
+
+    let (chapter, percentage) = match progression {
+        Progression::Ebook { chapter, .. } => (chapter, chapter as f64 / chapter_count as f64),
+        Progression::Complete => (chapter_count - 1, 1.0),
+        Progression::AudioBook { .. } => return None,
+    };
+
+    let progress = if REFLOWABLE_FILE_TYPES.contains(&file.file_type.as_str()) {
+        format!("/body/DocFragment[{}]/body", chapter + 1)
+    } else {
+        (chapter + 1).to_string()
+    };
+
+    Some((progress, percentage))
+}
+
+fn unauthorized() -> HttpResponse {
+    error_response(StatusCode::UNAUTHORIZED, 2001, "Unauthorized")
+}
+
+fn error_response(status: StatusCode, code: u16, message: &str) -> HttpResponse {
+    HttpResponse::build(status).json(json!({ "code": code, "message": message }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn koreader_progress_to_chapter() {
+        assert_eq!(
+            to_progression("/body/DocFragment[12]/body/div/p[3]/text().0", 0.4),
+            Some(Progression::Ebook {
+                chapter: 11,
+                page: 0,
+                char_pos: -1,
+            })
+        );
+
+        assert_eq!(
+            to_progression("7", 0.1),
+            Some(Progression::Ebook {
+                chapter: 6,
+                page: 0,
+                char_pos: -1,
+            })
+        );
+
+        assert_eq!(to_progression("7", 1.0), Some(Progression::Complete));
+        assert_eq!(to_progression("/body/p[2]", 0.2), None);
+    }
+}
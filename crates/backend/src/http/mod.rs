@@ -16,10 +16,12 @@ use crate::CliArgs;
 
 mod api;
 mod auth;
+mod koreader;
+mod opds;
 mod ws;
 pub use self::api::api_route;
 pub use self::auth::*;
-pub use ws::send_message_to_clients;
+pub use ws::{send_message_to_clients, RUNNING_TASKS};
 
 pub type JsonResponse<V> = web::Json<WrappingResponse<V>>;
 
@@ -78,6 +80,10 @@ pub async fn register_http_service(
             )
             // API
             .service(api_route())
+            // OPDS
+            .service(opds::opds_route())
+            // KOReader
+            .service(koreader::koreader_route())
             // WS
             .service(ws::ws_index)
             .route("/auth/logout", web::get().to(logout))
@@ -96,6 +102,10 @@ pub async fn register_http_service(
                 passwordless::PASSWORDLESS_PATH_CB,
                 web::get().to(passwordless::get_passwordless_oauth_callback),
             )
+            // OpenID Connect
+            .route(oidc::OIDC_PATH, web::get().to(oidc::get_oidc_login))
+            .route(oidc::OIDC_PATH_CB, web::get().to(oidc::get_oidc_callback))
+            .route(oidc::OIDC_PROVIDER_PATH, web::get().to(oidc::get_oidc_provider))
             // Other
             .service(actix_files::Files::new("/js", "./app/public/js"))
             .service(actix_files::Files::new("/css", "./app/public/css"))
@@ -0,0 +1,495 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, Utc};
+use common::BookId;
+use common_local::{BookIdentifier, FileId, IdentifierType};
+use serde_json::{json, Value};
+
+pub const ATOM_NAVIGATION_TYPE: &str = "application/atom+xml;profile=opds-catalog;kind=navigation";
+pub const ATOM_ACQUISITION_TYPE: &str =
+    "application/atom+xml;profile=opds-catalog;kind=acquisition";
+pub const OPENSEARCH_TYPE: &str = "application/opensearchdescription+xml";
+pub const JSON_TYPE: &str = "application/opds+json";
+
+/// Which OPDS version the feed is returned as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    /// OPDS 1.2
+    Atom,
+    /// OPDS 2.0
+    Json,
+}
+
+impl FeedFormat {
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Atom => "/opds",
+            Self::Json => "/opds/v2",
+        }
+    }
+
+    pub fn content_type(self, kind: FeedKind) -> &'static str {
+        match (self, kind) {
+            (Self::Atom, FeedKind::Navigation) => ATOM_NAVIGATION_TYPE,
+            (Self::Atom, FeedKind::Acquisition) => ATOM_ACQUISITION_TYPE,
+            (Self::Json, _) => JSON_TYPE,
+        }
+    }
+
+    pub fn render(self, feed: &Feed) -> String {
+        match self {
+            Self::Atom => feed.to_atom(),
+            Self::Json => feed.to_json().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedKind {
+    /// Links to other feeds.
+    Navigation,
+    /// Lists books.
+    Acquisition,
+}
+
+pub struct Feed {
+    pub format: FeedFormat,
+    pub kind: FeedKind,
+
+    /// Path of the feed without the prefix. (eg. "/authors")
+    pub path: String,
+    pub title: String,
+    pub updated: DateTime<Utc>,
+
+    pub navigation: Vec<NavigationEntry>,
+    pub publications: Vec<Publication>,
+
+    pub pagination: Option<Pagination>,
+}
+
+pub struct NavigationEntry {
+    /// Path of the linked feed without the prefix.
+    pub path: String,
+    pub title: String,
+    pub kind: FeedKind,
+    pub description: Option<String>,
+}
+
+pub struct Pagination {
+    pub offset: usize,
+    pub limit: usize,
+    pub total: usize,
+}
+
+pub struct Publication {
+    pub id: BookId,
+    pub title: String,
+    pub description: Option<String>,
+    pub authors: Vec<String>,
+    pub publisher: Option<String>,
+    pub language: Option<String>,
+    pub year: Option<i64>,
+    pub identifiers: Vec<BookIdentifier>,
+    pub subjects: Vec<String>,
+    /// (Name, Position)
+    pub series: Option<(String, Option<f64>)>,
+    pub cover: Option<String>,
+    pub updated: DateTime<Utc>,
+    pub files: Vec<PublicationFile>,
+}
+
+pub struct PublicationFile {
+    pub id: FileId,
+    pub file_type: String,
+    pub file_size: i64,
+}
+
+impl PublicationFile {
+    pub fn href(&self) -> String {
+        format!("/api/file/{}/download", self.id)
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self.file_type.as_str() {
+            "epub" => "application/epub+zip",
+            "pdf" => "application/pdf",
+            "mobi" | "azw" | "prc" => "application/x-mobipocket-ebook",
+            "azw3" => "application/x-mobi8-ebook",
+            "cbz" => "application/vnd.comicbook+zip",
+            "cbr" => "application/vnd.comicbook-rar",
+            "cb7" => "application/x-cb7",
+            "cbt" => "application/x-cbt",
+            _ => "application/octet-stream",
+        }
+    }
+}
+
+impl Feed {
+    fn href(&self, path: &str) -> String {
+        format!("{}{path}", self.format.prefix())
+    }
+
+    /// The same feed at a different offset.
+    fn page_href(&self, offset: usize) -> String {
+        let separator = if self.path.contains('?') { '&' } else { '?' };
+
+        format!("{}{separator}offset={offset}", self.href(&self.path))
+    }
+
+    /// (Previous Offset, Next Offset)
+    fn page_offsets(&self) -> (Option<usize>, Option<usize>) {
+        let Some(page) = self.pagination.as_ref() else {
+            return (None, None);
+        };
+
+        (
+            (page.offset != 0).then(|| page.offset.saturating_sub(page.limit)),
+            (page.offset + page.limit < page.total).then_some(page.offset + page.limit),
+        )
+    }
+
+    pub fn to_atom(&self) -> String {
+        let self_type = self.format.content_type(self.kind);
+
+        let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+
+        xml += r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/terms/" xmlns:opds="http://opds-spec.org/2010/catalog" xmlns:opensearch="http://a9.com/-/spec/opensearch/1.1/">"#;
+
+        let _ = write!(
+            xml,
+            "<id>urn:books:opds:{}</id><title>{}</title><updated>{}</updated>",
+            escape(&self.path),
+            escape(&self.title),
+            self.updated.to_rfc3339(),
+        );
+
+        write_atom_link(&mut xml, "self", &self.href(&self.path), self_type, None);
+        write_atom_link(
+            &mut xml,
+            "start",
+            &self.href(""),
+            ATOM_NAVIGATION_TYPE,
+            None,
+        );
+        write_atom_link(
+            &mut xml,
+            "search",
+            &self.href("/search.xml"),
+            OPENSEARCH_TYPE,
+            None,
+        );
+
+        let (previous, next) = self.page_offsets();
+
+        if let Some(offset) = previous {
+            write_atom_link(
+                &mut xml,
+                "previous",
+                &self.page_href(offset),
+                self_type,
+                None,
+            );
+        }
+
+        if let Some(offset) = next {
+            write_atom_link(&mut xml, "next", &self.page_href(offset), self_type, None);
+        }
+
+        if let Some(page) = self.pagination.as_ref() {
+            let _ = write!(
+                xml,
+                "<opensearch:totalResults>{}</opensearch:totalResults><opensearch:itemsPerPage>{}</opensearch:itemsPerPage><opensearch:startIndex>{}</opensearch:startIndex>",
+                page.total,
+                page.limit,
+                page.offset + 1,
+            );
+        }
+
+        for entry in &self.navigation {
+            let _ = write!(
+                xml,
+                "<entry><title>{}</title><id>urn:books:opds:{}</id><updated>{}</updated>",
+                escape(&entry.title),
+                escape(&entry.path),
+                self.updated.to_rfc3339(),
+            );
+
+            if let Some(description) = entry.description.as_deref() {
+                let _ = write!(
+                    xml,
+                    r#"<content type="text">{}</content>"#,
+                    escape(description)
+                );
+            }
+
+            write_atom_link(
+                &mut xml,
+                "subsection",
+                &self.href(&entry.path),
+                self.format.content_type(entry.kind),
+                None,
+            );
+
+            xml += "</entry>";
+        }
+
+        for book in &self.publications {
+            let _ = write!(
+                xml,
+                "<entry><title>{}</title><id>urn:books:book:{}</id><updated>{}</updated>",
+                escape(&book.title),
+                book.id,
+                book.updated.to_rfc3339(),
+            );
+
+            for name in &book.authors {
+                let _ = write!(xml, "<author><name>{}</name></author>", escape(name));
+            }
+
+            if let Some(value) = book.publisher.as_deref() {
+                let _ = write!(xml, "<dc:publisher>{}</dc:publisher>", escape(value));
+            }
+
+            if let Some(value) = book.language.as_deref() {
+                let _ = write!(xml, "<dc:language>{}</dc:language>", escape(value));
+            }
+
+            if let Some(year) = book.year {
+                let _ = write!(xml, "<dc:issued>{year}</dc:issued>");
+            }
+
+            for identifier in &book.identifiers {
+                let _ = write!(
+                    xml,
+                    "<dc:identifier>{}</dc:identifier>",
+                    escape(&identifier_urn(identifier))
+                );
+            }
+
+            for subject in &book.subjects {
+                let _ = write!(
+                    xml,
+                    r#"<category term="{0}" label="{0}"/>"#,
+                    escape(subject)
+                );
+            }
+
+            if let Some(description) = book.description.as_deref() {
+                let _ = write!(
+                    xml,
+                    r#"<summary type="text">{}</summary>"#,
+                    escape(description)
+                );
+            }
+
+            if let Some(cover) = book.cover.as_deref() {
+                write_atom_link(
+                    &mut xml,
+                    "http://opds-spec.org/image",
+                    cover,
+                    "image/jpeg",
+                    None,
+                );
+                write_atom_link(
+                    &mut xml,
+                    "http://opds-spec.org/image/thumbnail",
+                    cover,
+                    "image/jpeg",
+                    None,
+                );
+            }
+
+            for file in &book.files {
+                write_atom_link(
+                    &mut xml,
+                    "http://opds-spec.org/acquisition",
+                    &file.href(),
+                    file.mime_type(),
+                    Some(file.file_size),
+                );
+            }
+
+            xml += "</entry>";
+        }
+
+        xml += "</feed>";
+
+        xml
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut links = vec![
+            json!({ "rel": "self", "href": self.href(&self.path), "type": JSON_TYPE }),
+            json!({ "rel": "start", "href": self.href(""), "type": JSON_TYPE }),
+            json!({
+                "rel": "search",
+                "href": self.href("/search{?query}"),
+                "type": JSON_TYPE,
+                "templated": true,
+            }),
+        ];
+
+        let (previous, next) = self.page_offsets();
+
+        if let Some(offset) = previous {
+            links.push(
+                json!({ "rel": "previous", "href": self.page_href(offset), "type": JSON_TYPE }),
+            );
+        }
+
+        if let Some(offset) = next {
+            links.push(json!({ "rel": "next", "href": self.page_href(offset), "type": JSON_TYPE }));
+        }
+
+        let mut metadata = json!({
+            "title": self.title,
+            "modified": self.updated.to_rfc3339(),
+        });
+
+        if let Some(page) = self.pagination.as_ref() {
+            metadata["numberOfItems"] = json!(page.total);
+            metadata["itemsPerPage"] = json!(page.limit);
+            metadata["currentPage"] = json!(page.offset / page.limit.max(1) + 1);
+        }
+
+        let mut value = json!({
+            "metadata": metadata,
+            "links": links,
+        });
+
+        if self.kind == FeedKind::Navigation {
+            value["navigation"] = self
+                .navigation
+                .iter()
+                .map(|entry| {
+                    json!({
+                        "href": self.href(&entry.path),
+                        "title": entry.title,
+                        "type": JSON_TYPE,
+                        "rel": "subsection",
+                    })
+                })
+                .collect();
+        } else {
+            value["publications"] = self.publications.iter().map(publication_to_json).collect();
+        }
+
+        value
+    }
+}
+
+fn publication_to_json(book: &Publication) -> Value {
+    let mut metadata = json!({
+        "@type": "http://schema.org/Book",
+        "identifier": format!("urn:books:book:{}", book.id),
+        "title": book.title,
+        "modified": book.updated.to_rfc3339(),
+        "author": book.authors.iter().map(|name| json!({ "name": name })).collect::<Vec<_>>(),
+    });
+
+    if let Some(value) = book.description.as_deref() {
+        metadata["description"] = json!(value);
+    }
+
+    if let Some(value) = book.publisher.as_deref() {
+        metadata["publisher"] = json!(value);
+    }
+
+    if let Some(value) = book.language.as_deref() {
+        metadata["language"] = json!(value);
+    }
+
+    if let Some(year) = book.year {
+        metadata["published"] = json!(year.to_string());
+    }
+
+    if !book.subjects.is_empty() {
+        metadata["subject"] = json!(book.subjects);
+    }
+
+    if let Some((name, position)) = book.series.as_ref() {
+        let mut series = json!({ "name": name });
+
+        if let Some(position) = position {
+            series["position"] = json!(position);
+        }
+
+        metadata["belongsTo"] = json!({ "series": [series] });
+    }
+
+    // The ISBN is preferred since it's what readers look up.
+    if let Some(identifier) = book
+        .identifiers
+        .iter()
+        .find(|v| matches!(v.type_of, IdentifierType::Isbn13 | IdentifierType::Isbn10))
+    {
+        metadata["identifier"] = json!(identifier_urn(identifier));
+    }
+
+    let mut value = json!({
+        "metadata": metadata,
+        "links": book.files.iter().map(|file| json!({
+            "rel": "http://opds-spec.org/acquisition",
+            "href": file.href(),
+            "type": file.mime_type(),
+        })).collect::<Vec<_>>(),
+    });
+
+    if let Some(cover) = book.cover.as_deref() {
+        value["images"] = json!([{ "href": cover, "type": "image/jpeg" }]);
+    }
+
+    value
+}
+
+/// The OpenSearch description for OPDS 1.2 clients.
+pub fn opensearch_description(title: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><OpenSearchDescription xmlns="http://a9.com/-/spec/opensearch/1.1/"><ShortName>{}</ShortName><Description>Search the books.</Description><InputEncoding>UTF-8</InputEncoding><OutputEncoding>UTF-8</OutputEncoding><Url type="{ATOM_ACQUISITION_TYPE}" template="{}/search?query={{searchTerms}}"/></OpenSearchDescription>"#,
+        escape(title),
+        FeedFormat::Atom.prefix(),
+    )
+}
+
+fn identifier_urn(value: &BookIdentifier) -> String {
+    match value.type_of {
+        IdentifierType::Isbn10 | IdentifierType::Isbn13 => format!("urn:isbn:{}", value.value),
+        IdentifierType::Asin => format!("urn:asin:{}", value.value),
+        IdentifierType::Oclc => format!("urn:oclc:{}", value.value),
+    }
+}
+
+fn write_atom_link(xml: &mut String, rel: &str, href: &str, type_of: &str, length: Option<i64>) {
+    let _ = write!(
+        xml,
+        r#"<link rel="{}" href="{}" type="{}""#,
+        escape(rel),
+        escape(href),
+        escape(type_of)
+    );
+
+    if let Some(length) = length {
+        let _ = write!(xml, r#" length="{length}""#);
+    }
+
+    *xml += "/>";
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Not allowed in XML 1.0
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => (),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,534 @@
+use actix_web::{
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    web, HttpResponse, Scope,
+};
+use chrono::Utc;
+use common::{api::ApiErrorResponse, PersonId};
+use common_local::{
+    api,
+    filter::{FilterContainer, FilterTableType},
+    CollectionId, LibraryId, SeriesId, ThumbnailStoreExt,
+};
+
+use crate::{
+    config::get_config,
+    database::{Database, DatabaseAccess},
+    model::{
+        book::BookModel, book_identifier::BookIdentifierModel, collection::CollectionModel,
+        collection_item::CollectionItemModel, file::FileModel, library::LibraryModel,
+        member_library::LibraryAccess, person::PersonModel, series::SeriesModel,
+        subject::SubjectModel,
+    },
+    Result, WebResult,
+};
+
+use super::{LoginRequired, MemberCookie};
+
+use self::feed::{
+    opensearch_description, Feed, FeedFormat, FeedKind, NavigationEntry, Pagination, Publication,
+    PublicationFile,
+};
+
+mod feed;
+
+/// How many items are in each page of a feed.
+const PAGE_SIZE: usize = 50;
+
+/// OPDS 1.2 is served from `/opds` and OPDS 2.0 from `/opds/v2`.
+pub fn opds_route() -> Scope<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<actix_web::body::EitherBody<actix_web::body::BoxBody>>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    web::scope("/opds")
+        .wrap(LoginRequired)
+        .service(
+            web::scope("/v2")
+                .app_data(web::Data::new(FeedFormat::Json))
+                .configure(feed_routes),
+        )
+        .app_data(web::Data::new(FeedFormat::Atom))
+        .route("/search.xml", web::get().to(opensearch))
+        .configure(feed_routes)
+}
+
+fn feed_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("", web::get().to(root))
+        .route("/recent", web::get().to(recent))
+        .route("/search", web::get().to(search))
+        .route("/libraries", web::get().to(libraries))
+        .route("/library/{id}", web::get().to(library))
+        .route("/collections", web::get().to(collections))
+        .route("/collection/{id}", web::get().to(collection))
+        .route("/authors", web::get().to(authors))
+        .route("/author/{id}", web::get().to(author))
+        .route("/series", web::get().to(series_list))
+        .route("/series/{id}", web::get().to(series));
+}
+
+async fn root(format: web::Data<FeedFormat>) -> HttpResponse {
+    let entry = |path: &str, title: &str, kind| NavigationEntry {
+        path: path.to_string(),
+        title: title.to_string(),
+        kind,
+        description: None,
+    };
+
+    feed_response(Feed {
+        navigation: vec![
+            entry("/recent", "Recently Added", FeedKind::Acquisition),
+            entry("/libraries", "Libraries", FeedKind::Navigation),
+            entry("/collections", "Collections", FeedKind::Navigation),
+            entry("/authors", "Authors", FeedKind::Navigation),
+            entry("/series", "Series", FeedKind::Navigation),
+        ],
+        ..new_feed(
+            **format,
+            FeedKind::Navigation,
+            String::new(),
+            get_config().server.name.clone(),
+        )
+    })
+}
+
+async fn opensearch() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(feed::OPENSEARCH_TYPE)
+        .body(opensearch_description(&get_config().server.name))
+}
+
+async fn recent(
+    format: web::Data<FeedFormat>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let access = library_access(&member, &db.basic()).await?;
+
+    let filter = FilterContainer::default().order_by(FilterTableType::CreatedAt, true);
+
+    let books = BookModel::search_by(&filter, None, &access, 0, PAGE_SIZE, &db.basic()).await?;
+
+    Ok(feed_response(Feed {
+        publications: books_to_publications(books, &db.basic()).await?,
+        ..new_feed(
+            **format,
+            FeedKind::Acquisition,
+            String::from("/recent"),
+            String::from("Recently Added"),
+        )
+    }))
+}
+
+async fn search(
+    query: web::Query<api::SimpleListQuery>,
+    format: web::Data<FeedFormat>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let access = library_access(&member, &db.basic()).await?;
+
+    let value = query.query.clone().unwrap_or_default();
+
+    let mut filter = FilterContainer::default();
+    filter.add_query_filter(value.clone());
+
+    books_feed(
+        new_feed(
+            **format,
+            FeedKind::Acquisition,
+            format!("/search?query={}", urlencoding::encode(&value)),
+            format!("Search: {value}"),
+        ),
+        &filter,
+        None,
+        &access,
+        query.offset.unwrap_or(0),
+        &db.basic(),
+    )
+    .await
+}
+
+async fn libraries(
+    format: web::Data<FeedFormat>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let access = library_access(&member, &db.basic()).await?;
+
+    let navigation = LibraryModel::get_all(&db.basic())
+        .await?
+        .into_iter()
+        .filter(|v| access.contains(v.id))
+        .map(|v| NavigationEntry {
+            path: format!("/library/{}", v.id),
+            title: v.name,
+            kind: FeedKind::Acquisition,
+            description: None,
+        })
+        .collect();
+
+    Ok(feed_response(Feed {
+        navigation,
+        ..new_feed(
+            **format,
+            FeedKind::Navigation,
+            String::from("/libraries"),
+            String::from("Libraries"),
+        )
+    }))
+}
+
+async fn library(
+    id: web::Path<LibraryId>,
+    query: web::Query<api::SimpleListQuery>,
+    format: web::Data<FeedFormat>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let access = library_access(&member, &db.basic()).await?;
+
+    let Some(library) = LibraryModel::find_one_by_id(*id, &db.basic())
+        .await?
+        .filter(|v| access.contains(v.id))
+    else {
+        return Err(ApiErrorResponse::new("Unable to find Library").into());
+    };
+
+    books_feed(
+        new_feed(
+            **format,
+            FeedKind::Acquisition,
+            format!("/library/{}", library.id),
+            library.name,
+        ),
+        &FilterContainer::default().order_by(FilterTableType::Query, false),
+        Some(library.id),
+        &access,
+        query.offset.unwrap_or(0),
+        &db.basic(),
+    )
+    .await
+}
+
+async fn collections(
+    format: web::Data<FeedFormat>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+
+    let navigation = CollectionModel::find_by_member_id(member.id, &db.basic())
+        .await?
+        .into_iter()
+        .map(|v| NavigationEntry {
+            path: format!("/collection/{}", v.id),
+            title: v.name,
+            kind: FeedKind::Acquisition,
+            description: v.description,
+        })
+        .collect();
+
+    Ok(feed_response(Feed {
+        navigation,
+        ..new_feed(
+            **format,
+            FeedKind::Navigation,
+            String::from("/collections"),
+            String::from("Collections"),
+        )
+    }))
+}
+
+async fn collection(
+    id: web::Path<CollectionId>,
+    format: web::Data<FeedFormat>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let member = member.fetch_or_error(&db.basic()).await?;
+    let access = LibraryAccess::for_member(&member, &db.basic()).await?;
+
+    let Some(collection) = CollectionModel::find_one_by_id(*id, member.id, &db.basic()).await?
+    else {
+        return Err(ApiErrorResponse::new("Unable to find Collection").into());
+    };
+
+    let mut books = Vec::new();
+
+    for item in CollectionItemModel::find_by_collection_id(collection.id, &db.basic()).await? {
+        if let Some(book) = BookModel::find_one_by_id(item.book_id, &db.basic())
+            .await?
+            .filter(|v| access.contains(v.library_id))
+        {
+            books.push(book);
+        }
+    }
+
+    Ok(feed_response(Feed {
+        publications: books_to_publications(books, &db.basic()).await?,
+        ..new_feed(
+            **format,
+            FeedKind::Acquisition,
+            format!("/collection/{}", collection.id),
+            collection.name,
+        )
+    }))
+}
+
+async fn authors(
+    query: web::Query<api::SimpleListQuery>,
+    format: web::Data<FeedFormat>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let access = library_access(&member, &db.basic()).await?;
+
+    let offset = query.offset.unwrap_or(0);
+
+    let navigation = PersonModel::find(offset, PAGE_SIZE, &access, &db.basic())
+        .await?
+        .into_iter()
+        .map(|v| NavigationEntry {
+            path: format!("/author/{}", v.id),
+            title: v.name,
+            kind: FeedKind::Acquisition,
+            description: None,
+        })
+        .collect();
+
+    Ok(feed_response(Feed {
+        navigation,
+        pagination: Some(Pagination {
+            offset,
+            limit: PAGE_SIZE,
+            total: PersonModel::count(&access, &db.basic()).await?,
+        }),
+        ..new_feed(
+            **format,
+            FeedKind::Navigation,
+            String::from("/authors"),
+            String::from("Authors"),
+        )
+    }))
+}
+
+async fn author(
+    id: web::Path<PersonId>,
+    query: web::Query<api::SimpleListQuery>,
+    format: web::Data<FeedFormat>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let access = library_access(&member, &db.basic()).await?;
+
+    let Some(person) = PersonModel::find_one_by_id(*id, &db.basic()).await? else {
+        return Err(ApiErrorResponse::new("Unable to find Person").into());
+    };
+
+    if !PersonModel::is_visible(person.id, &access, &db.basic()).await? {
+        return Err(ApiErrorResponse::new("Unable to find Person").into());
+    }
+
+    let mut filter = FilterContainer::default().order_by(FilterTableType::Query, false);
+    filter.add_person_filter(person.id);
+
+    books_feed(
+        new_feed(
+            **format,
+            FeedKind::Acquisition,
+            format!("/author/{}", person.id),
+            person.name,
+        ),
+        &filter,
+        None,
+        &access,
+        query.offset.unwrap_or(0),
+        &db.basic(),
+    )
+    .await
+}
+
+async fn series_list(
+    query: web::Query<api::SimpleListQuery>,
+    format: web::Data<FeedFormat>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let access = library_access(&member, &db.basic()).await?;
+
+    let offset = query.offset.unwrap_or(0);
+
+    let navigation = SeriesModel::find(offset, PAGE_SIZE, &access, &db.basic())
+        .await?
+        .into_iter()
+        .map(|v| NavigationEntry {
+            path: format!("/series/{}", v.id),
+            title: v.name,
+            kind: FeedKind::Acquisition,
+            description: v.description,
+        })
+        .collect();
+
+    Ok(feed_response(Feed {
+        navigation,
+        pagination: Some(Pagination {
+            offset,
+            limit: PAGE_SIZE,
+            total: SeriesModel::count(&access, &db.basic()).await?,
+        }),
+        ..new_feed(
+            **format,
+            FeedKind::Navigation,
+            String::from("/series"),
+            String::from("Series"),
+        )
+    }))
+}
+
+async fn series(
+    id: web::Path<SeriesId>,
+    query: web::Query<api::SimpleListQuery>,
+    format: web::Data<FeedFormat>,
+    member: MemberCookie,
+    db: web::Data<Database>,
+) -> WebResult<HttpResponse> {
+    let access = library_access(&member, &db.basic()).await?;
+
+    let Some(series) = SeriesModel::find_one_by_id(*id, &db.basic()).await? else {
+        return Err(ApiErrorResponse::new("Unable to find Series").into());
+    };
+
+    if !SeriesModel::is_visible(series.id, &access, &db.basic()).await? {
+        return Err(ApiErrorResponse::new("Unable to find Series").into());
+    }
+
+    let mut filter = FilterContainer::default().order_by(FilterTableType::Series, false);
+    filter.add_series_filter(series.id);
+
+    books_feed(
+        new_feed(
+            **format,
+            FeedKind::Acquisition,
+            format!("/series/{}", series.id),
+            series.name,
+        ),
+        &filter,
+        None,
+        &access,
+        query.offset.unwrap_or(0),
+        &db.basic(),
+    )
+    .await
+}
+
+async fn library_access(member: &MemberCookie, db: &dyn DatabaseAccess) -> Result<LibraryAccess> {
+    LibraryAccess::for_member(&member.fetch_or_error(db).await?, db).await
+}
+
+fn new_feed(format: FeedFormat, kind: FeedKind, path: String, title: String) -> Feed {
+    Feed {
+        format,
+        kind,
+        path,
+        title,
+        updated: Utc::now(),
+        navigation: Vec::new(),
+        publications: Vec::new(),
+        pagination: None,
+    }
+}
+
+fn feed_response(feed: Feed) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(feed.format.content_type(feed.kind))
+        .body(feed.format.render(&feed))
+}
+
+/// A page of the books found by the filter.
+async fn books_feed(
+    feed: Feed,
+    filter: &FilterContainer,
+    library: Option<LibraryId>,
+    access: &LibraryAccess,
+    offset: usize,
+    db: &dyn DatabaseAccess,
+) -> WebResult<HttpResponse> {
+    let books = BookModel::search_by(filter, library, access, offset, PAGE_SIZE, db).await?;
+
+    Ok(feed_response(Feed {
+        publications: books_to_publications(books, db).await?,
+        pagination: Some(Pagination {
+            offset,
+            limit: PAGE_SIZE,
+            total: BookModel::count_search_by(filter, library, access, db).await?,
+        }),
+        ..feed
+    }))
+}
+
+async fn books_to_publications(
+    books: Vec<BookModel>,
+    db: &dyn DatabaseAccess,
+) -> Result<Vec<Publication>> {
+    let mut items = Vec::new();
+
+    for book in books {
+        let files = FileModel::find_by_book_id(book.id, db)
+            .await?
+            .into_iter()
+            .filter(|v| v.deleted_at.is_none())
+            .map(|v| PublicationFile {
+                id: v.id,
+                file_type: v.file_type,
+                file_size: v.file_size,
+            })
+            .collect();
+
+        let series = match book.series_id {
+            Some(id) => SeriesModel::find_one_by_id(id, db)
+                .await?
+                .map(|v| (v.name, book.series_index)),
+            None => None,
+        };
+
+        items.push(Publication {
+            id: book.id,
+            title: book
+                .title
+                .or(book.original_title)
+                .unwrap_or_else(|| String::from("Untitled")),
+            description: book.description,
+            authors: PersonModel::find_by_book_id(book.id, db)
+                .await?
+                .into_iter()
+                .map(|v| v.name)
+                .collect(),
+            publisher: book.cached.publisher,
+            language: book.language,
+            year: book.year,
+            identifiers: BookIdentifierModel::find_by_book_id(book.id, db)
+                .await?
+                .into_iter()
+                .map(|v| v.into())
+                .collect(),
+            subjects: SubjectModel::find_by_book_id(book.id, db)
+                .await?
+                .into_iter()
+                .map(|v| v.name)
+                .collect(),
+            series,
+            cover: book
+                .thumb_path
+                .is_some()
+                .then(|| book.thumb_path.get_book_http_path().into_owned()),
+            updated: book.updated_at,
+            files,
+        });
+    }
+
+    Ok(items)
+}
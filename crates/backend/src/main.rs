@@ -8,7 +8,7 @@
 
 use actix_web::web;
 use clap::Parser;
-use tracing::{info, subscriber::set_global_default, Level};
+use tracing::{error, info, subscriber::set_global_default, Level};
 use tracing_subscriber::FmtSubscriber;
 
 #[cfg(feature = "bundled")]
@@ -48,12 +48,30 @@ async fn main() -> Result<()> {
     // Save Config - Otherwise it'll be lazily loaded whenever this fn is first called.
     config::save_config().await?;
 
+    if cli_args.pending_migrations {
+        let pending = database::get_pending_migrations().await?;
+
+        if pending.is_empty() {
+            println!("No pending migrations");
+        }
+
+        for (id, title) in pending {
+            println!("{id}: {title}");
+        }
+
+        return Ok(());
+    }
+
     let db = database::init().await?;
 
     let db_data = web::Data::new(db);
 
     task::start_task_manager(db_data.clone());
 
+    if let Err(e) = scanner::watcher::start_watcher(db_data.clone()) {
+        error!(error = ?e, "Unable to start the File Watcher");
+    }
+
     info!(
         port = cli_args.port,
         host = cli_args.host,
@@ -2,20 +2,25 @@
 
 // TODO: Handle errors
 
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
 
 use crate::{model::file::FileModel, Result};
 use async_trait::async_trait;
 use common::Agent;
-use common_local::{BookItemCached, SearchForBooksBy};
+use common_local::{BookIdentifier, BookItemCached, SearchForBooksBy};
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tracing::{error, info};
 
-use super::{Metadata, MetadataReturned, SearchFor, SearchItem};
+use super::{rate_limit::RateLimiter, Metadata, MetadataReturned, SearchFor, SearchItem};
 use crate::metadata::{FoundImageLocation, FoundItem};
 
+pub static RATE_LIMITER: RateLimiter = RateLimiter::new(Duration::from_millis(250));
+
 lazy_static! {
     pub static ref REMOVE_HTML_TAGS: Regex = Regex::new("<(.|\n)*?>").unwrap();
 }
@@ -81,7 +86,7 @@ impl Metadata for GoogleBooksMetadata {
 
                 info!(url, "Searching");
 
-                let resp = reqwest::get(url).await?;
+                let resp = RATE_LIMITER.get(url).await?;
 
                 if resp.status().is_success() {
                     let books_cont = resp.json::<BookVolumesContainer>().await?;
@@ -107,6 +112,11 @@ impl Metadata for GoogleBooksMetadata {
                             cached: BookItemCached::default(),
                             available_at: None,
                             year: None,
+                            series_index: None,
+                            language: None,
+                            page_count: None,
+                            subjects: Vec::new(),
+                            identifiers: Vec::new(),
                         }));
                     }
 
@@ -121,11 +131,12 @@ impl Metadata for GoogleBooksMetadata {
 
 impl GoogleBooksMetadata {
     pub async fn request_query(&self, id: String) -> Result<Option<MetadataReturned>> {
-        let resp = reqwest::get(format!(
-            "https://www.googleapis.com/books/v1/volumes?q={}",
-            BookSearchKeyword::Isbn.combile_string(&id)
-        ))
-        .await?;
+        let resp = RATE_LIMITER
+            .get(format!(
+                "https://www.googleapis.com/books/v1/volumes?q={}",
+                BookSearchKeyword::Isbn.combile_string(&id)
+            ))
+            .await?;
 
         let book = if resp.status().is_success() {
             let mut books = resp.json::<BookVolumesContainer>().await?;
@@ -143,11 +154,12 @@ impl GoogleBooksMetadata {
     }
 
     pub async fn request_singular_id(&self, id: &str) -> Result<Option<MetadataReturned>> {
-        let resp = reqwest::get(format!(
-            "https://www.googleapis.com/books/v1/volumes/{}",
-            id
-        ))
-        .await?;
+        let resp = RATE_LIMITER
+            .get(format!(
+                "https://www.googleapis.com/books/v1/volumes/{}",
+                id
+            ))
+            .await?;
 
         if resp.status().is_success() {
             self.compile_book_volume_item(resp.json().await?).await
@@ -168,6 +180,7 @@ impl GoogleBooksMetadata {
         Ok(Some(MetadataReturned {
             authors: None,
             publisher: None,
+            field_sources: BTreeMap::new(),
             meta: FoundItem {
                 source: self.prefix_text(value.id).try_into()?,
                 title: value.volume_info.title.clone(),
@@ -183,6 +196,20 @@ impl GoogleBooksMetadata {
                     .author_optional(value.volume_info.authors.and_then(|v| v.first().cloned())),
                 available_at: None,
                 year: None,
+                series_index: None,
+                language: Some(value.volume_info.language),
+                page_count: value
+                    .volume_info
+                    .page_count
+                    .or(value.volume_info.printed_page_count),
+                subjects: value.volume_info.categories.unwrap_or_default(),
+                identifiers: value
+                    .volume_info
+                    .industry_identifiers
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|v| BookIdentifier::parse(&v.identifier))
+                    .collect(),
             },
         }))
     }
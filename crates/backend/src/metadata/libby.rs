@@ -1,4 +1,4 @@
-use std::convert::TryFrom;
+use std::{collections::BTreeMap, convert::TryFrom, time::Duration};
 
 use crate::{
     config::get_config,
@@ -18,7 +18,9 @@ use common_local::{BookItemCached, SearchFor};
 
 use tracing::{error, info};
 
-use super::{FoundImageLocation, FoundItem, Metadata, MetadataReturned};
+use super::{rate_limit::RateLimiter, FoundImageLocation, FoundItem, Metadata, MetadataReturned};
+
+pub static RATE_LIMITER: RateLimiter = RateLimiter::new(Duration::from_millis(100));
 
 pub struct LibbyMetadata;
 
@@ -160,6 +162,11 @@ impl Metadata for LibbyMetadata {
                                         .available_at
                                         .map(|v| v.and_hms(0, 0, 0).timestamp_millis()),
                                     year: None,
+                                    series_index: None,
+                                    language: None,
+                                    page_count: None,
+                                    subjects: Vec::new(),
+                                    identifiers: Vec::new(),
                                 }));
                             }
                         }
@@ -295,6 +302,7 @@ impl LibbyMetadata {
         Ok(Some(MetadataReturned {
             authors: Some(authors).filter(|v| !v.is_empty()),
             publisher: value.publisher.clone(),
+            field_sources: BTreeMap::new(),
             meta: FoundItem {
                 source: self.prefix_text(value.id.to_string()).try_into()?,
                 title: value.title,
@@ -311,15 +319,20 @@ impl LibbyMetadata {
                     .available_at
                     .map(|v| v.and_hms(0, 0, 0).timestamp_millis()),
                 year: None,
+                series_index: None,
+                language: None,
+                page_count: None,
+                subjects: Vec::new(),
+                identifiers: Vec::new(),
             },
         }))
     }
 }
 
 async fn request_books(value: &str) -> Result<PublicSearchResponse> {
-    Ok(reqwest::get(value).await?.json().await?)
+    Ok(RATE_LIMITER.get(value).await?.json().await?)
 }
 
 async fn request_authors(value: &str) -> Result<PublicSearchResponse> {
-    Ok(reqwest::get(value).await?.json().await?)
+    Ok(RATE_LIMITER.get(value).await?.json().await?)
 }
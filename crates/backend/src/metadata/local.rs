@@ -1,10 +1,10 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use crate::{model::file::FileModel, Result};
 use async_trait::async_trait;
 use bookie::BookSearch;
 use common::Agent;
-use common_local::BookItemCached;
+use common_local::{BookIdentifier, BookItemCached};
 
 use super::{AuthorInfo, FoundImageLocation, FoundItem, Metadata, MetadataReturned};
 
@@ -31,9 +31,35 @@ impl Metadata for LocalMetadata {
 
                 let source = self.prefix_text(book.get_unique_id()?);
 
+                // Comics
+                let series = book
+                    .find(BookSearch::Other("series"))
+                    .map(|mut v| v.remove(0));
+                let volume = book
+                    .find(BookSearch::Other("volume"))
+                    .map(|mut v| v.remove(0));
+                let issue = book
+                    .find(BookSearch::Other("issue"))
+                    .map(|mut v| v.remove(0));
+
+                // EPUBs store the position separately. Comics use the issue number.
+                let series_index = book
+                    .find(BookSearch::Other("series_index"))
+                    .map(|mut v| v.remove(0))
+                    .or_else(|| issue.clone())
+                    .and_then(|v| v.trim().parse::<f64>().ok());
+
                 let title = book
                     .find(BookSearch::Title)
                     .map(|mut v| v.remove(0))
+                    .or_else(|| {
+                        let series = series.as_deref()?;
+
+                        Some(match issue.as_deref() {
+                            Some(issue) => format!("{series} #{issue}"),
+                            None => series.to_string(),
+                        })
+                    })
                     .or_else(|| {
                         let path = PathBuf::from(&file.path);
                         Some(path.file_name()?.to_str()?.to_string())
@@ -50,7 +76,22 @@ impl Metadata for LocalMetadata {
                     .transpose()?;
 
                 let publisher = book.find(BookSearch::Publisher).map(|mut v| v.remove(0));
-                let authors = book.find(BookSearch::Creator).map(|items| {
+
+                // Creators are first since the first author is used as the main one.
+                let credits = book
+                    .find(BookSearch::Creator)
+                    .into_iter()
+                    .chain(book.find(BookSearch::Contributor))
+                    .flatten()
+                    .fold(Vec::new(), |mut names, name| {
+                        if !names.contains(&name) {
+                            names.push(name);
+                        }
+
+                        names
+                    });
+
+                let authors = Some(credits).filter(|v| !v.is_empty()).map(|items| {
                     items
                         .into_iter()
                         .map(|name| AuthorInfo {
@@ -72,9 +113,24 @@ impl Metadata for LocalMetadata {
                         description: book.find(BookSearch::Description).map(|mut v| v.remove(0)),
                         rating: 0.0,
                         thumb_locations: thumb_file_data.unwrap_or_default(),
-                        cached: BookItemCached::default(),
+                        cached: BookItemCached::default()
+                            .series_optional(series)
+                            .volume_optional(volume)
+                            .issue_optional(issue),
                         available_at: None,
-                        year: None,
+                        year: book
+                            .find(BookSearch::Date)
+                            .and_then(|v| v.first()?.get(..4)?.parse().ok()),
+                        series_index,
+                        language: book.find(BookSearch::Language).map(|mut v| v.remove(0)),
+                        page_count: None,
+                        subjects: book.find(BookSearch::Subject).unwrap_or_default(),
+                        identifiers: book
+                            .find(BookSearch::Identifier)
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|v| BookIdentifier::parse(&v))
+                            .collect(),
                     },
                     authors,
                     publisher,
@@ -85,6 +141,7 @@ impl Metadata for LocalMetadata {
                 authors,
                 publisher,
                 meta,
+                field_sources: BTreeMap::new(),
             }));
         }
 
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     ops::{Deref, DerefMut},
 };
 
@@ -9,15 +9,20 @@ use crate::{
         book::BookModel,
         book_person::BookPersonModel,
         file::FileModel,
+        library::LibraryModel,
         person::{NewPersonModel, PersonModel},
         person_alt::PersonAltModel,
+        person_locked_field::PersonLockedFieldModel,
     },
     util, Result,
 };
 use async_trait::async_trait;
 use chrono::{NaiveDate, TimeZone, Utc};
 use common::{Agent, BookId, Either, PersonId, Source, ThumbnailStore};
-use common_local::{BookItemCached, LibraryId, SearchFor};
+use common_local::{
+    AgentPriority, BookIdentifier, BookItemCached, LibraryId, MetadataAgent, MetadataField,
+    SearchFor,
+};
 use futures::Future;
 use tracing::error;
 
@@ -32,20 +37,10 @@ pub mod google_books;
 pub mod libby;
 pub mod local;
 pub mod openlibrary;
+pub mod rate_limit;
 
 // "source" column: [prefix]:[id]
 
-/// Simple return if found, If error then log it.
-macro_rules! return_if_found {
-    ($v: expr) => {
-        match $v {
-            Ok(Some(v)) => return Ok(Some(v)),
-            Ok(None) => (),
-            Err(error) => error!(?error),
-        }
-    };
-}
-
 macro_rules! return_if_found_vec {
     ($v: expr) => {
         match $v {
@@ -56,21 +51,44 @@ macro_rules! return_if_found_vec {
     };
 }
 
-pub struct ActiveAgents {
-    pub google: bool,
-    pub libby: bool,
-    pub local: bool,
-    pub openlib: bool,
-}
+/// The agents which are used and the order they're tried in.
+#[derive(Default)]
+pub struct ActiveAgents(AgentPriority);
+
+impl ActiveAgents {
+    pub fn only(agent: MetadataAgent) -> Self {
+        Self(AgentPriority::only(agent))
+    }
+
+    pub async fn for_library(library_id: LibraryId, db: &dyn DatabaseAccess) -> Result<Self> {
+        Ok(Self(
+            match LibraryModel::find_one_by_id(library_id, db).await? {
+                Some(library) => library.parse_agent_priority()?,
+                None => AgentPriority::default(),
+            },
+        ))
+    }
+
+    /// Libby also has to be configured.
+    pub fn is_enabled(&self, agent: MetadataAgent) -> bool {
+        if agent == MetadataAgent::Libby {
+            let config = get_config();
 
-impl Default for ActiveAgents {
-    fn default() -> Self {
-        Self {
-            google: true,
-            libby: true,
-            local: true,
-            openlib: true,
+            if !config.authenticators.main_server || config.libby.token.is_none() {
+                return false;
+            }
         }
+
+        self.0.is_enabled(agent)
+    }
+
+    /// Enabled agents in order of priority.
+    pub fn agents(&self) -> impl Iterator<Item = MetadataAgent> + '_ {
+        self.0
+            .agents
+            .iter()
+            .copied()
+            .filter(|v| self.is_enabled(*v))
     }
 }
 
@@ -109,34 +127,73 @@ pub trait Metadata {
 }
 
 // TODO: Utilize current metadata in get_metadata_from_files.
-// TODO: Order which metadata should be tried.
-/// Attempts to return the first valid Metadata from Files.
+/// Asks every active agent for Metadata from Files and merges what they found.
 ///
 /// Also checks local agent.
 pub async fn get_metadata_from_files(
     files: &[FileModel],
     agent: &ActiveAgents,
 ) -> Result<Option<MetadataReturned>> {
-    let config = get_config();
-
-    if agent.libby && config.authenticators.main_server && config.libby.token.is_some() {
-        return_if_found!(LibbyMetadata.get_metadata_from_files(files).await);
+    let mut found = Vec::new();
+
+    for value in agent.agents() {
+        let resp = match value {
+            MetadataAgent::Libby => LibbyMetadata.get_metadata_from_files(files).await,
+            MetadataAgent::GoogleBooks => GoogleBooksMetadata.get_metadata_from_files(files).await,
+            MetadataAgent::OpenLibrary => OpenLibraryMetadata.get_metadata_from_files(files).await,
+            // TODO: Don't re-scan file if we already have metadata from file.
+            MetadataAgent::Local => LocalMetadata.get_metadata_from_files(files).await,
+        };
+
+        match resp {
+            Ok(Some(v)) => found.push((value, v)),
+            Ok(None) => (),
+            Err(error) => error!(?error),
+        }
     }
 
-    if agent.google {
-        return_if_found!(GoogleBooksMetadata.get_metadata_from_files(files).await);
-    }
+    Ok(merge_metadata(found, agent))
+}
 
-    if agent.openlib {
-        return_if_found!(OpenLibraryMetadata.get_metadata_from_files(files).await);
+/// The first result is used as the base. Each field is then taken from the first agent in its priority which has it.
+fn merge_metadata(
+    mut found: Vec<(MetadataAgent, MetadataReturned)>,
+    agent: &ActiveAgents,
+) -> Option<MetadataReturned> {
+    if found.is_empty() {
+        return None;
     }
 
-    if agent.local {
-        // TODO: Don't re-scan file if we already have metadata from file.
-        return_if_found!(LocalMetadata.get_metadata_from_files(files).await);
+    let (base_agent, mut merged) = found.remove(0);
+
+    for field in MetadataField::ALL {
+        let from = agent.0.for_field(field).into_iter().find(|value| {
+            if *value == base_agent {
+                merged.has_field(field)
+            } else {
+                found
+                    .iter()
+                    .any(|(v, meta)| v == value && meta.has_field(field))
+            }
+        });
+
+        match from {
+            Some(from) if from != base_agent => {
+                if let Some((_, other)) = found.iter_mut().find(|(v, _)| *v == from) {
+                    merged.take_field(field, other);
+                    merged.field_sources.insert(field, from);
+                }
+            }
+
+            _ => {
+                if merged.has_field(field) {
+                    merged.field_sources.insert(field, base_agent);
+                }
+            }
+        }
     }
 
-    Ok(None)
+    Some(merged)
 }
 
 /// Doesn't check local
@@ -171,18 +228,19 @@ pub async fn search_and_return_first_valid_agent(
     search_for: SearchFor,
     agent: &ActiveAgents,
 ) -> Result<Vec<SearchItem>> {
-    let config = get_config();
-
-    if agent.libby && config.authenticators.main_server && config.libby.token.is_some() {
-        return_if_found_vec!(LibbyMetadata.search(query, search_for).await);
-    }
-
-    if agent.google {
-        return_if_found_vec!(GoogleBooksMetadata.search(query, search_for).await);
-    }
-
-    if agent.openlib {
-        return_if_found_vec!(OpenLibraryMetadata.search(query, search_for).await);
+    for value in agent.agents() {
+        match value {
+            MetadataAgent::Libby => {
+                return_if_found_vec!(LibbyMetadata.search(query, search_for).await)
+            }
+            MetadataAgent::GoogleBooks => {
+                return_if_found_vec!(GoogleBooksMetadata.search(query, search_for).await)
+            }
+            MetadataAgent::OpenLibrary => {
+                return_if_found_vec!(OpenLibraryMetadata.search(query, search_for).await)
+            }
+            MetadataAgent::Local => (),
+        }
     }
 
     Ok(Vec::new())
@@ -194,8 +252,6 @@ pub async fn search_all_agents(
     search_for: SearchFor,
     agent: &ActiveAgents,
 ) -> Result<SearchResults> {
-    let config = get_config();
-
     let mut map = HashMap::new();
 
     // Checks to see if we can use get_metadata_by_source (source:id)
@@ -227,14 +283,17 @@ pub async fn search_all_agents(
     ];
     let asdf = futures::future::join_all([
         search_or_ignore(
-            agent.libby && config.authenticators.main_server && config.libby.token.is_some(),
+            agent.is_enabled(MetadataAgent::Libby),
             LibbyMetadata.search(search, search_for),
         ),
         search_or_ignore(
-            agent.openlib,
+            agent.is_enabled(MetadataAgent::OpenLibrary),
             OpenLibraryMetadata.search(search, search_for),
         ),
-        search_or_ignore(agent.google, GoogleBooksMetadata.search(search, search_for)),
+        search_or_ignore(
+            agent.is_enabled(MetadataAgent::GoogleBooks),
+            GoogleBooksMetadata.search(search, search_for),
+        ),
     ])
     .await;
 
@@ -347,9 +406,86 @@ pub struct MetadataReturned {
     pub publisher: Option<String>, // TODO: Is this needed? We have BookItemCached in meta field
     // TODO: Add More.
     pub meta: FoundItem,
+
+    /// Filled in when the metadata was merged from multiple agents.
+    pub field_sources: BTreeMap<MetadataField, MetadataAgent>,
 }
 
 impl MetadataReturned {
+    /// Which agent each of the found fields came from.
+    ///
+    /// Should be called before the authors are added into the database.
+    pub fn field_sources(&self) -> Vec<(MetadataField, MetadataAgent)> {
+        let main_agent = MetadataAgent::from_prefix(self.meta.source.agent.as_ref());
+
+        MetadataField::ALL
+            .into_iter()
+            .filter(|v| self.has_field(*v))
+            .filter_map(|field| {
+                Some((
+                    field,
+                    self.field_sources.get(&field).copied().or(main_agent)?,
+                ))
+            })
+            .collect()
+    }
+
+    fn has_field(&self, field: MetadataField) -> bool {
+        let meta = &self.meta;
+
+        match field {
+            MetadataField::Title => meta.title.is_some(),
+            MetadataField::Description => meta.description.is_some(),
+            MetadataField::Rating => meta.rating != 0.0,
+            MetadataField::Cover => !meta.thumb_locations.is_empty(),
+            MetadataField::Authors => self.authors.as_ref().map_or(false, |v| !v.is_empty()),
+            MetadataField::Publisher => self.publisher.is_some() || meta.cached.publisher.is_some(),
+            MetadataField::Year => meta.year.is_some(),
+            MetadataField::AvailableAt => meta.available_at.is_some(),
+            MetadataField::Series => meta.cached.series.is_some(),
+            MetadataField::Language => meta.language.is_some(),
+            MetadataField::PageCount => meta.page_count.is_some(),
+            MetadataField::Subjects => !meta.subjects.is_empty(),
+            MetadataField::Identifiers => !meta.identifiers.is_empty(),
+        }
+    }
+
+    /// Replaces our field with the other one.
+    fn take_field(&mut self, field: MetadataField, other: &mut Self) {
+        let (meta, other_meta) = (&mut self.meta, &mut other.meta);
+
+        match field {
+            MetadataField::Title => meta.title = other_meta.title.take(),
+            MetadataField::Description => meta.description = other_meta.description.take(),
+            MetadataField::Rating => meta.rating = other_meta.rating,
+            MetadataField::Cover => {
+                meta.thumb_locations = std::mem::take(&mut other_meta.thumb_locations)
+            }
+            MetadataField::Authors => {
+                self.authors = other.authors.take();
+                meta.cached.author = other_meta.cached.author.take();
+            }
+            MetadataField::Publisher => {
+                self.publisher = other.publisher.take();
+                meta.cached.publisher = other_meta.cached.publisher.take();
+            }
+            MetadataField::Year => meta.year = other_meta.year.take(),
+            MetadataField::AvailableAt => meta.available_at = other_meta.available_at.take(),
+            MetadataField::Series => {
+                meta.cached.series = other_meta.cached.series.take();
+                meta.cached.volume = other_meta.cached.volume.take();
+                meta.cached.issue = other_meta.cached.issue.take();
+                meta.series_index = other_meta.series_index.take();
+            }
+            MetadataField::Language => meta.language = other_meta.language.take(),
+            MetadataField::PageCount => meta.page_count = other_meta.page_count.take(),
+            MetadataField::Subjects => meta.subjects = std::mem::take(&mut other_meta.subjects),
+            MetadataField::Identifiers => {
+                meta.identifiers = std::mem::take(&mut other_meta.identifiers)
+            }
+        }
+    }
+
     /// Returns (Main Author, Person IDs)
     pub async fn add_or_ignore_authors_into_database(
         &mut self,
@@ -365,8 +501,12 @@ impl MetadataReturned {
                 // Check if we already have a person by that name anywhere in the two database tables.
                 if let Some(person) = PersonModel::find_one_by_name(&author_info.name, db).await? {
                     // Check if it's from the same source.
-                    // If not, we remove the old one and replace it with the new one.
-                    if author_info.source != person.source {
+                    // If not, we remove the old one and replace it with the new one. Unless it was locked.
+                    if author_info.source != person.source
+                        && PersonLockedFieldModel::find_by_person_id(person.id, db)
+                            .await?
+                            .is_empty()
+                    {
                         PersonAltModel::delete_by_id(person.id, db).await?;
                         PersonModel::delete_by_id(person.id, db).await?;
 
@@ -458,6 +598,14 @@ pub struct FoundItem {
 
     pub available_at: Option<i64>,
     pub year: Option<i64>,
+
+    /// Position in the series stored in `cached`.
+    pub series_index: Option<f64>,
+
+    pub language: Option<String>,
+    pub page_count: Option<i64>,
+    pub subjects: Vec<String>,
+    pub identifiers: Vec<BookIdentifier>,
 }
 
 impl From<FoundItem> for BookModel {
@@ -488,6 +636,13 @@ impl From<FoundItem> for BookModel {
             deleted_at: None,
             available_at: val.available_at.map(|v| Utc.timestamp_millis(v)),
             year: val.year,
+            series_id: None,
+            series_index: val.series_index,
+            publisher_id: None,
+            language: val.language,
+            page_count: val.page_count,
+            subjects: val.subjects,
+            identifiers: val.identifiers,
         }
     }
 }
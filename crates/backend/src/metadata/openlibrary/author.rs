@@ -4,12 +4,12 @@ use tracing::info;
 
 use crate::Result;
 
-use super::book::BookId;
+use super::{book::BookId, RATE_LIMITER};
 
 // Call rfd after calling book.
 
 pub async fn get_authors_from_book_by_rfd(id: &BookId) -> Result<Vec<rfd::AuthorDescription>> {
-    let resp = reqwest::get(id.get_rdf_url()).await?;
+    let resp = RATE_LIMITER.get(id.get_rdf_url()).await?;
 
     let text = resp.text().await?;
 
@@ -27,7 +27,7 @@ pub async fn get_authors_from_book_by_rfd(id: &BookId) -> Result<Vec<rfd::Author
 }
 
 pub async fn get_author_from_url(url_or_path: &str) -> Result<Option<json::AuthorJson>> {
-    let resp = reqwest::get(into_url(url_or_path)).await?;
+    let resp = RATE_LIMITER.get(into_url(url_or_path)).await?;
 
     if resp.status().is_success() {
         Ok(Some(resp.json().await?))
@@ -54,7 +54,7 @@ pub async fn search_for_authors(value: &str) -> Result<Option<json::AuthorSearch
 
     info!(url, "Searching");
 
-    let resp = reqwest::get(url).await?;
+    let resp = RATE_LIMITER.get(url).await?;
 
     if resp.status().is_success() {
         Ok(Some(resp.json().await?))
@@ -5,12 +5,12 @@ use common::parse_book_id;
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
-use super::{KeyItem, RecordDescription, TypeValueItem};
+use super::{KeyItem, RecordDescription, TypeValueItem, RATE_LIMITER};
 
 // TODO: We can retrive all Editions from the Work (original) book by using RFD instead of JSON.
 
 pub async fn get_book_by_id(id: &BookId) -> Result<Option<BookInfo>> {
-    let resp = reqwest::get(id.get_json_url()).await?;
+    let resp = RATE_LIMITER.get(id.get_json_url()).await?;
 
     if resp.status().is_success() {
         Ok(Some(resp.json().await?))
@@ -27,7 +27,7 @@ pub async fn search_for_books(
 
     info!(url, "Searching");
 
-    let resp = reqwest::get(url).await?;
+    let resp = RATE_LIMITER.get(url).await?;
 
     if resp.status().is_success() {
         Ok(Some(resp.json().await?))
@@ -1,22 +1,27 @@
 // https://openlibrary.org/developers/api
 
+use std::{collections::BTreeMap, time::Duration};
+
 use crate::{model::file::FileModel, Result};
 use async_trait::async_trait;
 use chrono::NaiveDate;
 use common::Agent;
-use common_local::{BookItemCached, SearchForBooksBy};
+use common_local::{BookIdentifier, BookItemCached, SearchForBooksBy};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info};
 
 use self::book::BookSearchType;
 
 use super::{
-    AuthorInfo, FoundImageLocation, FoundItem, Metadata, MetadataReturned, SearchFor, SearchItem,
+    rate_limit::RateLimiter, AuthorInfo, FoundImageLocation, FoundItem, Metadata, MetadataReturned,
+    SearchFor, SearchItem,
 };
 
 pub mod author;
 pub mod book;
 
+pub static RATE_LIMITER: RateLimiter = RateLimiter::new(Duration::from_millis(500));
+
 use book::BookId;
 
 pub struct OpenLibraryMetadata;
@@ -149,6 +154,11 @@ impl Metadata for OpenLibraryMetadata {
                             cached: BookItemCached::default(),
                             available_at: None,
                             year: item.first_publish_year,
+                            series_index: None,
+                            language: None,
+                            page_count: None,
+                            subjects: Vec::new(),
+                            identifiers: Vec::new(),
                         }));
                     }
 
@@ -229,9 +239,51 @@ impl OpenLibraryMetadata {
             return Ok(None);
         };
 
+        let identifiers = book_info
+            .isbn_13
+            .iter()
+            .chain(book_info.isbn_10.iter())
+            .flatten()
+            .cloned()
+            .chain(
+                book_info
+                    .oclc_numbers
+                    .iter()
+                    .chain(book_info.oclc_number.iter())
+                    .flatten()
+                    .map(|v| format!("oclc:{v}")),
+            )
+            .chain(
+                book_info
+                    .identifiers
+                    .as_ref()
+                    .and_then(|v| v.get("amazon"))
+                    .into_iter()
+                    .flatten()
+                    .cloned(),
+            )
+            .filter_map(|v| BookIdentifier::parse(&v))
+            .fold(Vec::new(), |mut items, item| {
+                if !items.contains(&item) {
+                    items.push(item);
+                }
+
+                items
+            });
+
+        let (series, series_index) = match book_info.series.as_ref().and_then(|v| v.first()) {
+            Some(value) => {
+                let (name, index) = parse_series(value);
+                (Some(name), index)
+            }
+
+            None => (None, None),
+        };
+
         Ok(Some(MetadataReturned {
             authors: Some(authors).filter(|v| !v.is_empty()),
             publisher: book_info.publishers.and_then(|v| v.first().cloned()),
+            field_sources: BTreeMap::new(),
 
             meta: FoundItem {
                 source: format!("{}:{}", self.get_agent(), source_id).try_into()?,
@@ -250,9 +302,18 @@ impl OpenLibraryMetadata {
                         FoundImageLocation::Url(CoverId::Id(id.to_string()).get_book_cover_url())
                     })
                     .collect(),
-                cached: BookItemCached::default(),
+                cached: BookItemCached::default().series_optional(series),
                 available_at: None,
                 year: None,
+                series_index,
+                language: book_info
+                    .languages
+                    .as_ref()
+                    .and_then(|v| v.first())
+                    .map(|v| strip_url_or_path(&v.key)),
+                page_count: book_info.number_of_pages.map(|v| v as i64),
+                subjects: book_info.subjects.unwrap_or_default(),
+                identifiers,
             },
         }))
     }
@@ -325,6 +386,28 @@ fn strip_url_or_path<V: AsRef<str>>(value: V) -> String {
         .to_owned()
 }
 
+/// Editions store the series as free text. (eg. "The Wheel of Time ; 2", "Discworld #14")
+fn parse_series(value: &str) -> (String, Option<f64>) {
+    if let Some((name, position)) = value
+        .rsplit_once(';')
+        .or_else(|| value.rsplit_once('#'))
+        .or_else(|| value.rsplit_once(" -- "))
+    {
+        let position = position.trim().to_lowercase();
+
+        let position = ["volume", "vol.", "v.", "book", "bk.", "no."]
+            .into_iter()
+            .find_map(|prefix| position.strip_prefix(prefix))
+            .unwrap_or(&position);
+
+        if let Ok(position) = position.trim().parse::<f64>() {
+            return (name.trim().to_string(), Some(position));
+        }
+    }
+
+    (value.trim().to_string(), None)
+}
+
 /*
 Types
     /type/text = "Normal Text" (used in: description)
@@ -371,6 +454,26 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_parse_series() {
+        assert_eq!(
+            parse_series("The Wheel of Time ; 2"),
+            (String::from("The Wheel of Time"), Some(2.0))
+        );
+        assert_eq!(
+            parse_series("Discworld #14"),
+            (String::from("Discworld"), Some(14.0))
+        );
+        assert_eq!(
+            parse_series("Tales of Dunk and Egg -- v. 2.5"),
+            (String::from("Tales of Dunk and Egg"), Some(2.5))
+        );
+        assert_eq!(
+            parse_series("A Song of Ice and Fire"),
+            (String::from("A Song of Ice and Fire"), None)
+        );
+    }
+
     #[test]
     fn test_json_parse_url() {
         let rt = Runtime::new().unwrap();
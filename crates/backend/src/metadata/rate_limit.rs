@@ -0,0 +1,56 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use reqwest::{IntoUrl, Response, StatusCode};
+use tracing::warn;
+
+/// How long to stop sending requests after being told we've sent too many.
+const TOO_MANY_REQUESTS_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Spaces out the requests sent to an agent. Shared between every concurrent request.
+pub struct RateLimiter {
+    interval: Duration,
+    next_request: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub const fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_request: Mutex::new(None),
+        }
+    }
+
+    /// Waits until we're allowed to send the next request.
+    pub async fn wait(&self) {
+        let send_at = {
+            let mut next_request = self.next_request.lock().unwrap();
+
+            let now = Instant::now();
+            let send_at = next_request.map_or(now, |v| v.max(now));
+
+            *next_request = Some(send_at + self.interval);
+
+            send_at
+        };
+
+        tokio::time::sleep_until(send_at.into()).await;
+    }
+
+    /// Rate limited version of [`reqwest::get`].
+    pub async fn get<U: IntoUrl>(&self, url: U) -> reqwest::Result<Response> {
+        self.wait().await;
+
+        let resp = reqwest::get(url).await?;
+
+        if resp.status() == StatusCode::TOO_MANY_REQUESTS {
+            warn!(url = %resp.url(), "Too many requests. Backing off.");
+
+            *self.next_request.lock().unwrap() = Some(Instant::now() + TOO_MANY_REQUESTS_BACKOFF);
+        }
+
+        Ok(resp)
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use common::MemberId;
+use common_local::{ApiToken, ApiTokenId, ApiTokenScope};
+use rusqlite::{params, OptionalExtension};
+
+use crate::{DatabaseAccess, Result};
+
+use super::{AdvRow, TableRow};
+
+pub struct NewApiTokenModel {
+    pub member_id: MemberId,
+
+    pub name: String,
+    pub prefix: String,
+    /// SHA-256 of the token. The token itself is never stored.
+    pub token_hash: String,
+    pub scope: ApiTokenScope,
+
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A personal access token. Sent by scripts and other clients instead of logging in.
+pub struct ApiTokenModel {
+    pub id: ApiTokenId,
+
+    pub member_id: MemberId,
+
+    pub name: String,
+    pub prefix: String,
+    pub token_hash: String,
+    pub scope: ApiTokenScope,
+
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TableRow<'_> for ApiTokenModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.next()?,
+
+            member_id: row.next()?,
+
+            name: row.next()?,
+            prefix: row.next()?,
+            token_hash: row.next()?,
+            scope: row.next()?,
+
+            expires_at: row.next_opt()?,
+            last_used_at: row.next_opt()?,
+            created_at: row.next()?,
+        })
+    }
+}
+
+impl From<ApiTokenModel> for ApiToken {
+    fn from(value: ApiTokenModel) -> Self {
+        ApiToken {
+            id: value.id,
+            name: value.name,
+            prefix: value.prefix,
+            scope: value.scope,
+            expires_at: value.expires_at,
+            last_used_at: value.last_used_at,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl NewApiTokenModel {
+    pub async fn insert(self, db: &dyn DatabaseAccess) -> Result<ApiTokenModel> {
+        let conn = db.write().await;
+
+        conn.execute(
+            r#"
+            INSERT INTO api_token (member_id, name, prefix, token_hash, scope, expires_at, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+            params![
+                self.member_id,
+                &self.name,
+                &self.prefix,
+                &self.token_hash,
+                self.scope,
+                self.expires_at,
+                self.created_at,
+            ],
+        )?;
+
+        Ok(ApiTokenModel {
+            id: ApiTokenId::from(conn.last_insert_rowid() as usize),
+            member_id: self.member_id,
+            name: self.name,
+            prefix: self.prefix,
+            token_hash: self.token_hash,
+            scope: self.scope,
+            expires_at: self.expires_at,
+            last_used_at: None,
+            created_at: self.created_at,
+        })
+    }
+}
+
+impl ApiTokenModel {
+    /// Expired tokens aren't returned.
+    pub async fn find_one_by_token_hash(
+        value: &str,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(
+                r#"SELECT * FROM api_token WHERE token_hash = ?1 AND (expires_at IS NULL OR expires_at > ?2)"#,
+                params![value, Utc::now()],
+                |v| Self::from_row(v),
+            )
+            .optional()?)
+    }
+
+    pub async fn find_by_member_id(
+        member_id: MemberId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let mut conn =
+            this.prepare("SELECT * FROM api_token WHERE member_id = ?1 ORDER BY created_at DESC")?;
+
+        let map = conn.query_map([member_id], |v| Self::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn update_last_used_at(
+        id: ApiTokenId,
+        value: DateTime<Utc>,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        db.write().await.execute(
+            r#"UPDATE api_token SET last_used_at = ?1 WHERE id = ?2"#,
+            params![value, id],
+        )?;
+
+        Ok(())
+    }
+
+    pub async fn delete(
+        id: ApiTokenId,
+        member_id: MemberId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<usize> {
+        Ok(db.write().await.execute(
+            r#"DELETE FROM api_token WHERE id = ?1 AND member_id = ?2"#,
+            params![id, member_id],
+        )?)
+    }
+}
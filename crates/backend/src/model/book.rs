@@ -5,11 +5,17 @@ use rusqlite::{params, OptionalExtension};
 use crate::{DatabaseAccess, Result};
 use common_local::{
     filter::{FilterContainer, FilterModifier, FilterTableType},
-    BookEdit, BookItemCached, DisplayBookItem, LibraryId,
+    BookEdit, BookIdentifier, BookItemCached, DisplayBookItem, LibraryId, MetadataField,
+    PublisherId, SeriesId,
 };
 use serde::Serialize;
 
-use super::{book_person::BookPersonModel, AdvRow, TableRow};
+use super::{
+    book_field_source::BookFieldSourceModel, book_identifier::BookIdentifierModel,
+    book_locked_field::BookLockedFieldModel, book_person::BookPersonModel,
+    book_subject::BookSubjectModel, member_library::LibraryAccess, publisher::PublisherModel,
+    series::SeriesModel, subject::SubjectModel, AdvRow, TableRow,
+};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct BookModel {
@@ -37,6 +43,17 @@ pub struct BookModel {
 
     pub available_at: Option<DateTime<Utc>>,
     pub year: Option<i64>,
+
+    pub series_id: Option<SeriesId>,
+    pub series_index: Option<f64>,
+
+    pub publisher_id: Option<PublisherId>,
+    pub language: Option<String>,
+    pub page_count: Option<i64>,
+
+    // Stored in their own tables.
+    pub subjects: Vec<String>,
+    pub identifiers: Vec<BookIdentifier>,
 }
 
 impl From<BookModel> for DisplayBookItem {
@@ -58,6 +75,11 @@ impl From<BookModel> for DisplayBookItem {
             deleted_at: val.deleted_at,
             available_at: val.available_at.map(|v| v.timestamp_millis()),
             year: val.year,
+            series_id: val.series_id,
+            series_index: val.series_index,
+            publisher_id: val.publisher_id,
+            language: val.language,
+            page_count: val.page_count,
         }
     }
 }
@@ -85,6 +107,13 @@ impl TableRow<'_> for BookModel {
             created_at: row.next()?,
             updated_at: row.next()?,
             deleted_at: row.next_opt()?,
+            series_id: row.next_opt()?,
+            series_index: row.next_opt()?,
+            publisher_id: row.next_opt()?,
+            language: row.next_opt()?,
+            page_count: row.next_opt()?,
+            subjects: Vec::new(),
+            identifiers: Vec::new(),
         })
     }
 }
@@ -105,9 +134,11 @@ impl BookModel {
                     title, original_title, description, rating, thumb_url,
                     cached,
                     available_at, year,
-                    refreshed_at, created_at, updated_at, deleted_at
+                    refreshed_at, created_at, updated_at, deleted_at,
+                    series_id, series_index,
+                    publisher_id, language, page_count
                 )
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)"#,
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)"#,
                 params![
                     self.library_id,
                     self.source.to_string(),
@@ -124,10 +155,21 @@ impl BookModel {
                     self.created_at,
                     self.updated_at,
                     self.deleted_at,
+                    self.series_id,
+                    self.series_index,
+                    self.publisher_id,
+                    &self.language,
+                    self.page_count,
                 ],
             )?;
 
-            return Ok(Self::find_one_by_source(&self.source, db).await?.unwrap());
+            let mut book = Self::find_one_by_source(&self.source, db).await?.unwrap();
+
+            book.subjects = self.subjects.clone();
+            book.identifiers = self.identifiers.clone();
+            book.insert_subjects_and_identifiers(db).await?;
+
+            return Ok(book);
         } else if self.id != 0 {
             db.write().await.execute(
                 r#"UPDATE book SET file_item_count = file_item_count + 1 WHERE id = ?1"#,
@@ -153,7 +195,9 @@ impl BookModel {
                 title = ?5, original_title = ?6, description = ?7, rating = ?8, thumb_url = ?9,
                 cached = ?10,
                 available_at = ?11, year = ?12,
-                refreshed_at = ?13, updated_at = ?14, deleted_at = ?15
+                refreshed_at = ?13, updated_at = ?14, deleted_at = ?15,
+                series_id = ?16, series_index = ?17,
+                publisher_id = ?18, language = ?19, page_count = ?20
             WHERE id = ?1"#,
             params![
                 self.id,
@@ -171,12 +215,121 @@ impl BookModel {
                 self.refreshed_at,
                 self.updated_at,
                 self.deleted_at,
+                self.series_id,
+                self.series_index,
+                self.publisher_id,
+                &self.language,
+                self.page_count,
             ],
         )?;
 
         Ok(())
     }
 
+    /// Keeps the old values of the fields. Used so a refresh doesn't overwrite locked fields.
+    pub fn keep_fields(&mut self, old: &BookModel, fields: &[MetadataField]) {
+        for field in fields {
+            match field {
+                MetadataField::Title => {
+                    self.title = old.title.clone();
+                    self.original_title = old.original_title.clone();
+                }
+                MetadataField::Description => self.description = old.description.clone(),
+                MetadataField::Rating => self.rating = old.rating,
+                MetadataField::Cover => self.thumb_path = old.thumb_path.clone(),
+                MetadataField::Authors => self.cached.author = old.cached.author.clone(),
+                MetadataField::Publisher => {
+                    self.cached.publisher = old.cached.publisher.clone();
+                    self.publisher_id = old.publisher_id;
+                }
+                MetadataField::Year => self.year = old.year,
+                MetadataField::AvailableAt => self.available_at = old.available_at,
+                MetadataField::Series => {
+                    self.cached.series = old.cached.series.clone();
+                    self.cached.volume = old.cached.volume.clone();
+                    self.cached.issue = old.cached.issue.clone();
+                    self.series_id = old.series_id;
+                    self.series_index = old.series_index;
+                }
+                MetadataField::Language => self.language = old.language.clone(),
+                MetadataField::PageCount => self.page_count = old.page_count,
+                // Only new ones are inserted.
+                MetadataField::Subjects => self.subjects.clear(),
+                MetadataField::Identifiers => self.identifiers.clear(),
+            }
+        }
+    }
+
+    /// Links the book to the series in its cached metadata. Creates the series if it doesn't exist.
+    ///
+    /// Doesn't save the book.
+    pub async fn link_series(&mut self, db: &dyn DatabaseAccess) -> Result<()> {
+        let Some(name) = self
+            .cached
+            .series
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+        else {
+            return Ok(());
+        };
+
+        let mut series = SeriesModel::find_or_insert(name, db).await?;
+
+        // Use the first cover we find for the series.
+        if series.thumb_url.is_none() && self.thumb_path.is_some() {
+            series.thumb_url = self.thumb_path.clone();
+            series.update(db).await?;
+        }
+
+        self.series_id = Some(series.id);
+
+        Ok(())
+    }
+
+    /// Links the book to the publisher in its cached metadata. Creates the publisher if it doesn't exist.
+    ///
+    /// Doesn't save the book.
+    pub async fn link_publisher(&mut self, db: &dyn DatabaseAccess) -> Result<()> {
+        if let Some(name) = self
+            .cached
+            .publisher
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+        {
+            self.publisher_id = Some(PublisherModel::find_or_insert(name, db).await?.id);
+        }
+
+        Ok(())
+    }
+
+    /// Adds the subjects and identifiers to the saved book. Existing ones are kept.
+    pub async fn insert_subjects_and_identifiers(&self, db: &dyn DatabaseAccess) -> Result<()> {
+        for name in &self.subjects {
+            let name = name.trim();
+
+            if name.is_empty() {
+                continue;
+            }
+
+            BookSubjectModel {
+                book_id: self.id,
+                subject_id: SubjectModel::find_or_insert(name, db).await?.id,
+            }
+            .insert_or_ignore(db)
+            .await?;
+        }
+
+        for identifier in &self.identifiers {
+            BookIdentifierModel::new(self.id, identifier.clone())
+                .insert_or_ignore(db)
+                .await?;
+        }
+
+        Ok(())
+    }
+
     pub async fn delete_or_decrement(id: BookId, db: &dyn DatabaseAccess) -> Result<()> {
         if let Some(model) = Self::find_one_by_id(id, db).await? {
             if model.file_item_count < 1 {
@@ -291,6 +444,72 @@ impl BookModel {
         edit: BookEdit,
         db: &dyn DatabaseAccess,
     ) -> Result<usize> {
+        // The publisher name is also cached for the book list.
+        let (publisher_id, cached) = match edit.publisher.as_ref() {
+            Some(name) => {
+                let publisher = match name.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                    Some(name) => Some(PublisherModel::find_or_insert(name, db).await?),
+                    None => None,
+                };
+
+                let mut cached = Self::find_one_by_id(book_id, db)
+                    .await?
+                    .map(|v| v.cached)
+                    .unwrap_or_default();
+
+                cached.publisher = publisher.as_ref().map(|v| v.name.clone());
+
+                (
+                    Some(publisher.map(|v| v.id)),
+                    Some(cached.as_string_optional()),
+                )
+            }
+
+            None => (None, None),
+        };
+
+        // Refreshes won't overwrite these anymore.
+        let edited_fields = [
+            (
+                MetadataField::Title,
+                edit.title.is_some() || edit.original_title.is_some(),
+            ),
+            (MetadataField::Description, edit.description.is_some()),
+            (MetadataField::Rating, edit.rating.is_some()),
+            (MetadataField::AvailableAt, edit.available_at.is_some()),
+            (MetadataField::Year, edit.year.is_some()),
+            (MetadataField::Publisher, edit.publisher.is_some()),
+            (MetadataField::Language, edit.language.is_some()),
+            (MetadataField::PageCount, edit.page_count.is_some()),
+            (
+                MetadataField::Authors,
+                edit.added_people.is_some() || edit.removed_people.is_some(),
+            ),
+            (
+                MetadataField::Subjects,
+                edit.added_subjects.is_some() || edit.removed_subjects.is_some(),
+            ),
+            (
+                MetadataField::Identifiers,
+                edit.added_identifiers.is_some() || edit.removed_identifiers.is_some(),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(field, is_edited)| is_edited.then_some(field))
+        .collect::<Vec<_>>();
+
+        if let Some(fields) = edit.locked_fields.as_ref() {
+            BookLockedFieldModel::delete_by_book_id(book_id, db).await?;
+
+            for &field in fields {
+                BookLockedFieldModel { book_id, field }
+                    .insert_or_ignore(db)
+                    .await?;
+            }
+        }
+
+        BookFieldSourceModel::set_user_edited(book_id, &edited_fields, db).await?;
+
         let mut items = Vec::new();
 
         let mut values = vec![&book_id as &dyn rusqlite::ToSql];
@@ -325,8 +544,24 @@ impl BookModel {
             values.push(value as &dyn rusqlite::ToSql);
         }
 
-        if let Some(_value) = edit.publisher {
-            // TODO
+        if let Some(value) = publisher_id.as_ref() {
+            items.push("publisher_id");
+            values.push(value as &dyn rusqlite::ToSql);
+        }
+
+        if let Some(value) = cached.as_ref() {
+            items.push("cached");
+            values.push(value as &dyn rusqlite::ToSql);
+        }
+
+        if let Some(value) = edit.language.as_ref() {
+            items.push("language");
+            values.push(value as &dyn rusqlite::ToSql);
+        }
+
+        if let Some(value) = edit.page_count.as_ref() {
+            items.push("page_count");
+            values.push(value as &dyn rusqlite::ToSql);
         }
 
         if let Some(ids) = edit.added_people {
@@ -343,6 +578,46 @@ impl BookModel {
             }
         }
 
+        if let Some(names) = edit.added_subjects {
+            for name in names.iter().map(|v| v.trim()).filter(|v| !v.is_empty()) {
+                BookSubjectModel {
+                    book_id,
+                    subject_id: SubjectModel::find_or_insert(name, db).await?.id,
+                }
+                .insert_or_ignore(db)
+                .await?;
+            }
+        }
+
+        if let Some(names) = edit.removed_subjects {
+            for name in names {
+                if let Some(subject) = SubjectModel::find_one_by_name(name.trim(), db).await? {
+                    BookSubjectModel {
+                        book_id,
+                        subject_id: subject.id,
+                    }
+                    .delete(db)
+                    .await?;
+                }
+            }
+        }
+
+        if let Some(identifiers) = edit.added_identifiers {
+            for identifier in identifiers {
+                BookIdentifierModel::new(book_id, identifier)
+                    .insert_or_ignore(db)
+                    .await?;
+            }
+        }
+
+        if let Some(identifiers) = edit.removed_identifiers {
+            for identifier in identifiers {
+                BookIdentifierModel::new(book_id, identifier)
+                    .delete(db)
+                    .await?;
+            }
+        }
+
         if items.is_empty() {
             return Ok(0);
         }
@@ -362,9 +637,12 @@ impl BookModel {
     }
 
     // Search
-    fn gen_search_query(filter: &FilterContainer, library: Option<LibraryId>) -> String {
-        let mut sql = String::from("SELECT * FROM book WHERE ");
-        let orig_len = sql.len();
+    fn gen_search_query(
+        filter: &FilterContainer,
+        library: Option<LibraryId>,
+        access: &LibraryAccess,
+    ) -> String {
+        let mut sql = String::from("SELECT * FROM book ");
 
         let mut f_comp = Vec::new();
 
@@ -373,6 +651,10 @@ impl BookModel {
             f_comp.push(format!("library_id={} ", library));
         }
 
+        if let Some(condition) = access.sql_condition() {
+            f_comp.push(format!("{condition} "));
+        }
+
         for fil in &filter.filters {
             match fil.type_of {
                 FilterTableType::Id => todo!(),
@@ -417,6 +699,97 @@ impl BookModel {
                     }
                 }
 
+                FilterTableType::Series | FilterTableType::Publisher => {
+                    let column = if fil.type_of == FilterTableType::Series {
+                        "series_id"
+                    } else {
+                        "publisher_id"
+                    };
+
+                    match fil.modifier {
+                        FilterModifier::IsNull | FilterModifier::IsNotNull => {
+                            f_comp.push(format!(
+                                "{column} {}",
+                                get_modifier(fil.type_of, fil.modifier)
+                            ));
+                        }
+
+                        v => {
+                            for id in fil.value.values() {
+                                f_comp.push(format!(
+                                    "{column} {} {}",
+                                    get_modifier(fil.type_of, v),
+                                    id.parse::<usize>().unwrap_or_default()
+                                ));
+                            }
+                        }
+                    }
+                }
+
+                FilterTableType::Language => match fil.modifier {
+                    FilterModifier::IsNull | FilterModifier::IsNotNull => {
+                        f_comp.push(format!(
+                            "language {}",
+                            get_modifier(fil.type_of, fil.modifier)
+                        ));
+                    }
+
+                    v => {
+                        // "en" also matches "en-US"
+                        for query in fil.value.values() {
+                            f_comp.push(format!(
+                                "language {} '{}%'",
+                                get_modifier(fil.type_of, v),
+                                query.replace('\'', "''")
+                            ));
+                        }
+                    }
+                },
+
+                FilterTableType::Subject | FilterTableType::Identifier => {
+                    let inner_query = if fil.type_of == FilterTableType::Subject {
+                        "SELECT book_id FROM book_subject JOIN subject ON subject.id = book_subject.subject_id WHERE book_id = book.id"
+                    } else {
+                        "SELECT book_id FROM book_identifier WHERE book_id = book.id"
+                    };
+
+                    let column = if fil.type_of == FilterTableType::Subject {
+                        "subject.name"
+                    } else {
+                        "value"
+                    };
+
+                    match fil.modifier {
+                        FilterModifier::IsNull => {
+                            f_comp.push(format!("id NOT IN ({inner_query})"));
+                        }
+
+                        FilterModifier::IsNotNull => {
+                            f_comp.push(format!("id IN ({inner_query})"));
+                        }
+
+                        // Books without it, not books with another one.
+                        FilterModifier::DoesNotEqual => {
+                            for query in fil.value.values() {
+                                f_comp.push(format!(
+                                    "id NOT IN ({inner_query} AND {column} = '{}')",
+                                    normalize_filter_value(fil.type_of, &query)
+                                ));
+                            }
+                        }
+
+                        v => {
+                            for query in fil.value.values() {
+                                f_comp.push(format!(
+                                    "id IN ({inner_query} AND {column} {} '{}')",
+                                    get_modifier(fil.type_of, v),
+                                    normalize_filter_value(fil.type_of, &query)
+                                ));
+                            }
+                        }
+                    }
+                }
+
                 FilterTableType::Person => {
                     for pid in fil.value.values() {
                         match fil.modifier {
@@ -445,38 +818,52 @@ impl BookModel {
             }
         }
 
-        sql += &f_comp.join(" AND ");
+        if !f_comp.is_empty() {
+            sql += "WHERE ";
+            sql += &f_comp.join(" AND ");
+        }
 
         if let Some((order_name, is_desc)) = filter.order_by {
+            let direction = if is_desc { "DESC" } else { "ASC" };
+
             let field_name = match order_name {
-                FilterTableType::Id => "id",
-                FilterTableType::Query => "title",
-                FilterTableType::CreatedAt => "created_at",
+                FilterTableType::Id => String::from("id"),
+                FilterTableType::Query => String::from("title"),
+                FilterTableType::CreatedAt => String::from("created_at"),
                 FilterTableType::Source => todo!(),
                 FilterTableType::Person => todo!(),
+                // By the first one alphabetically.
+                FilterTableType::Subject => String::from(
+                    "(SELECT MIN(subject.name) FROM book_subject JOIN subject ON subject.id = book_subject.subject_id WHERE book_subject.book_id = book.id)",
+                ),
+                FilterTableType::Identifier => String::from(
+                    "(SELECT MIN(value) FROM book_identifier WHERE book_identifier.book_id = book.id)",
+                ),
+                FilterTableType::Language => String::from("language"),
+                FilterTableType::Publisher => String::from(
+                    "(SELECT name FROM publisher WHERE publisher.id = book.publisher_id)",
+                ),
+                // Grouped by series name, then by their position in it.
+                FilterTableType::Series => format!(
+                    "(SELECT name FROM series WHERE series.id = book.series_id) {direction}, series_index"
+                ),
             };
 
-            sql += &format!(
-                " ORDER BY {field_name} {} ",
-                if is_desc { "DESC" } else { "ASC" }
-            );
+            sql += &format!(" ORDER BY {field_name} {direction} ");
         }
 
-        if sql.len() == orig_len {
-            String::from("SELECT * FROM book ")
-        } else {
-            sql
-        }
+        sql
     }
 
     pub async fn search_by(
         filter: &FilterContainer,
         library: Option<LibraryId>,
+        access: &LibraryAccess,
         offset: usize,
         limit: usize,
         db: &dyn DatabaseAccess,
     ) -> Result<Vec<Self>> {
-        let mut sql = Self::gen_search_query(filter, library);
+        let mut sql = Self::gen_search_query(filter, library, access);
 
         sql += "LIMIT ?1 OFFSET ?2";
 
@@ -492,9 +879,11 @@ impl BookModel {
     pub async fn count_search_by(
         filter: &FilterContainer,
         library: Option<LibraryId>,
+        access: &LibraryAccess,
         db: &dyn DatabaseAccess,
     ) -> Result<usize> {
-        let sql = Self::gen_search_query(filter, library).replace("SELECT *", "SELECT COUNT(*)");
+        let sql =
+            Self::gen_search_query(filter, library, access).replace("SELECT *", "SELECT COUNT(*)");
 
         Ok(db.read().await.query_row(&sql, [], |v| v.get(0))?)
     }
@@ -503,7 +892,9 @@ impl BookModel {
 fn get_modifier(type_of: FilterTableType, modi: FilterModifier) -> &'static str {
     match (type_of, modi) {
         (FilterTableType::Source, FilterModifier::Equal)
-        | (FilterTableType::Query, FilterModifier::Equal) => "LIKE",
+        | (FilterTableType::Query, FilterModifier::Equal)
+        | (FilterTableType::Language, FilterModifier::Equal) => "LIKE",
+        (FilterTableType::Language, FilterModifier::DoesNotEqual) => "NOT LIKE",
 
         (_, FilterModifier::IsNull) => "IS NULL",
         (_, FilterModifier::IsNotNull) => "IS NOT NULL",
@@ -515,3 +906,16 @@ fn get_modifier(type_of: FilterTableType, modi: FilterModifier) -> &'static str
         (_, FilterModifier::DoesNotEqual) => "!=",
     }
 }
+
+/// Escapes the value for a SQL string. Identifiers are stored without separators.
+fn normalize_filter_value(type_of: FilterTableType, value: &str) -> String {
+    let value = if type_of == FilterTableType::Identifier {
+        BookIdentifier::parse(value)
+            .map(|v| v.value)
+            .unwrap_or_else(|| value.trim().to_string())
+    } else {
+        value.trim().to_string()
+    };
+
+    value.replace('\'', "''")
+}
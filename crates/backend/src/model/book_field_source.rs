@@ -0,0 +1,64 @@
+use chrono::Utc;
+use common::BookId;
+use common_local::{MetadataAgent, MetadataField};
+use rusqlite::params;
+
+use crate::{DatabaseAccess, Result};
+
+use super::book_locked_field::BookLockedFieldModel;
+
+/// Stored as the agent when a user edits a field.
+const USER_EDITED: &str = "user";
+
+/// Where the current value of a book field came from.
+pub struct BookFieldSourceModel;
+
+impl BookFieldSourceModel {
+    /// Records which agent each field was merged from. Locked fields are skipped.
+    pub async fn insert_or_replace(
+        book_id: BookId,
+        sources: &[(MetadataField, MetadataAgent)],
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        let now = Utc::now();
+
+        let conn = db.write().await;
+
+        for (field, agent) in sources {
+            conn.execute(
+                r#"
+                INSERT INTO book_field_source (book_id, field, agent, updated_at)
+                SELECT ?1, ?2, ?3, ?4
+                WHERE NOT EXISTS (SELECT * FROM book_locked_field WHERE book_id = ?1 AND field = ?2)
+                ON CONFLICT(book_id, field) DO UPDATE SET
+                    agent = excluded.agent,
+                    updated_at = excluded.updated_at"#,
+                params![book_id, field, agent.prefix(), now],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Also locks the fields so refreshes won't overwrite them.
+    pub async fn set_user_edited(
+        book_id: BookId,
+        fields: &[MetadataField],
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        let now = Utc::now();
+
+        for &field in fields {
+            db.write().await.execute(
+                r#"INSERT OR REPLACE INTO book_field_source (book_id, field, agent, updated_at) VALUES (?1, ?2, ?3, ?4)"#,
+                params![book_id, field, USER_EDITED, now],
+            )?;
+
+            BookLockedFieldModel { book_id, field }
+                .insert_or_ignore(db)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use common::BookId;
+use common_local::{BookIdentifier, IdentifierType};
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::{DatabaseAccess, Result};
+
+use super::{AdvRow, TableRow};
+
+#[derive(Debug, Serialize)]
+pub struct BookIdentifierModel {
+    pub book_id: BookId,
+
+    pub type_of: IdentifierType,
+    pub value: String,
+}
+
+impl TableRow<'_> for BookIdentifierModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            book_id: row.next()?,
+
+            type_of: row.next()?,
+            value: row.next()?,
+        })
+    }
+}
+
+impl From<BookIdentifierModel> for BookIdentifier {
+    fn from(value: BookIdentifierModel) -> Self {
+        BookIdentifier {
+            type_of: value.type_of,
+            value: value.value,
+        }
+    }
+}
+
+impl BookIdentifierModel {
+    pub fn new(book_id: BookId, value: BookIdentifier) -> Self {
+        Self {
+            book_id,
+            type_of: value.type_of,
+            value: value.value,
+        }
+    }
+
+    pub async fn insert_or_ignore(&self, db: &dyn DatabaseAccess) -> Result<()> {
+        db.write().await.execute(
+            r#"INSERT OR IGNORE INTO book_identifier (book_id, type_of, value) VALUES (?1, ?2, ?3)"#,
+            params![self.book_id, self.type_of, &self.value],
+        )?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, db: &dyn DatabaseAccess) -> Result<()> {
+        db.write().await.execute(
+            r#"DELETE FROM book_identifier WHERE book_id = ?1 AND type_of = ?2 AND value = ?3"#,
+            params![self.book_id, self.type_of, &self.value],
+        )?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_book_id(id: BookId, db: &dyn DatabaseAccess) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let mut conn =
+            this.prepare(r#"SELECT * FROM book_identifier WHERE book_id = ?1 ORDER BY type_of"#)?;
+
+        let map = conn.query_map([id], |v| Self::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+}
@@ -0,0 +1,44 @@
+use common::BookId;
+use common_local::MetadataField;
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::{DatabaseAccess, Result};
+
+/// A field which refreshing the book won't overwrite.
+#[derive(Debug, Serialize)]
+pub struct BookLockedFieldModel {
+    pub book_id: BookId,
+    pub field: MetadataField,
+}
+
+impl BookLockedFieldModel {
+    pub async fn insert_or_ignore(&self, db: &dyn DatabaseAccess) -> Result<()> {
+        db.write().await.execute(
+            r#"INSERT OR IGNORE INTO book_locked_field (book_id, field) VALUES (?1, ?2)"#,
+            params![self.book_id, self.field],
+        )?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_book_id(
+        book_id: BookId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<MetadataField>> {
+        let this = db.read().await;
+
+        let mut conn = this.prepare(r#"SELECT field FROM book_locked_field WHERE book_id = ?1"#)?;
+
+        let map = conn.query_map([book_id], |v| v.get(0))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn delete_by_book_id(book_id: BookId, db: &dyn DatabaseAccess) -> Result<usize> {
+        Ok(db.write().await.execute(
+            r#"DELETE FROM book_locked_field WHERE book_id = ?1"#,
+            [book_id],
+        )?)
+    }
+}
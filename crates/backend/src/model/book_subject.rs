@@ -0,0 +1,32 @@
+use common::BookId;
+use common_local::SubjectId;
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::{DatabaseAccess, Result};
+
+#[derive(Debug, Serialize)]
+pub struct BookSubjectModel {
+    pub book_id: BookId,
+    pub subject_id: SubjectId,
+}
+
+impl BookSubjectModel {
+    pub async fn insert_or_ignore(&self, db: &dyn DatabaseAccess) -> Result<()> {
+        db.write().await.execute(
+            r#"INSERT OR IGNORE INTO book_subject (book_id, subject_id) VALUES (?1, ?2)"#,
+            params![self.book_id, self.subject_id],
+        )?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, db: &dyn DatabaseAccess) -> Result<()> {
+        db.write().await.execute(
+            r#"DELETE FROM book_subject WHERE book_id = ?1 AND subject_id = ?2"#,
+            params![self.book_id, self.subject_id],
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use chrono::{DateTime, Utc};
+use common::MemberId;
+use common_local::{Bookmark, BookmarkId, FileId};
+use rusqlite::{params, OptionalExtension};
+
+use crate::{DatabaseAccess, Result};
+
+use super::{AdvRow, TableRow};
+
+pub struct NewFileBookmarkModel {
+    pub file_id: FileId,
+    pub member_id: MemberId,
+
+    pub chapter: i64,
+    pub char_pos: i64,
+
+    pub label: Option<String>,
+    pub snippet: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+/// A named position a member saved in a file.
+pub struct FileBookmarkModel {
+    pub id: BookmarkId,
+
+    pub file_id: FileId,
+    pub member_id: MemberId,
+
+    pub chapter: i64,
+    pub char_pos: i64,
+
+    pub label: Option<String>,
+    pub snippet: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl TableRow<'_> for FileBookmarkModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.next()?,
+
+            file_id: row.next()?,
+            member_id: row.next()?,
+
+            chapter: row.next()?,
+            char_pos: row.next()?,
+
+            label: row.next()?,
+            snippet: row.next()?,
+
+            created_at: row.next()?,
+        })
+    }
+}
+
+impl From<FileBookmarkModel> for Bookmark {
+    fn from(value: FileBookmarkModel) -> Self {
+        Bookmark {
+            id: value.id,
+            chapter: value.chapter as usize,
+            char_pos: value.char_pos as usize,
+            label: value.label,
+            snippet: value.snippet,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl NewFileBookmarkModel {
+    pub async fn insert(self, db: &dyn DatabaseAccess) -> Result<FileBookmarkModel> {
+        let conn = db.write().await;
+
+        conn.execute(
+            r#"
+            INSERT INTO file_bookmark (file_id, member_id, chapter, char_pos, label, snippet, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+            params![
+                self.file_id,
+                self.member_id,
+                self.chapter,
+                self.char_pos,
+                &self.label,
+                &self.snippet,
+                self.created_at,
+            ],
+        )?;
+
+        Ok(FileBookmarkModel {
+            id: BookmarkId::from(conn.last_insert_rowid() as usize),
+            file_id: self.file_id,
+            member_id: self.member_id,
+            chapter: self.chapter,
+            char_pos: self.char_pos,
+            label: self.label,
+            snippet: self.snippet,
+            created_at: self.created_at,
+        })
+    }
+}
+
+impl FileBookmarkModel {
+    pub async fn find_one(
+        id: BookmarkId,
+        member_id: MemberId,
+        file_id: FileId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(
+                "SELECT * FROM file_bookmark WHERE id = ?1 AND member_id = ?2 AND file_id = ?3",
+                params![id, member_id, file_id],
+                |v| Self::from_row(v),
+            )
+            .optional()?)
+    }
+
+    /// Ordered by their position in the file.
+    pub async fn find_by_file_id(
+        member_id: MemberId,
+        file_id: FileId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let mut conn = this.prepare(
+            "SELECT * FROM file_bookmark WHERE member_id = ?1 AND file_id = ?2 ORDER BY chapter, char_pos",
+        )?;
+
+        let map = conn.query_map(params![member_id, file_id], |v| Self::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn update_label(&self, db: &dyn DatabaseAccess) -> Result<()> {
+        db.write().await.execute(
+            "UPDATE file_bookmark SET label = ?1 WHERE id = ?2",
+            params![&self.label, self.id],
+        )?;
+
+        Ok(())
+    }
+
+    pub async fn delete(
+        id: BookmarkId,
+        member_id: MemberId,
+        file_id: FileId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<usize> {
+        Ok(db.write().await.execute(
+            "DELETE FROM file_bookmark WHERE id = ?1 AND member_id = ?2 AND file_id = ?3",
+            params![id, member_id, file_id],
+        )?)
+    }
+}
@@ -28,6 +28,9 @@ pub struct NewFileModel {
     pub accessed_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
+
+    /// The document identifier KOReader uses.
+    pub partial_md5: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -51,6 +54,9 @@ pub struct FileModel {
     pub accessed_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
+
+    /// The document identifier KOReader uses.
+    pub partial_md5: Option<String>,
 }
 
 impl From<FileModel> for MediaItem {
@@ -101,6 +107,8 @@ impl TableRow<'_> for FileModel {
             accessed_at: row.next()?,
             created_at: row.next()?,
             deleted_at: row.next_opt()?,
+
+            partial_md5: row.next_opt()?,
         })
     }
 }
@@ -122,6 +130,7 @@ impl NewFileModel {
             accessed_at: self.accessed_at,
             created_at: self.created_at,
             deleted_at: self.deleted_at,
+            partial_md5: self.partial_md5,
         }
     }
 
@@ -129,14 +138,15 @@ impl NewFileModel {
         let conn = db.write().await;
 
         conn.execute(r#"
-            INSERT INTO file (path, file_type, file_name, file_size, modified_at, accessed_at, created_at, identifier, hash, library_id, book_id, chapter_count)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            INSERT INTO file (path, file_type, file_name, file_size, modified_at, accessed_at, created_at, identifier, hash, library_id, book_id, chapter_count, partial_md5)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
         "#,
         params![
             &self.path, &self.file_type, &self.file_name, self.file_size,
             self.modified_at, self.accessed_at, self.created_at,
             self.identifier.as_deref(), &self.hash,
-            self.library_id, self.book_id, self.chapter_count
+            self.library_id, self.book_id, self.chapter_count,
+            self.partial_md5.as_deref()
         ])?;
 
         Ok(self.into_file(FileId::from(conn.last_insert_rowid() as usize)))
@@ -168,6 +178,19 @@ impl FileModel {
         Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
     }
 
+    pub async fn find_by_library_id(
+        library_id: LibraryId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let mut conn = this.prepare("SELECT * FROM file WHERE library_id = ?1")?;
+
+        let map = conn.query_map([library_id], |v| Self::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
     pub async fn find_with_book_by(
         library: usize,
         offset: usize,
@@ -217,6 +240,88 @@ impl FileModel {
             .optional()?)
     }
 
+    pub async fn find_one_by_path(path: &str, db: &dyn DatabaseAccess) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(r#"SELECT * FROM file WHERE path = ?1"#, [path], |v| {
+                Self::from_row(v)
+            })
+            .optional()?)
+    }
+
+    /// Files inside of a directory. Prefix should end with a slash.
+    pub async fn find_by_path_prefix(prefix: &str, db: &dyn DatabaseAccess) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let mut conn = this.prepare("SELECT * FROM file WHERE substr(path, 1, length(?1)) = ?1")?;
+
+        let map = conn.query_map([prefix], |v| Self::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn find_one_by_hash(hash: &str, db: &dyn DatabaseAccess) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(r#"SELECT * FROM file WHERE hash = ?1"#, [hash], |v| {
+                Self::from_row(v)
+            })
+            .optional()?)
+    }
+
+    pub async fn find_one_by_partial_md5(
+        value: &str,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(
+                r#"SELECT * FROM file WHERE partial_md5 = ?1 AND deleted_at IS NULL"#,
+                [value],
+                |v| Self::from_row(v),
+            )
+            .optional()?)
+    }
+
+    pub async fn find_by_file_type(file_type: &str, db: &dyn DatabaseAccess) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let mut conn =
+            this.prepare("SELECT * FROM file WHERE file_type = ?1 AND deleted_at IS NULL")?;
+
+        let map = conn.query_map([file_type], |v| Self::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    /// Files scanned before the partial MD5 was stored.
+    pub async fn find_by_missing_partial_md5(db: &dyn DatabaseAccess) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let mut conn =
+            this.prepare("SELECT * FROM file WHERE partial_md5 IS NULL AND deleted_at IS NULL")?;
+
+        let map = conn.query_map([], |v| Self::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn update_partial_md5(
+        file_id: FileId,
+        value: &str,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        db.write().await.execute(
+            r#"UPDATE file SET partial_md5 = ?1 WHERE id = ?2"#,
+            params![value, file_id],
+        )?;
+
+        Ok(())
+    }
+
     pub async fn find_one_by_id(id: FileId, db: &dyn DatabaseAccess) -> Result<Option<Self>> {
         Ok(db
             .read()
@@ -300,8 +405,9 @@ impl FileModel {
             r#"
             UPDATE file SET
                 path = ?2, file_name = ?3, file_type = ?4, file_size = ?5,
-                library_id = ?6, book_id = ?7, chapter_count = ?8, identifier = ?9,
-                modified_at = ?10, accessed_at = ?11, created_at = ?12, deleted_at = ?13
+                library_id = ?6, book_id = ?7, chapter_count = ?8, identifier = ?9, hash = ?10,
+                modified_at = ?11, accessed_at = ?12, created_at = ?13, deleted_at = ?14,
+                partial_md5 = ?15
             WHERE id = ?1"#,
             params![
                 self.id,
@@ -313,16 +419,34 @@ impl FileModel {
                 self.book_id,
                 self.chapter_count,
                 self.identifier,
+                &self.hash,
                 self.modified_at,
                 self.accessed_at,
                 self.created_at,
                 self.deleted_at,
+                self.partial_md5,
             ],
         )?;
 
         Ok(())
     }
 
+    pub async fn mark_deleted(
+        file_ids: &[FileId],
+        deleted_at: DateTime<Utc>,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        let conn = db.write().await;
+
+        let mut stmt = conn.prepare(r#"UPDATE file SET deleted_at = ?1 WHERE id = ?2"#)?;
+
+        for file_id in file_ids {
+            stmt.execute(params![deleted_at, file_id])?;
+        }
+
+        Ok(())
+    }
+
     pub async fn transfer_book_id(
         old_book_id: BookId,
         new_book_id: BookId,
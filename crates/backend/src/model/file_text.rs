@@ -0,0 +1,297 @@
+use chrono::{DateTime, Utc};
+use common_local::{api::SnippetPart, FileId, LibraryId};
+use rusqlite::params;
+
+use crate::{DatabaseAccess, Result};
+
+use super::{book::BookModel, file::FileModel, member_library::LibraryAccess, AdvRow, TableRow};
+
+/// Placed around the matched words of a snippet.
+pub const SNIPPET_START: &str = "\u{2}";
+pub const SNIPPET_END: &str = "\u{3}";
+
+/// Which version of a file is in the full-text index.
+pub struct FileTextModel {
+    pub file_id: FileId,
+    /// The hash of the file when it was indexed.
+    pub hash: String,
+    pub indexed_at: DateTime<Utc>,
+}
+
+impl TableRow<'_> for FileTextModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            file_id: row.next()?,
+            hash: row.next()?,
+            indexed_at: row.next()?,
+        })
+    }
+}
+
+/// A chapter which matched the search.
+pub struct FileTextMatchModel {
+    pub file_id: FileId,
+    pub chapter: i64,
+    /// The matched words are between [`SNIPPET_START`] and [`SNIPPET_END`].
+    pub snippet: String,
+
+    pub book: BookModel,
+}
+
+impl TableRow<'_> for FileTextMatchModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            file_id: row.next()?,
+            chapter: row.next()?,
+            snippet: row.next()?,
+
+            book: BookModel::create(row)?,
+        })
+    }
+}
+
+impl FileTextModel {
+    /// EPUBs which weren't indexed yet or changed since they were.
+    pub async fn find_unindexed_files(
+        library_id: Option<LibraryId>,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<FileModel>> {
+        let this = db.read().await;
+
+        let mut conn = this.prepare(
+            r#"
+            SELECT file.* FROM file
+            LEFT JOIN file_text ON file_text.file_id = file.id
+            WHERE file.deleted_at IS NULL
+                AND file.file_type = 'epub'
+                AND (file_text.hash IS NULL OR file_text.hash != file.hash)
+                AND (?1 IS NULL OR file.library_id = ?1)
+        "#,
+        )?;
+
+        let map = conn.query_map([library_id], |v| FileModel::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    /// Replaces the stored text of every chapter in the file.
+    pub async fn replace(&self, chapters: &[String], db: &dyn DatabaseAccess) -> Result<()> {
+        let conn = db.write().await;
+
+        let trx = conn.unchecked_transaction()?;
+
+        trx.execute(
+            "DELETE FROM file_text_chapter WHERE file_id = ?1",
+            [self.file_id],
+        )?;
+
+        {
+            let mut insert = trx.prepare(
+                "INSERT INTO file_text_chapter (file_id, chapter, content) VALUES (?1, ?2, ?3)",
+            )?;
+
+            for (chapter, content) in chapters.iter().enumerate() {
+                // Nothing to search in. Usually a cover or an image.
+                if content.is_empty() {
+                    continue;
+                }
+
+                insert.execute(params![self.file_id, chapter, content])?;
+            }
+        }
+
+        trx.execute(
+            "INSERT OR REPLACE INTO file_text (file_id, hash, indexed_at) VALUES (?1, ?2, ?3)",
+            params![self.file_id, &self.hash, self.indexed_at],
+        )?;
+
+        trx.commit()?;
+
+        Ok(())
+    }
+
+    /// Removes the files which were deleted. Returns how many were removed.
+    pub async fn delete_removed_files(db: &dyn DatabaseAccess) -> Result<usize> {
+        let conn = db.write().await;
+
+        let removed = conn.execute(
+            "DELETE FROM file_text WHERE file_id NOT IN (SELECT id FROM file WHERE deleted_at IS NULL)",
+            [],
+        )?;
+
+        conn.execute(
+            "DELETE FROM file_text_chapter WHERE file_id NOT IN (SELECT file_id FROM file_text)",
+            [],
+        )?;
+
+        Ok(removed)
+    }
+
+    /// Ordered by relevance. Files without a book are skipped.
+    ///
+    /// `query` has to be an FTS5 query. See [`build_match_query`].
+    pub async fn search(
+        query: &str,
+        library: Option<LibraryId>,
+        access: &LibraryAccess,
+        offset: usize,
+        limit: usize,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<FileTextMatchModel>> {
+        let this = db.read().await;
+
+        // Both the file and the book have a library_id column.
+        let access_condition = access
+            .sql_condition()
+            .map(|v| format!("AND file.id IN (SELECT id FROM file WHERE {v})"))
+            .unwrap_or_default();
+
+        let mut conn = this.prepare(&format!(
+            r#"
+            SELECT file_text_chapter.file_id, file_text_chapter.chapter, snippet(file_text_fts, 0, ?2, ?3, '…', 32), book.*
+            FROM file_text_fts
+            JOIN file_text_chapter ON file_text_chapter.id = file_text_fts.rowid
+            JOIN file ON file.id = file_text_chapter.file_id
+            JOIN book ON book.id = file.book_id
+            WHERE file_text_fts MATCH ?1
+                AND file.deleted_at IS NULL
+                AND (?4 IS NULL OR file.library_id = ?4)
+                {access_condition}
+            ORDER BY rank
+            LIMIT ?5
+            OFFSET ?6
+        "#
+        ))?;
+
+        let map = conn.query_map(
+            params![query, SNIPPET_START, SNIPPET_END, library, limit, offset],
+            |v| FileTextMatchModel::from_row(v),
+        )?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+}
+
+/// Converts the searched text into an FTS5 query so it can't contain any syntax errors.
+///
+/// Quoted text is a phrase and a word ending with `*` is a prefix. Every term has to match.
+pub fn build_match_query(value: &str) -> Option<String> {
+    let mut terms = Vec::new();
+
+    for (index, part) in value.split('"').enumerate() {
+        // Every other part is inside of quotes.
+        if index % 2 == 1 {
+            let phrase = part.split_whitespace().collect::<Vec<_>>().join(" ");
+
+            if !phrase.is_empty() {
+                terms.push(format!("\"{phrase}\""));
+            }
+
+            continue;
+        }
+
+        for word in part.split_whitespace() {
+            let (word, is_prefix) = match word.strip_suffix('*') {
+                Some(word) => (word.trim_end_matches('*'), true),
+                None => (word, false),
+            };
+
+            if word.is_empty() {
+                continue;
+            }
+
+            if is_prefix {
+                terms.push(format!("\"{word}\"*"));
+            } else {
+                terms.push(format!("\"{word}\""));
+            }
+        }
+    }
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Splits a snippet from [`FileTextModel::search`] into the matched and unmatched text.
+pub fn split_snippet(value: &str) -> Vec<SnippetPart> {
+    let mut parts = Vec::new();
+
+    let mut push = |text: &str, highlighted: bool| {
+        if !text.is_empty() {
+            parts.push(SnippetPart {
+                text: text.to_string(),
+                highlighted,
+            });
+        }
+    };
+
+    let mut split = value.split(SNIPPET_START);
+
+    if let Some(text) = split.next() {
+        push(text, false);
+    }
+
+    for part in split {
+        match part.split_once(SNIPPET_END) {
+            Some((matched, text)) => {
+                push(matched, true);
+                push(text, false);
+            }
+
+            None => push(part, true),
+        }
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_query() {
+        assert_eq!(build_match_query("  "), None);
+        assert_eq!(build_match_query("\"\" *"), None);
+
+        assert_eq!(
+            build_match_query("white whale"),
+            Some(String::from(r#""white" "whale""#))
+        );
+
+        assert_eq!(
+            build_match_query(r#"call me "Ishmael   some years" ago*"#),
+            Some(String::from(r#""call" "me" "Ishmael some years" "ago"*"#))
+        );
+
+        // Syntax is treated as text. An unclosed quote runs to the end.
+        assert_eq!(
+            build_match_query(r#"NOT a:b (c) "d e"#),
+            Some(String::from(r#""NOT" "a:b" "(c)" "d e""#))
+        );
+    }
+
+    #[test]
+    fn snippet() {
+        let part = |text: &str, highlighted| SnippetPart {
+            text: text.to_string(),
+            highlighted,
+        };
+
+        assert_eq!(
+            split_snippet("…the \u{2}white\u{3} \u{2}whale\u{3}."),
+            vec![
+                part("…the ", false),
+                part("white", true),
+                part(" ", false),
+                part("whale", true),
+                part(".", false),
+            ]
+        );
+
+        assert_eq!(split_snippet("\u{2}Call\u{3}"), vec![part("Call", true)]);
+    }
+}
@@ -139,6 +139,37 @@ impl UploadedImageModel {
             .optional()?)
     }
 
+    /// Images which aren't linked to or used as a thumbnail anywhere.
+    pub async fn find_orphaned(
+        created_before: DateTime<Utc>,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let mut conn = this.prepare(
+            r#"
+            SELECT * FROM uploaded_images
+            WHERE created_at < ?1
+                AND id NOT IN (SELECT image_id FROM image_link)
+                AND path NOT IN (SELECT thumb_url FROM book WHERE thumb_url IS NOT NULL)
+                AND path NOT IN (SELECT thumb_url FROM tag_person WHERE thumb_url IS NOT NULL)
+                AND path NOT IN (SELECT thumb_url FROM collection WHERE thumb_url IS NOT NULL)
+                AND path NOT IN (SELECT thumb_url FROM series WHERE thumb_url IS NOT NULL)
+        "#,
+        )?;
+
+        let map = conn.query_map([created_before], |v| Self::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn delete_by_id(id: ImageId, db: &dyn DatabaseAccess) -> Result<usize> {
+        Ok(db
+            .write()
+            .await
+            .execute(r#"DELETE FROM uploaded_images WHERE id = ?1"#, [id])?)
+    }
+
     pub async fn remove(
         link_id: BookId,
         path: ThumbnailStore,
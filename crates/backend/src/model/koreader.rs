@@ -0,0 +1,149 @@
+use chrono::{DateTime, Utc};
+use common::MemberId;
+use common_local::FileId;
+use rusqlite::{params, OptionalExtension};
+
+use crate::{DatabaseAccess, Result};
+
+use super::{AdvRow, TableRow};
+
+/// The password a member set for KOReader's progress sync.
+///
+/// KOReader only sends a MD5 of the password so it's stored separately from the login one.
+pub struct KoreaderKeyModel {
+    pub member_id: MemberId,
+
+    /// Bcrypt hash of the MD5 KOReader sends.
+    pub key_hash: String,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TableRow<'_> for KoreaderKeyModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            member_id: row.next()?,
+            key_hash: row.next()?,
+            created_at: row.next()?,
+            updated_at: row.next()?,
+        })
+    }
+}
+
+impl KoreaderKeyModel {
+    pub async fn insert_or_replace(&self, db: &dyn DatabaseAccess) -> Result<()> {
+        db.write().await.execute(
+            r#"
+            INSERT INTO koreader_key (member_id, key_hash, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(member_id) DO UPDATE SET
+                key_hash = excluded.key_hash,
+                updated_at = excluded.updated_at"#,
+            params![
+                self.member_id,
+                &self.key_hash,
+                self.created_at,
+                self.updated_at
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub async fn find_one_by_member_id(
+        member_id: MemberId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(
+                r#"SELECT * FROM koreader_key WHERE member_id = ?1"#,
+                [member_id],
+                |v| Self::from_row(v),
+            )
+            .optional()?)
+    }
+
+    pub async fn delete_by_member_id(
+        member_id: MemberId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<usize> {
+        Ok(db.write().await.execute(
+            r#"DELETE FROM koreader_key WHERE member_id = ?1"#,
+            [member_id],
+        )?)
+    }
+}
+
+/// The last position KOReader sent for a document. Also kept for documents which aren't in a library.
+pub struct KoreaderProgressModel {
+    pub member_id: MemberId,
+    /// The partial MD5 of the file.
+    pub document: String,
+    pub file_id: Option<FileId>,
+
+    /// A XPointer for reflowable documents, otherwise the page number.
+    pub progress: String,
+    pub percentage: f64,
+    pub device: String,
+    pub device_id: Option<String>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TableRow<'_> for KoreaderProgressModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            member_id: row.next()?,
+            document: row.next()?,
+            file_id: row.next_opt()?,
+
+            progress: row.next()?,
+            percentage: row.next()?,
+            device: row.next()?,
+            device_id: row.next_opt()?,
+
+            updated_at: row.next()?,
+        })
+    }
+}
+
+impl KoreaderProgressModel {
+    pub async fn insert_or_replace(&self, db: &dyn DatabaseAccess) -> Result<()> {
+        db.write().await.execute(
+            r#"
+            INSERT OR REPLACE INTO koreader_progress (member_id, document, file_id, progress, percentage, device, device_id, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+            params![
+                self.member_id,
+                &self.document,
+                self.file_id,
+                &self.progress,
+                self.percentage,
+                &self.device,
+                &self.device_id,
+                self.updated_at,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub async fn find_one(
+        member_id: MemberId,
+        document: &str,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(
+                r#"SELECT * FROM koreader_progress WHERE member_id = ?1 AND document = ?2"#,
+                params![member_id, document],
+                |v| Self::from_row(v),
+            )
+            .optional()?)
+    }
+}
@@ -3,9 +3,9 @@ use rusqlite::{params, OptionalExtension};
 use serde::Serialize;
 
 use crate::{DatabaseAccess, Result};
-use common_local::LibraryId;
+use common_local::{AgentPriority, LibraryId};
 
-use super::{directory::DirectoryModel, AdvRow, TableRow};
+use super::{directory::DirectoryModel, member_library::MemberLibraryModel, AdvRow, TableRow};
 
 pub struct NewLibraryModel {
     pub name: String,
@@ -24,6 +24,9 @@ pub struct LibraryModel {
     pub scanned_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+
+    /// JSON of [`AgentPriority`]
+    pub agent_priority: Option<String>,
 }
 
 impl TableRow<'_> for LibraryModel {
@@ -34,6 +37,7 @@ impl TableRow<'_> for LibraryModel {
             scanned_at: row.next()?,
             created_at: row.next()?,
             updated_at: row.next()?,
+            agent_priority: row.next_opt()?,
         })
     }
 }
@@ -58,6 +62,7 @@ impl NewLibraryModel {
             scanned_at: self.scanned_at,
             created_at: self.created_at,
             updated_at: self.updated_at,
+            agent_priority: None,
         })
     }
 }
@@ -65,6 +70,7 @@ impl NewLibraryModel {
 impl LibraryModel {
     pub async fn delete_by_id(id: LibraryId, db: &dyn DatabaseAccess) -> Result<usize> {
         DirectoryModel::delete_by_library_id(id, db).await?;
+        MemberLibraryModel::delete_by_library_id(id, db).await?;
 
         Ok(db
             .write()
@@ -123,8 +129,15 @@ impl LibraryModel {
         let write = db.write().await;
 
         Ok(write.execute(
-            "UPDATE library SET name = ?2, updated_at = ?3 WHERE id = ?1",
-            params![self.id, &self.name, self.updated_at,],
+            "UPDATE library SET name = ?2, updated_at = ?3, agent_priority = ?4 WHERE id = ?1",
+            params![self.id, &self.name, self.updated_at, &self.agent_priority],
         )?)
     }
+
+    pub fn parse_agent_priority(&self) -> Result<AgentPriority> {
+        Ok(match self.agent_priority.as_deref() {
+            Some(value) => serde_json::from_str(value)?,
+            None => AgentPriority::default(),
+        })
+    }
 }
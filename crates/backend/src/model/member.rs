@@ -6,7 +6,7 @@ use crate::{DatabaseAccess, Result};
 use common_local::{MemberAuthType, Permissions};
 use serde::Serialize;
 
-use super::{AdvRow, TableRow};
+use super::{member_library::MemberLibraryModel, AdvRow, TableRow};
 
 pub struct NewMemberModel {
     pub name: String,
@@ -207,6 +207,8 @@ impl MemberModel {
     }
 
     pub async fn delete(id: MemberId, db: &dyn DatabaseAccess) -> Result<usize> {
+        MemberLibraryModel::delete_by_member_id(id, db).await?;
+
         Ok(db
             .write()
             .await
@@ -0,0 +1,129 @@
+use common::MemberId;
+use common_local::{GroupPermissions, LibraryId};
+use rusqlite::params;
+
+use crate::{DatabaseAccess, Result};
+
+use super::{member::MemberModel, AdvRow, TableRow};
+
+/// A library the member can see when they aren't allowed to see all of them.
+pub struct MemberLibraryModel {
+    pub member_id: MemberId,
+    pub library_id: LibraryId,
+}
+
+impl TableRow<'_> for MemberLibraryModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            member_id: row.next()?,
+            library_id: row.next()?,
+        })
+    }
+}
+
+impl MemberLibraryModel {
+    pub async fn find_library_ids_by_member_id(
+        member_id: MemberId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<LibraryId>> {
+        let this = db.read().await;
+
+        let mut conn = this.prepare("SELECT * FROM member_library WHERE member_id = ?1")?;
+
+        let map = conn.query_map([member_id], |v| Self::from_row(v).map(|v| v.library_id))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    /// Replaces the libraries the member can see.
+    pub async fn set_for_member(
+        member_id: MemberId,
+        library_ids: &[LibraryId],
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        let conn = db.write().await;
+
+        conn.execute(
+            "DELETE FROM member_library WHERE member_id = ?1",
+            [member_id],
+        )?;
+
+        for library_id in library_ids {
+            conn.execute(
+                "INSERT OR IGNORE INTO member_library (member_id, library_id) VALUES (?1, ?2)",
+                params![member_id, library_id],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_by_member_id(
+        member_id: MemberId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<usize> {
+        Ok(db.write().await.execute(
+            "DELETE FROM member_library WHERE member_id = ?1",
+            [member_id],
+        )?)
+    }
+
+    pub async fn delete_by_library_id(
+        library_id: LibraryId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<usize> {
+        Ok(db.write().await.execute(
+            "DELETE FROM member_library WHERE library_id = ?1",
+            [library_id],
+        )?)
+    }
+}
+
+/// Which libraries a member can see. Everything in the other libraries is hidden from them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LibraryAccess {
+    All,
+    Only(Vec<LibraryId>),
+}
+
+impl LibraryAccess {
+    pub async fn for_member(member: &MemberModel, db: &dyn DatabaseAccess) -> Result<Self> {
+        if member.permissions.has(GroupPermissions::ALL_LIBRARIES) {
+            Ok(Self::All)
+        } else {
+            Ok(Self::Only(
+                MemberLibraryModel::find_library_ids_by_member_id(member.id, db).await?,
+            ))
+        }
+    }
+
+    pub fn contains(&self, library_id: LibraryId) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(ids) => ids.contains(&library_id),
+        }
+    }
+
+    /// Hidden libraries are treated as if they don't exist.
+    pub fn check(&self, library_id: LibraryId) -> Result<()> {
+        if self.contains(library_id) {
+            Ok(())
+        } else {
+            Err(crate::Error::Internal(crate::InternalError::ItemMissing))
+        }
+    }
+
+    /// A condition on the `library_id` column. None when every library is visible.
+    pub fn sql_condition(&self) -> Option<String> {
+        match self {
+            Self::All => None,
+            Self::Only(ids) => Some(format!(
+                "library_id IN ({})",
+                ids.iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )),
+        }
+    }
+}
@@ -1,19 +1,34 @@
 use rusqlite::{types::FromSql, Row};
 
+pub mod api_token;
 pub mod auth;
 pub mod book;
+pub mod book_field_source;
+pub mod book_identifier;
+pub mod book_locked_field;
 pub mod book_person;
+pub mod book_subject;
+pub mod bookmark;
 pub mod collection;
 pub mod collection_item;
 pub mod directory;
 pub mod file;
+pub mod file_text;
 pub mod image;
+pub mod koreader;
 pub mod library;
 pub mod member;
+pub mod member_library;
+pub mod notation;
 pub mod note;
 pub mod person;
 pub mod person_alt;
+pub mod person_locked_field;
 pub mod progress;
+pub mod publisher;
+pub mod series;
+pub mod subject;
+pub mod task;
 
 pub trait TableRow<'a>
 where
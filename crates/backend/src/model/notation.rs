@@ -0,0 +1,138 @@
+use chrono::{DateTime, Utc};
+use common::MemberId;
+use rusqlite::{params, OptionalExtension};
+
+use crate::{DatabaseAccess, Result};
+use common_local::{FileId, Notation, NOTATION_VERSION};
+
+use super::{AdvRow, TableRow};
+
+/// Every highlight a member made in a file. Stored as a single JSON list.
+#[derive(Debug)]
+pub struct FileNotationModel {
+    pub file_id: FileId,
+    pub member_id: MemberId,
+
+    pub data: String,
+    pub data_size: i64,
+    pub version: i64,
+
+    pub updated_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FileNotationModel {
+    pub fn new(file_id: FileId, member_id: MemberId, notations: &[Notation]) -> Result<Self> {
+        let data = serde_json::to_string(notations)?;
+
+        Ok(Self {
+            file_id,
+            member_id,
+            data_size: data.len() as i64,
+            data,
+            version: NOTATION_VERSION,
+            updated_at: Utc::now(),
+            created_at: Utc::now(),
+        })
+    }
+
+    pub fn parse_notations(&self) -> Result<Vec<Notation>> {
+        Ok(serde_json::from_str(&self.data)?)
+    }
+}
+
+impl TableRow<'_> for FileNotationModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            file_id: row.next()?,
+            member_id: row.next()?,
+
+            data: row.next()?,
+
+            data_size: row.next()?,
+            version: row.next()?,
+
+            updated_at: row.next()?,
+            created_at: row.next()?,
+        })
+    }
+}
+
+impl FileNotationModel {
+    pub async fn find_one(
+        file_id: FileId,
+        member_id: MemberId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(
+                "SELECT * FROM file_notation WHERE user_id = ?1 AND file_id = ?2",
+                params![member_id, file_id],
+                |v| Self::from_row(v),
+            )
+            .optional()?)
+    }
+
+    /// Returns an empty list if the member hasn't highlighted anything yet.
+    pub async fn find_notations(
+        file_id: FileId,
+        member_id: MemberId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<Notation>> {
+        match Self::find_one(file_id, member_id, db).await? {
+            Some(model) => model.parse_notations(),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Changes the member's notations while holding the write lock, so concurrent changes aren't lost.
+    ///
+    /// The row is removed once the list is empty.
+    pub async fn update_notations<V>(
+        file_id: FileId,
+        member_id: MemberId,
+        db: &dyn DatabaseAccess,
+        update: impl FnOnce(&mut Vec<Notation>) -> Result<V>,
+    ) -> Result<V> {
+        let conn = db.write().await;
+
+        let mut notations = match conn
+            .query_row(
+                "SELECT * FROM file_notation WHERE user_id = ?1 AND file_id = ?2",
+                params![member_id, file_id],
+                |v| Self::from_row(v),
+            )
+            .optional()?
+        {
+            Some(model) => model.parse_notations()?,
+            None => Vec::new(),
+        };
+
+        let value = update(&mut notations)?;
+
+        if notations.is_empty() {
+            conn.execute(
+                "DELETE FROM file_notation WHERE user_id = ?1 AND file_id = ?2",
+                params![member_id, file_id],
+            )?;
+        } else {
+            let this = Self::new(file_id, member_id, &notations)?;
+
+            conn.execute(
+                r#"
+                INSERT INTO file_notation (file_id, user_id, data, data_size, version, updated_at, created_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT(file_id, user_id) DO UPDATE SET
+                    data = excluded.data,
+                    data_size = excluded.data_size,
+                    version = excluded.version,
+                    updated_at = excluded.updated_at"#,
+                params![this.file_id, this.member_id, this.data, this.data_size, this.version, this.updated_at, this.created_at]
+            )?;
+        }
+
+        Ok(value)
+    }
+}
@@ -6,7 +6,7 @@ use crate::{DatabaseAccess, Result};
 use common_local::Person;
 use serde::Serialize;
 
-use super::{AdvRow, TableRow};
+use super::{member_library::LibraryAccess, AdvRow, TableRow};
 
 #[derive(Debug)]
 pub struct NewPersonModel {
@@ -100,10 +100,21 @@ impl NewPersonModel {
 }
 
 impl PersonModel {
-    pub async fn find(offset: usize, limit: usize, db: &dyn DatabaseAccess) -> Result<Vec<Self>> {
+    pub async fn find(
+        offset: usize,
+        limit: usize,
+        access: &LibraryAccess,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<Self>> {
         let this = db.read().await;
 
-        let mut conn = this.prepare(r#"SELECT * FROM tag_person LIMIT ?1 OFFSET ?2"#)?;
+        let condition = access_condition(access)
+            .map(|v| format!("WHERE {v}"))
+            .unwrap_or_default();
+
+        let mut conn = this.prepare(&format!(
+            "SELECT * FROM tag_person {condition} LIMIT ?1 OFFSET ?2"
+        ))?;
 
         let map = conn.query_map([limit, offset], |v| Self::from_row(v))?;
 
@@ -131,6 +142,7 @@ impl PersonModel {
         query: &str,
         offset: usize,
         limit: usize,
+        access: &LibraryAccess,
         db: &dyn DatabaseAccess,
     ) -> Result<Vec<Self>> {
         let mut escape_char = '\\';
@@ -147,8 +159,12 @@ impl PersonModel {
             }
         }
 
+        let condition = access_condition(access)
+            .map(|v| format!("AND {v}"))
+            .unwrap_or_default();
+
         let sql = format!(
-            r#"SELECT * FROM tag_person WHERE name LIKE '%{}%' ESCAPE '{}' LIMIT ?1 OFFSET ?2"#,
+            r#"SELECT * FROM tag_person WHERE name LIKE '%{}%' ESCAPE '{}' {condition} LIMIT ?1 OFFSET ?2"#,
             query
                 .replace('%', &format!("{}%", escape_char))
                 .replace('_', &format!("{}_", escape_char)),
@@ -210,11 +226,33 @@ impl PersonModel {
             .optional()?)
     }
 
-    pub async fn count(db: &dyn DatabaseAccess) -> Result<usize> {
-        Ok(db
-            .read()
-            .await
-            .query_row(r#"SELECT COUNT(*) FROM tag_person"#, [], |v| v.get(0))?)
+    pub async fn count(access: &LibraryAccess, db: &dyn DatabaseAccess) -> Result<usize> {
+        let condition = access_condition(access)
+            .map(|v| format!("WHERE {v}"))
+            .unwrap_or_default();
+
+        Ok(db.read().await.query_row(
+            &format!("SELECT COUNT(*) FROM tag_person {condition}"),
+            [],
+            |v| v.get(0),
+        )?)
+    }
+
+    /// Whether the person has a book in one of the visible libraries.
+    pub async fn is_visible(
+        id: PersonId,
+        access: &LibraryAccess,
+        db: &dyn DatabaseAccess,
+    ) -> Result<bool> {
+        let Some(condition) = access_condition(access) else {
+            return Ok(true);
+        };
+
+        Ok(db.read().await.query_row(
+            &format!("SELECT EXISTS(SELECT * FROM tag_person WHERE id = ?1 AND {condition})"),
+            [id],
+            |v| v.get(0),
+        )?)
     }
 
     pub async fn update(&self, db: &dyn DatabaseAccess) -> Result<()> {
@@ -251,3 +289,10 @@ impl PersonModel {
             .execute(r#"DELETE FROM tag_person WHERE id = ?1"#, params![id])?)
     }
 }
+
+/// People without a book in one of the visible libraries are hidden.
+fn access_condition(access: &LibraryAccess) -> Option<String> {
+    access.sql_condition().map(|v| {
+        format!("id IN (SELECT person_id FROM book_person JOIN book ON book.id = book_person.book_id WHERE book.{v})")
+    })
+}
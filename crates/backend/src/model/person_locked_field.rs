@@ -0,0 +1,48 @@
+use common::PersonId;
+use common_local::PersonField;
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::{DatabaseAccess, Result};
+
+/// A field which refreshing the person won't overwrite.
+#[derive(Debug, Serialize)]
+pub struct PersonLockedFieldModel {
+    pub person_id: PersonId,
+    pub field: PersonField,
+}
+
+impl PersonLockedFieldModel {
+    pub async fn insert_or_ignore(&self, db: &dyn DatabaseAccess) -> Result<()> {
+        db.write().await.execute(
+            r#"INSERT OR IGNORE INTO person_locked_field (person_id, field) VALUES (?1, ?2)"#,
+            params![self.person_id, self.field],
+        )?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_person_id(
+        person_id: PersonId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<PersonField>> {
+        let this = db.read().await;
+
+        let mut conn =
+            this.prepare(r#"SELECT field FROM person_locked_field WHERE person_id = ?1"#)?;
+
+        let map = conn.query_map([person_id], |v| v.get(0))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn delete_by_person_id(
+        person_id: PersonId,
+        db: &dyn DatabaseAccess,
+    ) -> Result<usize> {
+        Ok(db.write().await.execute(
+            r#"DELETE FROM person_locked_field WHERE person_id = ?1"#,
+            [person_id],
+        )?)
+    }
+}
@@ -132,17 +132,21 @@ impl FileProgressionModel {
     ) -> Result<()> {
         let prog = Self::new(progress, member_id, book_id, file_id);
 
-        if Self::find_one(member_id, file_id, db).await?.is_some() {
-            db.write().await.execute(
-                r#"UPDATE file_progression SET chapter = ?1, char_pos = ?2, page = ?3, seek_pos = ?4, updated_at = ?5 WHERE book_id = ?6 AND file_id = ?7 AND user_id = ?8"#,
-                params![prog.chapter, prog.char_pos, prog.page, prog.seek_pos, prog.updated_at, prog.book_id, prog.file_id, prog.user_id]
-            )?;
-        } else {
-            db.write().await.execute(
-                r#"INSERT INTO file_progression (book_id, file_id, user_id, type_of, chapter, char_pos, page, seek_pos, updated_at, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"#,
-                params![prog.book_id, prog.file_id, prog.user_id, prog.type_of, prog.chapter, prog.char_pos, prog.page, prog.seek_pos, prog.updated_at, prog.created_at]
-            )?;
-        }
+        // One per book. The last write wins, even if it came from a different file.
+        db.write().await.execute(
+            r#"
+            INSERT INTO file_progression (book_id, file_id, user_id, type_of, chapter, char_pos, page, seek_pos, updated_at, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ON CONFLICT(book_id, user_id) DO UPDATE SET
+                file_id = excluded.file_id,
+                type_of = excluded.type_of,
+                chapter = excluded.chapter,
+                char_pos = excluded.char_pos,
+                page = excluded.page,
+                seek_pos = excluded.seek_pos,
+                updated_at = excluded.updated_at"#,
+            params![prog.book_id, prog.file_id, prog.user_id, prog.type_of, prog.chapter, prog.char_pos, prog.page, prog.seek_pos, prog.updated_at, prog.created_at]
+        )?;
 
         Ok(())
     }
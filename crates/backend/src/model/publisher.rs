@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use common_local::PublisherId;
+use rusqlite::params;
+use serde::Serialize;
+
+use super::{AdvRow, TableRow};
+use crate::{DatabaseAccess, Result};
+
+#[derive(Debug, Serialize)]
+pub struct PublisherModel {
+    pub id: PublisherId,
+
+    pub name: String,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TableRow<'_> for PublisherModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.next()?,
+
+            name: row.next()?,
+
+            created_at: row.next()?,
+            updated_at: row.next()?,
+        })
+    }
+}
+
+impl PublisherModel {
+    /// Publisher names are case insensitive. The first spelling we come across is kept.
+    pub async fn find_or_insert(name: &str, db: &dyn DatabaseAccess) -> Result<Self> {
+        let now = Utc::now();
+
+        db.write().await.execute(
+            r#"INSERT OR IGNORE INTO publisher (name, created_at, updated_at) VALUES (?1, ?2, ?3)"#,
+            params![name, now, now],
+        )?;
+
+        Ok(db.read().await.query_row(
+            r#"SELECT * FROM publisher WHERE name = ?1"#,
+            params![name],
+            |v| Self::from_row(v),
+        )?)
+    }
+}
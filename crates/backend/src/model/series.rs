@@ -0,0 +1,220 @@
+use chrono::{DateTime, Utc};
+use common::ThumbnailStore;
+use common_local::{Series, SeriesId};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use super::{member_library::LibraryAccess, AdvRow, TableRow};
+use crate::{DatabaseAccess, Result};
+
+#[derive(Debug, Serialize)]
+pub struct SeriesModel {
+    pub id: SeriesId,
+
+    pub name: String,
+    pub description: Option<String>,
+
+    pub thumb_url: ThumbnailStore,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TableRow<'_> for SeriesModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.next()?,
+
+            name: row.next()?,
+            description: row.next()?,
+
+            thumb_url: ThumbnailStore::from(row.next_opt::<String>()?),
+
+            created_at: row.next()?,
+            updated_at: row.next()?,
+        })
+    }
+}
+
+impl From<SeriesModel> for Series {
+    fn from(val: SeriesModel) -> Self {
+        Series {
+            id: val.id,
+            name: val.name,
+            description: val.description,
+            thumb_url: val.thumb_url,
+            created_at: val.created_at,
+            updated_at: val.updated_at,
+        }
+    }
+}
+
+impl SeriesModel {
+    /// Series names are case insensitive. The first spelling we come across is kept.
+    pub async fn find_or_insert(name: &str, db: &dyn DatabaseAccess) -> Result<Self> {
+        let now = Utc::now();
+
+        db.write().await.execute(
+            r#"INSERT OR IGNORE INTO series (name, created_at, updated_at) VALUES (?1, ?2, ?3)"#,
+            params![name, now, now],
+        )?;
+
+        Ok(db.read().await.query_row(
+            r#"SELECT * FROM series WHERE name = ?1"#,
+            params![name],
+            |v| Self::from_row(v),
+        )?)
+    }
+
+    pub async fn find(
+        offset: usize,
+        limit: usize,
+        access: &LibraryAccess,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let condition = access_condition(access)
+            .map(|v| format!("WHERE {v}"))
+            .unwrap_or_default();
+
+        let mut conn = this.prepare(&format!(
+            "SELECT * FROM series {condition} ORDER BY name LIMIT ?1 OFFSET ?2"
+        ))?;
+
+        let map = conn.query_map([limit, offset], |v| Self::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn search_by(
+        query: &str,
+        offset: usize,
+        limit: usize,
+        access: &LibraryAccess,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<Self>> {
+        let mut escape_char = '\\';
+        // Change our escape character if it's in the query.
+        if query.contains(escape_char) {
+            for car in [
+                '!', '@', '#', '$', '^', '&', '*', '-', '=', '+', '|', '~', '`', '/', '?', '>',
+                '<', ',',
+            ] {
+                if !query.contains(car) {
+                    escape_char = car;
+                    break;
+                }
+            }
+        }
+
+        let condition = access_condition(access)
+            .map(|v| format!("AND {v}"))
+            .unwrap_or_default();
+
+        let sql = format!(
+            r#"SELECT * FROM series WHERE name LIKE ?3 ESCAPE '{escape_char}' {condition} ORDER BY name LIMIT ?1 OFFSET ?2"#
+        );
+
+        let this = db.read().await;
+
+        let mut conn = this.prepare(&sql)?;
+
+        let map = conn.query_map(
+            params![
+                limit,
+                offset,
+                format!(
+                    "%{}%",
+                    query
+                        .replace('%', &format!("{escape_char}%"))
+                        .replace('_', &format!("{escape_char}_"))
+                )
+            ],
+            |v| Self::from_row(v),
+        )?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn find_one_by_id(id: SeriesId, db: &dyn DatabaseAccess) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(r#"SELECT * FROM series WHERE id = ?1"#, params![id], |v| {
+                Self::from_row(v)
+            })
+            .optional()?)
+    }
+
+    pub async fn find_one_by_name(value: &str, db: &dyn DatabaseAccess) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(
+                r#"SELECT * FROM series WHERE name = ?1"#,
+                params![value],
+                |v| Self::from_row(v),
+            )
+            .optional()?)
+    }
+
+    pub async fn count(access: &LibraryAccess, db: &dyn DatabaseAccess) -> Result<usize> {
+        let condition = access_condition(access)
+            .map(|v| format!("WHERE {v}"))
+            .unwrap_or_default();
+
+        Ok(db.read().await.query_row(
+            &format!("SELECT COUNT(*) FROM series {condition}"),
+            [],
+            |v| v.get(0),
+        )?)
+    }
+
+    /// Whether the series has a book in one of the visible libraries.
+    pub async fn is_visible(
+        id: SeriesId,
+        access: &LibraryAccess,
+        db: &dyn DatabaseAccess,
+    ) -> Result<bool> {
+        let Some(condition) = access_condition(access) else {
+            return Ok(true);
+        };
+
+        Ok(db.read().await.query_row(
+            &format!("SELECT EXISTS(SELECT * FROM series WHERE id = ?1 AND {condition})"),
+            [id],
+            |v| v.get(0),
+        )?)
+    }
+
+    pub async fn update(&mut self, db: &dyn DatabaseAccess) -> Result<()> {
+        self.updated_at = Utc::now();
+
+        db.write().await.execute(
+            r#"
+            UPDATE series SET
+                name = ?2,
+                description = ?3,
+                thumb_url = ?4,
+                updated_at = ?5
+            WHERE id = ?1"#,
+            params![
+                self.id,
+                &self.name,
+                &self.description,
+                self.thumb_url.as_value(),
+                self.updated_at
+            ],
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Series without a book in one of the visible libraries are hidden.
+fn access_condition(access: &LibraryAccess) -> Option<String> {
+    access
+        .sql_condition()
+        .map(|v| format!("id IN (SELECT series_id FROM book WHERE {v})"))
+}
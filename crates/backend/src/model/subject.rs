@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use common::BookId;
+use common_local::SubjectId;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use super::{AdvRow, TableRow};
+use crate::{DatabaseAccess, Result};
+
+#[derive(Debug, Serialize)]
+pub struct SubjectModel {
+    pub id: SubjectId,
+
+    pub name: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl TableRow<'_> for SubjectModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.next()?,
+
+            name: row.next()?,
+
+            created_at: row.next()?,
+        })
+    }
+}
+
+impl SubjectModel {
+    /// Subject names are case insensitive. The first spelling we come across is kept.
+    pub async fn find_or_insert(name: &str, db: &dyn DatabaseAccess) -> Result<Self> {
+        db.write().await.execute(
+            r#"INSERT OR IGNORE INTO subject (name, created_at) VALUES (?1, ?2)"#,
+            params![name, Utc::now()],
+        )?;
+
+        Ok(db.read().await.query_row(
+            r#"SELECT * FROM subject WHERE name = ?1"#,
+            params![name],
+            |v| Self::from_row(v),
+        )?)
+    }
+
+    pub async fn find_one_by_name(value: &str, db: &dyn DatabaseAccess) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(
+                r#"SELECT * FROM subject WHERE name = ?1"#,
+                params![value],
+                |v| Self::from_row(v),
+            )
+            .optional()?)
+    }
+
+    pub async fn find_by_book_id(id: BookId, db: &dyn DatabaseAccess) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let mut conn = this.prepare(
+            r#"
+            SELECT subject.* FROM book_subject
+            LEFT JOIN
+                subject ON subject.id = book_subject.subject_id
+            WHERE book_id = ?1
+            ORDER BY subject.name
+        "#,
+        )?;
+
+        let map = conn.query_map([id], |v| Self::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+}
@@ -0,0 +1,185 @@
+use chrono::{DateTime, Utc};
+use common_local::{api::TaskHistoryItem, ws::TaskId, TaskStatus};
+use rusqlite::{params, OptionalExtension};
+
+use crate::{DatabaseAccess, Result};
+
+use super::{AdvRow, TableRow};
+
+pub struct NewTaskModel {
+    pub name: String,
+    /// JSON used to recreate the task.
+    pub data: String,
+    pub is_priority: bool,
+
+    pub status: TaskStatus,
+
+    pub queued_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub struct TaskModel {
+    pub id: TaskId,
+
+    pub name: String,
+    pub data: String,
+    pub is_priority: bool,
+
+    pub status: TaskStatus,
+    pub progress: Option<String>,
+    pub error: Option<String>,
+
+    pub queued_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl TableRow<'_> for TaskModel {
+    fn create(row: &mut AdvRow<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.next()?,
+
+            name: row.next()?,
+            data: row.next()?,
+            is_priority: row.next()?,
+
+            status: row.next()?,
+            progress: row.next()?,
+            error: row.next()?,
+
+            queued_at: row.next()?,
+            started_at: row.next_opt()?,
+            finished_at: row.next_opt()?,
+        })
+    }
+}
+
+impl From<TaskModel> for TaskHistoryItem {
+    fn from(value: TaskModel) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            status: value.status,
+            progress: value.progress,
+            error: value.error,
+            queued_at: value.queued_at,
+            started_at: value.started_at,
+            finished_at: value.finished_at,
+        }
+    }
+}
+
+impl NewTaskModel {
+    pub async fn insert(self, db: &dyn DatabaseAccess) -> Result<TaskModel> {
+        let conn = db.write().await;
+
+        conn.execute(
+            r#"
+            INSERT INTO task (name, data, is_priority, status, queued_at, started_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        "#,
+            params![
+                &self.name,
+                &self.data,
+                self.is_priority,
+                self.status,
+                self.queued_at,
+                self.started_at,
+            ],
+        )?;
+
+        Ok(TaskModel {
+            id: TaskId::from(conn.last_insert_rowid() as usize),
+            name: self.name,
+            data: self.data,
+            is_priority: self.is_priority,
+            status: self.status,
+            progress: None,
+            error: None,
+            queued_at: self.queued_at,
+            started_at: self.started_at,
+            finished_at: None,
+        })
+    }
+}
+
+impl TaskModel {
+    pub async fn find_one_by_id(id: TaskId, db: &dyn DatabaseAccess) -> Result<Option<Self>> {
+        Ok(db
+            .read()
+            .await
+            .query_row(r#"SELECT * FROM task WHERE id = ?1"#, [id], |v| {
+                Self::from_row(v)
+            })
+            .optional()?)
+    }
+
+    /// Tasks which haven't finished. Ones that are running were interrupted by a restart.
+    pub async fn find_unfinished(db: &dyn DatabaseAccess) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let mut conn =
+            this.prepare("SELECT * FROM task WHERE status = ?1 OR status = ?2 ORDER BY id")?;
+
+        let map = conn.query_map([TaskStatus::Queued, TaskStatus::Running], |v| {
+            Self::from_row(v)
+        })?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    /// Newest first.
+    pub async fn find_history(
+        offset: usize,
+        limit: usize,
+        db: &dyn DatabaseAccess,
+    ) -> Result<Vec<Self>> {
+        let this = db.read().await;
+
+        let mut conn = this.prepare("SELECT * FROM task ORDER BY id DESC LIMIT ?1 OFFSET ?2")?;
+
+        let map = conn.query_map([limit, offset], |v| Self::from_row(v))?;
+
+        Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    pub async fn update_status(
+        id: TaskId,
+        status: TaskStatus,
+        error: Option<&str>,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        let now = Utc::now();
+
+        db.write().await.execute(
+            r#"
+            UPDATE task SET
+                status = ?2, error = ?3,
+                started_at = COALESCE(?4, started_at), finished_at = ?5
+            WHERE id = ?1"#,
+            params![
+                id,
+                status,
+                error,
+                (status == TaskStatus::Running).then_some(now),
+                status.is_done().then_some(now),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub async fn update_progress(
+        id: TaskId,
+        progress: Option<&str>,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        db.write().await.execute(
+            r#"UPDATE task SET progress = ?2 WHERE id = ?1"#,
+            params![id, progress],
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,673 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Component, Path, PathBuf},
+};
+
+use chrono::Utc;
+use common::{MemberId, Source};
+use common_local::{
+    ws::{TaskId, TaskType, WebsocketNotification},
+    BookIdentifier, BookItemCached, Progression,
+};
+use rusqlite::{Connection, OpenFlags};
+use tokio::fs;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+use crate::{
+    database::DatabaseAccess,
+    http::send_message_to_clients,
+    metadata::{AuthorInfo, FoundImageLocation, FoundItem, MetadataReturned},
+    model::{
+        book::BookModel,
+        directory::DirectoryModel,
+        file::FileModel,
+        library::{LibraryModel, NewLibraryModel},
+        progress::FileProgressionModel,
+    },
+    task::overwrite_book_with_new_metadata,
+    InternalError, Result,
+};
+
+use super::{hash_file, insert_book, normalize_path, scan_file, MATCH_INSERT_LOCK};
+
+pub const METADATA_FILE_NAME: &str = "metadata.db";
+
+/// Used as the agent in the sources of imported books and people.
+const SOURCE_AGENT: &str = "calibre";
+
+/// What happened while importing a Calibre library.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CalibreImportSummary {
+    pub added: usize,
+    /// Linked to a book which was already scanned.
+    pub linked: usize,
+    /// None of its files could be read.
+    pub skipped: usize,
+    pub marked_read: usize,
+}
+
+/// A book stored in a Calibre `metadata.db`.
+#[derive(Debug, Default, PartialEq)]
+pub struct CalibreBook {
+    pub id: i64,
+    pub uuid: String,
+    pub title: String,
+    /// Folder of the book, relative to the library.
+    pub path: String,
+    pub has_cover: bool,
+    pub pubdate: Option<String>,
+    pub series_index: Option<f64>,
+
+    /// (Calibre Author ID, Name)
+    pub authors: Vec<(i64, String)>,
+    pub series: Option<String>,
+    pub publisher: Option<String>,
+    pub language: Option<String>,
+    pub description: Option<String>,
+    /// Out of 10. Calibre stores half stars.
+    pub rating: Option<i64>,
+    pub tags: Vec<String>,
+    /// (Type, Value)
+    pub identifiers: Vec<(String, String)>,
+    /// (Format, File name without the extension)
+    pub formats: Vec<(String, String)>,
+
+    /// Set in a custom "Read" yes/no column.
+    pub is_read: bool,
+}
+
+impl CalibreBook {
+    /// The files of every format stored for the book.
+    ///
+    /// Paths which would point outside of the library are skipped.
+    pub fn file_paths(&self, root: &Path) -> Vec<PathBuf> {
+        let Some(directory) = join_inside(root, &self.path) else {
+            return Vec::new();
+        };
+
+        self.formats
+            .iter()
+            .filter_map(|(format, name)| {
+                join_inside(&directory, &format!("{name}.{}", format.to_lowercase()))
+            })
+            .collect()
+    }
+
+    fn cover_path(&self, root: &Path) -> Option<PathBuf> {
+        join_inside(&join_inside(root, &self.path)?, "cover.jpg")
+    }
+
+    fn into_metadata(self, cover: Option<Vec<u8>>) -> Result<MetadataReturned> {
+        let source = Source::try_from(format!("{SOURCE_AGENT}:{}", self.uuid))?;
+
+        let authors = self
+            .authors
+            .into_iter()
+            .map(|(id, name)| {
+                Ok(AuthorInfo {
+                    // Shared between their books so the person isn't recreated for each one.
+                    source: Source::try_from(format!("{SOURCE_AGENT}:author-{id}"))?,
+                    name,
+                    other_names: None,
+                    description: None,
+                    cover_image_url: None,
+                    birth_date: None,
+                    death_date: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Calibre stores an unknown date as the year 101.
+        let year = self
+            .pubdate
+            .as_deref()
+            .and_then(|v| v.get(..4)?.parse::<i64>().ok())
+            .filter(|v| *v > 101);
+
+        Ok(MetadataReturned {
+            authors: Some(authors).filter(|v| !v.is_empty()),
+            publisher: self.publisher,
+            meta: FoundItem {
+                source,
+                title: Some(self.title),
+                description: self.description,
+                rating: self.rating.map(|v| v as f64 / 2.0).unwrap_or_default(),
+                thumb_locations: cover
+                    .map(|v| vec![FoundImageLocation::FileData(v)])
+                    .unwrap_or_default(),
+                cached: BookItemCached::default().series_optional(self.series.clone()),
+                available_at: None,
+                year,
+                series_index: self.series.and(self.series_index),
+                language: self.language,
+                page_count: None,
+                subjects: self.tags,
+                identifiers: self
+                    .identifiers
+                    .iter()
+                    .filter_map(|(type_of, value)| parse_identifier(type_of, value))
+                    .collect(),
+            },
+            field_sources: BTreeMap::new(),
+        })
+    }
+}
+
+/// Imports the books of a Calibre library. Its folder is added as a library directory if it isn't one already.
+///
+/// Books read in Calibre are marked as completed for the member.
+pub async fn import_library(
+    root: &Path,
+    member_id: MemberId,
+    task_id: TaskId,
+    token: &CancellationToken,
+    db: &dyn DatabaseAccess,
+) -> Result<CalibreImportSummary> {
+    let books = {
+        let conn = Connection::open_with_flags(
+            root.join(METADATA_FILE_NAME),
+            OpenFlags::SQLITE_OPEN_READ_ONLY,
+        )?;
+
+        read_books(&conn)?
+    };
+
+    let library = find_or_create_library(root, db).await?;
+
+    let mut stored_files = FileModel::find_by_library_id(library.id, db)
+        .await?
+        .into_iter()
+        .map(|v| (v.path.clone(), v))
+        .collect::<HashMap<_, _>>();
+
+    let mut summary = CalibreImportSummary::default();
+
+    let total = books.len();
+
+    let resolved_root = fs::canonicalize(root).await?;
+
+    for (index, book) in books.into_iter().enumerate() {
+        if token.is_cancelled() {
+            info!(library = ?library.id, "Calibre Import Cancelled");
+
+            return Ok(summary);
+        }
+
+        send_message_to_clients(WebsocketNotification::update_task(
+            task_id,
+            TaskType::CalibreImport(format!("Importing {}/{total}: {}", index + 1, book.title)),
+            true,
+        ));
+
+        let mut files = Vec::new();
+
+        for path in book.file_paths(root) {
+            if !is_resolved_inside(&path, &resolved_root).await {
+                continue;
+            }
+
+            scan_file(&path, &mut stored_files, library.id, task_id, db).await?;
+
+            // Files which are duplicates of already scanned ones aren't stored again.
+            let file = match FileModel::find_one_by_path(&normalize_path(&path), db).await? {
+                Some(file) => Some(file),
+                None => match hash_file(&path) {
+                    Some(hash) => FileModel::find_one_by_hash(&hash, db).await?,
+                    None => None,
+                },
+            };
+
+            files.extend(file);
+        }
+
+        if files.is_empty() {
+            error!(target: "calibre", id = book.id, title = book.title, "Unable to read any of the book files");
+            summary.skipped += 1;
+            continue;
+        }
+
+        let is_read = book.is_read;
+
+        let cover = match book.cover_path(root) {
+            Some(path) if book.has_cover && is_resolved_inside(&path, &resolved_root).await => {
+                fs::read(path).await.ok()
+            }
+            _ => None,
+        };
+
+        let metadata = book.into_metadata(cover)?;
+
+        let book_id = match files.iter().find_map(|v| v.book_id) {
+            Some(book_id) => {
+                let book_model = BookModel::find_one_by_id(book_id, db)
+                    .await?
+                    .ok_or(InternalError::ItemMissing)?;
+
+                overwrite_book_with_new_metadata(book_model, metadata, db).await?;
+
+                summary.linked += 1;
+
+                book_id
+            }
+
+            None => {
+                let _lock = MATCH_INSERT_LOCK.lock().await;
+
+                summary.added += 1;
+
+                insert_book(metadata, library.id, db).await?.id
+            }
+        };
+
+        for file in files.iter().filter(|v| v.book_id.is_none()) {
+            FileModel::update_book_id(file.id, book_id, db).await?;
+        }
+
+        BookModel::set_file_count(
+            book_id,
+            FileModel::find_by_book_id(book_id, db).await?.len(),
+            db,
+        )
+        .await?;
+
+        if is_read
+            && FileProgressionModel::find_one_by_book_id(member_id, book_id, db)
+                .await?
+                .is_none()
+        {
+            FileProgressionModel::insert_or_update(
+                member_id,
+                book_id,
+                files[0].id,
+                Progression::Complete,
+                db,
+            )
+            .await?;
+
+            summary.marked_read += 1;
+        }
+    }
+
+    info!(
+        library = ?library.id,
+        added = summary.added,
+        linked = summary.linked,
+        skipped = summary.skipped,
+        marked_read = summary.marked_read,
+        "Finished Calibre Import"
+    );
+
+    Ok(summary)
+}
+
+/// Uses the library which already has the Calibre folder as a directory. Otherwise one is created.
+async fn find_or_create_library(root: &Path, db: &dyn DatabaseAccess) -> Result<LibraryModel> {
+    let path = normalize_path(root).trim_end_matches('/').to_string();
+
+    if let Some(directory) = DirectoryModel::get_all(db)
+        .await?
+        .into_iter()
+        .find(|v| v.path.trim_end_matches('/') == path)
+    {
+        return Ok(LibraryModel::find_one_by_id(directory.library_id, db)
+            .await?
+            .ok_or(InternalError::ItemMissing)?);
+    }
+
+    let mut name = root
+        .file_name()
+        .and_then(|v| v.to_str())
+        .unwrap_or("Calibre Library")
+        .to_string();
+
+    if LibraryModel::find_one_by_name(&name, db).await?.is_some() {
+        name = format!("{name} #{}", LibraryModel::count(db).await?);
+    }
+
+    let now = Utc::now();
+
+    let library = NewLibraryModel {
+        name,
+        scanned_at: now,
+        created_at: now,
+        updated_at: now,
+    }
+    .insert(db)
+    .await?;
+
+    DirectoryModel {
+        library_id: library.id,
+        path,
+    }
+    .insert(db)
+    .await?;
+
+    super::watcher::reload_directories();
+
+    Ok(library)
+}
+
+/// Reads every book in a Calibre `metadata.db`.
+pub fn read_books(conn: &Connection) -> Result<Vec<CalibreBook>> {
+    let mut books = {
+        let mut stmt = conn.prepare(
+            "SELECT id, uuid, title, path, has_cover, pubdate, series_index FROM books ORDER BY id",
+        )?;
+
+        let map = stmt.query_map([], |v| {
+            Ok(CalibreBook {
+                id: v.get(0)?,
+                uuid: v.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                title: v.get(2)?,
+                path: v.get(3)?,
+                has_cover: v.get::<_, Option<bool>>(4)?.unwrap_or_default(),
+                pubdate: v.get(5)?,
+                series_index: v.get(6)?,
+                ..CalibreBook::default()
+            })
+        })?;
+
+        map.map(|v| v.map(|book| (book.id, book)))
+            .collect::<std::result::Result<HashMap<_, _>, _>>()?
+    };
+
+    // The UUID is used as the source.
+    for book in books.values_mut().filter(|v| v.uuid.is_empty()) {
+        book.uuid = format!("book-{}", book.id);
+    }
+
+    {
+        let mut stmt = conn.prepare(
+            "SELECT l.book, a.id, a.name FROM books_authors_link l JOIN authors a ON a.id = l.author ORDER BY l.id",
+        )?;
+
+        let mut rows = stmt.query([])?;
+
+        while let Some(row) = rows.next()? {
+            if let Some(book) = books.get_mut(&row.get(0)?) {
+                // Calibre stores commas in names as pipes.
+                book.authors
+                    .push((row.get(1)?, row.get::<_, String>(2)?.replace('|', ",")));
+            }
+        }
+    }
+
+    for (book_id, name) in query_values(
+        conn,
+        "SELECT l.book, s.name FROM books_series_link l JOIN series s ON s.id = l.series",
+    )? {
+        if let Some(book) = books.get_mut(&book_id) {
+            book.series = Some(name);
+        }
+    }
+
+    for (book_id, name) in query_values(
+        conn,
+        "SELECT l.book, p.name FROM books_publishers_link l JOIN publishers p ON p.id = l.publisher",
+    )? {
+        if let Some(book) = books.get_mut(&book_id) {
+            book.publisher = Some(name);
+        }
+    }
+
+    for (book_id, code) in query_values(
+        conn,
+        "SELECT l.book, g.lang_code FROM books_languages_link l JOIN languages g ON g.id = l.lang_code ORDER BY l.item_order",
+    )? {
+        if let Some(book) = books.get_mut(&book_id) {
+            book.language.get_or_insert(code);
+        }
+    }
+
+    for (book_id, text) in query_values(conn, "SELECT book, text FROM comments")? {
+        if let Some(book) = books.get_mut(&book_id) {
+            book.description = Some(text).filter(|v| !v.trim().is_empty());
+        }
+    }
+
+    for (book_id, name) in query_values(
+        conn,
+        "SELECT l.book, t.name FROM books_tags_link l JOIN tags t ON t.id = l.tag",
+    )? {
+        if let Some(book) = books.get_mut(&book_id) {
+            book.tags.push(name);
+        }
+    }
+
+    {
+        let mut stmt = conn.prepare(
+            "SELECT l.book, r.rating FROM books_ratings_link l JOIN ratings r ON r.id = l.rating",
+        )?;
+
+        let mut rows = stmt.query([])?;
+
+        while let Some(row) = rows.next()? {
+            if let Some(book) = books.get_mut(&row.get(0)?) {
+                book.rating = row.get::<_, Option<i64>>(1)?.filter(|v| *v > 0);
+            }
+        }
+    }
+
+    {
+        let mut stmt = conn.prepare("SELECT book, type, val FROM identifiers")?;
+
+        let mut rows = stmt.query([])?;
+
+        while let Some(row) = rows.next()? {
+            if let Some(book) = books.get_mut(&row.get(0)?) {
+                book.identifiers.push((row.get(1)?, row.get(2)?));
+            }
+        }
+    }
+
+    {
+        let mut stmt = conn.prepare("SELECT book, format, name FROM data")?;
+
+        let mut rows = stmt.query([])?;
+
+        while let Some(row) = rows.next()? {
+            if let Some(book) = books.get_mut(&row.get(0)?) {
+                book.formats.push((row.get(1)?, row.get(2)?));
+            }
+        }
+    }
+
+    for column_id in find_read_columns(conn)? {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT book FROM custom_column_{column_id} WHERE value = 1"
+        ))?;
+
+        let map = stmt.query_map([], |v| v.get::<_, i64>(0))?;
+
+        for book_id in map {
+            if let Some(book) = books.get_mut(&book_id?) {
+                book.is_read = true;
+            }
+        }
+    }
+
+    let mut books = books.into_values().collect::<Vec<_>>();
+
+    books.sort_unstable_by_key(|v| v.id);
+
+    Ok(books)
+}
+
+/// The yes/no custom columns named "Read".
+fn find_read_columns(conn: &Connection) -> Result<Vec<i64>> {
+    let has_table: bool = conn.query_row(
+        r#"SELECT EXISTS(SELECT * FROM sqlite_master WHERE type = "table" AND name = "custom_columns")"#,
+        [],
+        |v| v.get(0),
+    )?;
+
+    if !has_table {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT id FROM custom_columns WHERE datatype = 'bool' AND (label = 'read' OR name = 'Read' COLLATE NOCASE)",
+    )?;
+
+    let map = stmt.query_map([], |v| v.get(0))?;
+
+    Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+/// Rows of (Book ID, Value)
+fn query_values(conn: &Connection, sql: &str) -> Result<Vec<(i64, String)>> {
+    let mut stmt = conn.prepare(sql)?;
+
+    let map = stmt.query_map([], |v| Ok((v.get(0)?, v.get(1)?)))?;
+
+    Ok(map.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+fn parse_identifier(type_of: &str, value: &str) -> Option<BookIdentifier> {
+    match type_of {
+        // Amazon IDs are ISBN-10s for most print books.
+        "isbn" | "amazon" | "mobi-asin" => BookIdentifier::parse(value),
+        "oclc" => BookIdentifier::parse(&format!("oclc:{value}")),
+        _ => None,
+    }
+}
+
+/// Joins a path stored in the Calibre database onto the root.
+///
+/// Only plain components are allowed so it can't leave the root. (eg. "..", "/etc")
+fn join_inside(root: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+
+    if !relative
+        .components()
+        .all(|v| matches!(v, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let path = root.join(relative);
+
+    path.starts_with(root).then_some(path)
+}
+
+/// Whether the file exists and is still inside of the root once symlinks are resolved.
+async fn is_resolved_inside(path: &Path, resolved_root: &Path) -> bool {
+    fs::canonicalize(path)
+        .await
+        .is_ok_and(|v| v.starts_with(resolved_root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"
+        CREATE TABLE books (id INTEGER PRIMARY KEY, title TEXT NOT NULL, pubdate TIMESTAMP, series_index REAL NOT NULL DEFAULT 1.0, path TEXT NOT NULL, uuid TEXT, has_cover BOOL DEFAULT 0);
+        CREATE TABLE authors (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        CREATE TABLE books_authors_link (id INTEGER PRIMARY KEY, book INTEGER NOT NULL, author INTEGER NOT NULL);
+        CREATE TABLE series (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        CREATE TABLE books_series_link (id INTEGER PRIMARY KEY, book INTEGER NOT NULL, series INTEGER NOT NULL);
+        CREATE TABLE publishers (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        CREATE TABLE books_publishers_link (id INTEGER PRIMARY KEY, book INTEGER NOT NULL, publisher INTEGER NOT NULL);
+        CREATE TABLE languages (id INTEGER PRIMARY KEY, lang_code TEXT NOT NULL);
+        CREATE TABLE books_languages_link (id INTEGER PRIMARY KEY, book INTEGER NOT NULL, lang_code INTEGER NOT NULL, item_order INTEGER NOT NULL DEFAULT 0);
+        CREATE TABLE comments (id INTEGER PRIMARY KEY, book INTEGER NOT NULL, text TEXT NOT NULL);
+        CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        CREATE TABLE books_tags_link (id INTEGER PRIMARY KEY, book INTEGER NOT NULL, tag INTEGER NOT NULL);
+        CREATE TABLE ratings (id INTEGER PRIMARY KEY, rating INTEGER);
+        CREATE TABLE books_ratings_link (id INTEGER PRIMARY KEY, book INTEGER NOT NULL, rating INTEGER NOT NULL);
+        CREATE TABLE identifiers (id INTEGER PRIMARY KEY, book INTEGER NOT NULL, type TEXT NOT NULL DEFAULT 'isbn', val TEXT NOT NULL);
+        CREATE TABLE data (id INTEGER PRIMARY KEY, book INTEGER NOT NULL, format TEXT NOT NULL, uncompressed_size INTEGER NOT NULL, name TEXT NOT NULL);
+        CREATE TABLE custom_columns (id INTEGER PRIMARY KEY, label TEXT NOT NULL, name TEXT NOT NULL, datatype TEXT NOT NULL);
+        CREATE TABLE custom_column_1 (id INTEGER PRIMARY KEY, book INTEGER, value BOOL NOT NULL);
+    "#;
+
+    #[test]
+    fn read_metadata() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+
+        conn.execute_batch(SCHEMA)?;
+        conn.execute_batch(
+            r#"
+            INSERT INTO books VALUES (1, 'Mistborn', '2006-07-17 00:00:00+00:00', 1.0, 'Brandon Sanderson/Mistborn (1)', 'abc', 1);
+            INSERT INTO books VALUES (2, 'Untitled', '0101-01-01 00:00:00+00:00', 1.0, 'Unknown/Untitled (2)', 'def', 0);
+            INSERT INTO authors VALUES (5, 'Brandon Sanderson');
+            INSERT INTO books_authors_link VALUES (1, 1, 5);
+            INSERT INTO series VALUES (1, 'Mistborn');
+            INSERT INTO books_series_link VALUES (1, 1, 1);
+            INSERT INTO publishers VALUES (1, 'Tor');
+            INSERT INTO books_publishers_link VALUES (1, 1, 1);
+            INSERT INTO languages VALUES (1, 'eng');
+            INSERT INTO books_languages_link VALUES (1, 1, 1, 0);
+            INSERT INTO comments VALUES (1, 1, '<p>Ash falls from the sky.</p>');
+            INSERT INTO tags VALUES (1, 'Fantasy');
+            INSERT INTO books_tags_link VALUES (1, 1, 1);
+            INSERT INTO ratings VALUES (1, 8);
+            INSERT INTO books_ratings_link VALUES (1, 1, 1);
+            INSERT INTO identifiers VALUES (1, 1, 'isbn', '9780765311788');
+            INSERT INTO data VALUES (1, 1, 'EPUB', 1000, 'Mistborn - Brandon Sanderson');
+            INSERT INTO custom_columns VALUES (1, 'read', 'Read', 'bool');
+            INSERT INTO custom_column_1 VALUES (1, 1, 1);
+            "#,
+        )?;
+
+        let mut books = read_books(&conn)?;
+
+        assert_eq!(books.len(), 2);
+        assert_eq!(
+            books[0],
+            CalibreBook {
+                id: 1,
+                uuid: String::from("abc"),
+                title: String::from("Mistborn"),
+                path: String::from("Brandon Sanderson/Mistborn (1)"),
+                has_cover: true,
+                pubdate: Some(String::from("2006-07-17 00:00:00+00:00")),
+                series_index: Some(1.0),
+                authors: vec![(5, String::from("Brandon Sanderson"))],
+                series: Some(String::from("Mistborn")),
+                publisher: Some(String::from("Tor")),
+                language: Some(String::from("eng")),
+                description: Some(String::from("<p>Ash falls from the sky.</p>")),
+                rating: Some(8),
+                tags: vec![String::from("Fantasy")],
+                identifiers: vec![(String::from("isbn"), String::from("9780765311788"))],
+                formats: vec![(
+                    String::from("EPUB"),
+                    String::from("Mistborn - Brandon Sanderson")
+                )],
+                is_read: true,
+            }
+        );
+        assert!(!books[1].is_read);
+
+        assert_eq!(
+            books[0].file_paths(Path::new("/calibre")),
+            vec![PathBuf::from(
+                "/calibre/Brandon Sanderson/Mistborn (1)/Mistborn - Brandon Sanderson.epub"
+            )]
+        );
+
+        for path in ["../Other", "/etc", "Author/../../Other"] {
+            books[0].path = String::from(path);
+
+            assert!(
+                books[0].file_paths(Path::new("/calibre")).is_empty(),
+                "{path}"
+            );
+        }
+
+        books[0].path = String::from("Author/Book (1)");
+        books[0].formats[0].1 = String::from("../../../escape");
+
+        assert!(books[0].file_paths(Path::new("/calibre")).is_empty());
+
+        let meta = books.remove(1).into_metadata(None)?.meta;
+
+        assert_eq!(meta.year, None);
+        assert_eq!(meta.series_index, None);
+
+        Ok(())
+    }
+}
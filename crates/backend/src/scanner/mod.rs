@@ -1,11 +1,18 @@
-use std::{collections::VecDeque, path::PathBuf, time::UNIX_EPOCH};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use crate::{
+    config::get_config,
     database::DatabaseAccess,
     http::send_message_to_clients,
-    metadata::{get_metadata_from_files, MetadataReturned},
+    metadata::{get_metadata_from_files, ActiveAgents, MetadataReturned},
     model::{
         book::BookModel,
+        book_field_source::BookFieldSourceModel,
         book_person::BookPersonModel,
         directory::DirectoryModel,
         file::{FileModel, NewFileModel},
@@ -15,180 +22,424 @@ use crate::{
     Result,
 };
 use bookie::BookSearch;
-use chrono::{TimeZone, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use common::parse_book_id;
 use common_local::{
     ws::{TaskId, TaskType, WebsocketNotification},
     LibraryId,
 };
-use tokio::fs;
+use futures::{stream, StreamExt};
+use lazy_static::lazy_static;
+use tokio::{fs, sync::Mutex};
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, trace};
 
-pub static WHITELISTED_FILE_TYPES: [&str; 2] = ["epub", "cbz"];
+pub mod calibre;
+pub mod watcher;
+
+/// How many unmatched files are collected before they're matched concurrently.
+const MATCH_BATCH_SIZE: usize = 100;
+
+lazy_static! {
+    static ref MATCH_INSERT_LOCK: Mutex<()> = Mutex::new(());
+}
+
+pub static WHITELISTED_FILE_TYPES: [&str; 10] = [
+    "epub", "cbz", "cbr", "cb7", "cbt", "mobi", "azw", "azw3", "prc", "pdf",
+];
+
+/// What changed in a library since it was last scanned.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LibraryScanSummary {
+    pub added: usize,
+    pub changed: usize,
+    pub moved: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+/// What happened to a single file when it was scanned.
+#[derive(Debug)]
+pub enum FileScan {
+    Added(FileModel),
+    Changed(FileModel),
+    /// Previously stored at a different path.
+    Moved {
+        from: String,
+        file: FileModel,
+    },
+    Unchanged,
+    /// Not a book or unable to be read.
+    Skipped,
+}
+
+impl FileScan {
+    /// The file if it was inserted or updated.
+    pub fn into_file(self) -> Option<FileModel> {
+        match self {
+            Self::Added(file) | Self::Changed(file) | Self::Moved { file, .. } => Some(file),
+            Self::Unchanged | Self::Skipped => None,
+        }
+    }
+}
 
 pub async fn library_scan(
     library: &LibraryModel,
     directories: Vec<DirectoryModel>,
     task_id: TaskId,
+    token: &CancellationToken,
     db: &dyn DatabaseAccess,
-) -> Result<()> {
+) -> Result<LibraryScanSummary> {
     let mut folders: VecDeque<PathBuf> = directories
         .into_iter()
         .map(|v| PathBuf::from(&v.path))
         .collect::<VecDeque<_>>();
 
-    let (mut checked_items, mut imported_items, mut overwritten_files) = (0, 0, 0);
+    // Files which are stored for the library. Whatever is left after walking the directories was removed.
+    let mut stored_files = FileModel::find_by_library_id(library.id, db)
+        .await?
+        .into_iter()
+        .map(|v| (v.path.clone(), v))
+        .collect::<HashMap<_, _>>();
+
+    let mut summary = LibraryScanSummary::default();
+
+    // Files without a book. They're matched in batches since most of the time is spent waiting on the metadata agents.
+    let mut unmatched_files = Vec::new();
 
     while let Some(path) = folders.pop_front() {
         let mut dir = fs::read_dir(path).await?;
 
         while let Some(entry) = dir.next_entry().await? {
+            // Returned before marking files as removed since we didn't walk every directory.
+            if token.is_cancelled() {
+                info!(library = ?library.id, "Library Scan Cancelled");
+
+                return Ok(summary);
+            }
+
             let file_type = entry.file_type().await?;
-            let file_name = entry.file_name();
             let path = entry.path();
-            let meta = entry.metadata().await?;
 
             if file_type.is_dir() {
                 folders.push_back(path);
             } else if file_type.is_file() {
-                let file_name = file_name.into_string().unwrap();
-                let (file_name, file_type) = match file_name.rsplit_once('.') {
-                    Some((v1, v2)) => (v1.to_string(), v2.to_string().to_lowercase()),
-                    None => (file_name, String::new()),
-                };
-
-                send_message_to_clients(WebsocketNotification::update_task(
-                    task_id,
-                    TaskType::LibraryScan(file_name.clone()),
-                    true,
-                ));
-
-                if WHITELISTED_FILE_TYPES.contains(&file_type.as_str()) {
-                    let file_size = fs::metadata(&path).await?.len();
-
-                    checked_items += 1;
-
-                    let mut book = match bookie::load_from_path(&path.to_string_lossy()) {
-                        Ok(book) => {
-                            if let Some(book) = book {
-                                book
-                            } else {
-                                error!(target: "scanner", file = ?path, "Unable to find book from path");
-                                continue;
-                            }
-                        }
-
-                        Err(e) => {
-                            error!(target: "scanner", error = ?e, file = ?path);
-                            continue;
-                        }
-                    };
-
-                    let path = path.to_str().unwrap().replace('\\', "/");
-
-                    let Some(hash) = book.compute_hash() else {
-                        error!(target: "scanner", file = path, "Unable to compute hash");
-                        continue;
-                    };
-
-                    let chapter_count = book.chapter_count() as i64;
-
-                    // If file exists, check to see if the one currently in the database is valid.
-                    if let Some(mut model) =
-                        FileModel::find_one_by_hash_or_path(&path, &hash, db).await?
-                    {
-                        // We found it by path, no need to verify anything since it exists.
-                        // Update stored model with the new one that matched the hash.
-                        // TODO: Optimize? I don't want to check the FS for EVERY SINGLE FILE.
-                        if model.path != path && tokio::fs::metadata(&model.path).await.is_err() {
-                            model.path = path;
-                            model.file_name = file_name;
-                            model.file_type = file_type;
-                            model.file_size = file_size as i64;
-                            model.library_id = library.id;
-                            model.chapter_count = chapter_count;
-                            model.hash = hash;
-
-                            model.modified_at = Utc.timestamp_millis(
-                                meta.modified()?.duration_since(UNIX_EPOCH)?.as_millis() as i64,
-                            );
-                            model.accessed_at = Utc.timestamp_millis(
-                                meta.accessed()?.duration_since(UNIX_EPOCH)?.as_millis() as i64,
-                            );
-                            model.created_at = Utc.timestamp_millis(
-                                meta.created()?.duration_since(UNIX_EPOCH)?.as_millis() as i64,
-                            );
-                            model.deleted_at = None;
-
-                            info!(target: "scanner", id = ?model.id, "Overwriting Missing File");
-
-                            overwritten_files += 1;
-
-                            model.update(db).await?;
-                        }
-
-                        continue;
-                    }
-
-                    let identifier = if let Some(found) = book.find(BookSearch::Identifier) {
-                        let parsed = found
-                            .into_iter()
-                            .map(|v| parse_book_id(&v))
-                            .collect::<Vec<_>>();
-
-                        parsed
-                            .iter()
-                            .find_map(|v| v.as_isbn_13())
-                            .or_else(|| parsed.iter().find_map(|v| v.as_isbn_10()))
-                    } else {
-                        None
-                    };
-
-                    let file = NewFileModel {
-                        path,
-
-                        file_name,
-                        file_type,
-                        file_size: file_size as i64,
-
-                        library_id: library.id,
-                        book_id: None,
-                        chapter_count,
-
-                        identifier,
-                        hash,
-
-                        modified_at: Utc.timestamp_millis(
-                            meta.modified()?.duration_since(UNIX_EPOCH)?.as_millis() as i64,
-                        ),
-                        accessed_at: Utc.timestamp_millis(
-                            meta.accessed()?.duration_since(UNIX_EPOCH)?.as_millis() as i64,
-                        ),
-                        created_at: Utc.timestamp_millis(
-                            meta.created()?.duration_since(UNIX_EPOCH)?.as_millis() as i64,
-                        ),
-                        deleted_at: None,
-                    };
-
-                    let file = file.insert(db).await?;
-                    let file_id = file.id;
-
-                    imported_items += 1;
-
-                    // TODO: Run Concurrently.
-                    if let Err(e) = file_match_or_create_book(file, library.id, db).await {
-                        error!(error = ?e, "File #{file_id} file_match_or_create_metadata");
-                    }
-                } else {
-                    trace!(file = ?path, "Skipping File. Not a whitelisted file type.");
+                let scanned = scan_file(&path, &mut stored_files, library.id, task_id, db).await?;
+
+                match &scanned {
+                    FileScan::Added(_) => summary.added += 1,
+                    FileScan::Changed(_) => summary.changed += 1,
+                    FileScan::Moved { .. } => summary.moved += 1,
+                    FileScan::Unchanged => summary.unchanged += 1,
+                    FileScan::Skipped => (),
+                }
+
+                if let Some(file) = scanned.into_file().filter(|v| v.book_id.is_none()) {
+                    unmatched_files.push(file);
+                }
+
+                if unmatched_files.len() >= MATCH_BATCH_SIZE {
+                    match_files(
+                        std::mem::take(&mut unmatched_files),
+                        library.id,
+                        task_id,
+                        token,
+                        db,
+                    )
+                    .await;
                 }
             }
         }
     }
 
-    info!("Checked {checked_items} Files, Imported {imported_items} Files, Overwritten {overwritten_files} Files");
+    match_files(unmatched_files, library.id, task_id, token, db).await;
 
-    Ok(())
+    if token.is_cancelled() {
+        return Ok(summary);
+    }
+
+    let removed = stored_files
+        .into_values()
+        .filter(|v| v.deleted_at.is_none())
+        .map(|v| v.id)
+        .collect::<Vec<_>>();
+
+    if !removed.is_empty() {
+        FileModel::mark_deleted(&removed, Utc::now(), db).await?;
+    }
+
+    summary.removed = removed.len();
+
+    info!(
+        library = ?library.id,
+        added = summary.added,
+        changed = summary.changed,
+        moved = summary.moved,
+        removed = summary.removed,
+        unchanged = summary.unchanged,
+        "Finished Library Scan"
+    );
+
+    Ok(summary)
+}
+
+/// Imports or updates a single file. Doesn't match it to a book, use [`match_files`] after.
+///
+/// `stored_files` are the known files keyed by path. The entries for this file, and the one it was moved from, are removed.
+pub async fn scan_file(
+    path: &Path,
+    stored_files: &mut HashMap<String, FileModel>,
+    library_id: LibraryId,
+    task_id: TaskId,
+    db: &dyn DatabaseAccess,
+) -> Result<FileScan> {
+    let Some((file_name, file_type)) = split_file_name(path) else {
+        return Ok(FileScan::Skipped);
+    };
+
+    send_message_to_clients(WebsocketNotification::update_task(
+        task_id,
+        TaskType::LibraryScan(file_name.clone()),
+        true,
+    ));
+
+    if !WHITELISTED_FILE_TYPES.contains(&file_type.as_str()) {
+        trace!(file = ?path, "Skipping File. Not a whitelisted file type.");
+        return Ok(FileScan::Skipped);
+    }
+
+    let meta = fs::metadata(path).await?;
+
+    let path = normalize_path(path);
+    let file_size = meta.len() as i64;
+    let modified_at = system_time_to_utc(meta.modified()?)?;
+
+    let stored = stored_files.remove(&path);
+
+    // Nothing changed since the last scan. No need to open it.
+    if let Some(model) = stored.as_ref() {
+        if model.deleted_at.is_none()
+            && model.file_size == file_size
+            && model.modified_at == modified_at
+        {
+            return Ok(FileScan::Unchanged);
+        }
+    }
+
+    let mut book = match bookie::load_from_path(&path) {
+        Ok(book) => {
+            if let Some(book) = book {
+                book
+            } else {
+                error!(target: "scanner", file = path, "Unable to find book from path");
+                return Ok(FileScan::Skipped);
+            }
+        }
+
+        Err(e) => {
+            error!(target: "scanner", error = ?e, file = path);
+            return Ok(FileScan::Skipped);
+        }
+    };
+
+    let Some(hash) = book.compute_hash() else {
+        error!(target: "scanner", file = path, "Unable to compute hash");
+        return Ok(FileScan::Skipped);
+    };
+
+    let chapter_count = book.chapter_count() as i64;
+
+    let (mut model, moved_from) = if let Some(model) = stored {
+        // The contents changed. The hash has to stay unique.
+        if model.hash != hash {
+            if let Some(other) = FileModel::find_one_by_hash(&hash, db).await? {
+                error!(target: "scanner", file = path, other = other.path, "Changed File is a duplicate");
+                return Ok(FileScan::Skipped);
+            }
+        }
+
+        (model, None)
+    } else if let Some(model) = FileModel::find_one_by_hash_or_path(&path, &hash, db).await? {
+        // Found by hash. It was only moved if the previous location no longer exists.
+        if model.path != path && fs::metadata(&model.path).await.is_ok() {
+            trace!(target: "scanner", file = path, other = model.path, "Skipping Duplicate File");
+            return Ok(FileScan::Skipped);
+        }
+
+        stored_files.remove(&model.path);
+
+        let from = model.path.clone();
+
+        (model, Some(from))
+    } else {
+        let identifier = if let Some(found) = book.find(BookSearch::Identifier) {
+            let parsed = found
+                .into_iter()
+                .map(|v| parse_book_id(&v))
+                .collect::<Vec<_>>();
+
+            parsed
+                .iter()
+                .find_map(|v| v.as_isbn_13())
+                .or_else(|| parsed.iter().find_map(|v| v.as_isbn_10()))
+        } else {
+            None
+        };
+
+        let file = NewFileModel {
+            path,
+
+            file_name,
+            file_type,
+            file_size,
+
+            library_id,
+            book_id: None,
+            chapter_count,
+
+            identifier,
+            hash,
+
+            modified_at,
+            accessed_at: system_time_to_utc(meta.accessed()?)?,
+            created_at: system_time_to_utc(meta.created()?)?,
+            deleted_at: None,
+
+            partial_md5: partial_md5(Path::new(&path)),
+        };
+
+        return Ok(FileScan::Added(file.insert(db).await?));
+    };
+
+    model.path = path;
+    model.file_name = file_name;
+    model.file_type = file_type;
+    model.file_size = file_size;
+    model.library_id = library_id;
+    model.chapter_count = chapter_count;
+    model.hash = hash;
+
+    model.modified_at = modified_at;
+    model.accessed_at = system_time_to_utc(meta.accessed()?)?;
+    model.created_at = system_time_to_utc(meta.created()?)?;
+    model.deleted_at = None;
+    model.partial_md5 = partial_md5(Path::new(&model.path));
+
+    info!(target: "scanner", id = ?model.id, ?moved_from, "Updating File");
+
+    model.update(db).await?;
+
+    Ok(match moved_from {
+        Some(from) => FileScan::Moved { from, file: model },
+        None => FileScan::Changed(model),
+    })
+}
+
+/// Matches the files to a book, or creates one, using `scanner.workers` concurrent workers.
+///
+/// Once cancelled the remaining files are skipped. Ones already being matched are finished.
+pub async fn match_files(
+    files: Vec<FileModel>,
+    library_id: LibraryId,
+    task_id: TaskId,
+    token: &CancellationToken,
+    db: &dyn DatabaseAccess,
+) {
+    let total = files.len();
+
+    if total == 0 {
+        return;
+    }
+
+    let workers = get_config().scanner.workers.max(1);
+
+    let mut matching = stream::iter(files)
+        .map(|file| async move {
+            let file_id = file.id;
+            let file_name = file.file_name.clone();
+
+            if token.is_cancelled() {
+                return file_name;
+            }
+
+            if let Err(e) = file_match_or_create_book(file, library_id, db).await {
+                error!(error = ?e, "File #{file_id} file_match_or_create_metadata");
+            }
+
+            file_name
+        })
+        .buffer_unordered(workers);
+
+    let mut finished = 0;
+
+    while let Some(file_name) = matching.next().await {
+        finished += 1;
+
+        send_message_to_clients(WebsocketNotification::update_task(
+            task_id,
+            TaskType::LibraryScan(format!("Matched {finished}/{total}: {file_name}")),
+            true,
+        ));
+    }
+}
+
+/// The hash of the book contents. The same one [`scan_file`] stores.
+pub fn hash_file(path: &Path) -> Option<String> {
+    let mut book = bookie::load_from_path(path.to_str()?).ok()??;
+
+    book.compute_hash()
+}
+
+/// KOReader's document identifier. A MD5 of 1KB samples at exponentially growing offsets.
+pub fn partial_md5(path: &Path) -> Option<String> {
+    const SAMPLE_SIZE: usize = 1024;
+
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut context = md5::Context::new();
+    let mut buffer = [0; SAMPLE_SIZE];
+
+    // 0, 1KB, 4KB, 16KB, .. 1GB
+    let offsets = std::iter::once(0).chain((0..=10).map(|i| (SAMPLE_SIZE as u64) << (2 * i)));
+
+    for offset in offsets {
+        file.seek(SeekFrom::Start(offset)).ok()?;
+
+        let mut read = 0;
+
+        while read < SAMPLE_SIZE {
+            match file.read(&mut buffer[read..]).ok()? {
+                0 => break,
+                v => read += v,
+            }
+        }
+
+        if read == 0 {
+            break;
+        }
+
+        context.consume(&buffer[..read]);
+    }
+
+    Some(format!("{:x}", context.compute()))
+}
+
+/// Returns the file name without the extension and the lowercased extension.
+pub fn split_file_name(path: &Path) -> Option<(String, String)> {
+    let file_name = path.file_name()?.to_str()?;
+
+    Some(match file_name.rsplit_once('.') {
+        Some((v1, v2)) => (v1.to_string(), v2.to_lowercase()),
+        None => (file_name.to_string(), String::new()),
+    })
+}
+
+/// Paths are stored with forward slashes.
+pub fn normalize_path(path: &Path) -> String {
+    path.to_str().unwrap().replace('\\', "/")
+}
+
+fn system_time_to_utc(value: SystemTime) -> Result<DateTime<Utc>> {
+    Ok(Utc.timestamp_millis(value.duration_since(UNIX_EPOCH)?.as_millis() as i64))
 }
 
 async fn file_match_or_create_book(
@@ -199,53 +450,78 @@ async fn file_match_or_create_book(
     if file.book_id.is_none() {
         let file_id = file.id;
 
-        let meta = get_metadata_from_files(&[file], &Default::default()).await?;
+        let agent = ActiveAgents::for_library(library_id, db).await?;
 
-        if let Some(mut ret) = meta {
-            let (main_author, author_ids) = ret.add_or_ignore_authors_into_database(db).await?;
+        let meta = get_metadata_from_files(&[file], &agent).await?;
 
-            let MetadataReturned {
-                mut meta,
-                publisher,
-                ..
-            } = ret;
+        if let Some(ret) = meta {
+            // Books and people are looked up before they're inserted. Otherwise concurrent matches could create duplicates.
+            let _lock = MATCH_INSERT_LOCK.lock().await;
 
-            // TODO: This is For Local File Data. Need specify.
-            if let Some(item) = meta.thumb_locations.iter_mut().find(|v| v.is_file_data()) {
-                item.download(db).await?;
-            }
+            let book_model = insert_book(ret, library_id, db).await?;
 
-            let mut book_model: BookModel = meta.into();
+            FileModel::update_book_id(file_id, book_model.id, db).await?;
+        }
+    }
 
-            book_model.library_id = library_id;
+    Ok(())
+}
 
-            // TODO: Store Publisher inside Database
-            book_model.cached = book_model
-                .cached
-                .publisher_optional(publisher)
-                .author_optional(main_author);
+/// Inserts the book along with its authors. The files still have to be linked to it.
+async fn insert_book(
+    mut ret: MetadataReturned,
+    library_id: LibraryId,
+    db: &dyn DatabaseAccess,
+) -> Result<BookModel> {
+    let field_sources = ret.field_sources();
 
-            let book_model = book_model.insert_or_increment(db).await?;
-            FileModel::update_book_id(file_id, book_model.id, db).await?;
+    let (main_author, author_ids) = ret.add_or_ignore_authors_into_database(db).await?;
 
-            if let Some(thumb_path) = book_model.thumb_path.as_value() {
-                if let Some(image) = UploadedImageModel::get_by_path(thumb_path, db).await? {
-                    ImageLinkModel::new_book(image.id, book_model.id)
-                        .insert(db)
-                        .await?;
-                }
-            }
+    let MetadataReturned {
+        mut meta,
+        publisher,
+        ..
+    } = ret;
 
-            for person_id in author_ids {
-                BookPersonModel {
-                    book_id: book_model.id,
-                    person_id,
-                }
-                .insert_or_ignore(db)
+    // The cover may have been merged from another agent.
+    if !meta.thumb_locations.iter().any(|v| v.is_local()) {
+        if let Some(item) = meta.thumb_locations.first_mut() {
+            item.download(db).await?;
+        }
+    }
+
+    let mut book_model: BookModel = meta.into();
+
+    book_model.library_id = library_id;
+
+    book_model.cached = book_model
+        .cached
+        .publisher_optional(publisher)
+        .author_optional(main_author);
+
+    book_model.link_series(db).await?;
+    book_model.link_publisher(db).await?;
+
+    let book_model = book_model.insert_or_increment(db).await?;
+
+    BookFieldSourceModel::insert_or_replace(book_model.id, &field_sources, db).await?;
+
+    if let Some(thumb_path) = book_model.thumb_path.as_value() {
+        if let Some(image) = UploadedImageModel::get_by_path(thumb_path, db).await? {
+            ImageLinkModel::new_book(image.id, book_model.id)
+                .insert(db)
                 .await?;
-            }
         }
     }
 
-    Ok(())
+    for person_id in author_ids {
+        BookPersonModel {
+            book_id: book_model.id,
+            person_id,
+        }
+        .insert_or_ignore(db)
+        .await?;
+    }
+
+    Ok(book_model)
 }
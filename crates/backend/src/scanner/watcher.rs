@@ -0,0 +1,432 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use actix_web::web;
+use chrono::Utc;
+use common_local::{
+    ws::{TaskId, TaskType, WebsocketNotification},
+    LibraryId,
+};
+use notify::{
+    event::{AccessKind, AccessMode, ModifyKind, RenameMode},
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, runtime::Runtime};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info};
+
+use crate::{
+    database::{Database, DatabaseAccess},
+    http::send_message_to_clients,
+    model::{directory::DirectoryModel, file::FileModel},
+    task::{queue_task, TaskFileChanged},
+    Result,
+};
+
+use super::{match_files, normalize_path, scan_file, split_file_name, WHITELISTED_FILE_TYPES};
+
+/// How long a path has to stay untouched before it's queued. Copying a file emits events until it's done.
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// How often the watched directories are re-read from the database. Directories which couldn't be watched are retried.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+static RELOAD_DIRECTORIES: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FileChange {
+    /// Import the file or every file inside the directory.
+    Import(PathBuf),
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Delete(PathBuf),
+}
+
+impl FileChange {
+    fn path(&self) -> &Path {
+        match self {
+            Self::Import(path) | Self::Delete(path) => path,
+            Self::Move { to, .. } => to,
+        }
+    }
+}
+
+/// Re-read the watched directories. Call after they've been modified.
+pub fn reload_directories() {
+    RELOAD_DIRECTORIES.store(true, Ordering::Relaxed);
+}
+
+/// Watches every library directory and queues a [`TaskFileChanged`] for each debounced change.
+pub fn start_watcher(db: web::Data<Database>) -> Result<()> {
+    let (send, recv) = channel();
+
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = send.send(event);
+    })?;
+
+    thread::spawn(move || {
+        let rt = Runtime::new().unwrap();
+
+        // Directory path and its library.
+        let mut watching: Vec<(PathBuf, LibraryId)> = Vec::new();
+        let mut last_reload: Option<Instant> = None;
+
+        // Keyed by the path which the change ends up at.
+        let mut pending: HashMap<PathBuf, (FileChange, Instant)> = HashMap::new();
+
+        loop {
+            if RELOAD_DIRECTORIES.swap(false, Ordering::Relaxed)
+                || last_reload.map_or(true, |v| v.elapsed() >= RELOAD_INTERVAL)
+            {
+                last_reload = Some(Instant::now());
+
+                match rt.block_on(DirectoryModel::get_all(&db.basic())) {
+                    Ok(directories) => update_watching(&mut watcher, &mut watching, directories),
+                    Err(e) => error!(error = ?e, "Unable to load Directories to watch"),
+                }
+            }
+
+            match recv.recv_timeout(Duration::from_millis(500)) {
+                Ok(Ok(event)) => add_event(event, &mut pending),
+                Ok(Err(e)) => error!(error = ?e, "File Watcher"),
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            let ready = pending
+                .iter()
+                .filter(|(_, (_, last_event))| last_event.elapsed() >= DEBOUNCE)
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>();
+
+            for path in ready {
+                let Some((change, _)) = pending.remove(&path) else {
+                    continue;
+                };
+
+                // Ignore files we wouldn't import anyways.
+                if let FileChange::Import(path) = &change {
+                    let is_book = split_file_name(path).map_or(false, |(_, file_type)| {
+                        WHITELISTED_FILE_TYPES.contains(&file_type.as_str())
+                    });
+
+                    if !is_book && !path.is_dir() {
+                        continue;
+                    }
+                }
+
+                let Some(library_id) = find_library_id(change.path(), &watching) else {
+                    continue;
+                };
+
+                debug!(?change, "Queueing File Change");
+
+                queue_task(TaskFileChanged { library_id, change });
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Only the directories which are being watched are kept. The others are retried on the next call.
+fn update_watching(
+    watcher: &mut RecommendedWatcher,
+    watching: &mut Vec<(PathBuf, LibraryId)>,
+    directories: Vec<DirectoryModel>,
+) {
+    let mut directories = directories
+        .into_iter()
+        .map(|v| (PathBuf::from(v.path), v.library_id))
+        .collect::<Vec<_>>();
+
+    for (path, _) in watching.iter() {
+        if !directories.iter().any(|(v, _)| v == path) {
+            info!(?path, "No longer watching Directory");
+
+            if let Err(e) = watcher.unwatch(path) {
+                error!(error = ?e, ?path, "Unable to unwatch Directory");
+            }
+        }
+    }
+
+    directories.retain(|(path, _)| {
+        if watching.iter().any(|(v, _)| v == path) {
+            return true;
+        }
+
+        match watcher.watch(path, RecursiveMode::Recursive) {
+            Ok(_) => {
+                info!(?path, "Watching Directory");
+                true
+            }
+            Err(e) => {
+                error!(error = ?e, ?path, "Unable to watch Directory");
+                false
+            }
+        }
+    });
+
+    *watching = directories;
+}
+
+fn add_event(event: Event, pending: &mut HashMap<PathBuf, (FileChange, Instant)>) {
+    let now = Instant::now();
+
+    match event.kind {
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+            let (from, to) = (event.paths[0].clone(), event.paths[1].clone());
+
+            // The From and To events were already added. Combine them.
+            pending.remove(&to);
+
+            let change = match pending.remove(&from) {
+                // Wasn't imported yet. (eg. "book.epub.part" -> "book.epub")
+                Some((FileChange::Import(_), _)) => FileChange::Import(to.clone()),
+                // Moved more than once. Keep where it originally was.
+                Some((FileChange::Move { from, .. }, _)) => FileChange::Move {
+                    from,
+                    to: to.clone(),
+                },
+                _ => FileChange::Move {
+                    from,
+                    to: to.clone(),
+                },
+            };
+
+            pending.insert(to, (change, now));
+        }
+
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            for path in event.paths {
+                match pending.get_mut(&path) {
+                    // Keep it so the following Both event knows it was never imported.
+                    Some((FileChange::Import(_), last_event)) => *last_event = now,
+                    _ => {
+                        pending.insert(path.clone(), (FileChange::Delete(path), now));
+                    }
+                }
+            }
+        }
+
+        EventKind::Remove(_) => {
+            for path in event.paths {
+                pending.insert(path.clone(), (FileChange::Delete(path), now));
+            }
+        }
+
+        EventKind::Modify(ModifyKind::Name(_)) => {
+            for path in event.paths {
+                // Other platforms don't say which side of the rename the path is.
+                let change = if path.exists() {
+                    FileChange::Import(path.clone())
+                } else {
+                    FileChange::Delete(path.clone())
+                };
+
+                pending.insert(path, (change, now));
+            }
+        }
+
+        EventKind::Create(_)
+        | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any)
+        | EventKind::Access(AccessKind::Close(AccessMode::Write)) => {
+            for path in event.paths {
+                match pending.get_mut(&path) {
+                    // Keep the move. It'll import the file if it changed.
+                    Some((FileChange::Move { .. }, last_event)) => *last_event = now,
+                    _ => {
+                        pending.insert(path.clone(), (FileChange::Import(path), now));
+                    }
+                }
+            }
+        }
+
+        _ => (),
+    }
+}
+
+/// The library of the deepest watched directory containing the path.
+fn find_library_id(path: &Path, watching: &[(PathBuf, LibraryId)]) -> Option<LibraryId> {
+    watching
+        .iter()
+        .filter(|(dir, _)| path.starts_with(dir))
+        .max_by_key(|(dir, _)| dir.components().count())
+        .map(|(_, library_id)| *library_id)
+}
+
+pub async fn apply_file_change(
+    change: FileChange,
+    library_id: LibraryId,
+    task_id: TaskId,
+    token: &CancellationToken,
+    db: &dyn DatabaseAccess,
+) -> Result<()> {
+    match change {
+        FileChange::Import(path) => import_path(path, library_id, task_id, token, db).await?,
+
+        FileChange::Move { from, to } => {
+            let from_path = normalize_path(&from);
+            let to_path = normalize_path(&to);
+
+            let mut moved = Vec::new();
+
+            if let Some(model) = FileModel::find_one_by_path(&from_path, db).await? {
+                moved.push((model, to.clone()));
+            } else {
+                // Directory was moved.
+                for model in FileModel::find_by_path_prefix(&format!("{from_path}/"), db).await? {
+                    let path = PathBuf::from(model.path.replacen(&from_path, &to_path, 1));
+                    moved.push((model, path));
+                }
+            }
+
+            // Unknown. Could've been moved in from somewhere we aren't watching.
+            if moved.is_empty() {
+                return import_path(to, library_id, task_id, token, db).await;
+            }
+
+            for (mut model, path) in moved {
+                let Some((file_name, file_type)) = split_file_name(&path) else {
+                    continue;
+                };
+
+                send_message_to_clients(WebsocketNotification::update_task(
+                    task_id,
+                    TaskType::LibraryScan(file_name.clone()),
+                    true,
+                ));
+
+                info!(target: "scanner", id = ?model.id, from = model.path, to = ?path, "Moving File");
+
+                model.path = normalize_path(&path);
+                model.file_name = file_name;
+                model.file_type = file_type;
+                model.library_id = library_id;
+                model.deleted_at = None;
+
+                model.update(db).await?;
+            }
+        }
+
+        FileChange::Delete(path) => {
+            let path = normalize_path(&path);
+
+            let files = match FileModel::find_one_by_path(&path, db).await? {
+                Some(model) => vec![model],
+                // Directory was deleted.
+                None => FileModel::find_by_path_prefix(&format!("{path}/"), db).await?,
+            };
+
+            let removed = files
+                .into_iter()
+                .filter(|v| v.deleted_at.is_none())
+                .map(|v| v.id)
+                .collect::<Vec<_>>();
+
+            if !removed.is_empty() {
+                info!(target: "scanner", path, count = removed.len(), "Removing Files");
+
+                FileModel::mark_deleted(&removed, Utc::now(), db).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn import_path(
+    path: PathBuf,
+    library_id: LibraryId,
+    task_id: TaskId,
+    token: &CancellationToken,
+    db: &dyn DatabaseAccess,
+) -> Result<()> {
+    let mut paths = VecDeque::from([path]);
+    let mut unmatched_files = Vec::new();
+
+    while let Some(path) = paths.pop_front() {
+        if token.is_cancelled() {
+            break;
+        }
+
+        let Ok(meta) = fs::metadata(&path).await else {
+            // Already gone.
+            continue;
+        };
+
+        if meta.is_dir() {
+            let mut dir = fs::read_dir(path).await?;
+
+            while let Some(entry) = dir.next_entry().await? {
+                paths.push_back(entry.path());
+            }
+        } else if meta.is_file() {
+            let mut stored_files = FileModel::find_one_by_path(&normalize_path(&path), db)
+                .await?
+                .into_iter()
+                .map(|v| (v.path.clone(), v))
+                .collect();
+
+            let scanned = scan_file(&path, &mut stored_files, library_id, task_id, db).await?;
+
+            debug!(?path, ?scanned, "Scanned File");
+
+            if let Some(file) = scanned.into_file().filter(|v| v.book_id.is_none()) {
+                unmatched_files.push(file);
+            }
+        }
+    }
+
+    match_files(unmatched_files, library_id, task_id, token, db).await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_unwatched_directories() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("books-watcher-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+
+        let directories = || {
+            vec![DirectoryModel {
+                library_id: LibraryId::from(1),
+                path: path.to_string_lossy().into_owned(),
+            }]
+        };
+
+        let mut watcher = notify::recommended_watcher(|_| {})?;
+        let mut watching = Vec::new();
+
+        // Missing so it can't be watched yet.
+        update_watching(&mut watcher, &mut watching, directories());
+        assert!(watching.is_empty());
+
+        std::fs::create_dir_all(&path)?;
+
+        update_watching(&mut watcher, &mut watching, directories());
+        assert_eq!(watching, vec![(path.clone(), LibraryId::from(1))]);
+
+        update_watching(&mut watcher, &mut watching, Vec::new());
+        assert!(watching.is_empty());
+
+        std::fs::remove_dir_all(&path)?;
+
+        Ok(())
+    }
+}
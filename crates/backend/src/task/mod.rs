@@ -1,26 +1,37 @@
 use std::{
     collections::VecDeque,
-    sync::Mutex,
+    io::ErrorKind,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
     thread,
     time::{Duration, Instant},
 };
 
 use actix_web::web;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use common::{BookId, PersonId, Source};
+use chrono::{DateTime, Local, Utc};
+use common::{BookId, MemberId, PersonId, Source};
 use common_local::{
     filter::FilterContainer,
+    setup::ScheduledTask,
     ws::{TaskId, TaskType, WebsocketNotification},
-    LibraryId, SearchFor, SearchForBooksBy,
+    CronSchedule, LibraryId, MetadataAgent, MetadataField, PersonField, SearchFor,
+    SearchForBooksBy, TaskStatus,
 };
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 use tokio::{runtime::Runtime, time::sleep};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info};
 
 use crate::{
+    config::get_config,
     database::{Database, DatabaseAccess},
-    http::send_message_to_clients,
+    http::{send_message_to_clients, RUNNING_TASKS},
+    image::prefixhash_to_path,
     metadata::{
         get_metadata_by_source, get_metadata_from_files, get_person_by_source, search_all_agents,
         search_and_return_first_valid_agent, ActiveAgents, FoundImageLocation, MetadataReturned,
@@ -28,48 +39,336 @@ use crate::{
     },
     model::{
         book::BookModel,
+        book_field_source::BookFieldSourceModel,
+        book_locked_field::BookLockedFieldModel,
         book_person::BookPersonModel,
         directory::DirectoryModel,
         file::FileModel,
+        file_text::FileTextModel,
         image::{ImageLinkModel, UploadedImageModel},
         library::LibraryModel,
+        member_library::LibraryAccess,
         person::PersonModel,
         person_alt::PersonAltModel,
+        person_locked_field::PersonLockedFieldModel,
+        task::{NewTaskModel, TaskModel},
     },
+    scanner::watcher::FileChange,
     sort_by_similarity, Result,
 };
 
 // TODO: Sort into smaller functions. There's lots of redundant code.
-// TODO: A should stop boolean
-// TODO: Store what's currently running
+
+/// How often the progress of the running task is saved.
+const PROGRESS_SAVE_INTERVAL: Duration = Duration::from_secs(5);
 
 lazy_static! {
     /// The tasks which are currently queued.
-    pub static ref TASKS_QUEUED: Mutex<VecDeque<Box<dyn Task>>> = Mutex::new(VecDeque::new());
+    static ref TASKS_QUEUED: Mutex<VecDeque<QueuedTask>> = Mutex::new(VecDeque::new());
+
+    /// Tasks which were just queued. They're stored in the database before being queued.
+    static ref TASKS_UNSAVED: Mutex<Vec<(bool, Box<dyn Task>)>> = Mutex::new(Vec::new());
 
-    /// The tasks which run in intervals.
-    static ref TASK_INTERVALS: Mutex<Vec<TaskInterval>> = Mutex::new(vec![]);
+    /// The task which is currently running.
+    static ref TASK_RUNNING: Mutex<Option<(TaskId, CancellationToken)>> = Mutex::new(None);
+
+    /// The tasks which run on a schedule.
+    static ref TASK_SCHEDULES: Mutex<Vec<TaskSchedule>> = Mutex::new(Vec::new());
 }
 
-struct TaskInterval {
-    pub last_ran: Option<DateTime<Utc>>,
-    pub interval: Duration,
-    pub task: fn() -> Box<dyn Task>,
+static RELOAD_SCHEDULES: AtomicBool = AtomicBool::new(true);
+
+struct QueuedTask {
+    id: TaskId,
+    task: Box<dyn Task>,
+}
+
+struct TaskSchedule {
+    schedule: CronSchedule,
+    task: ScheduledTask,
+    next_run: Option<DateTime<Local>>,
 }
 
 #[async_trait]
 pub trait Task: Send {
-    async fn run(&mut self, task_id: TaskId, db: &dyn DatabaseAccess) -> Result<()>;
+    /// Long running tasks should check the token and return early once it's cancelled.
+    async fn run(
+        &mut self,
+        task_id: TaskId,
+        token: &CancellationToken,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()>;
 
+    /// Also used to find which task to recreate from the database.
     fn name(&self) -> &'static str;
+
+    /// Stored in the database to recreate the task after a restart.
+    fn to_json(&self) -> Result<String>;
+}
+
+/// Recreates a task which was stored in the database.
+fn task_from_json(name: &str, data: &str) -> Result<Option<Box<dyn Task>>> {
+    let task: Box<dyn Task> = match name {
+        "Library Scan" => Box::new(serde_json::from_str::<TaskLibraryScan>(data)?),
+        "File Changed" => Box::new(serde_json::from_str::<TaskFileChanged>(data)?),
+        "Updating Book" => Box::new(serde_json::from_str::<TaskUpdateInvalidBook>(data)?),
+        "Updating Person" => Box::new(serde_json::from_str::<TaskUpdatePeople>(data)?),
+        "Clean Orphan Images" => Box::new(serde_json::from_str::<TaskCleanOrphanImages>(data)?),
+        "Import Calibre" => Box::new(serde_json::from_str::<TaskImportCalibre>(data)?),
+        "Index Text" => Box::new(serde_json::from_str::<TaskIndexText>(data)?),
+        "Rehash Files" => Box::new(serde_json::from_str::<TaskRehashFiles>(data)?),
+        "Partial MD5" => Box::new(serde_json::from_str::<TaskPartialMd5>(data)?),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(task))
 }
 
 pub fn queue_task<T: Task + 'static>(task: T) {
-    TASKS_QUEUED.lock().unwrap().push_back(Box::new(task));
+    TASKS_UNSAVED.lock().unwrap().push((false, Box::new(task)));
 }
 
 pub fn queue_task_priority<T: Task + 'static>(task: T) {
-    TASKS_QUEUED.lock().unwrap().push_front(Box::new(task));
+    TASKS_UNSAVED.lock().unwrap().push((true, Box::new(task)));
+}
+
+/// Cancels a queued or running task. Returns false if it isn't either.
+pub async fn cancel_task(task_id: TaskId, db: &dyn DatabaseAccess) -> Result<bool> {
+    if let Some((_, token)) = TASK_RUNNING
+        .lock()
+        .unwrap()
+        .as_ref()
+        .filter(|(id, _)| *id == task_id)
+    {
+        info!(id = ?task_id, "Cancelling Running Task");
+
+        token.cancel();
+
+        return Ok(true);
+    }
+
+    let was_queued = {
+        let mut queued = TASKS_QUEUED.lock().unwrap();
+
+        if let Some(index) = queued.iter().position(|v| v.id == task_id) {
+            queued.remove(index);
+            true
+        } else {
+            false
+        }
+    };
+
+    if was_queued {
+        info!(id = ?task_id, "Cancelling Queued Task");
+
+        TaskModel::update_status(task_id, TaskStatus::Cancelled, None, db).await?;
+    }
+
+    Ok(was_queued)
+}
+
+async fn insert_task(
+    task: &dyn Task,
+    is_priority: bool,
+    db: &dyn DatabaseAccess,
+) -> Result<TaskId> {
+    let model = NewTaskModel {
+        name: task.name().to_string(),
+        data: task.to_json()?,
+        is_priority,
+        status: TaskStatus::Queued,
+        queued_at: Utc::now(),
+        started_at: None,
+    }
+    .insert(db)
+    .await?;
+
+    Ok(model.id)
+}
+
+/// Stores the newly queued tasks and moves them into the queue.
+///
+/// Tasks which couldn't be stored are kept and retried on the next call.
+async fn save_queued_tasks(db: &dyn DatabaseAccess) {
+    let unsaved = std::mem::take(&mut *TASKS_UNSAVED.lock().unwrap());
+    let mut failed = Vec::new();
+
+    for (is_priority, task) in unsaved {
+        let id = match insert_task(task.as_ref(), is_priority, db).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(task = task.name(), error = ?e, "Unable to save queued Task");
+                failed.push((is_priority, task));
+                continue;
+            }
+        };
+
+        let mut queued = TASKS_QUEUED.lock().unwrap();
+
+        if is_priority {
+            queued.push_front(QueuedTask { id, task });
+        } else {
+            queued.push_back(QueuedTask { id, task });
+        }
+    }
+
+    if !failed.is_empty() {
+        // Keep them ahead of the ones queued in the meantime.
+        let mut unsaved = TASKS_UNSAVED.lock().unwrap();
+        failed.append(&mut unsaved);
+        *unsaved = failed;
+    }
+}
+
+/// Queues the tasks which didn't finish before the last shutdown.
+async fn load_unfinished_tasks(db: &dyn DatabaseAccess) -> Result<()> {
+    for model in TaskModel::find_unfinished(db).await? {
+        let task = match task_from_json(&model.name, &model.data) {
+            Ok(Some(v)) => v,
+            Ok(None) => {
+                error!(id = ?model.id, name = model.name, "Unknown Task");
+                TaskModel::update_status(model.id, TaskStatus::Failed, Some("Unknown Task"), db)
+                    .await?;
+                continue;
+            }
+            Err(e) => {
+                error!(id = ?model.id, error = ?e, "Unable to recreate Task");
+                TaskModel::update_status(model.id, TaskStatus::Failed, Some(&e.to_string()), db)
+                    .await?;
+                continue;
+            }
+        };
+
+        if model.status == TaskStatus::Running {
+            info!(id = ?model.id, name = model.name, "Re-queueing interrupted Task");
+            TaskModel::update_status(model.id, TaskStatus::Queued, None, db).await?;
+        }
+
+        let mut queued = TASKS_QUEUED.lock().unwrap();
+
+        if model.is_priority {
+            queued.push_front(QueuedTask { id: model.id, task });
+        } else {
+            queued.push_back(QueuedTask { id: model.id, task });
+        }
+    }
+
+    Ok(())
+}
+
+/// Stores the last progress update of the running task.
+async fn save_task_progress(task_id: TaskId, db: &dyn DatabaseAccess) -> Result<()> {
+    let progress = RUNNING_TASKS
+        .lock()
+        .unwrap()
+        .get(&task_id)
+        .and_then(|v| v.current.as_ref())
+        .map(|v| v.to_string());
+
+    if let Some(progress) = progress {
+        TaskModel::update_progress(task_id, Some(&progress), db).await?;
+    }
+
+    Ok(())
+}
+
+/// Re-read the schedules from the config. Call after they've been modified.
+pub fn reload_schedules() {
+    RELOAD_SCHEDULES.store(true, Ordering::Relaxed);
+}
+
+fn load_schedules() {
+    let now = Local::now();
+
+    let schedules = get_config()
+        .schedules
+        .into_iter()
+        .filter_map(|v| match v.cron.parse::<CronSchedule>() {
+            Ok(schedule) => Some(TaskSchedule {
+                next_run: schedule.next_after(&now),
+                schedule,
+                task: v.task,
+            }),
+
+            Err(e) => {
+                error!(cron = v.cron, error = %e, "Invalid Task Schedule");
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for schedule in &schedules {
+        debug!(
+            task = schedule.task.name(),
+            cron = %schedule.schedule,
+            next_run = ?schedule.next_run,
+            "Loaded Task Schedule"
+        );
+    }
+
+    *TASK_SCHEDULES.lock().unwrap() = schedules;
+}
+
+/// Queues the scheduled tasks which are due.
+async fn queue_scheduled_tasks(db: &dyn DatabaseAccess) -> Result<()> {
+    if RELOAD_SCHEDULES.swap(false, Ordering::Relaxed) {
+        load_schedules();
+    }
+
+    let now = Local::now();
+
+    let due = TASK_SCHEDULES
+        .lock()
+        .unwrap()
+        .iter_mut()
+        .filter(|v| v.next_run.map_or(false, |v| v <= now))
+        .map(|v| {
+            v.next_run = v.schedule.next_after(&now);
+            v.task
+        })
+        .collect::<Vec<_>>();
+
+    for task in due {
+        info!(task = task.name(), "Queueing Scheduled Task");
+
+        match task {
+            ScheduledTask::LibraryScan { library_id } => {
+                for library_id in get_library_ids(library_id, db).await? {
+                    queue_task(TaskLibraryScan { library_id });
+                }
+            }
+
+            ScheduledTask::RefreshMetadata { library_id } => {
+                for library_id in get_library_ids(library_id, db).await? {
+                    queue_task(TaskUpdateInvalidBook::new(
+                        UpdatingBook::UpdateAllWithAgent {
+                            library_id,
+                            agent: String::new(),
+                        },
+                    ));
+                }
+            }
+
+            ScheduledTask::CleanOrphanImages => queue_task(TaskCleanOrphanImages),
+        }
+    }
+
+    Ok(())
+}
+
+/// The library or every library if it's not set.
+async fn get_library_ids(
+    library_id: Option<LibraryId>,
+    db: &dyn DatabaseAccess,
+) -> Result<Vec<LibraryId>> {
+    Ok(match library_id {
+        Some(v) => vec![v],
+        None => LibraryModel::get_all(db)
+            .await?
+            .into_iter()
+            .map(|v| v.id)
+            .collect(),
+    })
 }
 
 pub fn start_task_manager(db: web::Data<Database>) {
@@ -77,63 +376,101 @@ pub fn start_task_manager(db: web::Data<Database>) {
         let rt = Runtime::new().unwrap();
 
         rt.block_on(async {
+            let db = db.basic();
+
+            if let Err(e) = load_unfinished_tasks(&db).await {
+                error!(error = ?e, "Unable to load unfinished Tasks");
+            }
+
             loop {
                 sleep(Duration::from_secs(1)).await;
 
-                // TODO: Should I check intervals first or manually queued first?
+                if let Err(e) = queue_scheduled_tasks(&db).await {
+                    error!(error = ?e, "Unable to queue scheduled Tasks");
+                }
+
+                save_queued_tasks(&db).await;
 
                 // Used to prevent holding lock past await.
-                let task = {
-                    let now = Utc::now();
+                let task = TASKS_QUEUED.lock().unwrap().pop_front();
 
-                    // Get the next task in interval.
-                    let mut v = TASK_INTERVALS.lock().unwrap();
+                // Run the found task.
+                if let Some(QueuedTask {
+                    id: task_id,
+                    mut task,
+                }) = task
+                {
+                    let start_time = Instant::now();
+                    let token = CancellationToken::new();
 
-                    let interval = v.iter_mut().find_map(|v| {
-                        match v.last_ran {
-                            None => {
-                                // TODO: Update last_ran AFTER we've ran the task.
-                                v.last_ran = Some(Utc::now());
+                    *TASK_RUNNING.lock().unwrap() = Some((task_id, token.clone()));
 
-                                Some((v.task)())
-                            }
+                    info!(id = ?task_id, name = task.name(), "Task Started");
 
-                            Some(d)
-                                if now.signed_duration_since(d).to_std().unwrap() >= v.interval =>
-                            {
-                                // TODO: Update last_ran AFTER we've ran the task.
-                                v.last_ran = Some(Utc::now());
+                    if let Err(e) =
+                        TaskModel::update_status(task_id, TaskStatus::Running, None, &db).await
+                    {
+                        error!(id = ?task_id, ?e, "Unable to update Task");
+                    }
 
-                                Some((v.task)())
-                            }
+                    send_message_to_clients(WebsocketNotification::new_task(
+                        task_id,
+                        task.name().to_string(),
+                    ));
+
+                    let result = {
+                        let run = task.run(task_id, &token, &db);
+                        tokio::pin!(run);
+
+                        let mut save_progress = tokio::time::interval(PROGRESS_SAVE_INTERVAL);
 
-                            _ => None,
+                        loop {
+                            tokio::select! {
+                                result = &mut run => break result,
+
+                                _ = save_progress.tick() => {
+                                    if let Err(e) = save_task_progress(task_id, &db).await {
+                                        error!(id = ?task_id, ?e, "Unable to save Task progress");
+                                    }
+                                }
+                            }
                         }
-                    });
+                    };
 
-                    interval.or_else(|| TASKS_QUEUED.lock().unwrap().pop_front())
-                };
+                    *TASK_RUNNING.lock().unwrap() = None;
 
-                // Run the found task.
-                if let Some(mut task) = task {
-                    let start_time = Instant::now();
+                    if let Err(e) = save_task_progress(task_id, &db).await {
+                        error!(id = ?task_id, ?e, "Unable to save Task progress");
+                    }
 
-                    let task_id = TaskId::default();
+                    let (status, error) = match result {
+                        Ok(_) if token.is_cancelled() => {
+                            info!(name = task.name(), "Task Cancelled.");
 
-                    info!(id = ?task_id, name = task.name(), "Task Started");
+                            (TaskStatus::Cancelled, None)
+                        }
 
-                    send_message_to_clients(WebsocketNotification::new_task(
-                        task_id,
-                        task.name().to_string(),
-                    ));
+                        Ok(_) => {
+                            info!(
+                                name = task.name(),
+                                elapsed = ?start_time.elapsed(),
+                                "Task Finished Successfully.",
+                            );
+
+                            (TaskStatus::Finished, None)
+                        }
+
+                        Err(e) => {
+                            error!(task = task.name(), ?e);
+
+                            (TaskStatus::Failed, Some(e.to_string()))
+                        }
+                    };
 
-                    match task.run(task_id, &db.basic()).await {
-                        Ok(_) => info!(
-                            name = task.name(),
-                            elapsed = ?start_time.elapsed(),
-                            "Task Finished Successfully.",
-                        ),
-                        Err(e) => error!(task = task.name(), ?e),
+                    if let Err(e) =
+                        TaskModel::update_status(task_id, status, error.as_deref(), &db).await
+                    {
+                        error!(id = ?task_id, ?e, "Unable to update Task");
                     }
 
                     send_message_to_clients(WebsocketNotification::TaskEnd(task_id));
@@ -143,57 +480,70 @@ pub fn start_task_manager(db: web::Data<Database>) {
     });
 }
 
-// TODO: Now unused. Change into a Unused Image Deletion task.
-// pub struct TaskFileHashSetter;
+#[derive(Serialize, Deserialize)]
+pub struct TaskCleanOrphanImages;
 
-// #[async_trait]
-// impl Task for TaskFileHashSetter {
-//     async fn run(&mut self, _task_id: UniqueId, db: &dyn DatabaseAccess) -> Result<()> {
-//         const LIMIT: usize = 250;
+#[async_trait]
+impl Task for TaskCleanOrphanImages {
+    async fn run(
+        &mut self,
+        _task_id: TaskId,
+        token: &CancellationToken,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        // Recently stored images may not be linked yet.
+        let images =
+            UploadedImageModel::find_orphaned(Utc::now() - chrono::Duration::days(1), db).await?;
 
-//         let total = FileModel::count_by_missing_hash(db).await?;
+        let mut removed = 0;
 
-//         for _ in (0..total).step_by(LIMIT) {
-//             // send_message_to_clients(WebsocketNotification::new_task(task_id, TaskType));
+        for image in images {
+            if token.is_cancelled() {
+                break;
+            }
 
-//             let models = FileModel::find_by_missing_hash(0, LIMIT, db).await?;
+            let Some(hash) = image.path.as_value() else {
+                continue;
+            };
 
-//             let mut updates = Vec::new();
+            UploadedImageModel::delete_by_id(image.id, db).await?;
 
-//             for model in models {
-//                 if let Ok(Some(mut book)) = bookie::load_from_path(&model.path) {
-//                     if let Some(hash) = book.compute_hash() {
-//                         updates.push([hash, model.path]);
-//                     }
-//                 }
-//             }
+            match tokio::fs::remove_file(prefixhash_to_path(hash)).await {
+                Ok(_) => (),
+                Err(e) if e.kind() == ErrorKind::NotFound => (),
+                Err(e) => return Err(e.into()),
+            }
 
-//             let write = db.write().await;
+            removed += 1;
+        }
 
-//             let mut stmt = write
-//                 .prepare("UPDATE file SET hash = ?1 WHERE path = ?2")
-//                 .unwrap();
+        info!(removed, "Removed Orphaned Images");
 
-//             for update in updates {
-//                 stmt.execute(update).unwrap();
-//             }
-//         }
+        Ok(())
+    }
 
-//         Ok(())
-//     }
+    fn name(&self) -> &'static str {
+        "Clean Orphan Images"
+    }
 
-//     fn name(&self) -> &'static str {
-//         "File Hash Setter"
-//     }
-// }
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
 
+#[derive(Serialize, Deserialize)]
 pub struct TaskLibraryScan {
     pub library_id: LibraryId,
 }
 
 #[async_trait]
 impl Task for TaskLibraryScan {
-    async fn run(&mut self, task_id: TaskId, db: &dyn DatabaseAccess) -> Result<()> {
+    async fn run(
+        &mut self,
+        task_id: TaskId,
+        token: &CancellationToken,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
         let library = LibraryModel::find_one_by_id(self.library_id, db)
             .await?
             .unwrap();
@@ -202,7 +552,13 @@ impl Task for TaskLibraryScan {
         let directories =
             DirectoryModel::find_directories_by_library_id(self.library_id, db).await?;
 
-        crate::scanner::library_scan(&library, directories, task_id, db).await?;
+        crate::scanner::library_scan(&library, directories, task_id, token, db).await?;
+
+        if get_config().scanner.index_text && !token.is_cancelled() {
+            queue_task(TaskIndexText {
+                library_id: Some(self.library_id),
+            });
+        }
 
         Ok(())
     }
@@ -210,11 +566,303 @@ impl Task for TaskLibraryScan {
     fn name(&self) -> &'static str {
         "Library Scan"
     }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TaskFileChanged {
+    pub library_id: LibraryId,
+    pub change: FileChange,
+}
+
+#[async_trait]
+impl Task for TaskFileChanged {
+    async fn run(
+        &mut self,
+        task_id: TaskId,
+        token: &CancellationToken,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        crate::scanner::watcher::apply_file_change(
+            self.change.clone(),
+            self.library_id,
+            task_id,
+            token,
+            db,
+        )
+        .await?;
+
+        if get_config().scanner.index_text {
+            queue_task(TaskIndexText {
+                library_id: Some(self.library_id),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "File Changed"
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TaskImportCalibre {
+    /// Folder containing the `metadata.db`.
+    pub path: String,
+    /// Books read in Calibre are marked as completed for them.
+    pub member_id: MemberId,
+}
+
+#[async_trait]
+impl Task for TaskImportCalibre {
+    async fn run(
+        &mut self,
+        task_id: TaskId,
+        token: &CancellationToken,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        crate::scanner::calibre::import_library(
+            Path::new(&self.path),
+            self.member_id,
+            task_id,
+            token,
+            db,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Import Calibre"
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Adds the text of new or changed EPUBs to the full-text index.
+#[derive(Serialize, Deserialize)]
+pub struct TaskIndexText {
+    /// Every library if none is set.
+    pub library_id: Option<LibraryId>,
+}
+
+#[async_trait]
+impl Task for TaskIndexText {
+    async fn run(
+        &mut self,
+        task_id: TaskId,
+        token: &CancellationToken,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        let removed = FileTextModel::delete_removed_files(db).await?;
+
+        let files = FileTextModel::find_unindexed_files(self.library_id, db).await?;
+        let total = files.len();
+
+        let mut indexed = 0;
+
+        for (index, file) in files.into_iter().enumerate() {
+            if token.is_cancelled() {
+                info!("Text Indexing Cancelled");
+                break;
+            }
+
+            send_message_to_clients(WebsocketNotification::update_task(
+                task_id,
+                TaskType::IndexText(format!(
+                    "Indexing {}/{total}: {}",
+                    index + 1,
+                    file.file_name
+                )),
+                true,
+            ));
+
+            let mut book = match bookie::load_from_path(&file.path) {
+                Ok(Some(book)) => book,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!(file = file.path, error = ?e, "Unable to open file for indexing");
+                    continue;
+                }
+            };
+
+            let mut chapters = Vec::new();
+
+            for chapter in 0..book.chapter_count() {
+                book.set_chapter(chapter);
+
+                // Chapters which can't be read are left empty.
+                let text = match book.read_page_raw_as_string() {
+                    Ok(page) => bookie::epub::get_body_text(page.as_bytes()).unwrap_or_else(|e| {
+                        error!(file = file.path, chapter, error = ?e, "Unable to read chapter text");
+                        String::new()
+                    }),
+                    Err(_) => String::new(),
+                };
+
+                chapters.push(text);
+            }
+
+            FileTextModel {
+                file_id: file.id,
+                hash: file.hash,
+                indexed_at: Utc::now(),
+            }
+            .replace(&chapters, db)
+            .await?;
+
+            indexed += 1;
+        }
+
+        info!(indexed, removed, "Updated Text Index");
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Index Text"
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Recomputes the stored hash of files. Queued by a migration when the way a file type is hashed changes.
+#[derive(Serialize, Deserialize)]
+pub struct TaskRehashFiles {
+    pub file_type: String,
+}
+
+#[async_trait]
+impl Task for TaskRehashFiles {
+    async fn run(
+        &mut self,
+        task_id: TaskId,
+        token: &CancellationToken,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        let files = FileModel::find_by_file_type(&self.file_type, db).await?;
+        let total = files.len();
+
+        let mut updated = 0;
+
+        for (index, mut file) in files.into_iter().enumerate() {
+            if token.is_cancelled() {
+                info!("Rehashing Cancelled");
+                break;
+            }
+
+            send_message_to_clients(WebsocketNotification::update_task(
+                task_id,
+                TaskType::RehashFiles(format!("Hashing {}/{total}: {}", index + 1, file.file_name)),
+                true,
+            ));
+
+            let Some(hash) = crate::scanner::hash_file(Path::new(&file.path)) else {
+                error!(file = file.path, "Unable to compute hash");
+                continue;
+            };
+
+            if hash == file.hash {
+                continue;
+            }
+
+            // The hash has to stay unique.
+            if let Some(other) = FileModel::find_one_by_hash(&hash, db).await? {
+                error!(
+                    file = file.path,
+                    other = other.path,
+                    "Rehashed File is a duplicate"
+                );
+                continue;
+            }
+
+            file.hash = hash;
+            file.update(db).await?;
+
+            updated += 1;
+        }
+
+        info!(file_type = self.file_type, updated, "Rehashed Files");
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Rehash Files"
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Computes the KOReader document identifier of files scanned before it was stored.
+#[derive(Serialize, Deserialize)]
+pub struct TaskPartialMd5;
+
+#[async_trait]
+impl Task for TaskPartialMd5 {
+    async fn run(
+        &mut self,
+        task_id: TaskId,
+        token: &CancellationToken,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
+        let files = FileModel::find_by_missing_partial_md5(db).await?;
+        let total = files.len();
+
+        let mut updated = 0;
+
+        for (index, file) in files.into_iter().enumerate() {
+            if token.is_cancelled() {
+                break;
+            }
+
+            send_message_to_clients(WebsocketNotification::update_task(
+                task_id,
+                TaskType::RehashFiles(format!("Hashing {}/{total}: {}", index + 1, file.file_name)),
+                true,
+            ));
+
+            let Some(value) = crate::scanner::partial_md5(Path::new(&file.path)) else {
+                continue;
+            };
+
+            FileModel::update_partial_md5(file.id, &value, db).await?;
+
+            updated += 1;
+        }
+
+        info!(updated, "Stored Partial MD5s");
+
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Partial MD5"
+    }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
 }
 
 // Metadata
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum UpdatingBook {
     /// Refresh the books Metadata.
     Refresh(BookId),
@@ -242,6 +890,7 @@ pub enum UpdatingBook {
     UnMatch(BookId),
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct TaskUpdateInvalidBook {
     state: UpdatingBook,
 }
@@ -254,24 +903,20 @@ impl TaskUpdateInvalidBook {
 
 #[async_trait]
 impl Task for TaskUpdateInvalidBook {
-    async fn run(&mut self, task_id: TaskId, db: &dyn DatabaseAccess) -> Result<()> {
+    async fn run(
+        &mut self,
+        task_id: TaskId,
+        token: &CancellationToken,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
         match self.state.clone() {
             UpdatingBook::UnMatch(book_id) => {
                 info!(id = ?book_id, "Unmatching Book By Id");
 
                 let fm_book = BookModel::find_one_by_id(book_id, db).await?.unwrap();
 
-                Self::update_book_by_files(
-                    fm_book,
-                    &ActiveAgents {
-                        local: true,
-                        google: false,
-                        libby: false,
-                        openlib: false,
-                    },
-                    db,
-                )
-                .await?;
+                Self::update_book_by_files(fm_book, &ActiveAgents::only(MetadataAgent::Local), db)
+                    .await?;
             }
 
             UpdatingBook::AutoUpdateBookIdByFiles(book_id) => {
@@ -288,7 +933,9 @@ impl Task for TaskUpdateInvalidBook {
 
                 let fm_book = BookModel::find_one_by_id(book_id, db).await?.unwrap();
 
-                Self::update_book_by_files(fm_book, &ActiveAgents::default(), db).await?;
+                let agent = ActiveAgents::for_library(fm_book.library_id, db).await?;
+
+                Self::update_book_by_files(fm_book, &agent, db).await?;
             }
 
             UpdatingBook::Refresh(book_id) => {
@@ -334,7 +981,7 @@ impl Task for TaskUpdateInvalidBook {
                     let found = search_and_return_first_valid_agent(
                         search_query,
                         SearchFor::Book(SearchForBooksBy::Query),
-                        &ActiveAgents::default(),
+                        &ActiveAgents::for_library(book_model.library_id, db).await?,
                     )
                     .await?;
 
@@ -430,6 +1077,15 @@ impl Task for TaskUpdateInvalidBook {
                             if let Some(mut new_meta) = get_metadata_by_source(&source).await? {
                                 let mut current_book =
                                     BookModel::find_one_by_id(old_book_id, db).await?.unwrap();
+                                let old_book = current_book.clone();
+
+                                let locked_fields =
+                                    BookLockedFieldModel::find_by_book_id(old_book_id, db).await?;
+                                let field_sources = new_meta.field_sources();
+
+                                if locked_fields.contains(&MetadataField::Authors) {
+                                    new_meta.authors = None;
+                                }
 
                                 let (main_author, author_ids) =
                                     new_meta.add_or_ignore_authors_into_database(db).await?;
@@ -448,7 +1104,6 @@ impl Task for TaskUpdateInvalidBook {
 
                                 let mut new_book: BookModel = meta.into();
 
-                                // TODO: Store Publisher inside Database
                                 new_book.cached = new_book
                                     .cached
                                     .publisher_optional(publisher)
@@ -478,6 +1133,30 @@ impl Task for TaskUpdateInvalidBook {
                                     current_book.thumb_path = new_book.thumb_path;
                                 }
 
+                                if new_book.cached.series.is_some() {
+                                    current_book.cached.series = new_book.cached.series;
+                                    current_book.series_index = new_book.series_index;
+                                    current_book.link_series(db).await?;
+                                }
+
+                                if new_book.cached.publisher.is_some() {
+                                    current_book.cached.publisher = new_book.cached.publisher;
+                                    current_book.link_publisher(db).await?;
+                                }
+
+                                if current_book.language.is_none() {
+                                    current_book.language = new_book.language;
+                                }
+
+                                if current_book.page_count.is_none() {
+                                    current_book.page_count = new_book.page_count;
+                                }
+
+                                current_book.subjects = new_book.subjects;
+                                current_book.identifiers = new_book.identifiers;
+
+                                current_book.keep_fields(&old_book, &locked_fields);
+
                                 if let Some(thumb_path) = current_book.thumb_path.as_value() {
                                     if let Some(image) =
                                         UploadedImageModel::get_by_path(thumb_path, db).await?
@@ -489,6 +1168,14 @@ impl Task for TaskUpdateInvalidBook {
                                 }
 
                                 current_book.update(db).await?;
+                                current_book.insert_subjects_and_identifiers(db).await?;
+
+                                BookFieldSourceModel::insert_or_replace(
+                                    current_book.id,
+                                    &field_sources,
+                                    db,
+                                )
+                                .await?;
 
                                 for person_id in author_ids {
                                     BookPersonModel {
@@ -517,6 +1204,14 @@ impl Task for TaskUpdateInvalidBook {
                             let old_book =
                                 BookModel::find_one_by_id(old_book_id, db).await?.unwrap();
 
+                            let locked_fields =
+                                BookLockedFieldModel::find_by_book_id(old_book_id, db).await?;
+                            let field_sources = new_meta.field_sources();
+
+                            if locked_fields.contains(&MetadataField::Authors) {
+                                new_meta.authors = None;
+                            }
+
                             let (main_author, author_ids) =
                                 new_meta.add_or_ignore_authors_into_database(db).await?;
 
@@ -533,7 +1228,6 @@ impl Task for TaskUpdateInvalidBook {
 
                             let mut book: BookModel = meta.into();
 
-                            // TODO: Store Publisher inside Database
                             book.cached = book
                                 .cached
                                 .publisher_optional(publisher)
@@ -544,6 +1238,8 @@ impl Task for TaskUpdateInvalidBook {
                             book.file_item_count = old_book.file_item_count;
                             book.rating = old_book.rating;
 
+                            book.keep_fields(&old_book, &locked_fields);
+
                             if old_book.title != old_book.original_title {
                                 book.title = old_book.title;
                             }
@@ -557,6 +1253,26 @@ impl Task for TaskUpdateInvalidBook {
                                 book.description = old_book.description;
                             }
 
+                            if book.cached.series.is_none() {
+                                book.cached.series = old_book.cached.series;
+                                book.series_index = old_book.series_index;
+                            }
+
+                            if book.cached.publisher.is_none() {
+                                book.cached.publisher = old_book.cached.publisher;
+                            }
+
+                            if book.language.is_none() {
+                                book.language = old_book.language;
+                            }
+
+                            if book.page_count.is_none() {
+                                book.page_count = old_book.page_count;
+                            }
+
+                            book.link_series(db).await?;
+                            book.link_publisher(db).await?;
+
                             if let Some(thumb_path) = book.thumb_path.as_value() {
                                 if let Some(image) =
                                     UploadedImageModel::get_by_path(thumb_path, db).await?
@@ -568,17 +1284,23 @@ impl Task for TaskUpdateInvalidBook {
                             }
 
                             book.update(db).await?;
+                            book.insert_subjects_and_identifiers(db).await?;
 
-                            // TODO: Should I start with a clean slate like this?
-                            BookPersonModel::delete_by_book_id(old_book_id, db).await?;
+                            BookFieldSourceModel::insert_or_replace(book.id, &field_sources, db)
+                                .await?;
+
+                            if !locked_fields.contains(&MetadataField::Authors) {
+                                // TODO: Should I start with a clean slate like this?
+                                BookPersonModel::delete_by_book_id(old_book_id, db).await?;
 
-                            for person_id in author_ids {
-                                BookPersonModel {
-                                    book_id: book.id,
-                                    person_id,
+                                for person_id in author_ids {
+                                    BookPersonModel {
+                                        book_id: book.id,
+                                        person_id,
+                                    }
+                                    .insert_or_ignore(db)
+                                    .await?;
                                 }
-                                .insert_or_ignore(db)
-                                .await?;
                             }
                         } else {
                             info!(?source, "Unable to get metadata");
@@ -588,23 +1310,22 @@ impl Task for TaskUpdateInvalidBook {
                 }
             }
 
-            UpdatingBook::UpdateAllWithAgent {
-                library_id,
-                agent: _a,
-            } => {
-                // TODO: Use agent
-                let active_agent = ActiveAgents {
-                    google: false,
-                    libby: true,
-                    local: false,
-                    openlib: false,
+            UpdatingBook::UpdateAllWithAgent { library_id, agent } => {
+                // Otherwise use the library's agents.
+                let active_agent = match MetadataAgent::from_prefix(&agent) {
+                    Some(agent) => ActiveAgents::only(agent),
+                    None => ActiveAgents::for_library(library_id, db).await?,
                 };
 
                 const LIMIT: usize = 100;
 
-                let amount =
-                    BookModel::count_search_by(&FilterContainer::default(), Some(library_id), db)
-                        .await?;
+                let amount = BookModel::count_search_by(
+                    &FilterContainer::default(),
+                    Some(library_id),
+                    &LibraryAccess::All,
+                    db,
+                )
+                .await?;
                 let mut offset = 0;
 
                 while offset < amount {
@@ -612,6 +1333,10 @@ impl Task for TaskUpdateInvalidBook {
                         BookModel::find_by(Some(library_id), offset, LIMIT, None, db).await?;
 
                     for book in books {
+                        if token.is_cancelled() {
+                            return Ok(());
+                        }
+
                         if Utc::now()
                             .signed_duration_since(book.refreshed_at)
                             .num_days()
@@ -652,6 +1377,10 @@ impl Task for TaskUpdateInvalidBook {
     fn name(&self) -> &'static str {
         "Updating Book"
     }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
 }
 
 impl TaskUpdateInvalidBook {
@@ -728,11 +1457,18 @@ impl TaskUpdateInvalidBook {
     }
 }
 
-async fn overwrite_book_with_new_metadata(
+pub async fn overwrite_book_with_new_metadata(
     mut curr_book_model: BookModel,
     mut metadata: MetadataReturned,
     db: &dyn DatabaseAccess,
 ) -> Result<()> {
+    let locked_fields = BookLockedFieldModel::find_by_book_id(curr_book_model.id, db).await?;
+    let field_sources = metadata.field_sources();
+
+    if locked_fields.contains(&MetadataField::Authors) {
+        metadata.authors = None;
+    }
+
     let (main_author, author_ids) = metadata.add_or_ignore_authors_into_database(db).await?;
 
     let MetadataReturned {
@@ -750,7 +1486,6 @@ async fn overwrite_book_with_new_metadata(
 
     let mut new_book_model: BookModel = meta.into();
 
-    // TODO: Store Publisher inside Database
     new_book_model.cached = new_book_model
         .cached
         .publisher_optional(publisher)
@@ -762,10 +1497,25 @@ async fn overwrite_book_with_new_metadata(
     new_book_model.deleted_at = curr_book_model.deleted_at;
     new_book_model.file_item_count = curr_book_model.file_item_count;
 
+    new_book_model.keep_fields(&curr_book_model, &locked_fields);
+
     // If we're not replacing the metadata with local then we'll make sure everything is filled in.
     if new_book_model.source.agent.as_ref() != "local" {
         new_book_model.rating = curr_book_model.rating;
 
+        // Keep the old position unless the new metadata has its own series.
+        if new_book_model.cached.series.is_none() {
+            new_book_model.series_index = curr_book_model.series_index;
+        }
+
+        if new_book_model.language.is_none() {
+            new_book_model.language = curr_book_model.language;
+        }
+
+        if new_book_model.page_count.is_none() {
+            new_book_model.page_count = curr_book_model.page_count;
+        }
+
         // Overwrite prev with new and replace new with prev.
         curr_book_model.cached.overwrite_with(new_book_model.cached);
         new_book_model.cached = curr_book_model.cached;
@@ -787,6 +1537,9 @@ async fn overwrite_book_with_new_metadata(
     // TODO: Only if book exists and IS the same source.
     new_book_model.created_at = curr_book_model.created_at;
 
+    new_book_model.link_series(db).await?;
+    new_book_model.link_publisher(db).await?;
+
     if let Some(thumb_path) = new_book_model.thumb_path.as_value() {
         if let Some(image) = UploadedImageModel::get_by_path(thumb_path, db).await? {
             ImageLinkModel::new_book(image.id, new_book_model.id)
@@ -798,16 +1551,21 @@ async fn overwrite_book_with_new_metadata(
     new_book_model.refreshed_at = Utc::now();
 
     new_book_model.update(db).await?;
+    new_book_model.insert_subjects_and_identifiers(db).await?;
 
-    BookPersonModel::delete_by_book_id(new_book_model.id, db).await?;
+    BookFieldSourceModel::insert_or_replace(new_book_model.id, &field_sources, db).await?;
 
-    for person_id in author_ids {
-        BookPersonModel {
-            book_id: new_book_model.id,
-            person_id,
+    if !locked_fields.contains(&MetadataField::Authors) {
+        BookPersonModel::delete_by_book_id(new_book_model.id, db).await?;
+
+        for person_id in author_ids {
+            BookPersonModel {
+                book_id: new_book_model.id,
+                person_id,
+            }
+            .insert_or_ignore(db)
+            .await?;
         }
-        .insert_or_ignore(db)
-        .await?;
     }
 
     Ok(())
@@ -815,12 +1573,13 @@ async fn overwrite_book_with_new_metadata(
 
 // People
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum UpdatingPeople {
     AutoUpdateById(PersonId),
     UpdatePersonWithSource { person_id: PersonId, source: Source },
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct TaskUpdatePeople {
     state: UpdatingPeople,
 }
@@ -833,7 +1592,12 @@ impl TaskUpdatePeople {
 
 #[async_trait]
 impl Task for TaskUpdatePeople {
-    async fn run(&mut self, _task_id: TaskId, db: &dyn DatabaseAccess) -> Result<()> {
+    async fn run(
+        &mut self,
+        _task_id: TaskId,
+        _token: &CancellationToken,
+        db: &dyn DatabaseAccess,
+    ) -> Result<()> {
         match self.state.clone() {
             UpdatingPeople::AutoUpdateById(person_id) => {
                 let old_person = PersonModel::find_one_by_id(person_id, db).await?.unwrap();
@@ -853,6 +1617,10 @@ impl Task for TaskUpdatePeople {
     fn name(&self) -> &'static str {
         "Updating Person"
     }
+
+    fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
 }
 
 impl TaskUpdatePeople {
@@ -862,6 +1630,9 @@ impl TaskUpdatePeople {
         db: &dyn DatabaseAccess,
     ) -> Result<()> {
         if let Some(new_person) = get_person_by_source(source).await? {
+            let locked_fields =
+                PersonLockedFieldModel::find_by_person_id(old_person.id, db).await?;
+
             // TODO: Need to make sure it doesn't conflict with alt names or normal names if different.
             if old_person.name != new_person.name {
                 debug!(
@@ -871,7 +1642,10 @@ impl TaskUpdatePeople {
             }
 
             // Download thumb url and store it.
-            if let Some(mut url) = new_person.cover_image_url {
+            if let Some(mut url) = new_person
+                .cover_image_url
+                .filter(|_| !locked_fields.contains(&PersonField::Cover))
+            {
                 url.download(db).await?;
 
                 if let FoundImageLocation::Local(path) = url {
@@ -879,7 +1653,10 @@ impl TaskUpdatePeople {
                 }
             }
 
-            if let Some(alts) = new_person.other_names {
+            if let Some(alts) = new_person
+                .other_names
+                .filter(|_| !locked_fields.contains(&PersonField::OtherNames))
+            {
                 for name in alts {
                     // Ignore errors. Errors should just be UNIQUE constraint failed
                     if let Err(error) = (PersonAltModel {
@@ -894,8 +1671,14 @@ impl TaskUpdatePeople {
                 }
             }
 
-            old_person.birth_date = new_person.birth_date;
-            old_person.description = new_person.description;
+            if !locked_fields.contains(&PersonField::BirthDate) {
+                old_person.birth_date = new_person.birth_date;
+            }
+
+            if !locked_fields.contains(&PersonField::Description) {
+                old_person.description = new_person.description;
+            }
+
             old_person.source = new_person.source;
             old_person.updated_at = Utc::now();
 
@@ -0,0 +1,341 @@
+// Archive formats a comic book can be stored in.
+
+// https://en.wikipedia.org/wiki/Comic_book_archive
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use binstall_zip::ZipArchive;
+use sevenz_rust::{Password, SevenZReader};
+use unrar::Archive as RarArchive;
+
+use crate::Result;
+
+pub trait ComicArchive: Send {
+    /// All file names (not directories) inside the archive.
+    fn file_names(&self) -> Vec<String>;
+
+    /// Calls `visit` for each of the paths found. Files are visited in the order they're stored in the archive.
+    ///
+    /// Solid archives have to be decompressed from the start so this only does a single pass over the archive.
+    fn visit_files(
+        &mut self,
+        paths: &[String],
+        visit: &mut dyn FnMut(String, Vec<u8>),
+    ) -> Result<()>;
+
+    fn read_file(&mut self, path: &str) -> Result<Vec<u8>> {
+        self.read_files(&[path.to_string()])?
+            .pop()
+            .map(|v| v.1)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound).into())
+    }
+
+    /// Read multiple files. Returned in the same order as the paths.
+    fn read_files(&mut self, paths: &[String]) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut found = HashMap::new();
+
+        self.visit_files(paths, &mut |name, data| {
+            found.insert(name, data);
+        })?;
+
+        paths
+            .iter()
+            .map(|path| {
+                let data = found
+                    .remove(path)
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+
+                Ok((path.clone(), data))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveType {
+    Zip,
+    Rar,
+    SevenZip,
+    Tar,
+}
+
+impl ArchiveType {
+    /// Checks the magic bytes first. A lot of comics have the wrong extension. (eg. A Zip renamed to .cbr)
+    pub fn detect(path: &str) -> Result<Option<Self>> {
+        let mut magic = [0; 262];
+
+        let mut file = File::open(path)?;
+        let read = read_up_to(&mut file, &mut magic)?;
+        let magic = &magic[..read];
+
+        if magic.starts_with(b"PK\x03\x04") || magic.starts_with(b"PK\x05\x06") {
+            Ok(Some(Self::Zip))
+        } else if magic.starts_with(b"Rar!\x1A\x07") {
+            Ok(Some(Self::Rar))
+        } else if magic.starts_with(b"7z\xBC\xAF\x27\x1C") {
+            Ok(Some(Self::SevenZip))
+        } else if magic.get(257..262) == Some(b"ustar") {
+            Ok(Some(Self::Tar))
+        } else {
+            Ok(Self::from_extension(path))
+        }
+    }
+
+    pub fn from_extension(path: &str) -> Option<Self> {
+        Some(match path.rsplit_once('.')?.1.to_lowercase().as_str() {
+            "cbz" | "zip" => Self::Zip,
+            "cbr" | "rar" => Self::Rar,
+            "cb7" | "7z" => Self::SevenZip,
+            "cbt" | "tar" => Self::Tar,
+            _ => return None,
+        })
+    }
+
+    pub fn open(self, path: &str) -> Result<Box<dyn ComicArchive>> {
+        Ok(match self {
+            Self::Zip => Box::new(ZipComicArchive::open(path)?),
+            Self::Rar => Box::new(RarComicArchive::open(path)?),
+            Self::SevenZip => Box::new(SevenZipComicArchive::open(path)?),
+            Self::Tar => Box::new(TarComicArchive::open(path)?),
+        })
+    }
+}
+
+// Zip
+
+pub struct ZipComicArchive {
+    archive: ZipArchive<File>,
+}
+
+impl ZipComicArchive {
+    pub fn open(path: &str) -> Result<Self> {
+        Ok(Self {
+            archive: ZipArchive::new(File::open(path)?)?,
+        })
+    }
+}
+
+impl ComicArchive for ZipComicArchive {
+    fn file_names(&self) -> Vec<String> {
+        self.archive
+            .file_names()
+            .filter(|v| !v.ends_with('/'))
+            .map(|v| v.to_string())
+            .collect()
+    }
+
+    fn visit_files(
+        &mut self,
+        paths: &[String],
+        visit: &mut dyn FnMut(String, Vec<u8>),
+    ) -> Result<()> {
+        for path in paths {
+            let mut buf = Vec::new();
+
+            self.archive.by_name(path)?.read_to_end(&mut buf)?;
+
+            visit(path.clone(), buf);
+        }
+
+        Ok(())
+    }
+}
+
+// Rar
+
+pub struct RarComicArchive {
+    path: String,
+    files: Vec<String>,
+}
+
+impl RarComicArchive {
+    pub fn open(path: &str) -> Result<Self> {
+        let mut files = Vec::new();
+
+        for header in RarArchive::new(path).open_for_listing()? {
+            let header = header?;
+
+            if header.is_file() {
+                files.push(normalize_path(&header.filename));
+            }
+        }
+
+        Ok(Self {
+            path: path.to_string(),
+            files,
+        })
+    }
+}
+
+impl ComicArchive for RarComicArchive {
+    fn file_names(&self) -> Vec<String> {
+        self.files.clone()
+    }
+
+    fn visit_files(
+        &mut self,
+        paths: &[String],
+        visit: &mut dyn FnMut(String, Vec<u8>),
+    ) -> Result<()> {
+        // An archive can contain the same name more than once. Only the first is visited.
+        let mut remaining = paths.iter().collect::<HashSet<_>>();
+
+        let mut archive = RarArchive::new(&self.path).open_for_processing()?;
+
+        while let Some(header) = archive.read_header()? {
+            let name = normalize_path(&header.entry().filename);
+
+            archive = if header.entry().is_file() && remaining.remove(&name) {
+                let (data, rest) = header.read()?;
+
+                visit(name, data);
+
+                rest
+            } else {
+                header.skip()?
+            };
+
+            if remaining.is_empty() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// 7z
+
+pub struct SevenZipComicArchive {
+    reader: SevenZReader<File>,
+}
+
+impl SevenZipComicArchive {
+    pub fn open(path: &str) -> Result<Self> {
+        Ok(Self {
+            reader: SevenZReader::open(path, Password::empty())?,
+        })
+    }
+}
+
+impl ComicArchive for SevenZipComicArchive {
+    fn file_names(&self) -> Vec<String> {
+        self.reader
+            .archive()
+            .files
+            .iter()
+            .filter(|v| !v.is_directory())
+            .map(|v| normalize_path(v.name()))
+            .collect()
+    }
+
+    fn visit_files(
+        &mut self,
+        paths: &[String],
+        visit: &mut dyn FnMut(String, Vec<u8>),
+    ) -> Result<()> {
+        // An archive can contain the same name more than once. Only the first is visited.
+        let mut remaining = paths.iter().collect::<HashSet<_>>();
+
+        self.reader.for_each_entries(|entry, reader| {
+            let name = normalize_path(entry.name());
+
+            if !entry.is_directory() && remaining.remove(&name) {
+                let mut buf = Vec::new();
+                reader.read_to_end(&mut buf)?;
+
+                visit(name, buf);
+            } else {
+                // Entries in a solid block share a stream. We need to consume it to reach the next one.
+                io::copy(reader, &mut io::sink())?;
+            }
+
+            Ok(!remaining.is_empty())
+        })?;
+
+        Ok(())
+    }
+}
+
+// Tar
+
+pub struct TarComicArchive {
+    file: File,
+    /// File Name -> (Position, Size)
+    entries: Vec<(String, u64, u64)>,
+}
+
+impl TarComicArchive {
+    pub fn open(path: &str) -> Result<Self> {
+        let mut archive = tar::Archive::new(File::open(path)?);
+
+        let mut entries = Vec::new();
+
+        for entry in archive.entries()? {
+            let entry = entry?;
+
+            if entry.header().entry_type().is_file() {
+                entries.push((
+                    normalize_path(&entry.path()?),
+                    entry.raw_file_position(),
+                    entry.size(),
+                ));
+            }
+        }
+
+        Ok(Self {
+            file: File::open(path)?,
+            entries,
+        })
+    }
+}
+
+impl ComicArchive for TarComicArchive {
+    fn file_names(&self) -> Vec<String> {
+        self.entries.iter().map(|v| v.0.clone()).collect()
+    }
+
+    fn visit_files(
+        &mut self,
+        paths: &[String],
+        visit: &mut dyn FnMut(String, Vec<u8>),
+    ) -> Result<()> {
+        for (name, position, size) in &self.entries {
+            if !paths.contains(name) {
+                continue;
+            }
+
+            // Tar files aren't compressed. The contents are stored as-is.
+            let mut buf = vec![0; *size as usize];
+
+            self.file.seek(SeekFrom::Start(*position))?;
+            self.file.read_exact(&mut buf)?;
+
+            visit(name.clone(), buf);
+        }
+
+        Ok(())
+    }
+}
+
+fn normalize_path<P: AsRef<Path> + ?Sized>(value: &P) -> String {
+    value.as_ref().to_string_lossy().replace('\\', "/")
+}
+
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+
+    while read < buf.len() {
+        match reader.read(&mut buf[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+
+    Ok(read)
+}
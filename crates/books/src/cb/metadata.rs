@@ -0,0 +1,188 @@
+// https://anansi-project.github.io/docs/comicinfo/schemas/v2.0
+// https://www.denvog.com/comet/comet-specification/
+
+use serde::{Deserialize, Serialize};
+
+/// ComicInfo.xml which was made popular by ComicRack.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ComicInfo {
+    pub title: Option<String>,
+    pub series: Option<String>,
+    pub number: Option<String>,
+    pub count: Option<i64>,
+    pub volume: Option<i64>,
+    pub summary: Option<String>,
+
+    pub year: Option<i64>,
+    pub month: Option<i64>,
+    pub day: Option<i64>,
+
+    pub writer: Option<String>,
+    pub penciller: Option<String>,
+    pub inker: Option<String>,
+    pub colorist: Option<String>,
+    pub letterer: Option<String>,
+    pub cover_artist: Option<String>,
+    pub editor: Option<String>,
+
+    pub publisher: Option<String>,
+    pub genre: Option<String>,
+    pub web: Option<String>,
+    pub page_count: Option<i64>,
+    #[serde(rename = "LanguageISO")]
+    pub language_iso: Option<String>,
+    pub format: Option<String>,
+    #[serde(rename = "GTIN")]
+    pub gtin: Option<String>,
+
+    pub pages: Option<ComicPages>,
+}
+
+impl ComicInfo {
+    pub fn date(&self) -> Option<String> {
+        // ComicRack uses -1 for unset values.
+        let year = self.year.filter(|v| *v > 0)?;
+
+        Some(
+            match (self.month.filter(|v| *v > 0), self.day.filter(|v| *v > 0)) {
+                (Some(month), Some(day)) => format!("{year:04}-{month:02}-{day:02}"),
+                (Some(month), None) => format!("{year:04}-{month:02}"),
+                _ => format!("{year:04}"),
+            },
+        )
+    }
+
+    /// Index of the page which is the front cover.
+    pub fn front_cover_index(&self) -> Option<usize> {
+        self.pages
+            .as_ref()?
+            .page
+            .iter()
+            .filter(|v| v.type_of.as_deref() == Some("FrontCover"))
+            .find_map(|v| v.image)
+    }
+
+    pub fn creators(&self) -> Vec<String> {
+        split_list(self.writer.as_deref())
+    }
+
+    pub fn contributors(&self) -> Vec<String> {
+        [
+            &self.penciller,
+            &self.inker,
+            &self.colorist,
+            &self.letterer,
+            &self.cover_artist,
+            &self.editor,
+        ]
+        .into_iter()
+        .flat_map(|v| split_list(v.as_deref()))
+        .collect()
+    }
+
+    pub fn subjects(&self) -> Vec<String> {
+        split_list(self.genre.as_deref())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ComicPages {
+    #[serde(rename = "Page", default)]
+    pub page: Vec<ComicPage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComicPage {
+    /// Pages without one are skipped.
+    #[serde(rename = "Image")]
+    pub image: Option<usize>,
+    #[serde(rename = "Type")]
+    pub type_of: Option<String>,
+}
+
+/// CoMet is another metadata format. Fields can be repeated instead of being comma separated.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CoMet {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub series: Option<String>,
+    pub issue: Option<String>,
+    pub volume: Option<String>,
+    pub publisher: Option<String>,
+    pub date: Option<String>,
+    pub language: Option<String>,
+    pub rights: Option<String>,
+    pub identifier: Option<String>,
+    pub format: Option<String>,
+    pub cover_image: Option<String>,
+
+    pub genre: Vec<String>,
+
+    pub creator: Vec<String>,
+    pub writer: Vec<String>,
+    pub penciller: Vec<String>,
+    pub inker: Vec<String>,
+    pub colorist: Vec<String>,
+    pub letterer: Vec<String>,
+    pub cover_designer: Vec<String>,
+    pub editor: Vec<String>,
+}
+
+impl CoMet {
+    pub fn creators(&self) -> Vec<String> {
+        self.writer
+            .iter()
+            .chain(self.creator.iter())
+            .cloned()
+            .collect()
+    }
+
+    pub fn contributors(&self) -> Vec<String> {
+        [
+            &self.penciller,
+            &self.inker,
+            &self.colorist,
+            &self.letterer,
+            &self.cover_designer,
+            &self.editor,
+        ]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect()
+    }
+}
+
+fn split_list(value: Option<&str>) -> Vec<String> {
+    value
+        .map(|v| {
+            v.split(',')
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn front_cover_without_image() {
+        let info: ComicInfo = serde_xml_rs::from_str(
+            r#"<ComicInfo>
+                <Pages>
+                    <Page Type="FrontCover" />
+                    <Page Image="2" Type="FrontCover" />
+                </Pages>
+            </ComicInfo>"#,
+        )
+        .unwrap();
+
+        assert_eq!(info.front_cover_index(), Some(2));
+    }
+}
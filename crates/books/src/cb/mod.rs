@@ -1,47 +1,54 @@
 use std::{
     borrow::Cow,
-    fs::File,
-    io::Read,
+    collections::HashMap,
+    io,
     path::{Path, PathBuf},
 };
 
-use binstall_zip::ZipArchive;
 use regex::Regex;
 use xml::escape::escape_str_pcdata;
 
+pub mod archive;
+pub mod metadata;
+
 use super::Book;
 use crate::{BookSearch, Result};
+use archive::*;
+use metadata::*;
 
-// TODO: Rar, 7z , TAR, ACE, ...
-//       CBR, CB7, CBT, CBA, ...
+// TODO: ACE, ...
+//       CBA, ...
 
 const IMAGES_PER_CHAPTER: usize = 10;
 const CUSTOM_SECTION_PREFIX: &str = "section";
 
+const IMAGE_EXTENSIONS: [&str; 6] = ["jpg", "jpeg", "png", "gif", "webp", "bmp"];
+
 pub struct ComicBook {
     file_name: String,
 
-    archive: ZipArchive<File>,
+    archive: Box<dyn ComicArchive>,
 
     files: Vec<String>,
     chapter: usize,
+
+    comic_info: Option<ComicInfo>,
+    comet: Option<CoMet>,
 }
 
 impl ComicBook {
     pub fn get_images_in_chapter(&mut self) -> Result<Vec<(String, Vec<u8>)>> {
-        let mut images = Vec::new();
-
         let offset = self.chapter * IMAGES_PER_CHAPTER;
 
-        for path in self.files.iter().skip(offset).take(IMAGES_PER_CHAPTER) {
-            let mut buf = Vec::new();
-
-            self.archive.by_name(path.as_str())?.read_to_end(&mut buf)?;
-
-            images.push((path.clone(), buf));
-        }
+        let paths = self
+            .files
+            .iter()
+            .skip(offset)
+            .take(IMAGES_PER_CHAPTER)
+            .cloned()
+            .collect::<Vec<_>>();
 
-        Ok(images)
+        self.archive.read_files(&paths)
     }
 
     fn custom_section_to_html(
@@ -70,11 +77,18 @@ impl Book for ComicBook {
     where
         Self: Sized,
     {
-        let archive = ZipArchive::new(File::open(path)?)?;
+        let mut archive = ArchiveType::detect(path)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "Unknown archive type"))?
+            .open(path)?;
 
+        // Only images are pages. Skips files like "ComicInfo.xml"
         let mut files = archive
             .file_names()
-            .map(|v| v.to_string())
+            .into_iter()
+            .filter(|name| {
+                name.rsplit_once('.')
+                    .is_some_and(|(_, ext)| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            })
             .collect::<Vec<_>>();
         files.sort_unstable();
 
@@ -82,22 +96,37 @@ impl Book for ComicBook {
         // Example:
         //    {chap}-{page}.png
 
+        let comic_info = read_metadata_file(archive.as_mut(), "comicinfo.xml");
+        let comet = read_metadata_file(archive.as_mut(), "comet.xml");
+
         Ok(Self {
             archive,
             files,
 
             file_name: path.rsplit_once('/').unwrap().1.to_string(),
             chapter: 0,
+
+            comic_info,
+            comet,
         })
     }
 
     fn compute_hash(&mut self) -> Option<String> {
+        // Each page is hashed separately since archives can store them in any order.
+        let mut page_hashes = HashMap::new();
+
+        self.archive
+            .visit_files(&self.files, &mut |name, data| {
+                page_hashes.insert(name, blake3::hash(&data));
+            })
+            .ok()?;
+
+        // Combine them in page order. The same comic will have the same hash regardless of the archive type.
         let mut hasher = blake3::Hasher::new();
 
-        for path in self.files.clone() {
-            if let Ok(asdf) = self.read_path_as_bytes(&path, None, None) {
-                hasher.update(&asdf);
-            }
+        for path in &self.files {
+            hasher.update(path.as_bytes());
+            hasher.update(page_hashes.get(path)?.as_bytes());
         }
 
         Some(hasher.finalize().to_string())
@@ -107,11 +136,129 @@ impl Book for ComicBook {
         self.files.clone()
     }
 
-    fn find(&self, _search: BookSearch<'_>) -> Option<Vec<String>> {
-        None
+    fn find(&self, search: BookSearch<'_>) -> Option<Vec<String>> {
+        let info = self.comic_info.as_ref();
+        let comet = self.comet.as_ref();
+
+        let values = match search {
+            BookSearch::Title => first_of([
+                info.and_then(|v| v.title.clone()),
+                comet.and_then(|v| v.title.clone()),
+            ]),
+
+            BookSearch::CoverImage => {
+                let path = info
+                    .and_then(|v| v.front_cover_index())
+                    .and_then(|index| self.files.get(index).cloned())
+                    .or_else(|| {
+                        comet
+                            .and_then(|v| v.cover_image.as_deref())
+                            .and_then(|name| self.files.iter().find(|v| v.ends_with(name)).cloned())
+                    })
+                    .or_else(|| self.files.first().cloned())?;
+
+                vec![path]
+            }
+
+            BookSearch::Identifier => first_of([
+                info.and_then(|v| v.gtin.clone()),
+                comet.and_then(|v| v.identifier.clone()),
+            ]),
+            BookSearch::Language => first_of([
+                info.and_then(|v| v.language_iso.clone()),
+                comet.and_then(|v| v.language.clone()),
+            ]),
+            BookSearch::Creator => {
+                first_non_empty([info.map(|v| v.creators()), comet.map(|v| v.creators())])
+            }
+            BookSearch::Contributor => first_non_empty([
+                info.map(|v| v.contributors()),
+                comet.map(|v| v.contributors()),
+            ]),
+            BookSearch::Date => first_of([
+                info.and_then(|v| v.date()),
+                comet.and_then(|v| v.date.clone()),
+            ]),
+            BookSearch::Description => first_of([
+                info.and_then(|v| v.summary.clone()),
+                comet.and_then(|v| v.description.clone()),
+            ]),
+            BookSearch::Format => first_of([
+                info.and_then(|v| v.format.clone()),
+                comet.and_then(|v| v.format.clone()),
+            ]),
+            BookSearch::Publisher => first_of([
+                info.and_then(|v| v.publisher.clone()),
+                comet.and_then(|v| v.publisher.clone()),
+            ]),
+            BookSearch::Rights => first_of([comet.and_then(|v| v.rights.clone())]),
+            BookSearch::Source => first_of([info.and_then(|v| v.web.clone())]),
+            BookSearch::Subject => {
+                first_non_empty([info.map(|v| v.subjects()), comet.map(|v| v.genre.clone())])
+            }
+
+            BookSearch::Coverage | BookSearch::Relation | BookSearch::Type => Vec::new(),
+
+            // Comic specific values.
+            BookSearch::Other(value) => match value {
+                "series" => first_of([
+                    info.and_then(|v| v.series.clone()),
+                    comet.and_then(|v| v.series.clone()),
+                ]),
+                "volume" => first_of([
+                    info.and_then(|v| v.volume)
+                        .filter(|v| *v >= 0)
+                        .map(|v| v.to_string()),
+                    comet.and_then(|v| v.volume.clone()),
+                ]),
+                "issue" => first_of([
+                    info.and_then(|v| v.number.clone()),
+                    comet.and_then(|v| v.issue.clone()),
+                ]),
+                "count" => first_of([info
+                    .and_then(|v| v.count)
+                    .filter(|v| *v > 0)
+                    .map(|v| v.to_string())]),
+                _ => Vec::new(),
+            },
+        };
+
+        if values.is_empty() {
+            None
+        } else {
+            Some(values)
+        }
     }
 
     fn get_unique_id(&self) -> Result<Cow<str>> {
+        if let Some(id) = self.find(BookSearch::Identifier).and_then(|mut v| v.pop()) {
+            return Ok(Cow::Owned(id));
+        }
+
+        // Fallback to "{Series} v{Volume} #{Issue}" before the file name.
+        if let Some(series) = self
+            .find(BookSearch::Other("series"))
+            .and_then(|mut v| v.pop())
+        {
+            let mut id = series;
+
+            if let Some(volume) = self
+                .find(BookSearch::Other("volume"))
+                .and_then(|mut v| v.pop())
+            {
+                id += &format!(" v{volume}");
+            }
+
+            if let Some(issue) = self
+                .find(BookSearch::Other("issue"))
+                .and_then(|mut v| v.pop())
+            {
+                id += &format!(" #{issue}");
+            }
+
+            return Ok(Cow::Owned(id));
+        }
+
         Ok(Cow::Borrowed(self.file_name.as_str()))
     }
 
@@ -136,7 +283,12 @@ impl Book for ComicBook {
         prepend_to_urls: Option<&str>,
         add_css: Option<&[&str]>,
     ) -> Result<Vec<u8>> {
-        self.custom_section_to_html(path, prepend_to_urls, add_css)
+        // Pages (eg. the cover image) are returned as-is.
+        if self.files.iter().any(|v| v == path) {
+            self.archive.read_file(path)
+        } else {
+            self.custom_section_to_html(path, prepend_to_urls, add_css)
+        }
     }
 
     fn read_page_as_bytes(
@@ -150,7 +302,7 @@ impl Book for ComicBook {
     }
 
     fn chapter_count(&self) -> usize {
-        self.files.len() / IMAGES_PER_CHAPTER
+        self.files.len().div_ceil(IMAGES_PER_CHAPTER)
     }
 
     fn set_chapter(&mut self, value: usize) -> bool {
@@ -180,6 +332,41 @@ impl Book for ComicBook {
     }
 }
 
+/// Finds the metadata file in the archive by its name, ignoring case and the directory it's in.
+fn read_metadata_file<V: serde::de::DeserializeOwned>(
+    archive: &mut dyn ComicArchive,
+    file_name: &str,
+) -> Option<V> {
+    let path = archive.file_names().into_iter().find(|v| {
+        v.rsplit('/')
+            .next()
+            .is_some_and(|v| v.eq_ignore_ascii_case(file_name))
+    })?;
+
+    let data = archive.read_file(&path).ok()?;
+    let data = String::from_utf8_lossy(&data);
+
+    // An invalid metadata file shouldn't stop us from reading the comic.
+    serde_xml_rs::from_str(data.trim_start_matches('\u{feff}')).ok()
+}
+
+fn first_of<const N: usize>(values: [Option<String>; N]) -> Vec<String> {
+    values
+        .into_iter()
+        .flatten()
+        .find(|v| !v.trim().is_empty())
+        .into_iter()
+        .collect()
+}
+
+fn first_non_empty<const N: usize>(values: [Option<Vec<String>>; N]) -> Vec<String> {
+    values
+        .into_iter()
+        .flatten()
+        .find(|v| !v.is_empty())
+        .unwrap_or_default()
+}
+
 fn wrap_images_in_html_doc(images: &[(String, Vec<u8>)], all_css: &[&str]) -> String {
     let mut encoded = String::new();
 
@@ -17,14 +17,16 @@ use binstall_zip::ZipArchive;
 pub mod container;
 mod modifier;
 mod package_document;
+mod toc;
 
-use crate::{BookSearch, Result};
+use crate::{BookSearch, Result, TocItem};
 
 use super::Book;
 use container::*;
 
 pub use modifier::*;
 pub use package_document::*;
+pub use toc::*;
 
 // TODO: Ignore specific file entries? Eg. "META-INF/calibre_bookmarks.txt"
 // Would allow for better file hashing to compare against. Eg. One zip may have it, the other may not even though they're the same.
@@ -91,6 +93,100 @@ impl EpubBook {
         Ok(buf)
     }
 
+    /// The EPUB 3 Navigation Document, falling back to the EPUB 2 NCX.
+    fn get_toc_entries(&mut self) -> Result<Option<(PathBuf, Vec<TocEntry>)>> {
+        let nav_href = self
+            .package
+            .manifest
+            .items
+            .iter()
+            .find(|v| {
+                v.properties
+                    .as_deref()
+                    .is_some_and(|v| v.split_whitespace().any(|v| v == "nav"))
+            })
+            .map(|v| v.href.clone());
+
+        if let Some(href) = nav_href {
+            let entries = parse_nav(self.get_path_contents(&href)?.as_slice())?;
+
+            if !entries.is_empty() {
+                return Ok(Some((self.root_file_dir.join(href), entries)));
+            }
+        }
+
+        let manifest = &self.package.manifest;
+
+        let ncx_href = self
+            .package
+            .spine
+            .toc
+            .as_deref()
+            .and_then(|id| manifest.get_item_by_id(id))
+            .or_else(|| {
+                manifest
+                    .items
+                    .iter()
+                    .find(|v| v.media_type == "application/x-dtbncx+xml")
+            })
+            .map(|v| v.href.clone());
+
+        if let Some(href) = ncx_href {
+            let entries = parse_ncx(self.get_path_contents(&href)?.as_slice())?;
+
+            return Ok(Some((self.root_file_dir.join(href), entries)));
+        }
+
+        Ok(None)
+    }
+
+    /// Converts the entries into items which point at the chapter they're in.
+    fn resolve_toc_entries(&self, toc_path: &Path, entries: Vec<TocEntry>) -> Vec<TocItem> {
+        entries
+            .into_iter()
+            .map(|entry| {
+                let (chapter, fragment) = match entry.href.as_deref() {
+                    Some(href) => {
+                        let (path, fragment) = href
+                            .split_once('#')
+                            .map_or((href, None), |(a, b)| (a, Some(b.to_string())));
+
+                        (self.find_spine_index_by_path(toc_path, path), fragment)
+                    }
+
+                    None => (None, None),
+                };
+
+                TocItem {
+                    title: entry.title,
+                    chapter,
+                    fragment: fragment.filter(|v| !v.is_empty()),
+                    children: self.resolve_toc_entries(toc_path, entry.children),
+                }
+            })
+            .collect()
+    }
+
+    /// `path` is relative to `from_file`.
+    fn find_spine_index_by_path(&self, from_file: &Path, path: &str) -> Option<usize> {
+        let path = urlencoding::decode(path).ok()?;
+
+        let full_path =
+            update_value_with_relative_internal_path(from_file.to_path_buf(), &path, None);
+
+        // Manifest hrefs are relative to the package document.
+        let package_path = PathBuf::from(&self.container.root_files()[0].full_path);
+
+        (0..self.chapter_count()).find(|&index| {
+            self.get_manifest_item_by_spine(index)
+                .and_then(|item| Some(urlencoding::decode(&item.href).ok()?.into_owned()))
+                .is_some_and(|href| {
+                    update_value_with_relative_internal_path(package_path.clone(), &href, None)
+                        == full_path
+                })
+        })
+    }
+
     fn handle_update_attributes(
         &mut self,
         input: &[u8],
@@ -232,6 +328,14 @@ impl Book for EpubBook {
                 .href
                 .to_owned()]),
 
+            BookSearch::Other("series") => {
+                Some(vec![self.package.metadata.get_series()?.0.to_string()])
+            }
+
+            BookSearch::Other("series_index") => {
+                Some(vec![self.package.metadata.get_series()?.1?.to_string()])
+            }
+
             _ => {
                 let tag_name = match &search {
                     BookSearch::Title => "title",
@@ -287,6 +391,13 @@ impl Book for EpubBook {
         }
     }
 
+    fn get_table_of_contents(&mut self) -> Result<Vec<TocItem>> {
+        Ok(match self.get_toc_entries()? {
+            Some((toc_path, entries)) => self.resolve_toc_entries(&toc_path, entries),
+            None => Vec::new(),
+        })
+    }
+
     fn get_unique_id(&self) -> Result<Cow<str>> {
         if let Some(identifier_elements) = self.package.metadata.dcmes_elements.get("identifier") {
             // Find the unique ID based off of the specified one in the package attribute.
@@ -23,11 +23,7 @@ where
     F: FnMut(&mut B, &OwnedName, OwnedAttribute) -> OwnedAttribute,
     S: FnMut(&mut B, &OwnedName, &[OwnedAttribute], &mut EventWriter<&mut Vec<u8>>) -> bool,
 {
-    let reader = xml::ParserConfig::new()
-        .add_entity("nbsp", " ")
-        .add_entity("copy", "©")
-        .add_entity("reg", "®")
-        .create_reader(input);
+    let reader = page_parser_config().create_reader(input);
 
     let mut output = Vec::new();
     let mut writer = EmitterConfig::default()
@@ -105,6 +101,47 @@ where
     Ok(output)
 }
 
+/// The text of the `<body>`. Positions in it are counted the same way as the reader does.
+///
+/// Text nodes which only contain whitespace are skipped.
+pub fn get_body_text(input: &[u8]) -> Result<String> {
+    let reader = page_parser_config().create_reader(input);
+
+    let mut text = String::new();
+    let mut in_body = false;
+
+    for event in reader {
+        match event? {
+            ReaderEvent::StartElement { name, .. }
+                if name.local_name.eq_ignore_ascii_case("body") =>
+            {
+                in_body = true;
+            }
+
+            ReaderEvent::EndElement { name } if name.local_name.eq_ignore_ascii_case("body") => {
+                break;
+            }
+
+            ReaderEvent::Characters(value) | ReaderEvent::CData(value)
+                if in_body && !value.trim().is_empty() =>
+            {
+                text.push_str(&value);
+            }
+
+            _ => (),
+        }
+    }
+
+    Ok(text)
+}
+
+fn page_parser_config() -> xml::ParserConfig {
+    xml::ParserConfig::new()
+        .add_entity("nbsp", " ")
+        .add_entity("copy", "©")
+        .add_entity("reg", "®")
+}
+
 /// Updates the path `value` to include the internal zip `path`
 ///
 /// Also prepends the specific URI before everything
@@ -156,3 +193,25 @@ pub fn update_value_with_relative_internal_path(
         path
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_text() {
+        let value = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <html xmlns="http://www.w3.org/1999/xhtml">
+            <head><title>Ignored</title></head>
+            <body>
+                <h1>Title</h1>
+                <p>Some <b>bold</b>&nbsp;text &amp; more.</p>
+            </body>
+            </html>"#;
+
+        assert_eq!(
+            get_body_text(value.as_bytes()).unwrap(),
+            "TitleSome bold text & more."
+        );
+    }
+}
@@ -222,6 +222,58 @@ impl PackageMetadata {
         })
     }
 
+    /// Returns the series name and the position in it.
+    ///
+    /// Uses the EPUB 3 `belongs-to-collection` meta, falling back to Calibre's `calibre:series`.
+    pub fn get_series(&self) -> Option<(&str, Option<&str>)> {
+        let collections = self
+            .meta_items
+            .iter()
+            .filter(|v| v.property == "belongs-to-collection" && v.value.is_some())
+            .collect::<Vec<_>>();
+
+        // Collections aren't always series. Prefer the ones which say they are.
+        let collection = collections
+            .iter()
+            .find(|v| self.get_refinement(v, "collection-type") == Some("series"))
+            .or_else(|| collections.first());
+
+        if let Some(collection) = collection {
+            return Some((
+                collection.value.as_deref()?.trim(),
+                self.get_refinement(collection, "group-position"),
+            ));
+        }
+
+        let series = self.get_named_meta("calibre:series")?;
+
+        Some((series, self.get_named_meta("calibre:series_index")))
+    }
+
+    /// Value of a `<meta refines="#id" property="..">` pointing at the item.
+    fn get_refinement(&self, item: &MetaItem, property: &str) -> Option<&str> {
+        let id = item.id.as_deref()?;
+
+        self.meta_items
+            .iter()
+            .find(|v| {
+                v.property == property
+                    && v.refines.as_deref().map(|v| v.trim_start_matches('#')) == Some(id)
+            })
+            .and_then(|v| v.value.as_deref())
+            .map(|v| v.trim())
+    }
+
+    /// EPUB 2 `<meta name=".." content=".." />`
+    fn get_named_meta(&self, name: &str) -> Option<&str> {
+        self.meta_items
+            .iter()
+            .find(|v| v.get("name") == Some(name))
+            .and_then(|v| v.get("content"))
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    }
+
     // TODO: Actually utilize <meta refines="#dc-name" ..>
 }
 
@@ -425,6 +477,17 @@ pub struct PackageSpine {
 
 impl Parser for PackageSpine {
     fn parse(&mut self, mut element: XmlElement) -> Result<()> {
+        for attr in &element.attributes {
+            match attr.name.local_name.as_str() {
+                "id" => self.id = Some(attr.value.clone()),
+                "page-progression-direction" => {
+                    self.page_progression_direction = Some(attr.value.clone())
+                }
+                "toc" => self.toc = Some(attr.value.clone()),
+                _ => (),
+            }
+        }
+
         for child in element.take_inner_children() {
             self.items.push(SpineItemRef::try_from(child)?);
         }
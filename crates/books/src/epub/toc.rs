@@ -0,0 +1,272 @@
+// https://www.w3.org/publishing/epub3/epub-packages.html#sec-package-nav
+// http://idpf.org/epub/20/spec/OPF_2.0.1_draft.htm#Section2.4.1
+
+use std::io::Read;
+
+use xml::reader::XmlEvent;
+
+use crate::Result;
+
+/// An entry of the Navigation Document or of the NCX.
+///
+/// The href is left as written. It's relative to the document it came from.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TocEntry {
+    pub title: String,
+    pub href: Option<String>,
+    pub children: Vec<TocEntry>,
+}
+
+/// Parses the `<nav epub:type="toc">` of an EPUB 3 Navigation Document.
+pub fn parse_nav<R: Read>(value: R) -> Result<Vec<TocEntry>> {
+    let reader = xml::ParserConfig::new()
+        .add_entity("nbsp", " ")
+        .add_entity("copy", "©")
+        .add_entity("reg", "®")
+        .create_reader(value);
+
+    let mut root = Vec::new();
+    // The <li> elements we're currently inside of.
+    let mut stack: Vec<TocEntry> = Vec::new();
+
+    let mut in_toc = false;
+    // Depth inside of the <a> or <span> which labels the current <li>.
+    let mut label_depth = 0;
+
+    for event in reader {
+        match event? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                let name = name.local_name.as_str();
+
+                if !in_toc {
+                    in_toc = name == "nav"
+                        && attributes.iter().any(|v| {
+                            v.name.local_name == "type"
+                                && v.value.split_whitespace().any(|v| v == "toc")
+                        });
+
+                    continue;
+                }
+
+                if label_depth != 0 {
+                    label_depth += 1;
+                    continue;
+                }
+
+                match name {
+                    "li" => stack.push(TocEntry::default()),
+
+                    "a" | "span" => {
+                        if let Some(entry) = stack.last_mut().filter(|v| v.title.is_empty()) {
+                            entry.href = attributes
+                                .into_iter()
+                                .find(|v| v.name.local_name == "href")
+                                .map(|v| v.value);
+
+                            label_depth = 1;
+                        }
+                    }
+
+                    _ => (),
+                }
+            }
+
+            XmlEvent::EndElement { name } => {
+                if !in_toc {
+                    continue;
+                }
+
+                if label_depth != 0 {
+                    label_depth -= 1;
+                    continue;
+                }
+
+                match name.local_name.as_str() {
+                    "li" => {
+                        if let Some(entry) = stack.pop() {
+                            push_entry(&mut root, &mut stack, entry);
+                        }
+                    }
+
+                    // Nothing after the table of contents is needed.
+                    "nav" => break,
+
+                    _ => (),
+                }
+            }
+
+            XmlEvent::Characters(value) | XmlEvent::CData(value) | XmlEvent::Whitespace(value)
+                if label_depth != 0 =>
+            {
+                if let Some(entry) = stack.last_mut() {
+                    entry.title.push_str(&value);
+                }
+            }
+
+            XmlEvent::EndDocument => break,
+
+            _ => (),
+        }
+    }
+
+    Ok(root)
+}
+
+/// Parses the `<navMap>` of an EPUB 2 NCX.
+pub fn parse_ncx<R: Read>(value: R) -> Result<Vec<TocEntry>> {
+    let reader = xml::ParserConfig::new().create_reader(value);
+
+    let mut root = Vec::new();
+    // The <navPoint> elements we're currently inside of.
+    let mut stack: Vec<TocEntry> = Vec::new();
+
+    let mut in_nav_map = false;
+    let mut in_label_text = false;
+
+    for event in reader {
+        match event? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => match name.local_name.as_str() {
+                "navMap" => in_nav_map = true,
+
+                "navPoint" if in_nav_map => stack.push(TocEntry::default()),
+
+                "text" => in_label_text = !stack.is_empty(),
+
+                "content" => {
+                    if let Some(entry) = stack.last_mut() {
+                        entry.href = attributes
+                            .into_iter()
+                            .find(|v| v.name.local_name == "src")
+                            .map(|v| v.value);
+                    }
+                }
+
+                _ => (),
+            },
+
+            XmlEvent::EndElement { name } => match name.local_name.as_str() {
+                "navMap" => break,
+
+                "navPoint" => {
+                    if let Some(entry) = stack.pop() {
+                        push_entry(&mut root, &mut stack, entry);
+                    }
+                }
+
+                "text" => in_label_text = false,
+
+                _ => (),
+            },
+
+            XmlEvent::Characters(value) | XmlEvent::CData(value) | XmlEvent::Whitespace(value)
+                if in_label_text =>
+            {
+                if let Some(entry) = stack.last_mut() {
+                    entry.title.push_str(&value);
+                }
+            }
+
+            XmlEvent::EndDocument => break,
+
+            _ => (),
+        }
+    }
+
+    Ok(root)
+}
+
+fn push_entry(root: &mut Vec<TocEntry>, stack: &mut [TocEntry], mut entry: TocEntry) {
+    entry.title = entry.title.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(entry);
+    } else {
+        root.push(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str, href: Option<&str>, children: Vec<TocEntry>) -> TocEntry {
+        TocEntry {
+            title: title.to_string(),
+            href: href.map(|v| v.to_string()),
+            children,
+        }
+    }
+
+    #[test]
+    fn nav() {
+        let value = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+            <body>
+                <nav epub:type="landmarks"><ol><li><a href="cover.xhtml">Cover</a></li></ol></nav>
+                <nav epub:type="toc">
+                    <h1>Contents</h1>
+                    <ol>
+                        <li><a href="text/one.xhtml"><span>1.</span>&nbsp;The
+                            Beginning</a></li>
+                        <li>
+                            <span>Part Two</span>
+                            <ol>
+                                <li><a href="text/two.xhtml#start">Middle</a></li>
+                            </ol>
+                        </li>
+                    </ol>
+                </nav>
+            </body>
+            </html>"#;
+
+        assert_eq!(
+            parse_nav(value.as_bytes()).unwrap(),
+            vec![
+                entry("1. The Beginning", Some("text/one.xhtml"), Vec::new()),
+                entry(
+                    "Part Two",
+                    None,
+                    vec![entry("Middle", Some("text/two.xhtml#start"), Vec::new())]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn ncx() {
+        let value = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1">
+                <docTitle><text>Book</text></docTitle>
+                <navMap>
+                    <navPoint id="a" playOrder="1">
+                        <navLabel><text>One</text></navLabel>
+                        <content src="one.html"/>
+                        <navPoint id="b" playOrder="2">
+                            <navLabel><text>One &amp; a Half</text></navLabel>
+                            <content src="one.html#half"/>
+                        </navPoint>
+                    </navPoint>
+                    <navPoint id="c" playOrder="3">
+                        <navLabel><text>Two</text></navLabel>
+                        <content src="two.html"/>
+                    </navPoint>
+                </navMap>
+            </ncx>"#;
+
+        assert_eq!(
+            parse_ncx(value.as_bytes()).unwrap(),
+            vec![
+                entry(
+                    "One",
+                    Some("one.html"),
+                    vec![entry("One & a Half", Some("one.html#half"), Vec::new())]
+                ),
+                entry("Two", Some("two.html"), Vec::new()),
+            ]
+        );
+    }
+}
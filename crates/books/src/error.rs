@@ -1,9 +1,11 @@
 use std::{io, string::FromUtf8Error};
 
 use binstall_zip::result::ZipError;
-use mobi::MobiError;
+use lopdf::Error as PdfError;
 use serde_xml_rs::Error as SerdeXmlError;
+use sevenz_rust::Error as SevenZipError;
 use thiserror::Error as ThisError;
+use unrar::error::UnrarError;
 use xml::reader::Error as XmlReaderError;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -16,8 +18,17 @@ pub enum Error {
     #[error("Zip Error: {0}")]
     Zip(#[from] ZipError),
 
+    #[error("Rar Error: {0}")]
+    Rar(#[from] UnrarError),
+
+    #[error("7z Error: {0}")]
+    SevenZip(#[from] SevenZipError),
+
     #[error("Mobi Error: {0}")]
-    Mobi(#[from] MobiError),
+    Mobi(&'static str),
+
+    #[error("PDF Error: {0}")]
+    Pdf(#[from] PdfError),
 
     #[error("Serde XML Error: {0}")]
     SerdeXml(#[from] SerdeXmlError),
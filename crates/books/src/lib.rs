@@ -6,6 +6,7 @@ use std::{
 pub mod cb;
 pub mod epub;
 pub mod mobi;
+pub mod pdf;
 
 pub mod error;
 pub use error::*;
@@ -81,9 +82,24 @@ pub trait Book: Send {
 
     fn find(&self, search: BookSearch<'_>) -> Option<Vec<String>>;
 
+    /// The table of contents of the book. Empty if it doesn't have one.
+    fn get_table_of_contents(&mut self) -> Result<Vec<TocItem>> {
+        Ok(Vec::new())
+    }
+
     fn compute_hash(&mut self) -> Option<String>;
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocItem {
+    pub title: String,
+    /// `None` if it doesn't point to a chapter.
+    pub chapter: Option<usize>,
+    /// Id of the element inside of the chapter.
+    pub fragment: Option<String>,
+    pub children: Vec<TocItem>,
+}
+
 pub enum BookSearch<'a> {
     // Required
     Title,
@@ -116,8 +132,12 @@ impl<'a> From<&'a str> for BookSearch<'a> {
 
 pub fn load_from_path(path: &str) -> Result<Option<Box<dyn Book>>> {
     Ok(match path.rsplit_once('.').map(|v| v.1) {
-        Some("cbz") => Some(Box::new(cb::ComicBook::load_from_path(path)?)),
+        Some("cbz" | "cbr" | "cb7" | "cbt") => Some(Box::new(cb::ComicBook::load_from_path(path)?)),
         Some("epub") => Some(Box::new(epub::EpubBook::load_from_path(path)?)),
+        Some("mobi" | "azw" | "azw3" | "prc") => {
+            Some(Box::new(mobi::MobiBook::load_from_path(path)?))
+        }
+        Some("pdf") => Some(Box::new(pdf::PdfBook::load_from_path(path)?)),
 
         _ => None,
     })
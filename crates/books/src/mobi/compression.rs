@@ -0,0 +1,323 @@
+// https://wiki.mobileread.com/wiki/PalmDOC#PalmDoc_byte_pair_compression
+// https://wiki.mobileread.com/wiki/Huffman/CDIC
+
+use super::reader::{read_u16, read_u32};
+use crate::{Error, Result};
+
+/// Dictionary entries can reference other entries. Guards against malformed files.
+const MAX_HUFF_DEPTH: usize = 32;
+
+/// Decompress a PalmDOC (LZ77) record.
+pub fn decompress_palmdoc(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len() * 2);
+
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let byte = input[pos];
+        pos += 1;
+
+        match byte {
+            // Copy the next 1-8 bytes as-is.
+            0x01..=0x08 => {
+                let end = (pos + byte as usize).min(input.len());
+                output.extend_from_slice(&input[pos..end]);
+                pos = end;
+            }
+
+            // Distance/Length pair.
+            0x80..=0xBF => {
+                let Some(&next) = input.get(pos) else {
+                    break;
+                };
+
+                pos += 1;
+
+                let pair = (((byte as usize) << 8) | next as usize) & 0x3FFF;
+                let distance = pair >> 3;
+                let length = (pair & 0x07) + 3;
+
+                if distance == 0 || distance > output.len() {
+                    continue;
+                }
+
+                // Byte by byte since the copied section can overlap what we're writing.
+                let start = output.len() - distance;
+
+                for i in 0..length {
+                    output.push(output[start + i]);
+                }
+            }
+
+            // Space + Character
+            0xC0..=0xFF => {
+                output.push(b' ');
+                output.push(byte ^ 0x80);
+            }
+
+            // Literal
+            _ => output.push(byte),
+        }
+    }
+
+    output
+}
+
+/// Decompresses records which were compressed with the Huffman/CDIC compression.
+pub struct HuffCdicReader {
+    /// (code length, terminal, max code)
+    dict1: Vec<(usize, bool, u64)>,
+
+    min_codes: [u64; 33],
+    max_codes: [u64; 33],
+
+    /// (phrase, is fully decompressed)
+    dictionary: Vec<(Vec<u8>, bool)>,
+}
+
+impl HuffCdicReader {
+    pub fn new(huff: &[u8], cdics: &[&[u8]]) -> Result<Self> {
+        if huff.get(..4) != Some(b"HUFF") {
+            return Err(Error::Mobi("Invalid HUFF record"));
+        }
+
+        let (Some(offset1), Some(offset2)) = (read_u32(huff, 8), read_u32(huff, 12)) else {
+            return Err(Error::Mobi("Invalid HUFF record"));
+        };
+
+        let mut dict1 = Vec::with_capacity(256);
+
+        for i in 0..256 {
+            let value = read_u32(huff, offset1 as usize + i * 4)
+                .ok_or(Error::Mobi("Invalid HUFF record"))?;
+
+            let code_len = (value & 0x1F) as usize;
+            let term = value & 0x80 != 0;
+            let max_code = value as u64 >> 8;
+
+            // Five bits so it's at most 31. Zero would shift the code by 32.
+            if code_len == 0 {
+                return Err(Error::Mobi("Invalid HUFF code length"));
+            }
+
+            let max_code = (max_code + 1)
+                .checked_shl(32 - code_len as u32)
+                .ok_or(Error::Mobi("Invalid HUFF code length"))?;
+
+            dict1.push((code_len, term, max_code - 1));
+        }
+
+        let mut min_codes = [0; 33];
+        let mut max_codes = [u32::MAX as u64; 33];
+
+        for code_len in 1..=32 {
+            let offset = offset2 as usize + (code_len - 1) * 8;
+
+            let min_code = read_u32(huff, offset).ok_or(Error::Mobi("Invalid HUFF record"))?;
+            let max_code = read_u32(huff, offset + 4).ok_or(Error::Mobi("Invalid HUFF record"))?;
+
+            min_codes[code_len] = (min_code as u64) << (32 - code_len);
+            max_codes[code_len] = ((max_code as u64 + 1) << (32 - code_len)) - 1;
+        }
+
+        let mut dictionary = Vec::new();
+
+        for cdic in cdics {
+            if cdic.get(..4) != Some(b"CDIC") {
+                return Err(Error::Mobi("Invalid CDIC record"));
+            }
+
+            let (Some(phrases), Some(bits)) = (read_u32(cdic, 8), read_u32(cdic, 12)) else {
+                return Err(Error::Mobi("Invalid CDIC record"));
+            };
+
+            let count =
+                (1usize << bits.min(31)).min((phrases as usize).saturating_sub(dictionary.len()));
+
+            for i in 0..count {
+                let offset =
+                    read_u16(cdic, 16 + i * 2).ok_or(Error::Mobi("Invalid CDIC record"))? as usize;
+
+                let length =
+                    read_u16(cdic, 16 + offset).ok_or(Error::Mobi("Invalid CDIC record"))?;
+
+                let start = 18 + offset;
+                let end = start + (length & 0x7FFF) as usize;
+
+                let phrase = cdic
+                    .get(start..end)
+                    .ok_or(Error::Mobi("Invalid CDIC record"))?;
+
+                dictionary.push((phrase.to_vec(), length & 0x8000 != 0));
+            }
+        }
+
+        Ok(Self {
+            dict1,
+            min_codes,
+            max_codes,
+            dictionary,
+        })
+    }
+
+    pub fn decompress(&mut self, input: &[u8]) -> Result<Vec<u8>> {
+        self.unpack(input, 0)
+    }
+
+    fn unpack(&mut self, input: &[u8], depth: usize) -> Result<Vec<u8>> {
+        if depth > MAX_HUFF_DEPTH {
+            return Err(Error::Mobi("HUFF/CDIC dictionary is too deeply nested"));
+        }
+
+        let mut output = Vec::new();
+
+        let mut bits_left = input.len() as i64 * 8;
+        let mut pos = 0;
+        let mut bits = read_u64_padded(input, pos);
+        let mut n: i32 = 32;
+
+        loop {
+            if n <= 0 {
+                pos += 4;
+                bits = read_u64_padded(input, pos);
+                n += 32;
+            }
+
+            let code = (bits >> n) & 0xFFFF_FFFF;
+
+            let (mut code_len, term, mut max_code) = self.dict1[(code >> 24) as usize];
+
+            if !term {
+                while code_len < 32 && code < self.min_codes[code_len] {
+                    code_len += 1;
+                }
+
+                max_code = self.max_codes[code_len];
+            }
+
+            n -= code_len as i32;
+            bits_left -= code_len as i64;
+
+            if bits_left < 0 {
+                break;
+            }
+
+            let index = (max_code.wrapping_sub(code) >> (32 - code_len)) as usize;
+
+            let Some((phrase, is_unpacked)) = self.dictionary.get_mut(index) else {
+                return Err(Error::Mobi("HUFF code is out of the CDIC range"));
+            };
+
+            if *is_unpacked {
+                output.extend_from_slice(phrase);
+            } else {
+                let phrase = std::mem::take(phrase);
+                let phrase = self.unpack(&phrase, depth + 1)?;
+
+                output.extend_from_slice(&phrase);
+
+                self.dictionary[index] = (phrase, true);
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// Size of the trailing entries which are appended to each text record.
+pub fn trailing_entries_size(data: &[u8], extra_flags: u16) -> usize {
+    let mut size = 0;
+    let mut flags = extra_flags >> 1;
+
+    while flags != 0 {
+        if flags & 1 != 0 {
+            size += backward_variable_width(&data[..data.len().saturating_sub(size)]);
+        }
+
+        flags >>= 1;
+    }
+
+    // Multibyte character overlap
+    if extra_flags & 1 != 0 {
+        if let Some(&byte) = data
+            .len()
+            .checked_sub(size + 1)
+            .and_then(|index| data.get(index))
+        {
+            size += (byte & 0x03) as usize + 1;
+        }
+    }
+
+    size.min(data.len())
+}
+
+fn backward_variable_width(data: &[u8]) -> usize {
+    let mut value = 0;
+
+    for &byte in &data[data.len().saturating_sub(4)..] {
+        if byte & 0x80 != 0 {
+            value = 0;
+        }
+
+        value = (value << 7) | (byte & 0x7F) as usize;
+    }
+
+    value
+}
+
+fn read_u64_padded(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = data.get(offset + i).copied().unwrap_or_default();
+    }
+
+    u64::from_be_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palmdoc_literals_and_spaces() {
+        assert_eq!(decompress_palmdoc(b"ab\x02cd\xE5"), b"abcd e");
+    }
+
+    #[test]
+    fn palmdoc_back_reference() {
+        // "abc" then copy 3 bytes from 3 back -> "abcabc"
+        let pair = 0x8000u16 | (3 << 3);
+
+        let mut input = b"abc".to_vec();
+        input.extend_from_slice(&pair.to_be_bytes());
+
+        assert_eq!(decompress_palmdoc(&input), b"abcabc");
+    }
+
+    #[test]
+    fn trailing_entries() {
+        // One trailing entry of size 2 and a multibyte overlap of 1.
+        let data = [b'a', b'b', 0x00, 0x82];
+        assert_eq!(trailing_entries_size(&data, 0b10), 2);
+
+        let data = [b'a', 0x00, 0x00, 0x82];
+        assert_eq!(trailing_entries_size(&data, 0b11), 3);
+    }
+
+    #[test]
+    fn huff_rejects_zero_code_length() {
+        let mut huff = b"HUFF".to_vec();
+        huff.extend_from_slice(&[0; 4]);
+        huff.extend_from_slice(&16u32.to_be_bytes());
+        huff.extend_from_slice(&(16 + 256 * 4u32).to_be_bytes());
+        huff.extend_from_slice(&[0; 256 * 4 + 32 * 8]);
+
+        assert!(HuffCdicReader::new(&huff, &[]).is_err());
+
+        // Length of 8, terminal.
+        huff[16..16 + 256 * 4].copy_from_slice(&[0, 0, 0, 0x88].repeat(256));
+
+        assert!(HuffCdicReader::new(&huff, &[]).is_ok());
+    }
+}
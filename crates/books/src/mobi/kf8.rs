@@ -0,0 +1,264 @@
+// https://wiki.mobileread.com/wiki/KF8
+// https://wiki.mobileread.com/wiki/MOBI#INDX
+
+use std::collections::HashMap;
+
+use super::reader::{read_u16, read_u32, PalmDatabase};
+use crate::{Error, Result};
+
+pub struct IndexEntry {
+    pub ident: String,
+    pub tags: HashMap<u8, Vec<u32>>,
+}
+
+impl IndexEntry {
+    pub fn tag_value(&self, tag: u8, index: usize) -> Option<u32> {
+        self.tags.get(&tag)?.get(index).copied()
+    }
+}
+
+struct TagX {
+    tag: u8,
+    values_per_entry: u8,
+    mask: u8,
+    end_flag: u8,
+}
+
+/// Reads an INDX header record and every index record which follows it.
+pub fn read_index(database: &PalmDatabase, index: usize) -> Result<Vec<IndexEntry>> {
+    let header = database
+        .record(index)
+        .filter(|v| v.starts_with(b"INDX"))
+        .ok_or(Error::Mobi("Invalid INDX header record"))?;
+
+    let header_length = read_u32(header, 4).unwrap_or_default() as usize;
+    let record_count = read_u32(header, 24).unwrap_or_default() as usize;
+
+    let (control_byte_count, tagx) = read_tagx(header.get(header_length..).unwrap_or_default())?;
+
+    let mut entries = Vec::new();
+
+    for record_index in index + 1..=index + record_count {
+        let record = database
+            .record(record_index)
+            .filter(|v| v.starts_with(b"INDX"))
+            .ok_or(Error::Mobi("Invalid INDX record"))?;
+
+        let idxt_pos = read_u32(record, 20).unwrap_or_default() as usize;
+        let entry_count = read_u32(record, 24).unwrap_or_default() as usize;
+
+        if record.get(idxt_pos..idxt_pos + 4) != Some(b"IDXT") {
+            return Err(Error::Mobi("Invalid IDXT section"));
+        }
+
+        let offsets = (0..entry_count)
+            .map(|i| {
+                read_u16(record, idxt_pos + 4 + i * 2)
+                    .map(|v| v as usize)
+                    .ok_or(Error::Mobi("Invalid IDXT section"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for (i, &start) in offsets.iter().enumerate() {
+            let end = offsets.get(i + 1).copied().unwrap_or(idxt_pos);
+
+            let Some(entry) = record.get(start..end) else {
+                continue;
+            };
+
+            let Some(&ident_len) = entry.first() else {
+                continue;
+            };
+
+            let ident_end = (1 + ident_len as usize).min(entry.len());
+
+            entries.push(IndexEntry {
+                ident: String::from_utf8_lossy(&entry[1..ident_end]).into_owned(),
+                tags: read_tag_map(control_byte_count, &tagx, &entry[ident_end..]),
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+fn read_tagx(value: &[u8]) -> Result<(usize, Vec<TagX>)> {
+    if !value.starts_with(b"TAGX") {
+        return Err(Error::Mobi("Invalid TAGX section"));
+    }
+
+    let length = read_u32(value, 4).unwrap_or_default() as usize;
+    let control_byte_count = read_u32(value, 8).unwrap_or_default() as usize;
+
+    let tags = value
+        .get(12..length)
+        .unwrap_or_default()
+        .chunks_exact(4)
+        .map(|v| TagX {
+            tag: v[0],
+            values_per_entry: v[1],
+            mask: v[2],
+            end_flag: v[3],
+        })
+        .collect();
+
+    Ok((control_byte_count, tags))
+}
+
+fn read_tag_map(control_byte_count: usize, tagx: &[TagX], data: &[u8]) -> HashMap<u8, Vec<u32>> {
+    let mut control_bytes = data.iter().take(control_byte_count).copied();
+    let mut control_byte = control_bytes.next().unwrap_or_default();
+
+    let mut data = data.get(control_byte_count..).unwrap_or_default();
+
+    // (tag, value count, value bytes, values per entry)
+    let mut found = Vec::new();
+
+    for tag in tagx {
+        if tag.end_flag == 0x01 {
+            control_byte = control_bytes.next().unwrap_or_default();
+            continue;
+        }
+
+        let mut mask = tag.mask;
+        let mut value = control_byte & mask;
+
+        if value == 0 {
+            continue;
+        }
+
+        if value == mask {
+            if mask.count_ones() > 1 {
+                // A variable width value follows which contains the amount of bytes for the values.
+                let (value_bytes, consumed) = forward_variable_width(data);
+                data = &data[consumed..];
+
+                found.push((
+                    tag.tag,
+                    None,
+                    Some(value_bytes as usize),
+                    tag.values_per_entry,
+                ));
+            } else {
+                found.push((tag.tag, Some(1), None, tag.values_per_entry));
+            }
+        } else {
+            while mask & 0x01 == 0 {
+                mask >>= 1;
+                value >>= 1;
+            }
+
+            found.push((tag.tag, Some(value as usize), None, tag.values_per_entry));
+        }
+    }
+
+    let mut tags = HashMap::new();
+
+    for (tag, value_count, value_bytes, values_per_entry) in found {
+        let mut values = Vec::new();
+
+        if let Some(count) = value_count {
+            for _ in 0..count * values_per_entry as usize {
+                let (value, consumed) = forward_variable_width(data);
+                data = &data[consumed..];
+                values.push(value);
+            }
+        } else if let Some(byte_count) = value_bytes {
+            let mut total = 0;
+
+            while total < byte_count && !data.is_empty() {
+                let (value, consumed) = forward_variable_width(data);
+                data = &data[consumed..];
+                total += consumed;
+                values.push(value);
+            }
+        }
+
+        tags.insert(tag, values);
+    }
+
+    tags
+}
+
+fn forward_variable_width(data: &[u8]) -> (u32, usize) {
+    let mut value = 0u32;
+
+    for (i, &byte) in data.iter().enumerate() {
+        value = value.wrapping_shl(7) | (byte & 0x7F) as u32;
+
+        if byte & 0x80 != 0 {
+            return (value, i + 1);
+        }
+    }
+
+    (value, data.len())
+}
+
+/// Reads the FDST record which splits the text into its flows. (Flow 0 is the HTML)
+pub fn read_fdst(record: &[u8]) -> Option<Vec<(usize, usize)>> {
+    if !record.starts_with(b"FDST") {
+        return None;
+    }
+
+    let offset = read_u32(record, 4)? as usize;
+    let count = read_u32(record, 8)? as usize;
+
+    (0..count)
+        .map(|i| {
+            Some((
+                read_u32(record, offset + i * 8)? as usize,
+                read_u32(record, offset + i * 8 + 4)? as usize,
+            ))
+        })
+        .collect()
+}
+
+/// Rebuilds the HTML files by inserting each fragment into its skeleton.
+pub fn build_parts(
+    text: &[u8],
+    skeletons: &[IndexEntry],
+    fragments: &[IndexEntry],
+) -> Vec<Vec<u8>> {
+    let mut parts = Vec::with_capacity(skeletons.len());
+    let mut fragments = fragments.iter();
+
+    for skeleton in skeletons {
+        let fragment_count = skeleton.tag_value(1, 0).unwrap_or_default() as usize;
+        let skel_start = skeleton.tag_value(6, 0).unwrap_or_default() as usize;
+        let skel_length = skeleton.tag_value(6, 1).unwrap_or_default() as usize;
+
+        // Always consume the fragments so the following skeletons stay aligned.
+        let skel_fragments = fragments.by_ref().take(fragment_count).collect::<Vec<_>>();
+
+        let Some(skel_text) = text.get(skel_start..skel_start + skel_length) else {
+            continue;
+        };
+
+        let mut part = skel_text.to_vec();
+        let mut base = skel_start + skel_length;
+
+        for fragment in skel_fragments {
+            let insert_pos = fragment.ident.parse::<usize>().unwrap_or_default();
+            let length = fragment.tag_value(6, 1).unwrap_or_default() as usize;
+
+            let Some(frag_text) = text.get(base..base + length) else {
+                break;
+            };
+
+            let insert_pos = insert_pos.saturating_sub(skel_start).min(part.len());
+
+            part.splice(insert_pos..insert_pos, frag_text.iter().copied());
+
+            base += length;
+        }
+
+        parts.push(part);
+    }
+
+    parts
+}
+
+/// KF8 uses base 32 numbers for its links. Eg. "kindle:embed:000A"
+pub fn parse_base32(value: &str) -> Option<usize> {
+    usize::from_str_radix(value, 32).ok()
+}
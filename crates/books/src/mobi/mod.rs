@@ -1,122 +1,704 @@
-// For .mobi and .prc files
+// For .mobi, .prc, .azw and .azw3 files
 
 // https://wiki.mobileread.com/wiki/MOBI
+// https://wiki.mobileread.com/wiki/KF8
 
 use std::{
     borrow::Cow,
+    ops::Range,
     path::{Path, PathBuf},
 };
 
-use mobi::Mobi;
+use regex::{bytes::Regex as BytesRegex, Captures, Regex};
+
+pub mod compression;
+pub mod kf8;
+pub mod reader;
 
 use super::Book;
-use crate::{BookSearch, Result};
+use crate::{BookSearch, Error, Result};
+use compression::*;
+use reader::*;
+
+const PART_PREFIX: &str = "part";
+const IMAGE_PREFIX: &str = "images/";
+
+pub struct MobiBook {
+    database: PalmDatabase,
+    /// Header of the section we're reading. Will be the KF8 Header for combination files.
+    header: MobiHeader,
+
+    /// Absolute record index of the first image.
+    first_image_index: Option<usize>,
+
+    /// HTML for each chapter.
+    parts: Vec<String>,
+    /// KF8 only. Flow 0 is the HTML, the rest are mostly CSS and SVG.
+    flows: Vec<String>,
+    /// KF8 only. The part each fragment is located in.
+    fragment_parts: Vec<usize>,
 
-pub struct EpubBook {
-    reader: Mobi,
+    unique_id: String,
+
+    chapter: usize,
 }
 
-impl Book for EpubBook {
+impl MobiBook {
+    fn read_text(
+        database: &PalmDatabase,
+        header: &MobiHeader,
+        section_start: usize,
+    ) -> Result<Vec<u8>> {
+        let mut huff_reader = if header.compression == Compression::HuffCdic {
+            let huff_index = section_start + header.huffman_record_offset as usize;
+
+            let huff = database
+                .record(huff_index)
+                .ok_or(Error::Mobi("Missing HUFF record"))?;
+
+            let cdics = (huff_index + 1..huff_index + header.huffman_record_count as usize)
+                .filter_map(|i| database.record(i))
+                .collect::<Vec<_>>();
+
+            Some(HuffCdicReader::new(huff, &cdics)?)
+        } else {
+            None
+        };
+
+        let mut text = Vec::with_capacity(header.text_length);
+
+        for index in section_start + 1..=section_start + header.text_record_count {
+            let record = database
+                .record(index)
+                .ok_or(Error::Mobi("Missing text record"))?;
+
+            let record =
+                &record[..record.len() - trailing_entries_size(record, header.extra_flags)];
+
+            match huff_reader.as_mut() {
+                Some(reader) => text.append(&mut reader.decompress(record)?),
+                None if header.compression == Compression::PalmDoc => {
+                    text.append(&mut decompress_palmdoc(record))
+                }
+                None => text.extend_from_slice(record),
+            }
+        }
+
+        text.truncate(header.text_length);
+
+        Ok(text)
+    }
+
+    fn get_image(&self, rec_index: usize) -> Option<(&'static str, &[u8])> {
+        let record = self
+            .database
+            .record(self.first_image_index? + rec_index.checked_sub(1)?)?;
+
+        Some((image_extension(record)?, record))
+    }
+
+    fn get_image_path(&self, rec_index: usize) -> Option<String> {
+        let (ext, _) = self.get_image(rec_index)?;
+
+        Some(format!("{IMAGE_PREFIX}{rec_index:05}.{ext}"))
+    }
+
+    fn encode_image(&self, rec_index: usize) -> Option<String> {
+        let (ext, data) = self.get_image(rec_index)?;
+
+        Some(format!(
+            "data:image/{ext};charset=utf-8;base64,{}",
+            base64::encode(data)
+        ))
+    }
+
+    fn exth_values(&self, kinds: &[ExthRecord]) -> Vec<String> {
+        let Some(exth) = self.header.exth.as_ref() else {
+            return Vec::new();
+        };
+
+        kinds
+            .iter()
+            .flat_map(|&kind| exth.get(kind))
+            .map(|v| self.header.decode_text(v).trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect()
+    }
+
+    /// Replaces the internal image and link references with ones a browser can understand.
+    fn resolve_references(&self, value: &str, prepend_to_urls: Option<&str>) -> String {
+        let prefix = prepend_to_urls.map(|v| format!("{v}/")).unwrap_or_default();
+
+        if self.header.is_kf8() {
+            let value = Regex::new(
+                r#"<link[^>]*href=["']kindle:flow:([0-9A-Va-v]+)\?mime=text/css["'][^>]*>"#,
+            )
+            .unwrap()
+            .replace_all(value, |caps: &Captures| {
+                kf8::parse_base32(&caps[1])
+                    .and_then(|i| self.flows.get(i))
+                    .map(|css| format!("<style>{css}</style>"))
+                    .unwrap_or_default()
+            });
+
+            let value = Regex::new(r"kindle:embed:([0-9A-Va-v]+)(\?mime=[\w/+.-]*)?")
+                .unwrap()
+                .replace_all(&value, |caps: &Captures| {
+                    kf8::parse_base32(&caps[1])
+                        .and_then(|i| self.encode_image(i))
+                        .unwrap_or_default()
+                });
+
+            Regex::new(r"kindle:pos:fid:([0-9A-Va-v]+):off:([0-9A-Va-v]+)")
+                .unwrap()
+                .replace_all(&value, |caps: &Captures| {
+                    let part = kf8::parse_base32(&caps[1])
+                        .and_then(|i| self.fragment_parts.get(i).copied())
+                        .unwrap_or_default();
+
+                    format!("{prefix}{}", part_file_name(part))
+                })
+                .into_owned()
+        } else {
+            let value = Regex::new(r#"(?i)\b(?:hi|lo)?recindex\s*=\s*["']?(\d+)["']?"#)
+                .unwrap()
+                .replace_all(value, |caps: &Captures| {
+                    let src = caps[1]
+                        .parse()
+                        .ok()
+                        .and_then(|i| self.encode_image(i))
+                        .unwrap_or_default();
+
+                    format!(r#"src="{src}""#)
+                });
+
+            value.replace(
+                &format!(r#"href="{PART_PREFIX}"#),
+                &format!(r#"href="{prefix}{PART_PREFIX}"#),
+            )
+        }
+    }
+
+    fn render_part(
+        &self,
+        index: usize,
+        prepend_to_urls: Option<&str>,
+        add_css: Option<&[&str]>,
+    ) -> Result<Vec<u8>> {
+        let part = self
+            .parts
+            .get(index)
+            .ok_or(Error::MissingValueFor("chapter"))?;
+
+        let body = self.resolve_references(part, prepend_to_urls);
+        let all_css = add_css.unwrap_or_default().concat();
+
+        if self.header.is_kf8() {
+            let style = format!("<style>{all_css}</style>");
+
+            Ok(match body.find("</head>") {
+                Some(pos) => format!("{}{style}{}", &body[..pos], &body[pos..]),
+                None => format!("{style}{body}"),
+            }
+            .into_bytes())
+        } else {
+            Ok(format!(
+                r#"
+                <!DOCTYPE html>
+                <html lang="en">
+                    <head>
+                        <meta charset="UTF-8">
+                        <meta name="viewport" content="width=device-width, initial-scale=1.0">
+                        <title>Page</title>
+                        <style>{all_css}</style>
+                    </head>
+                    <body>
+                        {body}
+                    </body>
+                </html>
+            "#
+            )
+            .into_bytes())
+        }
+    }
+}
+
+impl Book for MobiBook {
     fn load_from_path(path: &str) -> Result<Self>
     where
         Self: Sized,
     {
-        let this = Self {
-            reader: Mobi::from_path(path)?,
-        };
+        let database = PalmDatabase::new(std::fs::read(path)?)?;
 
-        // Testing
+        let first_header = MobiHeader::parse(
+            database
+                .record(0)
+                .ok_or(Error::Mobi("Missing MOBI Header"))?,
+        )?;
 
-        let records = this.reader.raw_records();
-        let records = records.range(this.reader.readable_records_range());
+        if first_header.encryption != 0 {
+            return Err(Error::Mobi("Encrypted (DRM) books are not supported"));
+        }
 
-        println!("Records: {}", records.len());
+        // Combination files contain both a MOBI6 and KF8 version of the book. Prefer the KF8 one.
+        let kf8_section = first_header
+            .kf8_boundary()
+            .filter(|_| !first_header.is_kf8())
+            .and_then(|boundary| {
+                // The boundary can either point to the KF8 Header or the "BOUNDARY" record before it.
+                [boundary, boundary + 1].into_iter().find_map(|index| {
+                    let header = MobiHeader::parse(database.record(index)?).ok()?;
+                    Some((index, header)).filter(|v| v.1.is_kf8())
+                })
+            });
 
-        let rec = &records[0];
+        // Images are shared between both sections in combination files.
+        let first_image_index = Some(first_header.first_image_index)
+            .filter(|&v| v != NULL_INDEX)
+            .map(|v| v as usize);
 
-        let mut recompiled = Vec::new();
+        let (section_start, header) = kf8_section.unwrap_or((0, first_header));
 
-        // TODO: For some reason random u8's are combined. We remove the space and 96 (tilde) from it. For some reason it always equals 96 after subtracting space and the correct char.
-        for v in rec.content {
-            if *v < 160 {
-                recompiled.push(*v);
-            } else {
-                recompiled.push(32);
-                recompiled.push(*v - 128);
-            }
-        }
+        let text = Self::read_text(&database, &header, section_start)?;
+
+        let unique_id = header
+            .exth
+            .as_ref()
+            .and_then(|exth| {
+                exth.get(ExthRecord::Asin)
+                    .chain(exth.get(ExthRecord::AsinAlt))
+                    .map(|v| String::from_utf8_lossy(v).trim().to_string())
+                    .find(|v| !v.is_empty())
+            })
+            .unwrap_or_else(|| header.unique_id.to_string());
+
+        let mut this = Self {
+            database,
+            header,
+            first_image_index,
+            parts: Vec::new(),
+            flows: Vec::new(),
+            fragment_parts: Vec::new(),
+            unique_id,
+            chapter: 0,
+        };
+
+        if this.header.is_kf8() {
+            let relative_index = |index: u32| {
+                Some(index)
+                    .filter(|&v| v != NULL_INDEX)
+                    .map(|v| section_start + v as usize)
+            };
+
+            let flows = relative_index(this.header.fdst_index)
+                .and_then(|i| this.database.record(i))
+                .and_then(kf8::read_fdst)
+                .unwrap_or_else(|| vec![(0, text.len())])
+                .into_iter()
+                .map(|(start, end)| text.get(start..end.min(text.len())).unwrap_or_default())
+                .collect::<Vec<_>>();
+
+            let html = flows.first().copied().unwrap_or_default();
+
+            let parts = match (
+                relative_index(this.header.skeleton_index),
+                relative_index(this.header.fragment_index),
+            ) {
+                (Some(skeleton_index), Some(fragment_index)) => {
+                    let skeletons = kf8::read_index(&this.database, skeleton_index)?;
+                    let fragments = kf8::read_index(&this.database, fragment_index)?;
 
-        println!("{:?}", &recompiled[0..128]);
+                    this.fragment_parts = fragments
+                        .iter()
+                        .map(|v| v.tag_value(3, 0).unwrap_or_default() as usize)
+                        .collect();
 
-        std::fs::write(
-            "./app/testing.html",
-            String::from_utf8_lossy(&recompiled).to_string(),
-        )
-        .unwrap();
+                    kf8::build_parts(html, &skeletons, &fragments)
+                }
 
-        // std::fs::write("./app/testing.html", &this.reader.content_as_string_lossy()).unwrap();
+                _ => vec![html.to_vec()],
+            };
+
+            this.flows = flows
+                .into_iter()
+                .map(|v| String::from_utf8_lossy(v).into_owned())
+                .collect();
+            this.parts = parts
+                .into_iter()
+                .map(|v| String::from_utf8_lossy(&v).into_owned())
+                .collect();
+        } else {
+            this.parts = split_mobi6_text(&text)
+                .into_iter()
+                .map(|v| this.header.decode_text(&v))
+                .collect();
+        }
 
         Ok(this)
     }
 
     fn compute_hash(&mut self) -> Option<String> {
-        None
+        let mut hasher = blake3::Hasher::new();
+
+        for index in 0..self.database.record_count() {
+            if let Some(record) = self.database.record(index) {
+                hasher.update(record);
+            }
+        }
+
+        Some(hasher.finalize().to_string())
     }
 
-    fn find(&self, _search: BookSearch<'_>) -> Option<Vec<String>> {
-        None
+    fn find(&self, search: BookSearch<'_>) -> Option<Vec<String>> {
+        let values = match search {
+            BookSearch::Title => {
+                let mut values = self.exth_values(&[ExthRecord::UpdatedTitle]);
+
+                if values.is_empty() {
+                    values.push(
+                        self.header
+                            .full_name
+                            .clone()
+                            .unwrap_or_else(|| self.database.name.replace('_', " ")),
+                    );
+                }
+
+                values
+            }
+
+            BookSearch::CoverImage => {
+                let exth = self.header.exth.as_ref()?;
+
+                let offset = exth
+                    .get_u32(ExthRecord::CoverOffset)
+                    .or_else(|| exth.get_u32(ExthRecord::ThumbOffset))
+                    .filter(|&v| v != NULL_INDEX)?;
+
+                vec![self.get_image_path(offset as usize + 1)?]
+            }
+
+            BookSearch::Identifier => {
+                self.exth_values(&[ExthRecord::Isbn, ExthRecord::Asin, ExthRecord::AsinAlt])
+            }
+            BookSearch::Language => self.exth_values(&[ExthRecord::Language]),
+            BookSearch::Contributor => self.exth_values(&[ExthRecord::Contributor]),
+            BookSearch::Creator => self.exth_values(&[ExthRecord::Author]),
+            BookSearch::Date => self.exth_values(&[ExthRecord::PublishedDate]),
+            BookSearch::Description => self.exth_values(&[ExthRecord::Description]),
+            BookSearch::Publisher => self.exth_values(&[ExthRecord::Publisher]),
+            BookSearch::Rights => self.exth_values(&[ExthRecord::Rights]),
+            BookSearch::Source => self.exth_values(&[ExthRecord::Source]),
+            BookSearch::Subject => self.exth_values(&[ExthRecord::Subject]),
+            BookSearch::Type => self.exth_values(&[ExthRecord::Type]),
+
+            BookSearch::Coverage | BookSearch::Format | BookSearch::Relation => Vec::new(),
+
+            // Allows searching by the EXTH record type. Eg. "100" for the Author.
+            BookSearch::Other(value) => {
+                let kind = value.parse::<u32>().ok()?;
+
+                self.header
+                    .exth
+                    .as_ref()?
+                    .get_by_id(kind)
+                    .map(|v| self.header.decode_text(v))
+                    .collect()
+            }
+        };
+
+        if values.is_empty() {
+            None
+        } else {
+            Some(values)
+        }
     }
 
     fn get_unique_id(&self) -> Result<Cow<str>> {
-        todo!()
+        Ok(Cow::Borrowed(self.unique_id.as_str()))
+    }
+
+    fn get_files(&self) -> Vec<String> {
+        let mut files = (0..self.parts.len())
+            .map(part_file_name)
+            .collect::<Vec<_>>();
+
+        if let Some(first_image_index) = self.first_image_index {
+            files.extend(
+                (1..=self
+                    .database
+                    .record_count()
+                    .saturating_sub(first_image_index))
+                    .filter_map(|i| self.get_image_path(i)),
+            );
+        }
+
+        files
     }
 
     fn get_root_file_dir(&self) -> &Path {
-        todo!()
+        Path::new("")
     }
 
     fn get_page_path(&self) -> PathBuf {
-        todo!()
+        PathBuf::from(part_file_name(self.chapter))
     }
 
     fn read_page_raw_as_bytes(&mut self) -> Result<Vec<u8>> {
-        todo!()
+        Ok(self
+            .parts
+            .get(self.chapter)
+            .map(|v| v.as_bytes().to_vec())
+            .unwrap_or_default())
     }
 
     fn read_path_as_bytes(
         &mut self,
-        _path: &str,
-        _prepend_to_urls: Option<&str>,
-        _add_css: Option<&[&str]>,
+        path: &str,
+        prepend_to_urls: Option<&str>,
+        add_css: Option<&[&str]>,
     ) -> Result<Vec<u8>> {
-        todo!()
+        let path = path.trim_start_matches('/');
+        let path = path.split_once('#').map(|v| v.0).unwrap_or(path);
+
+        if let Some(image) = path.strip_prefix(IMAGE_PREFIX) {
+            let rec_index = image
+                .split_once('.')
+                .map(|v| v.0)
+                .unwrap_or(image)
+                .parse::<usize>()
+                .map_err(|_| Error::MissingValueFor("image"))?;
+
+            return self
+                .get_image(rec_index)
+                .map(|(_, data)| data.to_vec())
+                .ok_or(Error::MissingValueFor("image"));
+        }
+
+        let index = path
+            .strip_prefix(PART_PREFIX)
+            .and_then(|v| v.strip_suffix(".html"))
+            .and_then(|v| v.parse::<usize>().ok())
+            .ok_or(Error::MissingValueFor("chapter"))?;
+
+        self.render_part(index, prepend_to_urls, add_css)
     }
 
     fn read_page_as_bytes(
         &mut self,
-        _prepend_to_urls: Option<&str>,
-        _add_css: Option<&[&str]>,
+        prepend_to_urls: Option<&str>,
+        add_css: Option<&[&str]>,
     ) -> Result<Vec<u8>> {
-        todo!()
+        self.render_part(self.chapter, prepend_to_urls, add_css)
     }
 
     fn chapter_count(&self) -> usize {
-        todo!()
+        self.parts.len()
     }
 
-    fn set_chapter(&mut self, _value: usize) -> bool {
-        todo!()
+    fn set_chapter(&mut self, value: usize) -> bool {
+        if value < self.chapter_count() {
+            self.chapter = value;
+            true
+        } else {
+            false
+        }
     }
 
     fn next_chapter(&mut self) -> bool {
-        todo!()
+        self.set_chapter(self.chapter + 1)
     }
 
     fn previous_chapter(&mut self) -> bool {
-        todo!()
+        if self.chapter != 0 {
+            self.set_chapter(self.chapter - 1)
+        } else {
+            false
+        }
     }
 
     fn get_chapter(&self) -> usize {
-        todo!()
+        self.chapter
+    }
+}
+
+fn part_file_name(index: usize) -> String {
+    format!("{PART_PREFIX}{index:04}.html")
+}
+
+/// Splits the MOBI6 text into chapters at each page break.
+///
+/// "filepos" links are byte offsets into the whole text. They're converted into links to
+/// the chapter they point to and an anchor is inserted at the offset.
+fn split_mobi6_text(text: &[u8]) -> Vec<Vec<u8>> {
+    let body_start = BytesRegex::new(r"(?i)<body[^>]*>")
+        .unwrap()
+        .find(text)
+        .map(|v| v.end())
+        .unwrap_or_default();
+
+    let body_end = BytesRegex::new(r"(?i)</body>")
+        .unwrap()
+        .find_iter(text)
+        .last()
+        .map(|v| v.start())
+        .filter(|&v| v >= body_start)
+        .unwrap_or(text.len());
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut start = body_start;
+
+    for found in BytesRegex::new(r"(?i)<mbp:pagebreak[^>]*>")
+        .unwrap()
+        .find_iter(&text[body_start..body_end])
+    {
+        ranges.push(start..body_start + found.start());
+        start = body_start + found.end();
+    }
+
+    ranges.push(start..body_end);
+
+    ranges.retain(|range| text[range.clone()].iter().any(|v| !v.is_ascii_whitespace()));
+
+    if ranges.is_empty() {
+        ranges.push(body_start..body_end);
+    }
+
+    // Offsets which land in between chapters (usually on a page break) belong to the next chapter.
+    let chapter_of = |pos: usize| {
+        ranges
+            .iter()
+            .position(|r| r.end > pos)
+            .unwrap_or(ranges.len() - 1)
+    };
+
+    // (start, end, replacement)
+    let mut edits = Vec::new();
+    let mut targets = Vec::new();
+
+    for caps in BytesRegex::new(r#"(?i)filepos\s*=\s*["']?0*(\d+)["']?"#)
+        .unwrap()
+        .captures_iter(text)
+    {
+        let Some(pos) = std::str::from_utf8(&caps[1])
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+        else {
+            continue;
+        };
+
+        let found = caps.get(0).unwrap();
+
+        edits.push((
+            found.start(),
+            found.end(),
+            format!(r#"href="{}#filepos{pos}""#, part_file_name(chapter_of(pos))).into_bytes(),
+        ));
+
+        targets.push(pos);
+    }
+
+    targets.sort_unstable();
+    targets.dedup();
+
+    for pos in targets {
+        let range = &ranges[chapter_of(pos)];
+        let insert_at = move_to_tag_boundary(text, pos.clamp(range.start, range.end), range.start);
+
+        edits.push((
+            insert_at,
+            insert_at,
+            format!(r#"<a id="filepos{pos}"></a>"#).into_bytes(),
+        ));
+    }
+
+    // Insertions are ordered before replacements starting at the same position.
+    edits.sort_by_key(|v| (v.0, v.1));
+
+    ranges
+        .iter()
+        .map(|range| {
+            let mut output = Vec::with_capacity(range.len());
+            let mut cursor = range.start;
+
+            for (start, end, value) in edits
+                .iter()
+                .filter(|v| v.0 >= range.start && v.1 <= range.end)
+            {
+                // Skip any overlapping edits.
+                if *start < cursor {
+                    continue;
+                }
+
+                output.extend_from_slice(&text[cursor..*start]);
+                output.extend_from_slice(value);
+                cursor = *end;
+            }
+
+            output.extend_from_slice(&text[cursor..range.end]);
+
+            output
+        })
+        .collect()
+}
+
+/// Offsets point at raw bytes. Moves one which lands inside of a tag or an entity back to where it starts.
+fn move_to_tag_boundary(text: &[u8], pos: usize, min: usize) -> usize {
+    let before = &text[min..pos];
+
+    if let Some(index) = before.iter().rposition(|&v| v == b'<' || v == b'>') {
+        if before[index] == b'<' {
+            return min + index;
+        }
+    }
+
+    match before
+        .iter()
+        .rposition(|&v| matches!(v, b'&' | b';' | b'>') || v.is_ascii_whitespace())
+    {
+        Some(index) if before[index] == b'&' => min + index,
+        _ => pos,
+    }
+}
+
+fn image_extension(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpeg")
+    } else if data.starts_with(b"\x89PNG") {
+        Some("png")
+    } else if data.starts_with(b"GIF8") {
+        Some("gif")
+    } else if data.starts_with(b"BM") {
+        Some("bmp")
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        Some("webp")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filepos_anchor_outside_of_tags() {
+        let text = b"<html><body><p>A &amp; B</p><p class=\"x\">C</p></body></html>";
+
+        // Inside of the attribute.
+        assert_eq!(move_to_tag_boundary(text, 35, 12), 28);
+        // Inside of the entity.
+        assert_eq!(move_to_tag_boundary(text, 19, 12), 17);
+        // In the text.
+        assert_eq!(move_to_tag_boundary(text, 24, 12), 24);
+
+        let text =
+            b"<html><body><p><a filepos=0000000030>Link</a></p><p class=\"x\">C</p></body></html>";
+        let parts = split_mobi6_text(text);
+
+        assert_eq!(parts.len(), 1);
+
+        let part = String::from_utf8(parts.into_iter().next().unwrap()).unwrap();
+
+        assert!(part.contains(r#"<a id="filepos30"></a><a href="#), "{part}");
     }
 }
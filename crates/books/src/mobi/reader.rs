@@ -1,58 +1,353 @@
-// TODO: Was going to create my own. Decided against it for now.
+// https://wiki.mobileread.com/wiki/PDB
+// https://wiki.mobileread.com/wiki/MOBI#MOBI_Header
+// https://wiki.mobileread.com/wiki/MOBI#EXTH_Header
 
+use crate::{Error, Result};
 
-use std::io::{Read, Seek, SeekFrom};
+/// Used by the MOBI Header to say an index doesn't exist.
+pub const NULL_INDEX: u32 = 0xFFFF_FFFF;
 
-use crate::Result;
+const PDB_HEADER_LEN: usize = 78;
 
+/// The Palm Database which every MOBI file is wrapped in.
+pub struct PalmDatabase {
+    pub name: String,
+    pub type_creator: [u8; 8],
 
-pub struct MobiReader<D: Read + Seek> {
-    data: D
+    data: Vec<u8>,
+    offsets: Vec<usize>,
 }
 
-impl<D: Read + Seek> MobiReader<D> {
-    pub fn new(data: D) -> Result<Self> {
-        let mut this = Self {
-            data
-        };
+impl PalmDatabase {
+    pub fn new(data: Vec<u8>) -> Result<Self> {
+        if data.len() < PDB_HEADER_LEN {
+            return Err(Error::Mobi("File is too small to be a Palm Database"));
+        }
+
+        let name = String::from_utf8_lossy(&data[..32])
+            .trim_end_matches('\0')
+            .to_string();
+
+        let mut type_creator = [0; 8];
+        type_creator.copy_from_slice(&data[60..68]);
+
+        if &type_creator != b"BOOKMOBI" && &type_creator != b"TEXtREAd" {
+            return Err(Error::Mobi("Unknown Palm Database type"));
+        }
+
+        let record_count = read_u16(&data, 76).unwrap_or_default() as usize;
+
+        let mut offsets = Vec::with_capacity(record_count);
+
+        for i in 0..record_count {
+            let offset = read_u32(&data, PDB_HEADER_LEN + i * 8)
+                .ok_or(Error::Mobi("Record list is out of bounds"))?
+                as usize;
+
+            if offset > data.len() {
+                return Err(Error::Mobi("Record offset is out of bounds"));
+            }
+
+            offsets.push(offset);
+        }
 
-        this.verify()?;
+        Ok(Self {
+            name,
+            type_creator,
+            data,
+            offsets,
+        })
+    }
 
-        Ok(this)
+    pub fn record_count(&self) -> usize {
+        self.offsets.len()
     }
 
-    fn verify(&mut self) -> Result<()> {
-        let name = read_next_to_vec(&mut self.data, 32)?;
-        println!("{:?}", std::str::from_utf8(&name));
+    pub fn record(&self, index: usize) -> Option<&[u8]> {
+        let start = *self.offsets.get(index)?;
+        let end = self
+            .offsets
+            .get(index + 1)
+            .copied()
+            .unwrap_or(self.data.len());
+
+        self.data.get(start..end.max(start))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    PalmDoc,
+    HuffCdic,
+}
+
+/// The PalmDOC and MOBI Headers which are both stored inside of the first record.
+#[derive(Debug)]
+pub struct MobiHeader {
+    pub compression: Compression,
+    pub text_length: usize,
+    pub text_record_count: usize,
+    pub encryption: u16,
+
+    pub mobi_type: u32,
+    pub text_encoding: u32,
+    pub unique_id: u32,
+    pub version: u32,
+
+    pub full_name: Option<String>,
+
+    pub first_non_book_index: u32,
+    pub first_image_index: u32,
+    pub huffman_record_offset: u32,
+    pub huffman_record_count: u32,
+
+    pub extra_flags: u16,
+
+    // KF8
+    pub fdst_index: u32,
+    pub fragment_index: u32,
+    pub skeleton_index: u32,
+
+    pub exth: Option<ExtHeader>,
+}
+
+impl MobiHeader {
+    pub fn parse(record: &[u8]) -> Result<Self> {
+        let compression = match read_u16(record, 0) {
+            Some(1) => Compression::None,
+            Some(2) => Compression::PalmDoc,
+            Some(17480) => Compression::HuffCdic,
+            _ => return Err(Error::Mobi("Unknown compression type")),
+        };
+
+        let text_length = read_u32(record, 4).unwrap_or_default() as usize;
+        let text_record_count = read_u16(record, 8).unwrap_or_default() as usize;
+        let encryption = read_u16(record, 12).unwrap_or_default();
+
+        // Older PalmDOC files don't contain a MOBI Header.
+        if record.get(16..20) != Some(b"MOBI") {
+            return Ok(Self {
+                compression,
+                text_length,
+                text_record_count,
+                encryption,
+                mobi_type: 0,
+                text_encoding: 1252,
+                unique_id: 0,
+                version: 0,
+                full_name: None,
+                first_non_book_index: NULL_INDEX,
+                first_image_index: NULL_INDEX,
+                huffman_record_offset: NULL_INDEX,
+                huffman_record_count: 0,
+                extra_flags: 0,
+                fdst_index: NULL_INDEX,
+                fragment_index: NULL_INDEX,
+                skeleton_index: NULL_INDEX,
+                exth: None,
+            });
+        }
+
+        let header_length = read_u32(record, 20).unwrap_or_default() as usize;
+        let header_end = 16 + header_length;
+
+        // Fields past the end of the header are treated as non-existent.
+        let field_u32 = |offset: usize| {
+            if offset + 4 <= header_end {
+                read_u32(record, offset)
+            } else {
+                None
+            }
+        };
+
+        let full_name = match (field_u32(0x54), field_u32(0x58)) {
+            (Some(offset), Some(length)) => {
+                let end = offset
+                    .checked_add(length)
+                    .ok_or(Error::Mobi("Invalid full name location"))?;
+
+                record
+                    .get(offset as usize..end as usize)
+                    .map(|v| String::from_utf8_lossy(v).into_owned())
+            }
+            _ => None,
+        };
+
+        let exth_flags = field_u32(0x80).unwrap_or_default();
+
+        let exth = if exth_flags & 0x40 != 0 {
+            record.get(header_end..).and_then(ExtHeader::parse)
+        } else {
+            None
+        };
+
+        let extra_flags = if header_length >= 0xE4 {
+            read_u16(record, 0xF2).unwrap_or_default()
+        } else {
+            0
+        };
+
+        let version = field_u32(0x24).unwrap_or_default();
+
+        Ok(Self {
+            compression,
+            text_length,
+            text_record_count,
+            encryption,
+
+            mobi_type: field_u32(0x18).unwrap_or_default(),
+            text_encoding: field_u32(0x1C).unwrap_or(1252),
+            unique_id: field_u32(0x20).unwrap_or_default(),
+            version,
+
+            full_name,
+
+            first_non_book_index: field_u32(0x50).unwrap_or(NULL_INDEX),
+            first_image_index: field_u32(0x6C).unwrap_or(NULL_INDEX),
+            huffman_record_offset: field_u32(0x70).unwrap_or(NULL_INDEX),
+            huffman_record_count: field_u32(0x74).unwrap_or_default(),
+
+            extra_flags,
 
-        // let compression = read_u16(&mut self.data)?;
-        // let unused = read_u16(&mut self.data)?;
+            fdst_index: if version >= 8 {
+                field_u32(0xC0).unwrap_or(NULL_INDEX)
+            } else {
+                NULL_INDEX
+            },
+            fragment_index: if version >= 8 {
+                field_u32(0xF8).unwrap_or(NULL_INDEX)
+            } else {
+                NULL_INDEX
+            },
+            skeleton_index: if version >= 8 {
+                field_u32(0xFC).unwrap_or(NULL_INDEX)
+            } else {
+                NULL_INDEX
+            },
 
-        // println!("{:?}", compression);
-        // println!("{:?}", unused);
+            exth,
+        })
+    }
+
+    pub fn is_kf8(&self) -> bool {
+        self.version >= 8
+    }
+
+    /// Record index of the KF8 Header inside of a combination (MOBI6 + KF8) file.
+    pub fn kf8_boundary(&self) -> Option<usize> {
+        self.exth
+            .as_ref()?
+            .get_u32(ExthRecord::Kf8Boundary)
+            .filter(|&v| v != NULL_INDEX)
+            .map(|v| v as usize)
+    }
 
-        Ok(())
+    pub fn decode_text(&self, value: &[u8]) -> String {
+        decode_text(value, self.text_encoding)
     }
 }
 
-fn read_next_to_vec<D: Read + Seek>(data: &mut D, len: usize) -> Result<Vec<u8>> {
-    let mut read = vec![0; len];
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExthRecord {
+    Author = 100,
+    Publisher = 101,
+    Description = 103,
+    Isbn = 104,
+    Subject = 105,
+    PublishedDate = 106,
+    Contributor = 108,
+    Rights = 109,
+    Type = 111,
+    Source = 112,
+    Asin = 113,
+    Kf8Boundary = 121,
+    CoverOffset = 201,
+    ThumbOffset = 202,
+    UpdatedTitle = 503,
+    AsinAlt = 504,
+    Language = 524,
+}
+
+/// The optional EXTH Header which follows the MOBI Header.
+#[derive(Debug, Default)]
+pub struct ExtHeader {
+    pub records: Vec<(u32, Vec<u8>)>,
+}
+
+impl ExtHeader {
+    fn parse(value: &[u8]) -> Option<Self> {
+        if value.get(..4)? != b"EXTH" {
+            return None;
+        }
 
-    data.read_exact(&mut read)?;
+        let count = read_u32(value, 8)? as usize;
 
-    data.seek(SeekFrom::Current(len as i64))?;
+        let mut records = Vec::new();
+        let mut pos = 12;
 
-    Ok(read.to_vec())
+        for _ in 0..count {
+            let kind = read_u32(value, pos)?;
+            let length = read_u32(value, pos + 4)? as usize;
+
+            if length < 8 {
+                break;
+            }
+
+            records.push((kind, value.get(pos + 8..pos + length)?.to_vec()));
+
+            pos += length;
+        }
+
+        Some(Self { records })
+    }
+
+    pub fn get(&self, kind: ExthRecord) -> impl Iterator<Item = &[u8]> {
+        self.get_by_id(kind as u32)
+    }
+
+    pub fn get_by_id(&self, kind: u32) -> impl Iterator<Item = &[u8]> {
+        self.records
+            .iter()
+            .filter(move |v| v.0 == kind)
+            .map(|v| v.1.as_slice())
+    }
+
+    pub fn get_u32(&self, kind: ExthRecord) -> Option<u32> {
+        self.get(kind).find_map(|v| read_u32(v, 0))
+    }
+}
+
+/// Converts the text into UTF-8. MOBI files are either CP1252 or UTF-8 (65001).
+pub fn decode_text(value: &[u8], encoding: u32) -> String {
+    if encoding == 65001 {
+        return String::from_utf8_lossy(value).into_owned();
+    }
+
+    // https://en.wikipedia.org/wiki/Windows-1252
+    const CP1252_HIGH: [u16; 32] = [
+        0x20AC, 0x0081, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160,
+        0x2039, 0x0152, 0x008D, 0x017D, 0x008F, 0x0090, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022,
+        0x2013, 0x2014, 0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x009D, 0x017E, 0x0178,
+    ];
+
+    value
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9F => char::from_u32(CP1252_HIGH[(b - 0x80) as usize] as u32).unwrap(),
+            _ => b as char,
+        })
+        .collect()
 }
 
-fn read_u16<D: Read + Seek>(data: &mut D) -> Result<u16> {
-    let mut bytes = [0; 2];
-    data.read_exact(&mut bytes)?;
-    Ok(u16::from_be_bytes(bytes))
+pub fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
 }
 
-fn read_u8<D: Read + Seek>(data: &mut D) -> Result<u8> {
-    let mut bytes = [0; 1];
-    data.read_exact(&mut bytes)?;
-    Ok(u8::from_be_bytes(bytes))
-}
\ No newline at end of file
+pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
@@ -0,0 +1,242 @@
+// Document Information Dictionary (14.3.3) and XMP Metadata (14.3.2)
+
+// https://developer.adobe.com/xmp/docs/XMPSpecifications/
+
+use std::collections::HashMap;
+
+use lopdf::{Dictionary, Document, Object};
+use xml::{name::OwnedName, reader::XmlEvent, EventReader};
+
+const DC_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+const XMP_NAMESPACE: &str = "http://ns.adobe.com/xap/1.0/";
+const PRISM_NAMESPACE_PREFIX: &str = "http://prismstandard.org/namespaces/basic/";
+
+/// Reads the Info Dictionary. Keys are converted into their Dublin Core names.
+pub fn read_info_dictionary(document: &Document) -> HashMap<String, Vec<String>> {
+    let mut found = HashMap::new();
+
+    let Some(info) = get_value(document, &document.trailer, b"Info").and_then(|v| v.as_dict().ok())
+    else {
+        return found;
+    };
+
+    let mut insert = |name: &str, values: Vec<String>| {
+        let values = values
+            .into_iter()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<_>>();
+
+        if !values.is_empty() {
+            found.insert(name.to_string(), values);
+        }
+    };
+
+    let get_text = |key: &[u8]| {
+        get_value(document, info, key)
+            .and_then(|v| v.as_str().ok())
+            .map(decode_text_string)
+    };
+
+    if let Some(title) = get_text(b"Title") {
+        insert("title", vec![title]);
+    }
+
+    if let Some(author) = get_text(b"Author") {
+        // Commas aren't used since the name can be "Last, First".
+        insert(
+            "creator",
+            author.split(';').map(|v| v.to_string()).collect(),
+        );
+    }
+
+    if let Some(subject) = get_text(b"Subject") {
+        insert("description", vec![subject]);
+    }
+
+    if let Some(keywords) = get_text(b"Keywords") {
+        insert(
+            "subject",
+            keywords.split([',', ';']).map(|v| v.to_string()).collect(),
+        );
+    }
+
+    if let Some(date) = get_text(b"CreationDate").and_then(|v| parse_date(&v)) {
+        insert("date", vec![date]);
+    }
+
+    found
+}
+
+/// Reads the XMP Metadata stream from the Catalog. Only the Dublin Core values and a few others are kept.
+pub fn read_xmp_metadata(document: &Document) -> HashMap<String, Vec<String>> {
+    let mut found: HashMap<String, Vec<String>> = HashMap::new();
+
+    let Some(stream) = document
+        .catalog()
+        .ok()
+        .and_then(|v| get_value(document, v, b"Metadata"))
+        .and_then(|v| v.as_stream().ok())
+    else {
+        return found;
+    };
+
+    let Ok(data) = stream.decompressed_content() else {
+        return found;
+    };
+
+    // Name of the property we're currently reading the values of.
+    let mut current: Option<String> = None;
+
+    for event in EventReader::new(data.as_slice()) {
+        let Ok(event) = event else {
+            break;
+        };
+
+        match event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                if current.is_some() {
+                    continue;
+                }
+
+                if let Some(key) = xmp_property_name(&name) {
+                    current = Some(key);
+                } else {
+                    // Simple properties can also be stored as attributes. eg. <rdf:Description xmp:CreateDate="...">
+                    for attr in attributes {
+                        if let Some(key) = xmp_property_name(&attr.name) {
+                            found.entry(key).or_default().push(attr.value);
+                        }
+                    }
+                }
+            }
+
+            XmlEvent::Characters(value) | XmlEvent::CData(value) => {
+                if let Some(key) = current.clone() {
+                    found.entry(key).or_default().push(value);
+                }
+            }
+
+            XmlEvent::EndElement { name }
+                if current.is_some() && current == xmp_property_name(&name) =>
+            {
+                current = None;
+            }
+
+            _ => (),
+        }
+    }
+
+    for values in found.values_mut() {
+        *values = values
+            .iter()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect();
+    }
+
+    found.retain(|_, v| !v.is_empty());
+
+    // Only used if Dublin Core doesn't have the date.
+    if let Some(date) = found.remove("xmp:CreateDate") {
+        found.entry(String::from("date")).or_insert(date);
+    }
+
+    // XMP dates include the time. eg. "2020-03-04T10:00:00Z"
+    if let Some(dates) = found.get_mut("date") {
+        for date in dates {
+            if let Some((day, _)) = date.split_once('T') {
+                *date = day.to_string();
+            }
+        }
+    }
+
+    found
+}
+
+fn xmp_property_name(name: &OwnedName) -> Option<String> {
+    let namespace = name.namespace.as_deref()?;
+
+    if namespace == DC_NAMESPACE {
+        Some(name.local_name.clone())
+    } else if namespace == XMP_NAMESPACE && name.local_name == "CreateDate" {
+        Some(String::from("xmp:CreateDate"))
+    } else if namespace.starts_with(PRISM_NAMESPACE_PREFIX)
+        && matches!(name.local_name.as_str(), "doi" | "isbn")
+    {
+        Some(String::from("identifier"))
+    } else {
+        None
+    }
+}
+
+/// Dereferences the value if it's a reference.
+pub fn get_value<'a>(
+    document: &'a Document,
+    dict: &'a Dictionary,
+    key: &[u8],
+) -> Option<&'a Object> {
+    document.dereference(dict.get(key).ok()?).ok().map(|v| v.1)
+}
+
+/// Text strings are either UTF-16BE with a BOM or PDFDocEncoding. (which is mostly Latin-1)
+pub fn decode_text_string(value: &[u8]) -> String {
+    if let Some(value) = value.strip_prefix(&[0xFE, 0xFF]) {
+        let units = value
+            .chunks_exact(2)
+            .map(|v| u16::from_be_bytes([v[0], v[1]]))
+            .collect::<Vec<_>>();
+
+        String::from_utf16_lossy(&units)
+    } else if let Some(value) = value.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        String::from_utf8_lossy(value).into_owned()
+    } else {
+        value.iter().map(|&v| v as char).collect()
+    }
+}
+
+/// Converts a PDF date "D:YYYYMMDDHHmmSSOHH'mm" into "YYYY-MM-DD". Everything after the year is optional.
+pub fn parse_date(value: &str) -> Option<String> {
+    let value = value.trim().trim_start_matches("D:");
+
+    let digits = value
+        .chars()
+        .take_while(|v| v.is_ascii_digit())
+        .take(8)
+        .collect::<String>();
+
+    match digits.len() {
+        8 => Some(format!(
+            "{}-{}-{}",
+            &digits[..4],
+            &digits[4..6],
+            &digits[6..8]
+        )),
+        6 => Some(format!("{}-{}", &digits[..4], &digits[4..6])),
+        4 => Some(digits),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_strings() {
+        assert_eq!(decode_text_string(b"Caf\xE9"), "Café");
+        assert_eq!(decode_text_string(b"\xFE\xFF\x00H\x00i"), "Hi");
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(
+            parse_date("D:20230115120000+01'00'").as_deref(),
+            Some("2023-01-15")
+        );
+        assert_eq!(parse_date("D:199812").as_deref(), Some("1998-12"));
+        assert_eq!(parse_date("D:19"), None);
+    }
+}
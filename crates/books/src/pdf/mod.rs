@@ -0,0 +1,496 @@
+// For .pdf files
+
+// https://opensource.adobe.com/dc-acrobat-sdk-docs/standards/pdfstandards/pdf/PDF32000_2008.pdf
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use lopdf::{Document, Object, ObjectId, Stream};
+use xml::escape::escape_str_pcdata;
+
+pub mod metadata;
+
+use super::Book;
+use crate::{BookSearch, Error, Result};
+use metadata::*;
+
+const PAGE_PREFIX: &str = "page";
+const IMAGE_PREFIX: &str = "images/";
+
+pub struct PdfBook {
+    path: String,
+    file_name: String,
+
+    document: Document,
+    /// Page Number -> Page Object
+    pages: Vec<(u32, ObjectId)>,
+
+    /// Dublin Core name -> Values. XMP values take priority over the Info Dictionary.
+    metadata: HashMap<String, Vec<String>>,
+
+    chapter: usize,
+}
+
+impl PdfBook {
+    /// Image XObjects which are used directly on the page.
+    fn get_page_images(&self, page_id: ObjectId) -> Vec<(ObjectId, &Stream)> {
+        let Ok((inline, referenced)) = self.document.get_page_resources(page_id) else {
+            return Vec::new();
+        };
+
+        let mut images = Vec::new();
+
+        for resources in inline.into_iter().chain(
+            referenced
+                .into_iter()
+                .filter_map(|id| self.document.get_dictionary(id).ok()),
+        ) {
+            let Ok(xobjects) = self.document.get_dict_in_dict(resources, b"XObject") else {
+                continue;
+            };
+
+            for (_, value) in xobjects.iter() {
+                let Ok(id) = value.as_reference() else {
+                    continue;
+                };
+
+                let Ok(stream) = self.document.get_object(id).and_then(Object::as_stream) else {
+                    continue;
+                };
+
+                if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Image")
+                    && !images.iter().any(|(v, _)| *v == id)
+                {
+                    images.push((id, stream));
+                }
+            }
+        }
+
+        images
+    }
+
+    /// Page images which a browser can display.
+    fn get_displayable_page_images(
+        &self,
+        page_id: ObjectId,
+    ) -> Vec<(ObjectId, &'static str, Vec<u8>)> {
+        self.get_page_images(page_id)
+            .into_iter()
+            .filter_map(|(id, stream)| {
+                let (ext, data) = convert_image(stream)?;
+                Some((id, ext, data))
+            })
+            .collect()
+    }
+
+    /// A scanned page is a single image which covers the whole page.
+    fn is_scanned_page(
+        &self,
+        page_id: ObjectId,
+        images: &[(ObjectId, &'static str, Vec<u8>)],
+    ) -> bool {
+        let [(id, _, _)] = images else {
+            return false;
+        };
+
+        let (Some([width, height]), Ok(stream)) = (
+            self.get_page_size(page_id),
+            self.document.get_object(*id).and_then(Object::as_stream),
+        ) else {
+            return false;
+        };
+
+        let (Ok(image_width), Ok(image_height)) = (
+            stream.dict.get(b"Width").and_then(Object::as_i64),
+            stream.dict.get(b"Height").and_then(Object::as_i64),
+        ) else {
+            return false;
+        };
+
+        if height <= 0.0 || image_height <= 0 {
+            return false;
+        }
+
+        let page_ratio = width / height;
+        let image_ratio = image_width as f32 / image_height as f32;
+
+        (page_ratio - image_ratio).abs() / page_ratio < 0.05
+    }
+
+    /// Width and Height of the MediaBox. It can be inherited from the parent page tree nodes.
+    fn get_page_size(&self, page_id: ObjectId) -> Option<[f32; 2]> {
+        let mut node = self.document.get_dictionary(page_id).ok()?;
+
+        // Guards against a cyclic page tree.
+        for _ in 0..32 {
+            if let Ok(media_box) = node.get(b"MediaBox") {
+                let values = self
+                    .document
+                    .dereference(media_box)
+                    .ok()?
+                    .1
+                    .as_array()
+                    .ok()?
+                    .iter()
+                    .map(|v| v.as_float().ok())
+                    .collect::<Option<Vec<_>>>()?;
+
+                let [x1, y1, x2, y2] = values[..] else {
+                    return None;
+                };
+
+                return Some([(x2 - x1).abs(), (y2 - y1).abs()]);
+            }
+
+            node = self
+                .document
+                .get_dictionary(node.get(b"Parent").and_then(Object::as_reference).ok()?)
+                .ok()?;
+        }
+
+        None
+    }
+
+    fn render_page(&self, index: usize, add_css: Option<&[&str]>) -> Result<Vec<u8>> {
+        let &(page_number, page_id) = self
+            .pages
+            .get(index)
+            .ok_or(Error::MissingValueFor("chapter"))?;
+
+        let images = self.get_displayable_page_images(page_id);
+
+        let mut body = String::new();
+
+        if self.is_scanned_page(page_id, &images) {
+            body += &image_tag(images[0].1, &images[0].2, "width: 100%;");
+        } else {
+            // Fonts with a custom encoding can't always be decoded. We'll still display the images.
+            let text = self
+                .document
+                .extract_text(&[page_number])
+                .unwrap_or_default();
+
+            for line in text.lines().map(|v| v.trim()).filter(|v| !v.is_empty()) {
+                body += "<p>";
+                body += &escape_str_pcdata(line);
+                body += "</p>\n";
+            }
+
+            for (_, ext, data) in &images {
+                body += &image_tag(ext, data, "max-width: 100%;");
+                body += "\n";
+            }
+        }
+
+        // Escaping would break selectors like `a > b`.
+        let all_css = add_css.unwrap_or_default().concat();
+
+        Ok(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+                <head>
+                    <meta charset="UTF-8">
+                    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+                    <title>Page {page_number}</title>
+                    <style>{all_css}</style>
+                </head>
+                <body>
+                    {body}
+                </body>
+            </html>
+        "#
+        )
+        .into_bytes())
+    }
+
+    fn get_cover_image_path(&self) -> Option<String> {
+        let &(_, page_id) = self.pages.first()?;
+
+        // The largest image on the first page.
+        let (id, ext, _) = self
+            .get_displayable_page_images(page_id)
+            .into_iter()
+            .max_by_key(|v| v.2.len())?;
+
+        Some(image_file_name(id, ext))
+    }
+}
+
+impl Book for PdfBook {
+    fn load_from_path(path: &str) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut document = Document::load(path)?;
+
+        // Encrypted PDFs with an empty user password are decrypted when loaded. Others require a password.
+        if document.is_encrypted() && !document.was_encrypted() {
+            document.decrypt("")?;
+        }
+
+        let pages = document.get_pages().into_iter().collect();
+
+        let mut metadata = read_info_dictionary(&document);
+
+        for (name, values) in read_xmp_metadata(&document) {
+            metadata.insert(name, values);
+        }
+
+        Ok(Self {
+            path: path.to_string(),
+            file_name: path
+                .rsplit_once('/')
+                .map(|v| v.1)
+                .unwrap_or(path)
+                .to_string(),
+
+            document,
+            pages,
+            metadata,
+
+            chapter: 0,
+        })
+    }
+
+    fn compute_hash(&mut self) -> Option<String> {
+        let data = std::fs::read(&self.path).ok()?;
+
+        Some(blake3::hash(&data).to_string())
+    }
+
+    fn get_unique_id(&self) -> Result<Cow<str>> {
+        if let Some(id) = self.metadata.get("identifier").and_then(|v| v.first()) {
+            return Ok(Cow::Borrowed(id.as_str()));
+        }
+
+        // The first value of the File Identifier is created with the file and doesn't change.
+        let file_id = self
+            .document
+            .trailer
+            .get(b"ID")
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|v| v.first()?.as_str().ok())
+            .filter(|v| !v.is_empty());
+
+        if let Some(file_id) = file_id {
+            return Ok(Cow::Owned(
+                file_id.iter().map(|v| format!("{v:02x}")).collect(),
+            ));
+        }
+
+        Ok(Cow::Borrowed(self.file_name.as_str()))
+    }
+
+    fn find(&self, search: BookSearch<'_>) -> Option<Vec<String>> {
+        let name = match search {
+            BookSearch::CoverImage => return Some(vec![self.get_cover_image_path()?]),
+
+            BookSearch::Title => "title",
+            BookSearch::Identifier => "identifier",
+            BookSearch::Language => "language",
+            BookSearch::Contributor => "contributor",
+            BookSearch::Coverage => "coverage",
+            BookSearch::Creator => "creator",
+            BookSearch::Date => "date",
+            BookSearch::Description => "description",
+            BookSearch::Format => "format",
+            BookSearch::Publisher => "publisher",
+            BookSearch::Relation => "relation",
+            BookSearch::Rights => "rights",
+            BookSearch::Source => "source",
+            BookSearch::Subject => "subject",
+            BookSearch::Type => "type",
+            BookSearch::Other(v) => v,
+        };
+
+        self.metadata.get(name).cloned().filter(|v| !v.is_empty())
+    }
+
+    fn get_files(&self) -> Vec<String> {
+        (0..self.pages.len()).map(page_file_name).collect()
+    }
+
+    fn get_root_file_dir(&self) -> &Path {
+        Path::new("")
+    }
+
+    fn get_page_path(&self) -> PathBuf {
+        PathBuf::from(page_file_name(self.chapter))
+    }
+
+    fn read_page_raw_as_bytes(&mut self) -> Result<Vec<u8>> {
+        self.render_page(self.chapter, None)
+    }
+
+    fn read_path_as_bytes(
+        &mut self,
+        path: &str,
+        _prepend_to_urls: Option<&str>,
+        add_css: Option<&[&str]>,
+    ) -> Result<Vec<u8>> {
+        let path = path.trim_start_matches('/');
+        let path = path.split_once('#').map(|v| v.0).unwrap_or(path);
+
+        if let Some(image) = path.strip_prefix(IMAGE_PREFIX) {
+            let id = image
+                .split_once('.')
+                .and_then(|(id, _)| id.split_once('_'))
+                .and_then(|(number, generation)| {
+                    Some((number.parse().ok()?, generation.parse().ok()?))
+                })
+                .ok_or(Error::MissingValueFor("image"))?;
+
+            return self
+                .document
+                .get_object(id)
+                .and_then(Object::as_stream)
+                .ok()
+                .and_then(convert_image)
+                .map(|(_, data)| data)
+                .ok_or(Error::MissingValueFor("image"));
+        }
+
+        let index = path
+            .strip_prefix(PAGE_PREFIX)
+            .and_then(|v| v.strip_suffix(".html"))
+            .and_then(|v| v.parse::<usize>().ok())
+            .ok_or(Error::MissingValueFor("chapter"))?;
+
+        self.render_page(index, add_css)
+    }
+
+    fn read_page_as_bytes(
+        &mut self,
+        _prepend_to_urls: Option<&str>,
+        add_css: Option<&[&str]>,
+    ) -> Result<Vec<u8>> {
+        self.render_page(self.chapter, add_css)
+    }
+
+    fn chapter_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    fn set_chapter(&mut self, value: usize) -> bool {
+        if value < self.chapter_count() {
+            self.chapter = value;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next_chapter(&mut self) -> bool {
+        self.set_chapter(self.chapter + 1)
+    }
+
+    fn previous_chapter(&mut self) -> bool {
+        if self.chapter != 0 {
+            self.set_chapter(self.chapter - 1)
+        } else {
+            false
+        }
+    }
+
+    fn get_chapter(&self) -> usize {
+        self.chapter
+    }
+}
+
+fn page_file_name(index: usize) -> String {
+    format!("{PAGE_PREFIX}{index:04}.html")
+}
+
+fn image_file_name((number, generation): ObjectId, ext: &str) -> String {
+    format!("{IMAGE_PREFIX}{number}_{generation}.{ext}")
+}
+
+fn image_tag(ext: &str, data: &[u8], style: &str) -> String {
+    format!(
+        r#"<img src="data:image/{ext};charset=utf-8;base64,{}" style="{style}" alt="Failed to load image" />"#,
+        base64::encode(data)
+    )
+}
+
+/// Converts an Image XObject into a format a browser can display.
+///
+/// JPEGs are stored as-is. Other 8-bit RGB and Grayscale images are encoded as PNGs.
+/// Everything else (JPEG 2000, CCITT, JBIG2, indexed colors, ...) is skipped.
+fn convert_image(stream: &Stream) -> Option<(&'static str, Vec<u8>)> {
+    let filters = stream.filters().unwrap_or_default();
+
+    if filters.last() == Some(&&b"DCTDecode"[..]) {
+        return if filters.len() == 1 {
+            Some(("jpeg", stream.content.clone()))
+        } else {
+            // Only decode the filters which were applied on top of the JPEG.
+            let mut stream = stream.clone();
+            stream.dict.set(
+                "Filter",
+                Object::Array(
+                    filters[..filters.len() - 1]
+                        .iter()
+                        .map(|v| Object::Name(v.to_vec()))
+                        .collect(),
+                ),
+            );
+
+            Some(("jpeg", stream.decompressed_content().ok()?))
+        };
+    }
+
+    let width = stream.dict.get(b"Width").and_then(Object::as_i64).ok()? as u32;
+    let height = stream.dict.get(b"Height").and_then(Object::as_i64).ok()? as u32;
+    let bits = stream
+        .dict
+        .get(b"BitsPerComponent")
+        .and_then(Object::as_i64)
+        .ok()?;
+
+    let color_type = match stream
+        .dict
+        .get(b"ColorSpace")
+        .and_then(Object::as_name)
+        .ok()?
+    {
+        b"DeviceRGB" => png::ColorType::Rgb,
+        b"DeviceGray" => png::ColorType::Grayscale,
+        _ => return None,
+    };
+
+    if bits != 8 {
+        return None;
+    }
+
+    let data = stream.decompressed_content().ok()?;
+
+    let expected_size = width as usize
+        * height as usize
+        * if color_type == png::ColorType::Rgb {
+            3
+        } else {
+            1
+        };
+
+    if data.len() < expected_size {
+        return None;
+    }
+
+    let mut output = Vec::new();
+
+    {
+        let mut encoder = png::Encoder::new(&mut output, width, height);
+        encoder.set_color(color_type);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().ok()?;
+        writer.write_image_data(&data[..expected_size]).ok()?;
+    }
+
+    Some(("png", output))
+}
@@ -1,13 +1,18 @@
 use std::{collections::HashMap, path::PathBuf};
 
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use common::{api::QueryListResponse, BookId, Either, ImageId, MemberId, PersonId, Source};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    filter::FilterContainer, setup::Config, BasicLibrary, BookEdit, Chapter, Collection,
-    DisplayBookItem, DisplayItem, LibraryColl, LibraryId, MediaItem, Member, ModifyValuesBy,
-    Person, Poster, Progression, Result, SearchType,
+    filter::FilterContainer,
+    setup::{Config, ConfigSchedule},
+    ws::TaskId,
+    AgentPriority, ApiToken, ApiTokenScope, BasicLibrary, BookEdit, BookIdentifier, Bookmark,
+    Chapter, Collection, DisplayBookItem, DisplayItem, FileId, HighlightColor, LibraryColl,
+    LibraryId, MediaItem, Member, MetadataField, ModifyValuesBy, Notation, NotationAnchor,
+    Permissions, Person, PersonField, Poster, Progression, Result, SearchType, Series, TaskStatus,
+    TocItem,
 };
 
 // API Routes
@@ -19,6 +24,10 @@ pub type ApiGetFileByIdResponse = Option<self::GetFileByIdResponse>;
 pub type ApiGetFileResourceByIdResponse = String;
 /// GET     /file/{id}/pages/{pages}
 pub type ApiGetFilePagesByIdResponse = self::GetChaptersResponse;
+/// GET     /file/{id}/toc
+pub type ApiGetFileTocByIdResponse = Vec<TocItem>;
+/// GET     /file/{id}/search
+pub type ApiGetFileSearchByIdResponse = Vec<FileSearchMatch>;
 /// GET     /file/{id}/debug/{tail:.*}
 pub type ApiGetFileDebugByIdResponse = String;
 /// POST    /file/{id}/progress
@@ -31,6 +40,22 @@ pub type ApiGetFileNotesByIdResponse = Option<String>;
 pub type ApiPostFileNotesByIdResponse = ();
 /// DELETE  /file/{id}/notes
 pub type ApiDeleteFileNotesByIdResponse = ();
+/// GET     /file/{id}/notations
+pub type ApiGetFileNotationsByIdResponse = Vec<Notation>;
+/// POST    /file/{id}/notations
+pub type ApiPostFileNotationsByIdResponse = Notation;
+/// POST    /file/{id}/notations/{notation_id}
+pub type ApiPostFileNotationByIdResponse = Notation;
+/// DELETE  /file/{id}/notations/{notation_id}
+pub type ApiDeleteFileNotationByIdResponse = ();
+/// GET     /file/{id}/bookmarks
+pub type ApiGetFileBookmarksByIdResponse = Vec<Bookmark>;
+/// POST    /file/{id}/bookmarks
+pub type ApiPostFileBookmarksByIdResponse = Bookmark;
+/// POST    /file/{id}/bookmarks/{bookmark_id}
+pub type ApiPostFileBookmarkByIdResponse = Bookmark;
+/// DELETE  /file/{id}/bookmarks/{bookmark_id}
+pub type ApiDeleteFileBookmarkByIdResponse = ();
 
 // IMAGES
 /// GET     /image/{type}/{id}
@@ -55,12 +80,24 @@ pub type ApiGetCollectionIdBooksResponse = self::GetBookListResponse;
 pub type ApiGetMemberSelfResponse = self::GetMemberSelfResponse;
 /// GET     /members
 pub type ApiGetMembersListResponse = self::GetMembersListResponse;
+/// GET     /member/koreader
+pub type ApiGetKoreaderSyncResponse = self::GetKoreaderSyncResponse;
+/// GET     /member/tokens
+pub type ApiGetApiTokenListResponse = Vec<ApiToken>;
+/// POST    /member/tokens
+pub type ApiPostApiTokenResponse = self::NewApiTokenResponse;
+
+// Auth
+/// GET     /auth/oidc/provider
+pub type ApiGetOidcProviderResponse = Option<self::OidcProvider>;
 
 // Books
 /// GET     /books
 pub type ApiGetBookListResponse = self::GetBookListResponse;
 /// GET     /books/preset
 pub type ApiGetBookPresetListResponse = self::GetBookPresetListResponse;
+/// GET     /books/text
+pub type ApiGetBookTextSearchResponse = Vec<BookTextMatch>;
 /// GET     /book/{id}/posters
 pub type ApiGetPosterByBookIdResponse = self::GetPostersResponse;
 /// POST    /book/{id}/posters
@@ -95,9 +132,23 @@ pub type ApiGetPersonThumbnailResponse = Vec<u8>;
 /// POST    /person/{id}
 pub type ApiPostUpdatePersonResponse = ();
 
+// Series
+/// GET     /series
+pub type ApiGetSeriesListResponse = self::GetSeriesListResponse;
+/// GET     /series/{id}
+pub type ApiGetSeriesByIdResponse = Series;
+/// POST    /series/{id}
+pub type ApiPostUpdateSeriesResponse = ();
+/// GET     /series/{id}/books
+pub type ApiGetSeriesBooksByIdResponse = self::GetBookListResponse;
+
 // Task
 /// POST    /task
 pub type ApiPostRunTaskResponse = ();
+/// DELETE  /task/{id}
+pub type ApiDeleteTaskByIdResponse = ();
+/// GET     /tasks/history
+pub type ApiGetTaskHistoryResponse = Vec<self::TaskHistoryItem>;
 
 // Setup
 /// GET     /setup
@@ -132,6 +183,52 @@ pub struct GetPostersResponse {
     pub items: Vec<Poster>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewNotationBody {
+    pub anchor: NotationAnchor,
+    pub color: HighlightColor,
+    pub text: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct UpdateNotationBody {
+    pub color: Option<HighlightColor>,
+    /// An empty note removes it.
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileSearchQuery {
+    pub query: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FileSearchMatch {
+    pub chapter: usize,
+    /// Counted the same way as the character position of the progression.
+    pub char_pos: usize,
+
+    /// Text surrounding the match.
+    pub before: String,
+    pub text: String,
+    pub after: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewBookmarkBody {
+    pub chapter: usize,
+    pub char_pos: usize,
+    pub label: Option<String>,
+    pub snippet: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateBookmarkBody {
+    /// An empty label removes it.
+    pub label: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ChangePosterBody {
     pub url_or_id: Either<String, ImageId>,
@@ -148,13 +245,59 @@ pub struct GetMemberSelfResponse {
 pub struct GetMembersListResponse {
     pub count: usize,
     pub items: Vec<Member>,
+    /// The libraries each member was given access to.
+    pub libraries: Vec<(MemberId, Vec<LibraryId>)>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum UpdateMember {
-    Delete { id: MemberId },
+    Delete {
+        id: MemberId,
+    },
+
+    Invite {
+        email: String,
+    },
+
+    Permissions {
+        id: MemberId,
+        permissions: Permissions,
+        /// Only used when the member can't see all libraries.
+        libraries: Vec<LibraryId>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetKoreaderSyncResponse {
+    /// Entered as the username in KOReader.
+    pub username: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateKoreaderSync {
+    /// Removes the sync password when empty.
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewApiTokenBody {
+    pub name: String,
+    pub scope: ApiTokenScope,
+    /// Never expires when None.
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewApiTokenResponse {
+    pub token: ApiToken,
+    /// The full token. It isn't stored so it can't be shown again.
+    pub secret: String,
+}
 
-    Invite { email: String },
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OidcProvider {
+    pub display_name: String,
 }
 
 // Collections
@@ -178,6 +321,8 @@ pub struct UpdateLibrary {
 
     pub add_directories: Vec<String>,
     pub remove_directories: Vec<String>,
+
+    pub agent_priority: Option<AgentPriority>,
 }
 
 // Book
@@ -267,6 +412,33 @@ pub struct BookProgression {
     pub file: MediaItem,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BookTextSearchQuery {
+    /// Quoted words are searched as a phrase. A trailing `*` matches the word as a prefix.
+    pub query: String,
+    pub library: Option<LibraryId>,
+
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// A chapter of a book which contains the searched text. Ordered by relevance.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BookTextMatch {
+    pub book: DisplayItem,
+    pub file_id: FileId,
+    pub chapter: usize,
+
+    pub snippet: Vec<SnippetPart>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SnippetPart {
+    pub text: String,
+    /// Whether it's part of the match.
+    pub highlighted: bool,
+}
+
 pub type GetChaptersResponse = QueryListResponse<Chapter>;
 
 // People
@@ -280,6 +452,9 @@ pub enum PostPersonBody {
     UpdateBySource(Source),
 
     CombinePersonWith(PersonId),
+
+    /// Replaces the fields which a refresh won't overwrite.
+    UpdateLockedFields(Vec<PersonField>),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -290,6 +465,17 @@ pub struct GetPeopleSearch {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GetPersonResponse {
     pub person: Person,
+    pub locked_fields: Vec<PersonField>,
+}
+
+// Series
+
+pub type GetSeriesListResponse = QueryListResponse<Series>;
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct UpdateSeriesBody {
+    pub name: Option<String>,
+    pub description: Option<String>,
 }
 
 // Options
@@ -305,6 +491,8 @@ pub struct ModifyOptionsBody {
     pub library: Option<BasicLibrary>,
 
     pub libby_public_search: Option<bool>,
+
+    pub schedule: Option<ConfigSchedule>,
 }
 
 // Metadata
@@ -315,6 +503,9 @@ pub struct GetBookResponse {
     pub media: Vec<MediaItem>,
     pub progress: Vec<Option<Progression>>,
     pub people: Vec<Person>,
+    pub subjects: Vec<String>,
+    pub identifiers: Vec<BookIdentifier>,
+    pub locked_fields: Vec<MetadataField>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -392,6 +583,26 @@ pub struct MetadataBookSearchItem {
 pub struct RunTaskBody {
     pub run_search: Option<LibraryId>,
     pub run_metadata: Option<LibraryId>,
+    /// Folder of a Calibre library. The one containing `metadata.db`.
+    #[serde(default)]
+    pub import_calibre: Option<String>,
+    /// Updates the full-text index of the library.
+    #[serde(default)]
+    pub index_text: Option<LibraryId>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskHistoryItem {
+    pub id: TaskId,
+    pub name: String,
+    pub status: TaskStatus,
+    /// The last progress update.
+    pub progress: Option<String>,
+    pub error: Option<String>,
+
+    pub queued_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize)]
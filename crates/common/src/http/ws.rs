@@ -1,25 +1,37 @@
-use std::{
-    fmt,
-    sync::atomic::{AtomicUsize, Ordering},
-};
+use std::fmt;
 
 use common::BookId;
 use serde::{Deserialize, Serialize};
 
-pub static UNIQUE_ID: AtomicUsize = AtomicUsize::new(0);
+#[cfg(feature = "backend")]
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+    Result,
+};
 
+/// The ID of the task stored in the database.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TaskId(usize);
 
-impl TaskId {
-    pub fn new() -> Self {
-        Self(UNIQUE_ID.fetch_add(1, Ordering::SeqCst))
+impl From<usize> for TaskId {
+    fn from(value: usize) -> Self {
+        Self(value)
+    }
+}
+
+#[cfg(feature = "backend")]
+impl FromSql for TaskId {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Ok(Self(usize::column_result(value)?))
     }
 }
 
-impl Default for TaskId {
-    fn default() -> Self {
-        Self::new()
+#[cfg(feature = "backend")]
+impl ToSql for TaskId {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        self.0.to_sql()
     }
 }
 
@@ -92,4 +104,29 @@ pub enum TaskType {
     },
 
     LibraryScan(String),
+
+    CalibreImport(String),
+
+    IndexText(String),
+
+    RehashFiles(String),
+}
+
+impl fmt::Display for TaskType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UpdatingBook { id, subtitle } => match subtitle {
+                Some(v) => f.write_str(v),
+                None => write!(f, "Updating {id:?}"),
+            },
+
+            Self::LibraryScan(file_name) => f.write_str(file_name),
+
+            Self::CalibreImport(value) => f.write_str(value),
+
+            Self::IndexText(value) => f.write_str(value),
+
+            Self::RehashFiles(value) => f.write_str(value),
+        }
+    }
 }
@@ -93,6 +93,19 @@ pub struct Collection {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Series {
+    pub id: SeriesId,
+
+    pub name: String,
+    pub description: Option<String>,
+
+    pub thumb_url: ThumbnailStore,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 // Used for Library View
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -147,6 +160,14 @@ pub struct DisplayBookItem {
 
     pub available_at: Option<i64>,
     pub year: Option<i64>,
+
+    pub series_id: Option<SeriesId>,
+    /// Position in the series. Can be fractional. (eg. 2.5)
+    pub series_index: Option<f64>,
+
+    pub publisher_id: Option<PublisherId>,
+    pub language: Option<String>,
+    pub page_count: Option<i64>,
 }
 
 impl DisplayBookItem {
@@ -181,6 +202,11 @@ impl Default for DisplayBookItem {
             deleted_at: Default::default(),
             available_at: Default::default(),
             year: Default::default(),
+            series_id: Default::default(),
+            series_index: Default::default(),
+            publisher_id: Default::default(),
+            language: Default::default(),
+            page_count: Default::default(),
         }
     }
 }
@@ -238,6 +264,17 @@ pub struct Chapter {
     pub value: usize,
 }
 
+/// An entry of a book's table of contents.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TocItem {
+    pub title: String,
+    /// `None` if it doesn't point to a chapter.
+    pub chapter: Option<usize>,
+    /// Id of the element inside of the chapter.
+    pub fragment: Option<String>,
+    pub children: Vec<TocItem>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LibraryColl {
     pub id: LibraryId,
@@ -248,6 +285,8 @@ pub struct LibraryColl {
     pub updated_at: i64,
 
     pub directories: Vec<String>,
+
+    pub agent_priority: AgentPriority,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -268,6 +307,10 @@ pub struct BasicDirectory {
 pub struct BookItemCached {
     pub author: Option<String>,
     pub publisher: Option<String>,
+
+    pub series: Option<String>,
+    pub volume: Option<String>,
+    pub issue: Option<String>,
 }
 
 impl BookItemCached {
@@ -292,6 +335,18 @@ impl BookItemCached {
         if value.publisher.is_some() {
             self.publisher = value.publisher;
         }
+
+        if value.series.is_some() {
+            self.series = value.series;
+        }
+
+        if value.volume.is_some() {
+            self.volume = value.volume;
+        }
+
+        if value.issue.is_some() {
+            self.issue = value.issue;
+        }
     }
 
     pub fn author(mut self, value: String) -> Self {
@@ -319,6 +374,30 @@ impl BookItemCached {
 
         self
     }
+
+    pub fn series_optional(mut self, value: Option<String>) -> Self {
+        if value.is_some() {
+            self.series = value;
+        }
+
+        self
+    }
+
+    pub fn volume_optional(mut self, value: Option<String>) -> Self {
+        if value.is_some() {
+            self.volume = value;
+        }
+
+        self
+    }
+
+    pub fn issue_optional(mut self, value: Option<String>) -> Self {
+        if value.is_some() {
+            self.issue = value;
+        }
+
+        self
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
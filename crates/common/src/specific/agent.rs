@@ -0,0 +1,291 @@
+use std::collections::BTreeMap;
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "backend")]
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ToSqlOutput, ValueRef},
+    Result, ToSql,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataAgent {
+    Local,
+    Libby,
+    GoogleBooks,
+    OpenLibrary,
+}
+
+impl MetadataAgent {
+    pub const ALL: [Self; 4] = [
+        Self::Local,
+        Self::Libby,
+        Self::GoogleBooks,
+        Self::OpenLibrary,
+    ];
+
+    /// The prefix used in the "source" column.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Libby => "libby",
+            Self::GoogleBooks => "googlebooks",
+            Self::OpenLibrary => "openlibrary",
+        }
+    }
+
+    pub fn from_prefix(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.prefix() == value)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Local => "Local",
+            Self::Libby => "Libby",
+            Self::GoogleBooks => "Google Books",
+            Self::OpenLibrary => "Open Library",
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    IntoPrimitive,
+    TryFromPrimitive,
+    Serialize,
+    Deserialize,
+)]
+#[repr(u8)]
+pub enum MetadataField {
+    Title = 0,
+    Description = 1,
+    Rating = 2,
+    Cover = 3,
+    Authors = 4,
+    Publisher = 5,
+    Year = 6,
+    AvailableAt = 7,
+    Series = 8,
+    Language = 9,
+    PageCount = 10,
+    Subjects = 11,
+    Identifiers = 12,
+}
+
+impl MetadataField {
+    pub const ALL: [Self; 13] = [
+        Self::Title,
+        Self::Description,
+        Self::Rating,
+        Self::Cover,
+        Self::Authors,
+        Self::Publisher,
+        Self::Year,
+        Self::AvailableAt,
+        Self::Series,
+        Self::Language,
+        Self::PageCount,
+        Self::Subjects,
+        Self::Identifiers,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Title => "Title",
+            Self::Description => "Description",
+            Self::Rating => "Rating",
+            Self::Cover => "Cover",
+            Self::Authors => "Authors",
+            Self::Publisher => "Publisher",
+            Self::Year => "Year",
+            Self::AvailableAt => "Available At",
+            Self::Series => "Series",
+            Self::Language => "Language",
+            Self::PageCount => "Page Count",
+            Self::Subjects => "Subjects",
+            Self::Identifiers => "Identifiers",
+        }
+    }
+}
+
+#[cfg(feature = "backend")]
+impl FromSql for MetadataField {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Ok(Self::try_from(u8::column_result(value)?).unwrap())
+    }
+}
+
+#[cfg(feature = "backend")]
+impl ToSql for MetadataField {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(u8::from(*self)))
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    IntoPrimitive,
+    TryFromPrimitive,
+    Serialize,
+    Deserialize,
+)]
+#[repr(u8)]
+pub enum PersonField {
+    Name = 0,
+    Description = 1,
+    BirthDate = 2,
+    Cover = 3,
+    OtherNames = 4,
+}
+
+impl PersonField {
+    pub const ALL: [Self; 5] = [
+        Self::Name,
+        Self::Description,
+        Self::BirthDate,
+        Self::Cover,
+        Self::OtherNames,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::Description => "Description",
+            Self::BirthDate => "Birth Date",
+            Self::Cover => "Cover",
+            Self::OtherNames => "Other Names",
+        }
+    }
+}
+
+#[cfg(feature = "backend")]
+impl FromSql for PersonField {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Ok(Self::try_from(u8::column_result(value)?).unwrap())
+    }
+}
+
+#[cfg(feature = "backend")]
+impl ToSql for PersonField {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(u8::from(*self)))
+    }
+}
+
+/// Which metadata agents a library uses and in which order they're tried.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentPriority {
+    /// Enabled agents. The first one which finds the book is used as its source.
+    pub agents: Vec<MetadataAgent>,
+
+    /// Overrides the agent order for specific fields. Disabled agents are ignored.
+    #[serde(default)]
+    pub fields: BTreeMap<MetadataField, Vec<MetadataAgent>>,
+}
+
+impl AgentPriority {
+    pub fn only(agent: MetadataAgent) -> Self {
+        Self {
+            agents: vec![agent],
+            fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_enabled(&self, agent: MetadataAgent) -> bool {
+        self.agents.contains(&agent)
+    }
+
+    /// The order agents are picked from when merging the field.
+    pub fn for_field(&self, field: MetadataField) -> Vec<MetadataAgent> {
+        match self.fields.get(&field) {
+            Some(order) => order
+                .iter()
+                .copied()
+                .filter(|v| self.is_enabled(*v))
+                .collect(),
+            None => self.agents.clone(),
+        }
+    }
+}
+
+impl Default for AgentPriority {
+    fn default() -> Self {
+        Self {
+            agents: vec![
+                MetadataAgent::Libby,
+                MetadataAgent::GoogleBooks,
+                MetadataAgent::OpenLibrary,
+                MetadataAgent::Local,
+            ],
+            fields: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_field() {
+        let mut priority = AgentPriority {
+            agents: vec![MetadataAgent::GoogleBooks, MetadataAgent::Local],
+            fields: BTreeMap::new(),
+        };
+
+        priority.fields.insert(
+            MetadataField::Cover,
+            vec![MetadataAgent::OpenLibrary, MetadataAgent::Local],
+        );
+
+        assert_eq!(
+            priority.for_field(MetadataField::Title),
+            vec![MetadataAgent::GoogleBooks, MetadataAgent::Local]
+        );
+        // Open Library isn't enabled.
+        assert_eq!(
+            priority.for_field(MetadataField::Cover),
+            vec![MetadataAgent::Local]
+        );
+    }
+
+    #[test]
+    fn serialize() {
+        let mut priority = AgentPriority::only(MetadataAgent::GoogleBooks);
+
+        priority
+            .fields
+            .insert(MetadataField::Description, vec![MetadataAgent::GoogleBooks]);
+
+        let value = serde_json::to_string(&priority).unwrap();
+
+        assert_eq!(
+            value,
+            r#"{"agents":["googlebooks"],"fields":{"Description":["googlebooks"]}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<AgentPriority>(&value).unwrap(),
+            priority
+        );
+    }
+}
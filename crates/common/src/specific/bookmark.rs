@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::BookmarkId;
+
+/// A saved position in a file. A file can have any number of them, unlike the progression.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub id: BookmarkId,
+
+    pub chapter: usize,
+    /// Counted the same way as the character position of the progression.
+    pub char_pos: usize,
+
+    pub label: Option<String>,
+    /// A bit of the text found at the position.
+    pub snippet: String,
+
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,284 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// How far ahead we look for the next occurrence. Covers a leap day.
+const MAX_DAYS_AHEAD: i64 = 366 * 8;
+
+/// A standard five field cron expression. `minute hour day-of-month month day-of-week`
+///
+/// Fields accept `*`, numbers, ranges (`1-5`), steps (`*/15`, `0-30/10`) and comma separated lists.
+/// Months and days of the week can also be written as names (`jan`, `mon`).
+/// The shortcuts `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` are accepted as well.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    source: String,
+
+    minutes: u64,
+    hours: u32,
+    days_of_month: u32,
+    months: u16,
+    days_of_week: u8,
+
+    // A restricted day of the month OR day of the week has to match. Unless one of them is `*`.
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl CronSchedule {
+    /// The first time after `after` which the schedule matches.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+
+        let mut date = start.date();
+
+        for _ in 0..MAX_DAYS_AHEAD {
+            if self.matches_date(date) {
+                for hour in (0..24).filter(|v| self.hours & (1 << v) != 0) {
+                    for minute in (0..60).filter(|v| self.minutes & (1 << v) != 0) {
+                        let time = date.and_time(NaiveTime::from_hms_opt(hour, minute, 0)?);
+
+                        if time < start {
+                            continue;
+                        }
+
+                        // Skipped if it doesn't exist in the time zone. (eg. daylight savings)
+                        if let Some(value) = tz.from_local_datetime(&time).earliest() {
+                            return Some(value);
+                        }
+                    }
+                }
+            }
+
+            date = date.succ_opt()?;
+        }
+
+        None
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+
+        let day_of_month = self.days_of_month & (1 << date.day()) != 0;
+        let day_of_week = self.days_of_week & (1 << date.weekday().num_days_from_sunday()) != 0;
+
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (true, false) => day_of_week,
+            (false, true) => day_of_month,
+            (false, false) => day_of_month || day_of_week,
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = CronError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let source = value.trim();
+
+        let expanded = match source {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            v if v.starts_with('@') => return Err(CronError::UnknownShortcut(v.to_string())),
+            v => v,
+        };
+
+        let fields = expanded.split_whitespace().collect::<Vec<_>>();
+
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(CronError::FieldCount(fields.len()));
+        };
+
+        Ok(Self {
+            source: source.to_string(),
+
+            minutes: parse_field(minutes, 0, 59, &[])?,
+            hours: parse_field(hours, 0, 23, &[])? as u32,
+            days_of_month: parse_field(days_of_month, 1, 31, &[])? as u32,
+            months: parse_field(months, 1, 12, &MONTH_NAMES)? as u16,
+            // Sunday can be either 0 or 7.
+            days_of_week: {
+                let value = parse_field(days_of_week, 0, 7, &DAY_NAMES)?;
+                ((value | (value >> 7)) & 0x7F) as u8
+            },
+
+            any_day_of_month: days_of_month.starts_with('*'),
+            any_day_of_week: days_of_week.starts_with('*'),
+        })
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = CronError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(value: CronSchedule) -> Self {
+        value.source
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CronError {
+    #[error("Expected 5 fields, found {0}")]
+    FieldCount(usize),
+
+    #[error("Unknown shortcut {0}")]
+    UnknownShortcut(String),
+
+    #[error("Invalid value \"{0}\"")]
+    InvalidValue(String),
+
+    #[error("{value} is outside of {min}-{max}")]
+    OutOfRange { value: u32, min: u32, max: u32 },
+}
+
+/// Returns a bit set with every matching value.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, CronError> {
+    let mut bits = 0;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, parse_value(step, 1, max.max(1), &[], 0)?),
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, min, max, names, min)?,
+                parse_value(end, min, max, names, min)?,
+            )
+        } else {
+            let start = parse_value(range, min, max, names, min)?;
+
+            // "5/15" is the same as "5-59/15"
+            (start, if step == 1 { start } else { max })
+        };
+
+        if start > end {
+            return Err(CronError::InvalidValue(part.to_string()));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+fn parse_value(
+    value: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+    names_offset: u32,
+) -> Result<u32, CronError> {
+    let lowercase = value.to_lowercase();
+
+    let value = match names.iter().position(|v| *v == lowercase) {
+        Some(index) => index as u32 + names_offset,
+        None => value
+            .parse()
+            .map_err(|_| CronError::InvalidValue(value.to_string()))?,
+    };
+
+    if value < min || value > max {
+        return Err(CronError::OutOfRange { value, min, max });
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Utc;
+
+    fn next(cron: &str, after: &str) -> String {
+        let after = DateTime::parse_from_rfc3339(after)
+            .unwrap()
+            .with_timezone(&Utc);
+
+        cron.parse::<CronSchedule>()
+            .unwrap()
+            .next_after(&after)
+            .unwrap()
+            .to_rfc3339()
+    }
+
+    #[test]
+    fn next_occurrence() {
+        assert_eq!(
+            next("0 3 * * *", "2023-01-01T02:59:00Z"),
+            "2023-01-01T03:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 3 * * *", "2023-01-01T03:00:00Z"),
+            "2023-01-02T03:00:00+00:00"
+        );
+        assert_eq!(
+            next("*/15 * * * *", "2023-01-01T10:16:30Z"),
+            "2023-01-01T10:30:00+00:00"
+        );
+        assert_eq!(
+            next("0 4 * * sun", "2023-01-02T00:00:00Z"),
+            "2023-01-08T04:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 0 29 feb *", "2023-03-01T00:00:00Z"),
+            "2024-02-29T00:00:00+00:00"
+        );
+        assert_eq!(
+            next("@monthly", "2023-01-15T00:00:00Z"),
+            "2023-02-01T00:00:00+00:00"
+        );
+        // Either the 15th or a Monday.
+        assert_eq!(
+            next("0 0 15 * 1", "2023-01-10T00:00:00Z"),
+            "2023-01-15T00:00:00+00:00"
+        );
+        assert_eq!(
+            next("0 0 15 * 1", "2023-01-15T00:00:00Z"),
+            "2023-01-16T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("* * * *".parse::<CronSchedule>().is_err());
+        assert!("60 * * * *".parse::<CronSchedule>().is_err());
+        assert!("* * 0 * *".parse::<CronSchedule>().is_err());
+        assert!("5-1 * * * *".parse::<CronSchedule>().is_err());
+        assert!("* * * * abc".parse::<CronSchedule>().is_err());
+        assert!("@sometimes".parse::<CronSchedule>().is_err());
+    }
+}
@@ -158,6 +158,8 @@ mod book_edit {
     use common::PersonId;
     use serde::{Deserialize, Serialize};
 
+    use crate::{BookIdentifier, MetadataField};
+
     #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
     pub struct BookEdit {
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -177,11 +179,29 @@ mod book_edit {
 
         #[serde(skip_serializing_if = "Option::is_none")]
         pub publisher: Option<Option<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub language: Option<Option<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub page_count: Option<Option<i64>>,
 
         #[serde(skip_serializing_if = "Option::is_none")]
         pub added_people: Option<Vec<PersonId>>,
         #[serde(skip_serializing_if = "Option::is_none")]
         pub removed_people: Option<Vec<PersonId>>,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub added_subjects: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub removed_subjects: Option<Vec<String>>,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub added_identifiers: Option<Vec<BookIdentifier>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub removed_identifiers: Option<Vec<BookIdentifier>>,
+
+        /// Replaces the locked fields. Edited fields are always locked.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub locked_fields: Option<Vec<MetadataField>>,
     }
 
     impl BookEdit {
@@ -193,8 +213,15 @@ mod book_edit {
                 && self.available_at.is_none()
                 && self.year.is_none()
                 && self.publisher.is_none()
+                && self.language.is_none()
+                && self.page_count.is_none()
                 && self.added_people.is_none()
                 && self.removed_people.is_none()
+                && self.added_subjects.is_none()
+                && self.removed_subjects.is_none()
+                && self.added_identifiers.is_none()
+                && self.removed_identifiers.is_none()
+                && self.locked_fields.is_none()
         }
 
         pub fn insert_added_person(&mut self, value: PersonId) {
@@ -1,7 +1,7 @@
 use common::PersonId;
 use serde::{Deserialize, Serialize};
 
-use crate::Result;
+use crate::{PublisherId, Result, SeriesId};
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct FilterContainer {
@@ -39,6 +39,46 @@ impl FilterContainer {
         ))
     }
 
+    pub fn add_series_filter(&mut self, id: SeriesId) {
+        self.filters.push(FilterOperator::new(
+            FilterTableType::Series,
+            FilterModifier::Equal,
+            FilterValue::Value(id.to_string()),
+        ))
+    }
+
+    pub fn add_publisher_filter(&mut self, id: PublisherId) {
+        self.filters.push(FilterOperator::new(
+            FilterTableType::Publisher,
+            FilterModifier::Equal,
+            FilterValue::Value(id.to_string()),
+        ))
+    }
+
+    pub fn add_language_filter(&mut self, value: String) {
+        self.filters.push(FilterOperator::new(
+            FilterTableType::Language,
+            FilterModifier::Equal,
+            FilterValue::Value(value),
+        ))
+    }
+
+    pub fn add_subject_filter(&mut self, value: String) {
+        self.filters.push(FilterOperator::new(
+            FilterTableType::Subject,
+            FilterModifier::Equal,
+            FilterValue::Value(value),
+        ))
+    }
+
+    pub fn add_identifier_filter(&mut self, value: String) {
+        self.filters.push(FilterOperator::new(
+            FilterTableType::Identifier,
+            FilterModifier::Equal,
+            FilterValue::Value(value),
+        ))
+    }
+
     pub fn add_query_filter(&mut self, value: String) {
         self.filters.push(FilterOperator::new(
             FilterTableType::Query,
@@ -105,6 +145,15 @@ pub enum FilterTableType {
     //
     Query,
     Person,
+    /// Ordering by it groups the books by series and sorts them by their position.
+    Series,
+    Publisher,
+    /// Language code. (eg. "en")
+    Language,
+    /// Subject name. Case insensitive.
+    Subject,
+    /// Any identifier value. (eg. ISBN, ASIN)
+    Identifier,
 
     CreatedAt,
 }
@@ -18,3 +18,9 @@ use common::create_single_id;
 create_single_id!(FileId);
 create_single_id!(LibraryId);
 create_single_id!(CollectionId);
+create_single_id!(SeriesId);
+create_single_id!(PublisherId);
+create_single_id!(SubjectId);
+create_single_id!(ApiTokenId);
+create_single_id!(NotationId);
+create_single_id!(BookmarkId);
@@ -0,0 +1,142 @@
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "backend")]
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ToSqlOutput, ValueRef},
+    Result, ToSql,
+};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive, Serialize, Deserialize,
+)]
+#[repr(u8)]
+pub enum IdentifierType {
+    Isbn10 = 0,
+    Isbn13 = 1,
+    Asin = 2,
+    Oclc = 3,
+}
+
+impl IdentifierType {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Isbn10 => "ISBN-10",
+            Self::Isbn13 => "ISBN-13",
+            Self::Asin => "ASIN",
+            Self::Oclc => "OCLC",
+        }
+    }
+}
+
+#[cfg(feature = "backend")]
+impl FromSql for IdentifierType {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Ok(Self::try_from(u8::column_result(value)?).unwrap())
+    }
+}
+
+#[cfg(feature = "backend")]
+impl ToSql for IdentifierType {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(u8::from(*self)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BookIdentifier {
+    pub type_of: IdentifierType,
+    pub value: String,
+}
+
+impl BookIdentifier {
+    pub fn new(type_of: IdentifierType, value: String) -> Self {
+        Self { type_of, value }
+    }
+
+    /// Works out the type from the value. (eg. "urn:isbn:978-0-7653-2635-5", "oclc:123456", "B00ABC1234")
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let lowercase = value.to_lowercase();
+
+        for prefix in ["urn:oclc:", "oclc:", "(ocolc)"] {
+            if let Some(number) = lowercase.strip_prefix(prefix) {
+                let number = number.trim();
+
+                return (!number.is_empty() && number.chars().all(|v| v.is_ascii_digit()))
+                    .then(|| Self::new(IdentifierType::Oclc, number.to_string()));
+            }
+        }
+
+        let value = ["urn:isbn:", "isbn:", "asin:", "urn:asin:"]
+            .into_iter()
+            .find_map(|prefix| lowercase.strip_prefix(prefix))
+            .unwrap_or(&lowercase)
+            .replace(['-', ' '], "")
+            .to_uppercase();
+
+        if !value.is_ascii() {
+            return None;
+        }
+
+        if value.len() == 13
+            && (value.starts_with("978") || value.starts_with("979"))
+            && value.chars().all(|v| v.is_ascii_digit())
+        {
+            Some(Self::new(IdentifierType::Isbn13, value))
+        } else if value.len() == 10
+            && value[..9].chars().all(|v| v.is_ascii_digit())
+            && value[9..].chars().all(|v| v.is_ascii_digit() || v == 'X')
+        {
+            Some(Self::new(IdentifierType::Isbn10, value))
+        } else if value.len() == 10
+            && value.starts_with('B')
+            && value.chars().all(|v| v.is_ascii_alphanumeric())
+        {
+            Some(Self::new(IdentifierType::Asin, value))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            BookIdentifier::parse("urn:isbn:978-0-7653-2635-5"),
+            Some(BookIdentifier::new(
+                IdentifierType::Isbn13,
+                String::from("9780765326355")
+            ))
+        );
+        assert_eq!(
+            BookIdentifier::parse("0-306-40615-x"),
+            Some(BookIdentifier::new(
+                IdentifierType::Isbn10,
+                String::from("030640615X")
+            ))
+        );
+        assert_eq!(
+            BookIdentifier::parse("B00ABC1234"),
+            Some(BookIdentifier::new(
+                IdentifierType::Asin,
+                String::from("B00ABC1234")
+            ))
+        );
+        assert_eq!(
+            BookIdentifier::parse("oclc:123456"),
+            Some(BookIdentifier::new(
+                IdentifierType::Oclc,
+                String::from("123456")
+            ))
+        );
+        assert_eq!(BookIdentifier::parse("urn:uuid:1234"), None);
+        assert_eq!(BookIdentifier::parse("12345"), None);
+    }
+}
@@ -7,17 +7,29 @@ use rusqlite::{
     Result, ToSql,
 };
 
+mod agent;
+mod bookmark;
+mod cron;
 mod edit;
 pub mod filter;
 mod id;
+mod identifier;
+mod notation;
 mod perms;
 pub mod setup;
 mod preferences;
+mod token;
 
+pub use agent::*;
+pub use bookmark::*;
+pub use cron::*;
 pub use edit::*;
 pub use id::*;
+pub use identifier::*;
+pub use notation::*;
 pub use perms::*;
 pub use preferences::*;
+pub use token::*;
 
 // TODO: Place this into own file.
 
@@ -38,6 +50,40 @@ impl MemberAuthType {
     }
 }
 
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive, Serialize, Deserialize,
+)]
+#[repr(u8)]
+pub enum TaskStatus {
+    Queued = 0,
+    Running = 1,
+    Finished = 2,
+    Failed = 3,
+    Cancelled = 4,
+}
+
+impl TaskStatus {
+    pub fn is_done(self) -> bool {
+        matches!(self, Self::Finished | Self::Failed | Self::Cancelled)
+    }
+}
+
+#[cfg(feature = "backend")]
+impl FromSql for TaskStatus {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Ok(Self::try_from(u8::column_result(value)?).unwrap())
+    }
+}
+
+#[cfg(feature = "backend")]
+impl ToSql for TaskStatus {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(u8::from(*self)))
+    }
+}
+
 #[cfg(feature = "backend")]
 impl FromSql for MemberAuthType {
     #[inline]
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::NotationId;
+
+/// Stored alongside the notations of a file. Increase it when their format changes.
+pub const NOTATION_VERSION: i64 = 1;
+
+/// A highlight in a file, with an optional note.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notation {
+    pub id: NotationId,
+
+    pub anchor: NotationAnchor,
+    pub color: HighlightColor,
+
+    /// The highlighted text.
+    pub text: String,
+    pub note: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Where a highlight starts and ends inside of a chapter.
+///
+/// Positions are counted the same way as the character position of the progression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NotationAnchor {
+    pub chapter: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HighlightColor {
+    #[default]
+    Yellow,
+    Green,
+    Blue,
+    Pink,
+}
+
+impl HighlightColor {
+    pub fn get_all() -> [Self; 4] {
+        [Self::Yellow, Self::Green, Self::Blue, Self::Pink]
+    }
+
+    /// Also used as the class name of the highlight.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Yellow => "yellow",
+            Self::Green => "green",
+            Self::Blue => "blue",
+            Self::Pink => "pink",
+        }
+    }
+}
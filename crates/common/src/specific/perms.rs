@@ -12,6 +12,31 @@ bitflags! {
     pub struct GroupPermissions: u64 {
         const OWNER             = 1 << 0;
         const BASIC             = 1 << 1;
+
+        const EDIT_METADATA     = 1 << 2;
+        const UPLOAD            = 1 << 3;
+        const DELETE_FILES      = 1 << 4;
+        const RUN_TASKS         = 1 << 5;
+        const MANAGE_MEMBERS    = 1 << 6;
+        const DOWNLOAD          = 1 << 7;
+
+        /// Otherwise only the libraries the member was given access to are visible.
+        const ALL_LIBRARIES     = 1 << 8;
+    }
+}
+
+impl GroupPermissions {
+    /// Everything which can be given to a member besides being an owner.
+    pub fn get_capabilities() -> [(Self, &'static str); 7] {
+        [
+            (Self::EDIT_METADATA, "Edit Metadata"),
+            (Self::UPLOAD, "Upload"),
+            (Self::DELETE_FILES, "Delete Files"),
+            (Self::RUN_TASKS, "Run Tasks"),
+            (Self::MANAGE_MEMBERS, "Manage Members"),
+            (Self::DOWNLOAD, "Download"),
+            (Self::ALL_LIBRARIES, "All Libraries"),
+        ]
     }
 }
 
@@ -30,7 +55,9 @@ impl Permissions {
 
     pub fn basic() -> Self {
         Self {
-            group: GroupPermissions::BASIC,
+            group: GroupPermissions::BASIC
+                | GroupPermissions::DOWNLOAD
+                | GroupPermissions::ALL_LIBRARIES,
         }
     }
 
@@ -59,6 +86,11 @@ impl Permissions {
     pub fn is_basic(self) -> bool {
         self.contains_group(GroupPermissions::BASIC)
     }
+
+    /// Owners can do everything.
+    pub fn has(self, value: GroupPermissions) -> bool {
+        self.is_owner() || self.contains_group(value)
+    }
 }
 
 #[cfg(feature = "backend")]
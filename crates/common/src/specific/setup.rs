@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::{LibraryId, Permissions};
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize, Validate)]
 pub struct SetupConfig {
     #[validate]
@@ -26,6 +28,10 @@ pub struct Config {
     pub libby: LibraryConnection,
     pub email: Option<ConfigEmail>,
     pub authenticators: Authenticators,
+    #[serde(default)]
+    pub scanner: ConfigScanner,
+    #[serde(default)]
+    pub schedules: Vec<ConfigSchedule>,
 
     pub has_admin_account: bool,
 }
@@ -94,6 +100,8 @@ pub struct Authenticators {
     pub email_pass: bool,
     pub email_no_pass: bool,
     pub main_server: bool,
+    #[serde(default)]
+    pub oidc: Option<ConfigOidc>,
 }
 
 impl Default for Authenticators {
@@ -102,6 +110,97 @@ impl Default for Authenticators {
             email_pass: true,
             email_no_pass: false,
             main_server: false,
+            oidc: None,
+        }
+    }
+}
+
+/// A generic OpenID Connect provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigOidc {
+    /// Shown on the login button.
+    pub display_name: String,
+    /// The discovery document is loaded from "{issuer}/.well-known/openid-configuration".
+    pub issuer: String,
+    pub client_id: String,
+    /// Not needed for public clients. PKCE is always used.
+    pub client_secret: Option<String>,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub claims: ConfigOidcClaims,
+    /// Given to members created on their first login.
+    #[serde(default = "Permissions::basic")]
+    pub default_permissions: Permissions,
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+    vec![
+        String::from("openid"),
+        String::from("email"),
+        String::from("profile"),
+    ]
+}
+
+/// Which claims of the ID Token or UserInfo are used for the member.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigOidcClaims {
+    /// Members are matched by their email.
+    pub email: String,
+    pub name: String,
+}
+
+impl Default for ConfigOidcClaims {
+    fn default() -> Self {
+        Self {
+            email: String::from("email"),
+            name: String::from("name"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigScanner {
+    /// How many files are matched to their metadata at once.
+    pub workers: usize,
+    /// Keeps a full-text index of the EPUB chapters. It's updated after the library is scanned.
+    #[serde(default)]
+    pub index_text: bool,
+}
+
+impl Default for ConfigScanner {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            index_text: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigSchedule {
+    /// Cron expression. See [`CronSchedule`](crate::CronSchedule).
+    pub cron: String,
+    pub task: ScheduledTask,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ScheduledTask {
+    /// Scans the library, or every library if none is set.
+    LibraryScan { library_id: Option<LibraryId> },
+    /// Refreshes the metadata of books which weren't updated in a while.
+    RefreshMetadata { library_id: Option<LibraryId> },
+    /// Deletes stored images which nothing uses anymore.
+    CleanOrphanImages,
+}
+
+impl ScheduledTask {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::LibraryScan { .. } => "Library Scan",
+            Self::RefreshMetadata { .. } => "Refresh Metadata",
+            Self::CleanOrphanImages => "Clean Orphan Images",
         }
     }
 }
@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "backend")]
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ToSqlOutput, ValueRef},
+    Result, ToSql,
+};
+
+use crate::{ApiTokenId, GroupPermissions};
+
+/// What a personal access token can do. Never more than the member it belongs to.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive, Serialize, Deserialize,
+)]
+#[repr(u8)]
+pub enum ApiTokenScope {
+    /// Only GET requests.
+    Read = 0,
+    /// Everything except what requires the owner or managing members.
+    ReadWrite = 1,
+    Admin = 2,
+}
+
+impl ApiTokenScope {
+    pub fn get_all() -> [Self; 3] {
+        [Self::Read, Self::ReadWrite, Self::Admin]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Read => "Read Only",
+            Self::ReadWrite => "Read & Write",
+            Self::Admin => "Admin",
+        }
+    }
+
+    pub fn is_admin(self) -> bool {
+        self == Self::Admin
+    }
+
+    /// The member's permissions which the token keeps.
+    pub fn permissions(self) -> GroupPermissions {
+        match self {
+            Self::Read => {
+                GroupPermissions::BASIC
+                    | GroupPermissions::DOWNLOAD
+                    | GroupPermissions::ALL_LIBRARIES
+            }
+
+            Self::ReadWrite => {
+                GroupPermissions::all() - GroupPermissions::OWNER - GroupPermissions::MANAGE_MEMBERS
+            }
+
+            Self::Admin => GroupPermissions::all(),
+        }
+    }
+}
+
+#[cfg(feature = "backend")]
+impl FromSql for ApiTokenScope {
+    #[inline]
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Ok(Self::try_from(u8::column_result(value)?).unwrap())
+    }
+}
+
+#[cfg(feature = "backend")]
+impl ToSql for ApiTokenScope {
+    #[inline]
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(u8::from(*self)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: ApiTokenId,
+
+    pub name: String,
+    /// The start of the token. Only shown in full once it's created.
+    pub prefix: String,
+    pub scope: ApiTokenScope,
+
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
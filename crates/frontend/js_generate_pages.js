@@ -311,6 +311,24 @@ export function js_get_element_from_byte_position(iframe, position) {
 	return findTextPos(document.body);
 }
 
+/**
+ * @param {HTMLIFrameElement} iframe
+ * @param {string} id
+ * @returns {number | null}
+**/
+export function js_get_page_from_element_id(iframe, id) {
+	let document = iframe.contentDocument;
+	let element = document && document.getElementById(id);
+
+	if (element == null) {
+		return null;
+	}
+
+	// Same as js_get_page_from_byte_position.
+	// TODO: Account for margins on body.
+	return Math.abs(Math.round((element.offsetLeft + element.offsetWidth) / document.body.offsetWidth));
+}
+
 /**
  * @param {HTMLIFrameElement} iframe
  * @param {boolean} is_vscroll
@@ -333,4 +351,206 @@ export function js_get_visible_links(iframe, is_vscroll) {
 	}
 
 	return rendering;
-}
\ No newline at end of file
+}
+
+// Notations
+// Positions are counted like js_get_current_byte_pos. Text nodes which are only whitespace are skipped.
+
+const HIGHLIGHT_COLORS = {
+	yellow: 'rgba(255, 221, 0, 0.4)',
+	green: 'rgba(0, 200, 83, 0.4)',
+	blue: 'rgba(41, 121, 255, 0.4)',
+	pink: 'rgba(255, 64, 129, 0.4)',
+};
+
+/**
+ * @param {Document} document
+ * @returns {Text[]}
+**/
+function getCountedTextNodes(document) {
+	let walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT);
+	let nodes = [];
+
+	while (walker.nextNode()) {
+		if (walker.currentNode.nodeValue.trim().length != 0) {
+			nodes.push(walker.currentNode);
+		}
+	}
+
+	return nodes;
+}
+
+/**
+ * @param {Document} document
+ * @param {Node} container
+ * @param {number} offset
+ * @returns {number}
+**/
+function getTextPosition(document, container, offset) {
+	let boundary = document.createRange();
+	boundary.setStart(container, offset);
+
+	let byte_count = 0;
+
+	for (let node of getCountedTextNodes(document)) {
+		if (node === container) {
+			return byte_count + offset;
+		}
+
+		// The node starts after the boundary.
+		if (boundary.comparePoint(node, 0) > 0) {
+			break;
+		}
+
+		byte_count += node.nodeValue.length;
+	}
+
+	return byte_count;
+}
+
+/**
+ * @param {HTMLIFrameElement} iframe
+ * @returns {{ start: number, end: number, text: string } | null}
+**/
+export function js_get_selection_position(iframe) {
+	let document = iframe.contentDocument;
+	let selection = document && document.getSelection();
+
+	if (selection == null || selection.isCollapsed || selection.rangeCount == 0) {
+		return null;
+	}
+
+	let range = selection.getRangeAt(0);
+	let text = range.toString();
+
+	// Don't include surrounding whitespace in the highlight.
+	let leading = text.length - text.trimStart().length;
+	let trailing = text.length - text.trimEnd().length;
+
+	let start = getTextPosition(document, range.startContainer, range.startOffset) + leading;
+	let end = getTextPosition(document, range.endContainer, range.endOffset) - trailing;
+
+	if (start >= end) {
+		return null;
+	}
+
+	return { start, end, text: text.trim() };
+}
+
+/**
+ * @param {HTMLIFrameElement} iframe
+**/
+export function js_clear_selection(iframe) {
+	let document = iframe.contentDocument;
+
+	if (document != null) {
+		document.getSelection().removeAllRanges();
+	}
+}
+
+/**
+ * Uses the CSS Custom Highlight API so the text nodes (and positions) stay the same.
+ *
+ * @param {HTMLIFrameElement} iframe
+ * @param {{ anchor: { start: number, end: number }, color: string }[]} notations
+**/
+export function js_render_notations(iframe, notations) {
+	let document = iframe.contentDocument;
+	let window = iframe.contentWindow;
+
+	if (document == null || document.body == null || window.CSS == null || window.CSS.highlights == null) {
+		return;
+	}
+
+	if (document.getElementById('notation-styles') == null) {
+		let style = document.createElement('style');
+		style.id = 'notation-styles';
+		style.textContent = Object.entries(HIGHLIGHT_COLORS)
+			.map(([name, color]) => `::highlight(notation-${name}) { background-color: ${color}; }`)
+			.join('\n');
+
+		document.head.appendChild(style);
+	}
+
+	let nodes = getCountedTextNodes(document);
+
+	/**
+	 * @param {number} position
+	 * @returns {[Text, number] | null}
+	**/
+	function findNode(position) {
+		let byte_count = 0;
+
+		for (let node of nodes) {
+			if (byte_count + node.nodeValue.length >= position) {
+				return [node, position - byte_count];
+			}
+
+			byte_count += node.nodeValue.length;
+		}
+
+		return null;
+	}
+
+	let highlights = {};
+
+	for (let name of Object.keys(HIGHLIGHT_COLORS)) {
+		highlights[name] = new window.Highlight();
+	}
+
+	for (let notation of notations) {
+		let start = findNode(notation.anchor.start);
+		let end = findNode(notation.anchor.end);
+		let highlight = highlights[notation.color.toLowerCase()];
+
+		if (start == null || end == null || highlight == null) {
+			continue;
+		}
+
+		let range = document.createRange();
+		range.setStart(start[0], start[1]);
+		range.setEnd(end[0], end[1]);
+
+		highlight.add(range);
+	}
+
+	for (let [name, highlight] of Object.entries(highlights)) {
+		window.CSS.highlights.set(`notation-${name}`, highlight);
+	}
+}
+
+
+// Bookmarks
+
+/**
+ * @param {HTMLIFrameElement} iframe
+ * @param {number} position
+ * @param {number} length
+ * @returns {string}
+**/
+export function js_get_text_at_byte_position(iframe, position, length) {
+	let document = iframe.contentDocument;
+
+	if (document == null || document.body == null) {
+		return '';
+	}
+
+	let byte_count = 0;
+	let text = '';
+
+	for (let node of getCountedTextNodes(document)) {
+		let node_length = node.nodeValue.length;
+
+		if (byte_count + node_length > position) {
+			text += node.nodeValue.slice(Math.max(position - byte_count, 0));
+
+			if (text.length >= length) {
+				break;
+			}
+		}
+
+		byte_count += node_length;
+	}
+
+	return text.replace(/\s+/g, ' ').trim().slice(0, length);
+}
@@ -4,7 +4,9 @@ use common::{
     component::{Popup, PopupClose, PopupType},
     BookId, Either,
 };
-use common_local::{api, CollectionId, DisplayItem, MediaItem, Progression, ThumbnailStoreExt};
+use common_local::{
+    api, CollectionId, DisplayItem, GroupPermissions, MediaItem, Progression, ThumbnailStoreExt,
+};
 use web_sys::{HtmlElement, HtmlInputElement, MouseEvent};
 use yew::{
     classes, function_component, html, use_context, Callback, Component, Context, Html, Properties,
@@ -363,7 +365,7 @@ impl BookPosterItem {
                 {
                     if let Some(editing_items) = editing_items.as_ref() {
                         html! {
-                            <OwnerBarrier>
+                            <OwnerBarrier permission={ GroupPermissions::EDIT_METADATA }>
                                 <div class="top-left">
                                     <input
                                         class="form-check-input"
@@ -395,7 +397,7 @@ impl BookPosterItem {
                     <span class="material-icons" onclick={ on_click_more } title="More Options">{ "more_horiz" }</span>
                 </div>
 
-                <OwnerBarrier>
+                <OwnerBarrier permission={ GroupPermissions::EDIT_METADATA }>
                     <div class="bottom-left">
                         <span class="material-icons" onclick={ on_click_edit } title="More Options">{ "edit" }</span>
                     </div>
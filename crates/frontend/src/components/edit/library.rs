@@ -1,5 +1,5 @@
 use common::component::PopupClose;
-use common_local::{api::UpdateLibrary, LibraryId};
+use common_local::{api::UpdateLibrary, AgentPriority, LibraryId, MetadataAgent};
 use web_sys::{Event, HtmlInputElement};
 use yew::prelude::*;
 use yew_hooks::{use_async, use_async_with_options, UseAsyncOptions};
@@ -93,6 +93,17 @@ pub fn _lib_edit(prop: &LibraryEditProperty) -> Html {
         })
     };
 
+    let on_change_agents = {
+        let library_update = library_update.clone();
+        let update = update.clone();
+
+        Callback::from(move |value: AgentPriority| {
+            library_update.borrow_mut().agent_priority = Some(value);
+
+            update();
+        })
+    };
+
     let library_update2 = library_update.clone();
 
     let func = use_async(async move {
@@ -196,6 +207,11 @@ pub fn _lib_edit(prop: &LibraryEditProperty) -> Html {
                                 </div>
 
                                 <NewLibraryDirectory id={ library.id } callback={ on_add_directory } />
+
+                                <AgentPriorityEdit
+                                    priority={ library_update.borrow().agent_priority.clone().unwrap_or_else(|| library.agent_priority.clone()) }
+                                    callback={ on_change_agents }
+                                />
                             </>
                         }
                     } else {
@@ -259,3 +275,87 @@ fn new_library_dir(props: &NewLibraryDirectoryProps) -> Html {
         </>
     }
 }
+
+#[derive(Properties, PartialEq)]
+struct AgentPriorityEditProps {
+    pub priority: AgentPriority,
+
+    pub callback: Callback<AgentPriority>,
+}
+
+/// Enabled agents are listed first in the order they're tried.
+#[function_component(AgentPriorityEdit)]
+fn agent_priority_edit(props: &AgentPriorityEditProps) -> Html {
+    let disabled = MetadataAgent::ALL
+        .into_iter()
+        .filter(|v| !props.priority.is_enabled(*v));
+
+    html! {
+        <div class="mb-3">
+            <h5>{ "Metadata Agents:" }</h5>
+
+            <ul>
+                {
+                    for props.priority.agents.iter().copied().enumerate().map(|(index, agent)| {
+                        let on_toggle = {
+                            let priority = props.priority.clone();
+
+                            props.callback.reform(move |_| {
+                                let mut priority = priority.clone();
+                                priority.agents.retain(|v| *v != agent);
+                                priority
+                            })
+                        };
+
+                        let on_move_up = {
+                            let priority = props.priority.clone();
+
+                            props.callback.reform(move |_| {
+                                let mut priority = priority.clone();
+                                priority.agents.swap(index - 1, index);
+                                priority
+                            })
+                        };
+
+                        html! {
+                            <li>
+                                <input type="checkbox" checked=true onchange={ on_toggle } />
+                                { " " }{ agent.name() }{ " " }
+                                {
+                                    if index != 0 {
+                                        html! {
+                                            <button class="btn btn-secondary btn-sm" onclick={ on_move_up }>{ "▲" }</button>
+                                        }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                            </li>
+                        }
+                    })
+                }
+
+                {
+                    for disabled.map(|agent| {
+                        let on_toggle = {
+                            let priority = props.priority.clone();
+
+                            props.callback.reform(move |_| {
+                                let mut priority = priority.clone();
+                                priority.agents.push(agent);
+                                priority
+                            })
+                        };
+
+                        html! {
+                            <li>
+                                <input type="checkbox" checked=false onchange={ on_toggle } />
+                                { " " }{ agent.name() }
+                            </li>
+                        }
+                    })
+                }
+            </ul>
+        </div>
+    }
+}
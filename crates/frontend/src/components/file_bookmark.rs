@@ -0,0 +1,83 @@
+use std::rc::Rc;
+
+use common_local::{Bookmark, BookmarkId};
+use gloo_utils::window;
+use wasm_bindgen::UnwrapThrowExt;
+use yew::prelude::*;
+
+use super::reader::ReaderPosition;
+
+#[derive(Properties, PartialEq)]
+pub struct Property {
+    pub bookmarks: Rc<Vec<Bookmark>>,
+
+    pub on_jump: Callback<ReaderPosition>,
+    pub on_rename: Callback<(BookmarkId, String)>,
+    pub on_delete: Callback<BookmarkId>,
+}
+
+/// Every bookmark of the file. Listed in the order they appear in the book.
+#[function_component(FileBookmarks)]
+pub fn _file_bookmarks(props: &Property) -> Html {
+    let mut bookmarks = props.bookmarks.iter().collect::<Vec<_>>();
+    bookmarks.sort_by_key(|v| (v.chapter, v.char_pos));
+
+    html! {
+        <div class="bookmarks">
+            <h5>{ "Bookmarks" }</h5>
+
+            {
+                if bookmarks.is_empty() {
+                    html! {
+                        <span class="text-muted">{ "Use \"Add Bookmark\" in the reader to add one." }</span>
+                    }
+                } else {
+                    html! {
+                        for bookmarks.into_iter().map(|bookmark| render_bookmark(bookmark, props))
+                    }
+                }
+            }
+        </div>
+    }
+}
+
+fn render_bookmark(bookmark: &Bookmark, props: &Property) -> Html {
+    let id = bookmark.id;
+    let position = ReaderPosition {
+        chapter: bookmark.chapter,
+        char_pos: bookmark.char_pos,
+    };
+
+    let label = bookmark.label.clone().unwrap_or_default();
+
+    html! {
+        <div class="bookmark">
+            <div class="bookmark-text" title="Go to bookmark" onclick={ props.on_jump.reform(move |_| position) }>
+                <b>{ bookmark.label.clone().unwrap_or_else(|| format!("Section {}", bookmark.chapter + 1)) }</b>
+                <div class="text-muted">{ bookmark.snippet.clone() }</div>
+            </div>
+
+            <div class="bookmark-tools">
+                <button class="btn btn-secondary btn-sm" onclick={ {
+                    let on_rename = props.on_rename.clone();
+
+                    Callback::from(move |_| {
+                        if let Some(value) = window().prompt_with_message_and_default("Bookmark Label", &label).unwrap_throw() {
+                            on_rename.emit((id, value));
+                        }
+                    })
+                } }>{ "Rename" }</button>
+
+                <button class="btn btn-danger btn-sm" onclick={ {
+                    let on_delete = props.on_delete.clone();
+
+                    Callback::from(move |_| {
+                        if window().confirm_with_message("Are you sure you want to delete this?").unwrap_throw() {
+                            on_delete.emit(id);
+                        }
+                    })
+                } }>{ "Delete" }</button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,93 @@
+use std::rc::Rc;
+
+use common_local::{api::UpdateNotationBody, HighlightColor, Notation, NotationAnchor, NotationId};
+use gloo_utils::window;
+use wasm_bindgen::UnwrapThrowExt;
+use web_sys::HtmlTextAreaElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct Property {
+    pub notations: Rc<Vec<Notation>>,
+
+    pub on_jump: Callback<NotationAnchor>,
+    pub on_update: Callback<(NotationId, UpdateNotationBody)>,
+    pub on_delete: Callback<NotationId>,
+}
+
+/// Every highlight of the file. Listed in the order they appear in the book.
+#[function_component(FileNotations)]
+pub fn _file_notations(props: &Property) -> Html {
+    let mut notations = props.notations.iter().collect::<Vec<_>>();
+    notations.sort_by_key(|v| v.anchor);
+
+    html! {
+        <div class="annotations">
+            <h5>{ "Annotations" }</h5>
+
+            {
+                if notations.is_empty() {
+                    html! {
+                        <span class="text-muted">{ "Select text while highlighting to add one." }</span>
+                    }
+                } else {
+                    html! {
+                        for notations.into_iter().map(|notation| render_notation(notation, props))
+                    }
+                }
+            }
+        </div>
+    }
+}
+
+fn render_notation(notation: &Notation, props: &Property) -> Html {
+    let id = notation.id;
+    let anchor = notation.anchor;
+
+    html! {
+        <div class={ classes!("annotation", notation.color.name()) }>
+            <blockquote class="annotation-text" title="Go to highlight" onclick={ props.on_jump.reform(move |_| anchor) }>
+                { notation.text.clone() }
+            </blockquote>
+
+            <textarea
+                class="form-control form-control-sm"
+                placeholder="Add a note"
+                value={ notation.note.clone().unwrap_or_default() }
+                onchange={ props.on_update.reform(move |e: Event| {
+                    (id, UpdateNotationBody {
+                        note: Some(e.target_unchecked_into::<HtmlTextAreaElement>().value()),
+                        ..UpdateNotationBody::default()
+                    })
+                }) }
+            />
+
+            <div class="annotation-tools">
+                {
+                    for HighlightColor::get_all().into_iter().map(|color| html! {
+                        <span
+                            class={ classes!("annotation-color", color.name(), (color == notation.color).then_some("active")) }
+                            title={ color.name() }
+                            onclick={ props.on_update.reform(move |_| {
+                                (id, UpdateNotationBody {
+                                    color: Some(color),
+                                    ..UpdateNotationBody::default()
+                                })
+                            }) }
+                        ></span>
+                    })
+                }
+
+                <button class="btn btn-danger btn-sm" onclick={ {
+                    let on_delete = props.on_delete.clone();
+
+                    Callback::from(move |_| {
+                        if window().confirm_with_message("Are you sure you want to delete this?").unwrap_throw() {
+                            on_delete.emit(id);
+                        }
+                    })
+                } }>{ "Delete" }</button>
+            </div>
+        </div>
+    }
+}
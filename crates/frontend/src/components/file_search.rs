@@ -0,0 +1,143 @@
+use common::api::WrappingResponse;
+use common_local::{api::ApiGetFileSearchByIdResponse, FileId};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use super::reader::ReaderPosition;
+use crate::request;
+
+#[derive(Properties, PartialEq)]
+pub struct Property {
+    pub file_id: FileId,
+
+    pub on_jump: Callback<ReaderPosition>,
+}
+
+pub enum Msg {
+    UpdateQuery(String),
+    Search,
+
+    RetrieveResults(WrappingResponse<ApiGetFileSearchByIdResponse>),
+}
+
+/// Searches the text of every chapter in the file.
+pub struct FileSearch {
+    query: String,
+    is_searching: bool,
+    results: Option<ApiGetFileSearchByIdResponse>,
+}
+
+impl Component for FileSearch {
+    type Message = Msg;
+    type Properties = Property;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            query: String::new(),
+            is_searching: false,
+            results: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::UpdateQuery(value) => {
+                self.query = value;
+
+                return false;
+            }
+
+            Msg::Search => {
+                if self.is_searching || self.query.trim().is_empty() {
+                    return false;
+                }
+
+                self.is_searching = true;
+
+                let file_id = ctx.props().file_id;
+                let query = self.query.clone();
+
+                ctx.link().send_future(async move {
+                    Msg::RetrieveResults(request::search_file(file_id, query).await)
+                });
+            }
+
+            Msg::RetrieveResults(resp) => {
+                self.is_searching = false;
+
+                match resp.ok() {
+                    Ok(items) => self.results = Some(items),
+                    Err(err) => crate::display_error(err),
+                }
+            }
+        }
+
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <div class="file-search">
+                <h5>{ "Search" }</h5>
+
+                <form class="input-group input-group-sm" onsubmit={ ctx.link().callback(|e: SubmitEvent| {
+                    e.prevent_default();
+                    Msg::Search
+                }) }>
+                    <input
+                        class="form-control"
+                        type="search"
+                        placeholder="Find in book"
+                        value={ self.query.clone() }
+                        oninput={ ctx.link().callback(|e: InputEvent| Msg::UpdateQuery(e.target_unchecked_into::<HtmlInputElement>().value())) }
+                    />
+                    <button class="btn btn-secondary" type="submit" disabled={ self.is_searching }>{ "Search" }</button>
+                </form>
+
+                { self.render_results(ctx) }
+            </div>
+        }
+    }
+}
+
+impl FileSearch {
+    fn render_results(&self, ctx: &Context<Self>) -> Html {
+        if self.is_searching {
+            return html! {
+                <span class="text-muted">{ "Searching..." }</span>
+            };
+        }
+
+        let Some(results) = self.results.as_ref() else {
+            return html! {};
+        };
+
+        if results.is_empty() {
+            return html! {
+                <span class="text-muted">{ "Nothing found." }</span>
+            };
+        }
+
+        html! {
+            <div class="file-search-results">
+                {
+                    for results.iter().map(|item| {
+                        let position = ReaderPosition {
+                            chapter: item.chapter,
+                            char_pos: item.char_pos,
+                        };
+
+                        html! {
+                            <div class="file-search-result" title="Go to match" onclick={ ctx.props().on_jump.reform(move |_| position) }>
+                                <small class="text-muted">{ format!("Section {}", item.chapter + 1) }</small>
+                                <div>
+                                    { "…" }{ item.before.clone() }<mark>{ item.text.clone() }</mark>{ item.after.clone() }{ "…" }
+                                </div>
+                            </div>
+                        }
+                    })
+                }
+            </div>
+        }
+    }
+}
@@ -2,17 +2,25 @@ pub mod book_list;
 mod book_list_item_info;
 pub mod book_poster_item;
 pub mod edit;
+pub mod file_bookmark;
+pub mod file_notation;
+pub mod file_search;
 pub mod mass_selector_bar;
 pub mod navbar;
 pub mod notes;
 mod owner_barrier;
 pub mod popup;
 pub mod reader;
+pub mod table_of_contents;
 mod sidebar;
 
 pub use book_list::{BookListComponent, BookListRequest, BookListScope};
 pub use book_list_item_info::BookListItemInfo;
 pub use book_poster_item::{BookPosterItem, DropdownInfoPopup, DropdownInfoPopupEvent};
+pub use file_bookmark::FileBookmarks;
+pub use file_notation::FileNotations;
+pub use file_search::FileSearch;
+pub use table_of_contents::TableOfContents;
 pub use mass_selector_bar::MassSelectBar;
 pub use navbar::NavbarModule;
 pub use notes::Notes;
@@ -1,7 +1,7 @@
 use std::{sync::{Arc, Mutex}, rc::Rc};
 
 use common::{api::WrappingResponse, util::does_parent_contain_class};
-use common_local::{api::{GetBookListResponse, GetLibrariesResponse}, filter::FilterContainer, ThumbnailStoreExt};
+use common_local::{api::{BookTextSearchQuery, GetBookListResponse, GetLibrariesResponse}, filter::FilterContainer, GroupPermissions, ThumbnailStoreExt};
 use gloo_utils::body;
 use wasm_bindgen::{prelude::Closure, JsCast, UnwrapThrowExt};
 use web_sys::HtmlInputElement;
@@ -209,7 +209,7 @@ impl NavbarModule {
     fn render_item(&self, route: BaseRoute, name: &DisplayType) -> Html {
         let inner = if route == BaseRoute::Settings {
             let route = if self.state.member.as_ref()
-                .map(|v| v.permissions.is_owner())
+                .map(|v| v.permissions.has(GroupPermissions::RUN_TASKS))
                 .unwrap_or_default()
             {
                 SettingsRoute::AdminTasks
@@ -251,8 +251,23 @@ impl NavbarModule {
             search.add_query_filter(self.input_ref.cast::<HtmlInputElement>().unwrap().value());
             let query = serde_qs::to_string(&search).unwrap_throw();
 
+            let text_query = serde_qs::to_string(&BookTextSearchQuery {
+                query: self.input_ref.cast::<HtmlInputElement>().unwrap().value(),
+                library: None,
+                offset: None,
+                limit: None,
+            }).unwrap_throw();
+
             html! {
                 <div class="search-dropdown">
+                    <div class="d-grid p-2">
+                        <a
+                            href={ format!("{}?{text_query}", BaseRoute::TextSearch.to_path()) }
+                            type="button"
+                            class="btn btn-primary"
+                        >{ "Search inside Books" }</a>
+                    </div>
+
                 {
                     for libs.items.iter().zip(self.search_results.iter()).map(|(lib, results)| {
                         let url = BaseRoute::ViewLibrary { id: lib.id }.to_path();
@@ -1,5 +1,6 @@
 use std::rc::Rc;
 
+use common_local::GroupPermissions;
 use yew::prelude::*;
 
 use crate::AppState;
@@ -7,6 +8,10 @@ use crate::AppState;
 #[derive(Properties, PartialEq)]
 pub struct Props {
     pub children: Children,
+
+    /// Shown when the member has any of these. Owners always see it.
+    #[prop_or(GroupPermissions::OWNER)]
+    pub permission: GroupPermissions,
 }
 
 #[function_component(OwnerBarrier)]
@@ -17,7 +22,7 @@ pub fn _owner_barrier(props: &Props) -> Html {
         return html! {};
     };
 
-    if member.permissions.is_owner() {
+    if member.permissions.is_owner() || member.permissions.intersects_group(props.permission) {
         html! {
             for props.children.iter()
         }
@@ -8,7 +8,7 @@ use common::{
 };
 use common_local::{
     api::{ApiGetPeopleResponse, GetBookResponse, GetPostersResponse, PostBookBody},
-    BookEdit, Person,
+    BookEdit, MetadataField, Person,
 };
 use gloo_timers::callback::Timeout;
 use web_sys::{HtmlInputElement, HtmlTextAreaElement};
@@ -44,6 +44,7 @@ pub enum Msg {
 
     SearchPerson(String),
     TogglePerson { toggle: bool, id: PersonId },
+    ToggleLock(MetadataField),
 
     Edit(Box<dyn Fn(&mut BookEdit, String, &GetBookResponse)>, String),
     Save,
@@ -167,6 +168,19 @@ impl Component for PopupEditBook {
                 }
             }
 
+            Msg::ToggleLock(field) => {
+                let locked = self
+                    .edits
+                    .locked_fields
+                    .get_or_insert_with(|| ctx.props().media_resp.locked_fields.clone());
+
+                if let Some(index) = locked.iter().position(|v| *v == field) {
+                    locked.remove(index);
+                } else {
+                    locked.push(field);
+                }
+            }
+
             Msg::Save => {
                 let edit = self.edits.clone();
                 let id = ctx.props().media_resp.book.id;
@@ -236,7 +250,7 @@ impl PopupEditBook {
 
                 self.render_tab_poster(ctx)
             }
-            TabDisplay::Info => self.render_tab_info(ctx.props()),
+            TabDisplay::Info => self.render_tab_info(ctx),
         }
     }
 
@@ -371,9 +385,30 @@ impl PopupEditBook {
         }
     }
 
-    fn render_tab_info(&self, _props: &<Self as Component>::Properties) -> Html {
+    fn render_tab_info(&self, ctx: &Context<Self>) -> Html {
+        let locked = self
+            .edits
+            .locked_fields
+            .as_ref()
+            .unwrap_or(&ctx.props().media_resp.locked_fields);
+
         html! {
             <div class="content">
+                <h5>{ "Locked Fields" }</h5>
+                <p>{ "Refreshing the metadata won't overwrite these. Edited fields are locked automatically." }</p>
+
+                {
+                    for MetadataField::ALL.into_iter().map(|field| html! {
+                        <div class="form-check">
+                            <input
+                                class="form-check-input" type="checkbox" id={ format!("lock-{}", u8::from(field)) }
+                                checked={ locked.contains(&field) }
+                                onchange={ ctx.link().callback(move |_| Msg::ToggleLock(field)) }
+                            />
+                            <label class="form-check-label" for={ format!("lock-{}", u8::from(field)) }>{ field.name() }</label>
+                        </div>
+                    })
+                }
             </div>
         }
     }
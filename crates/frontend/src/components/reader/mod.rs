@@ -1,12 +1,13 @@
 use std::{path::PathBuf, rc::Rc, sync::Mutex};
 
-use common_local::{api, Chapter, FileId, MediaItem, Progression, MemberReaderPreferences, reader::ReaderColor};
+use common_local::{api, Bookmark, Chapter, FileId, MediaItem, Notation, NotationAnchor, Progression, MemberReaderPreferences, reader::ReaderColor};
 use gloo_timers::callback::Timeout;
-use gloo_utils::{body, window};
+use gloo_utils::{body, format::JsValueSerdeExt, window};
 use num_enum::{TryFromPrimitive, IntoPrimitive};
+use serde::Deserialize;
 use wasm_bindgen::{
     prelude::{wasm_bindgen, Closure},
-    JsCast, UnwrapThrowExt,
+    JsCast, JsValue, UnwrapThrowExt,
 };
 use web_sys::{DomRect, Element, HtmlElement, HtmlIFrameElement};
 use yew::{html::Scope, prelude::*};
@@ -45,6 +46,14 @@ extern "C" {
     );
 
     fn js_get_visible_links(iframe: &HtmlIFrameElement, is_vscroll: bool) -> Vec<DomRect>;
+
+    fn js_get_selection_position(iframe: &HtmlIFrameElement) -> JsValue;
+    fn js_clear_selection(iframe: &HtmlIFrameElement);
+    fn js_render_notations(iframe: &HtmlIFrameElement, notations: &JsValue);
+
+    fn js_get_page_from_element_id(iframe: &HtmlIFrameElement, id: &str) -> Option<usize>;
+
+    fn js_get_text_at_byte_position(iframe: &HtmlIFrameElement, position: usize, length: usize) -> String;
 }
 
 macro_rules! get_current_section_mut {
@@ -125,8 +134,39 @@ impl LoadedChapters {
     }
 }
 
+/// Text the member selected inside of the current section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextSelection {
+    pub anchor: NotationAnchor,
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+struct SelectionPosition {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+/// Counted the same way as the character position of the progression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ReaderPosition {
+    pub chapter: usize,
+    pub char_pos: usize,
+}
+
+/// Where the reader should move to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReaderJump {
+    Position(ReaderPosition),
+    /// A section and the id of an element inside of it.
+    Fragment(usize, Option<String>),
+}
+
 pub enum ReaderEvent {
     ViewOverlay(OverlayEvent),
+    TextSelection(Option<TextSelection>),
+    AddBookmark(api::NewBookmarkBody),
 }
 
 #[derive(Properties)]
@@ -140,6 +180,13 @@ pub struct Property {
     pub chapters: Rc<Mutex<LoadedChapters>>,
 
     pub progress: Rc<Mutex<Option<Progression>>>,
+
+    pub notations: Rc<Vec<Notation>>,
+    pub bookmarks: Rc<Vec<Bookmark>>,
+    /// Taken once the reader has moved to it.
+    pub jump_to: Rc<Mutex<Option<ReaderJump>>>,
+    /// Hides the page overlay so the text can be selected.
+    pub allow_text_selection: bool,
 }
 
 impl PartialEq for Property {
@@ -161,8 +208,13 @@ pub enum ReaderMsg {
 
     HandleScrollChangePage(DragType),
     HandleViewOverlay(OverlayEvent),
+    HandleTextSelection,
     UploadProgress,
 
+    AddBookmark,
+    NextBookmark,
+    PreviousBookmark,
+
     NextPage,
     PreviousPage,
     SetPage(usize),
@@ -188,6 +240,8 @@ pub struct Reader {
 
     _handle_keyboard: ElementEvent,
     handle_js_redirect_clicks: Closure<dyn FnMut(usize, String)>,
+    handle_text_selection: Closure<dyn FnMut()>,
+    cached_notations: Rc<Vec<Notation>>,
     cursor_type: &'static str,
     visible_redirect_rects: Vec<DomRect>,
 
@@ -232,6 +286,10 @@ impl Component for Reader {
                 }
             });
 
+        let link = ctx.link().clone();
+        let handle_text_selection: Closure<dyn FnMut()> =
+            Closure::new(move || link.send_message(ReaderMsg::HandleTextSelection));
+
         let link = ElementEvent::link(
             window().unchecked_into(),
             handle_keyboard,
@@ -256,6 +314,8 @@ impl Component for Reader {
             scroll_change_page_timeout: None,
 
             handle_js_redirect_clicks,
+            handle_text_selection,
+            cached_notations: Rc::clone(&ctx.props().notations),
             _handle_keyboard: link,
             is_transitioning: false,
         }
@@ -279,8 +339,8 @@ impl Component for Reader {
                 return Component::update(self, ctx, ReaderMsg::UploadProgress);
             }
 
-            ReaderMsg::HandleJsRedirect(_chapter, file_path, _id_name) => {
-                log::debug!("ReaderMsg::HandleJsRedirect(chapter: {_chapter}, file_path: {file_path:?}, id_name: {_id_name:?})");
+            ReaderMsg::HandleJsRedirect(_chapter, file_path, id_name) => {
+                log::debug!("ReaderMsg::HandleJsRedirect(chapter: {_chapter}, file_path: {file_path:?}, id_name: {id_name:?})");
 
                 let file_path = PathBuf::from(file_path);
 
@@ -297,8 +357,7 @@ impl Component for Reader {
                     .cloned()
                 {
                     drop(chaps);
-                    self.set_section(chap.value, ctx);
-                    // TODO: Handle id_name
+                    self.jump_to_fragment(chap.value, id_name.as_deref(), ctx);
                 }
             }
 
@@ -597,6 +656,51 @@ impl Component for Reader {
 
             ReaderMsg::UploadProgress => self.upload_progress_and_emit(ctx),
 
+            ReaderMsg::AddBookmark => {
+                let Some(section) = self
+                    .get_current_section()
+                    .filter(|_| self.sections[self.viewing_chapter].is_loaded())
+                else {
+                    return false;
+                };
+
+                let char_pos = js_get_current_byte_pos(section.get_iframe()).unwrap_or_default();
+
+                // Cancelling the prompt cancels the bookmark. An empty label is fine.
+                let Ok(Some(label)) = window().prompt_with_message("Bookmark Label (optional)") else {
+                    return false;
+                };
+
+                ctx.props().event.emit(ReaderEvent::AddBookmark(api::NewBookmarkBody {
+                    chapter: self.viewing_chapter,
+                    char_pos,
+                    label: Some(label),
+                    snippet: js_get_text_at_byte_position(section.get_iframe(), char_pos, 120),
+                }));
+
+                return false;
+            }
+
+            ReaderMsg::NextBookmark => return self.jump_to_bookmark(true, ctx),
+            ReaderMsg::PreviousBookmark => return self.jump_to_bookmark(false, ctx),
+
+            ReaderMsg::HandleTextSelection => {
+                let selection = self.get_current_section()
+                    .and_then(|section| js_get_selection_position(section.get_iframe()).into_serde::<Option<SelectionPosition>>().ok().flatten())
+                    .map(|v| TextSelection {
+                        anchor: NotationAnchor {
+                            chapter: self.viewing_chapter,
+                            start: v.start,
+                            end: v.end,
+                        },
+                        text: v.text,
+                    });
+
+                ctx.props().event.emit(ReaderEvent::TextSelection(selection));
+
+                return false;
+            }
+
             // Called after iframe is loaded.
             ReaderMsg::GenerateIFrameLoaded(chapter) => {
                 self.sections[chapter.value].convert_to_loaded();
@@ -610,6 +714,14 @@ impl Component for Reader {
                         &self.handle_js_redirect_clicks,
                         ctx,
                     );
+
+                    if let Some(document) = section.get_iframe().content_document() {
+                        for name in ["mouseup", "touchend"] {
+                            document.add_event_listener_with_callback(name, self.handle_text_selection.as_ref().unchecked_ref()).unwrap_throw();
+                        }
+                    }
+
+                    render_notations(section, &self.cached_notations);
                 }
 
                 if self.are_all_sections_generated() {
@@ -662,7 +774,7 @@ impl Component for Reader {
 
                 <div class="pages" style={ pages_style.clone() }>
                     {
-                        if !self.cached_display.is_scroll() {
+                        if !self.cached_display.is_scroll() && !ctx.props().allow_text_selection {
                             html! {
                                 <ViewOverlay event={ ctx.link().callback(ReaderMsg::HandleViewOverlay) } />
                             }
@@ -735,10 +847,38 @@ impl Component for Reader {
             self.update_cached_pages();
         }
 
+        if !Rc::ptr_eq(&self.cached_notations, &props.notations) {
+            self.cached_notations = Rc::clone(&props.notations);
+
+            for prog in &self.sections {
+                if let SectionLoadProgress::Loaded(section) = prog {
+                    render_notations(section, &self.cached_notations);
+                }
+            }
+
+            if let Some(section) = self.get_current_section() {
+                js_clear_selection(section.get_iframe());
+            }
+        }
+
         self.load_surrounding_sections(ctx);
 
         self.use_progression(*props.progress.lock().unwrap(), ctx);
 
+        let jump_to = props.jump_to.lock().unwrap().take();
+
+        match jump_to {
+            Some(ReaderJump::Position(position)) => {
+                self.jump_to_position(position, ctx);
+            }
+
+            Some(ReaderJump::Fragment(chapter, fragment)) => {
+                self.jump_to_fragment(chapter, fragment.as_deref(), ctx);
+            }
+
+            None => (),
+        }
+
         true
     }
 }
@@ -796,6 +936,26 @@ impl Reader {
                         }
                     }
                 }
+
+                {
+                    if ctx.props().bookmarks.is_empty() {
+                        html! {}
+                    } else {
+                        html! {
+                            <a onclick={ ctx.link().callback(|_| ReaderMsg::PreviousBookmark) }>{ "Previous Bookmark" }</a>
+                        }
+                    }
+                }
+                <a onclick={ ctx.link().callback(|_| ReaderMsg::AddBookmark) }>{ "Add Bookmark" }</a>
+                {
+                    if ctx.props().bookmarks.is_empty() {
+                        html! {}
+                    } else {
+                        html! {
+                            <a onclick={ ctx.link().callback(|_| ReaderMsg::NextBookmark) }>{ "Next Bookmark" }</a>
+                        }
+                    }
+                }
             </div>
         }
     }
@@ -869,38 +1029,126 @@ impl Reader {
                     log::debug!("use_progression - set section: {chapter}");
 
                     // TODO: utilize page. Main issue is resizing the reader w/h will return a different page. Hence the char_pos.
-                    self.set_section(chapter as usize, ctx);
+                    self.set_position(chapter as usize, char_pos, ctx);
+                }
 
-                    if char_pos != -1 {
-                        if let SectionLoadProgress::Loaded(section) =
-                            &mut self.sections[chapter as usize]
-                        {
-                            if self.cached_display.is_scroll() {
-                                if let Some(_element) = js_get_element_from_byte_position(
-                                    section.get_iframe(),
-                                    char_pos as usize,
-                                ) {
-                                    // TODO: Not scrolling properly. Is it somehow scrolling the div@frames html element?
-                                    // element.scroll_into_view();
-                                }
-                            } else {
-                                let page = js_get_page_from_byte_position(
-                                    section.get_iframe(),
-                                    char_pos as usize,
-                                );
+                _ => (),
+            }
+        }
+    }
 
-                                log::debug!("use_progression - set page: {:?}", page);
+    /// Moves to the section and the page which contains the character position.
+    fn set_position(&mut self, chapter: usize, char_pos: i64, ctx: &Context<Self>) {
+        self.set_section(chapter, ctx);
+
+        if char_pos != -1 {
+            if let SectionLoadProgress::Loaded(section) = &mut self.sections[chapter] {
+                if self.cached_display.is_scroll() {
+                    if let Some(_element) = js_get_element_from_byte_position(
+                        section.get_iframe(),
+                        char_pos as usize,
+                    ) {
+                        // TODO: Not scrolling properly. Is it somehow scrolling the div@frames html element?
+                        // element.scroll_into_view();
+                    }
+                } else {
+                    let page = js_get_page_from_byte_position(
+                        section.get_iframe(),
+                        char_pos as usize,
+                    );
 
-                                if let Some(page) = page {
-                                    self.cached_display.set_page(page, section);
-                                }
-                            }
-                        }
+                    log::debug!("set_position - set page: {:?}", page);
+
+                    if let Some(page) = page {
+                        self.cached_display.set_page(page, section);
                     }
                 }
+            }
+        }
+    }
 
-                _ => (),
+    /// Moves to the page which contains the position.
+    fn jump_to_position(&mut self, position: ReaderPosition, ctx: &Context<Self>) -> bool {
+        let page = self.get_position_page(position);
+
+        self.jump_to_page_or_section(page, position.chapter, ctx)
+    }
+
+    /// Moves to the element with the id. Without one it moves to the start of the section.
+    fn jump_to_fragment(&mut self, chapter: usize, fragment: Option<&str>, ctx: &Context<Self>) -> bool {
+        let page = self.sections.get(chapter)
+            .and_then(|v| v.as_loaded())
+            .filter(|_| !self.cached_display.is_scroll())
+            .map(|section| {
+                section.gpi + fragment
+                    .and_then(|id| js_get_page_from_element_id(section.get_iframe(), id))
+                    .unwrap_or_default()
+            });
+
+        self.jump_to_page_or_section(page, chapter, ctx)
+    }
+
+    /// Goes through `SetPage` when we know the page.
+    ///
+    /// If the section isn't loaded yet we can only move to the section itself.
+    fn jump_to_page_or_section(&mut self, page: Option<usize>, chapter: usize, ctx: &Context<Self>) -> bool {
+        match page {
+            Some(page) => {
+                let updated = Component::update(self, ctx, ReaderMsg::SetPage(page));
+
+                self.upload_progress_and_emit(ctx);
+
+                updated
             }
+
+            None => Component::update(self, ctx, ReaderMsg::SetSection(chapter)),
+        }
+    }
+
+    /// Moves to the closest bookmark after (or before) the current page.
+    fn jump_to_bookmark(&mut self, is_next: bool, ctx: &Context<Self>) -> bool {
+        let current_page = self.get_current_page();
+
+        let mut positions = ctx.props().bookmarks.iter()
+            .map(|v| ReaderPosition { chapter: v.chapter, char_pos: v.char_pos })
+            .collect::<Vec<_>>();
+
+        positions.sort_unstable();
+
+        if !is_next {
+            positions.reverse();
+        }
+
+        // Sections which aren't loaded yet only have their chapter to compare against.
+        let found = positions.into_iter().find(|pos| match (self.get_position_page(*pos), is_next) {
+            (Some(page), true) => page > current_page,
+            (Some(page), false) => page < current_page,
+            (None, true) => pos.chapter > self.viewing_chapter,
+            (None, false) => pos.chapter < self.viewing_chapter,
+        });
+
+        match found {
+            Some(position) => self.jump_to_position(position, ctx),
+            None => false,
+        }
+    }
+
+    /// The page the position is on. While scrolling every section is a single page.
+    fn get_position_page(&self, position: ReaderPosition) -> Option<usize> {
+        if self.cached_display.is_scroll() {
+            return Some(position.chapter);
+        }
+
+        let section = self.sections.get(position.chapter)?.as_loaded()?;
+
+        Some(section.gpi + js_get_page_from_byte_position(section.get_iframe(), position.char_pos)?)
+    }
+
+    fn get_current_page(&self) -> usize {
+        if self.cached_display.is_scroll() {
+            self.viewing_chapter
+        } else {
+            self.current_page_pos()
         }
     }
 
@@ -1264,6 +1512,18 @@ fn generate_pages(
     SectionContents::new(chap_value, iframe, f)
 }
 
+fn render_notations(section: &SectionContents, notations: &[Notation]) {
+    let notations = notations
+        .iter()
+        .filter(|v| v.anchor.chapter == section.chapter())
+        .collect::<Vec<_>>();
+
+    js_render_notations(
+        section.get_iframe(),
+        &<JsValue as JsValueSerdeExt>::from_serde(&notations).unwrap_throw(),
+    );
+}
+
 fn update_iframe_size(book_dimensions: Option<(i32, i32)>, iframe: &HtmlIFrameElement) {
     let (width, height) = match book_dimensions {
         // TODO: Use Option.unzip once stable.
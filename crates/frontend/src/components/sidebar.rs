@@ -2,7 +2,7 @@ use common::{
     api::WrappingResponse,
     component::popup::{button::ButtonWithPopup, Popup, PopupClose, PopupType},
 };
-use common_local::{api, LibraryColl, LibraryId};
+use common_local::{api, GroupPermissions, LibraryColl, LibraryId};
 use yew::{html::Scope, prelude::*};
 use yew_router::{
     prelude::{Link, Location},
@@ -169,8 +169,9 @@ impl Sidebar {
                     ("Libraries", SettingsRoute::AdminLibraries),
                 ];
 
-                const MEMBERS_LOCATIONS: [(&str, SettingsRoute); 1] = [
+                const MEMBERS_LOCATIONS: [(&str, SettingsRoute); 2] = [
                     ("General", SettingsRoute::MemberGeneral),
+                    ("API Tokens", SettingsRoute::MemberTokens),
                 ];
 
                 let cr = ctx.link().route::<SettingsRoute>().unwrap();
@@ -183,7 +184,7 @@ impl Sidebar {
 
                         <hr />
 
-                        <OwnerBarrier>
+                        <OwnerBarrier permission={ GroupPermissions::RUN_TASKS | GroupPermissions::MANAGE_MEMBERS }>
                             <div class="sidebar-item">
                                 <h3>
                                     { "Admin" }
@@ -192,11 +193,13 @@ impl Sidebar {
 
                             <ul class="nav nav-pills flex-column">
                                 { for ADMIN_LOCATIONS.iter().map(|&(title, route)| html! {
-                                    <li class="nav-item">
-                                        <Link<SettingsRoute> to={route} classes={ classes!("nav-link", (cr == route).then_some("active")) }>
-                                            <span class="title">{ title }</span>
-                                        </Link<SettingsRoute>>
-                                    </li>
+                                    <OwnerBarrier permission={ route.required_permission().unwrap_or(GroupPermissions::OWNER) }>
+                                        <li class="nav-item">
+                                            <Link<SettingsRoute> to={route} classes={ classes!("nav-link", (cr == route).then_some("active")) }>
+                                                <span class="title">{ title }</span>
+                                            </Link<SettingsRoute>>
+                                        </li>
+                                    </OwnerBarrier>
                                 }) }
                             </ul>
                         </OwnerBarrier>
@@ -234,7 +237,7 @@ impl Sidebar {
                     { item.name.clone() }
                 </Link<BaseRoute>>
 
-                <OwnerBarrier>
+                <OwnerBarrier permission={ GroupPermissions::RUN_TASKS }>
                     <div class="options">
                         <ButtonWithPopup class="menu-list">
                             <PopupClose class="dropdown-item" onclick={ scope.callback_future(move |e: MouseEvent| {
@@ -269,14 +272,36 @@ impl Sidebar {
                                 { "Library Scan" }
                             </PopupClose>
 
-                            <PopupClose class="dropdown-item" onclick={ scope.callback(move |e: MouseEvent| {
+                            <PopupClose class="dropdown-item" onclick={ scope.callback_future(move |e: MouseEvent| {
                                 e.prevent_default();
-                                e.stop_propagation();
 
-                                Msg::EditLibrary(library_id)
+                                async move {
+                                    let resp = request::run_task(api::RunTaskBody {
+                                        index_text: Some(library_id),
+
+                                        .. Default::default()
+                                    }).await;
+
+                                    if let Err(err) = resp.ok() {
+                                        crate::display_error(err);
+                                    }
+
+                                    Msg::Ignore
+                                }
                             }) }>
-                                { "Edit Library" }
+                                { "Index Book Text" }
                             </PopupClose>
+
+                            <OwnerBarrier>
+                                <PopupClose class="dropdown-item" onclick={ scope.callback(move |e: MouseEvent| {
+                                    e.prevent_default();
+                                    e.stop_propagation();
+
+                                    Msg::EditLibrary(library_id)
+                                }) }>
+                                    { "Edit Library" }
+                                </PopupClose>
+                            </OwnerBarrier>
                         </ButtonWithPopup>
                     </div>
                 </OwnerBarrier>
@@ -0,0 +1,64 @@
+use std::rc::Rc;
+
+use common_local::TocItem;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct Property {
+    pub items: Rc<Vec<TocItem>>,
+
+    pub on_jump: Callback<(usize, Option<String>)>,
+}
+
+#[function_component(TableOfContents)]
+pub fn _table_of_contents(props: &Property) -> Html {
+    html! {
+        <div class="table-of-contents">
+            <h5>{ "Contents" }</h5>
+
+            {
+                if props.items.is_empty() {
+                    html! {
+                        <span class="text-muted">{ "This book doesn't have a table of contents." }</span>
+                    }
+                } else {
+                    render_items(&props.items, &props.on_jump)
+                }
+            }
+        </div>
+    }
+}
+
+fn render_items(items: &[TocItem], on_jump: &Callback<(usize, Option<String>)>) -> Html {
+    html! {
+        <ul>
+            {
+                for items.iter().map(|item| html! {
+                    <li>
+                        {
+                            if let Some(chapter) = item.chapter {
+                                let fragment = item.fragment.clone();
+
+                                html! {
+                                    <a onclick={ on_jump.reform(move |_| (chapter, fragment.clone())) }>{ item.title.clone() }</a>
+                                }
+                            } else {
+                                html! {
+                                    <span>{ item.title.clone() }</span>
+                                }
+                            }
+                        }
+
+                        {
+                            if item.children.is_empty() {
+                                html! {}
+                            } else {
+                                render_items(&item.children, on_jump)
+                            }
+                        }
+                    </li>
+                })
+            }
+        </ul>
+    }
+}
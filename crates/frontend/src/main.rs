@@ -15,7 +15,7 @@ use common::{
 use common_local::{
     api,
     ws::{TaskId, TaskInfo},
-    CollectionId, FileId, LibraryId, Member, Permissions,
+    CollectionId, FileId, GroupPermissions, LibraryId, Member, Permissions,
 };
 use gloo_utils::body;
 use lazy_static::lazy_static;
@@ -127,7 +127,7 @@ impl Component for Model {
 
                 if let WrappingResponse::Resp(resp) = resp {
                     if let Some(member) = resp.member.as_ref() {
-                        if member.permissions.is_owner() {
+                        if member.permissions.has(GroupPermissions::RUN_TASKS) {
                             ctx.link().send_future(async {
                                 Msg::GetTasksResponse(request::get_tasks().await)
                             });
@@ -246,6 +246,9 @@ pub enum BaseRoute {
     #[at("/collection/:id")]
     ViewCollection { id: CollectionId },
 
+    #[at("/search")]
+    TextSearch,
+
     #[at("/settings/*")]
     Settings,
 
@@ -300,9 +303,13 @@ fn switch_base(route: BaseRoute, permissions: Option<Permissions>) -> Html {
             html! { <pages::CollectionItemPage {id} /> }
         }
 
+        BaseRoute::TextSearch => {
+            html! { <pages::TextSearchPage /> }
+        }
+
         BaseRoute::Settings => {
             html! { <Switch<pages::settings::SettingsRoute> render={ move |route: pages::settings::SettingsRoute| {
-                if route.is_admin() && !permissions.unwrap().is_owner() {
+                if route.required_permission().is_some_and(|v| !permissions.unwrap().has(v)) {
                     return html_container("No Permissions");
                 }
 
@@ -1,7 +1,7 @@
 use common::api::ApiErrorResponse;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
-use yew_hooks::use_async;
+use yew_hooks::{use_async, use_async_with_options, UseAsyncOptions};
 use yew_router::prelude::RouterScopeExt;
 
 use crate::{request, BaseRoute};
@@ -53,6 +53,7 @@ impl Component for LoginPage {
                         // TODO: Impl. display for selecting with login to use. Don't display both at the same time.
                         <PasswordlessLogin cb={ ctx.link().callback(Msg::LoginPasswordlessResponse) } />
                         <PasswordLogin cb={ ctx.link().callback(Msg::LoginPasswordResponse) } />
+                        <OidcLogin />
                     </div>
                 </div>
             </div>
@@ -223,3 +224,24 @@ pub fn _password(props: &InnerProps) -> Html {
         </>
     }
 }
+
+/// Only shown when an OpenID Connect provider is configured.
+#[function_component(OidcLogin)]
+pub fn _oidc() -> Html {
+    let resp = use_async_with_options(
+        async move { request::get_oidc_provider().await.ok() },
+        UseAsyncOptions::enable_auto(),
+    );
+
+    let Some(provider) = resp.data.clone().flatten() else {
+        return html! {};
+    };
+
+    html! {
+        <>
+            <h2>{ "Single Sign-On" }</h2>
+
+            <a class="btn btn-primary" href="/auth/oidc">{ format!("Log in with {}", provider.display_name) }</a>
+        </>
+    }
+}
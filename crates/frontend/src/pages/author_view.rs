@@ -7,6 +7,7 @@ use common::{
 use common_local::{
     api::{self, GetPersonResponse, GetPostersResponse},
     filter::FilterContainer,
+    PersonField,
 };
 use wasm_bindgen::JsCast;
 use web_sys::{HtmlInputElement, HtmlTextAreaElement};
@@ -27,6 +28,7 @@ pub enum Msg {
     ToggleEdit,
     SaveEdits,
     UpdateEditing(ChangingType, String),
+    ToggleLock(PersonField),
 
     Ignore,
 }
@@ -113,6 +115,28 @@ impl Component for AuthorView {
             }
 
             Msg::SaveEdits => {
+                if let Some((media, editing)) = self.media.as_ref().zip(self.editing_item.as_ref())
+                {
+                    if media.locked_fields != editing.locked_fields {
+                        let person_id = editing.person.id;
+                        let locked_fields = editing.locked_fields.clone();
+
+                        ctx.link().send_future(async move {
+                            let resp = request::update_person(
+                                person_id,
+                                &api::PostPersonBody::UpdateLockedFields(locked_fields),
+                            )
+                            .await;
+
+                            if let Err(err) = resp.ok() {
+                                crate::display_error(err);
+                            }
+
+                            Msg::Ignore
+                        });
+                    }
+                }
+
                 self.media = self.editing_item.clone();
 
                 // let metadata = self.media.as_ref().and_then(|v| v.resp.as_ref()).unwrap().person.clone();
@@ -144,6 +168,16 @@ impl Component for AuthorView {
                 }
             }
 
+            Msg::ToggleLock(field) => {
+                let locked = &mut self.editing_item.as_mut().unwrap().locked_fields;
+
+                if let Some(index) = locked.iter().position(|v| *v == field) {
+                    locked.remove(index);
+                } else {
+                    locked.push(field);
+                }
+            }
+
             Msg::RetrievePosters(resp) => match resp.ok() {
                 Ok(resp) => self.cached_posters = Some(resp),
                 Err(err) => crate::display_error(err),
@@ -163,7 +197,11 @@ impl Component for AuthorView {
 
         let resp = self.editing_item.as_ref().or(media);
 
-        if let Some(GetPersonResponse { person }) = resp {
+        if let Some(GetPersonResponse {
+            person,
+            locked_fields,
+        }) = resp
+        {
             html! {
                 <div class="outer-view-container">
                     <div class="sidebar-container">
@@ -267,6 +305,20 @@ impl Component for AuthorView {
                                                 <input class="title" type="text" />
 
                                                 <h5>{ "Tags" }</h5>
+
+                                                <h5>{ "Locked Fields" }</h5>
+                                                {
+                                                    for PersonField::ALL.into_iter().map(|field| html! {
+                                                        <div class="form-check">
+                                                            <input
+                                                                class="form-check-input" type="checkbox" id={ format!("lock-{}", u8::from(field)) }
+                                                                checked={ locked_fields.contains(&field) }
+                                                                onchange={ ctx.link().callback(move |_| Msg::ToggleLock(field)) }
+                                                            />
+                                                            <label class="form-check-label" for={ format!("lock-{}", u8::from(field)) }>{ field.name() }</label>
+                                                        </div>
+                                                    })
+                                                }
                                             </div>
                                         }
                                     } else {
@@ -139,6 +139,7 @@ impl BookPage {
             book,
             media,
             progress,
+            ..
         }) = self.media.as_ref()
         {
             let book_id = book.id;
@@ -15,6 +15,7 @@ pub mod list_collections;
 pub mod reading;
 pub mod settings;
 pub mod setup;
+pub mod text_search;
 
 pub use author_view::AuthorView;
 pub use book::BookPage;
@@ -25,3 +26,4 @@ pub use list_authors::AuthorListPage;
 pub use list_collections::CollectionListPage;
 pub use reading::ReadingBook;
 pub use setup::SetupPage;
+pub use text_search::TextSearchPage;
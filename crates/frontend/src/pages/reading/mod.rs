@@ -11,7 +11,8 @@ use common::{
 };
 use common_local::{
     api::{self, GetChaptersResponse},
-    FileId, MediaItem, Progression,
+    Bookmark, BookmarkId, FileId, HighlightColor, MediaItem, Notation, NotationAnchor, NotationId,
+    Progression, TocItem,
 };
 use gloo_timers::callback::Timeout;
 use gloo_utils::window;
@@ -23,8 +24,9 @@ use crate::{
     components::{
         notes::Notes,
         reader::{
-            DragType, LoadedChapters, ReaderEvent, ReaderSettings, OverlayEvent, Reader
-        }
+            DragType, LoadedChapters, ReaderEvent, ReaderSettings, OverlayEvent, Reader, ReaderJump, ReaderPosition, TextSelection
+        },
+        FileBookmarks, FileNotations, FileSearch, TableOfContents,
     },
     request, AppState,
 };
@@ -35,6 +37,10 @@ use settings::*;
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum LocalPopupType {
     Notes,
+    Contents,
+    Search,
+    Annotations,
+    Bookmarks,
     Settings,
 }
 
@@ -59,12 +65,29 @@ pub enum Msg {
 
     ChangeReaderSettings(ReaderSettings),
 
+    ToggleHighlighting,
+    CreateNotation(HighlightColor),
+    UpdateNotation(NotationId, api::UpdateNotationBody),
+    DeleteNotation(NotationId),
+
+    RenameBookmark(BookmarkId, String),
+    DeleteBookmark(BookmarkId),
+
+    JumpTo(ReaderJump),
+
     // Send
     ReaderEvent(ReaderEvent),
 
     // Retrieve
     RetrieveBook(WrappingResponse<api::ApiGetFileByIdResponse>),
     RetrievePages(WrappingResponse<GetChaptersResponse>),
+    RetrieveNotations(WrappingResponse<api::ApiGetFileNotationsByIdResponse>),
+    RetrieveNotation(WrappingResponse<Notation>),
+    NotationDeleted(NotationId),
+    RetrieveBookmarks(WrappingResponse<api::ApiGetFileBookmarksByIdResponse>),
+    RetrieveBookmark(WrappingResponse<Bookmark>),
+    BookmarkDeleted(BookmarkId),
+    RetrieveToc(WrappingResponse<api::ApiGetFileTocByIdResponse>),
 
     ContextChanged(Rc<AppState>),
 }
@@ -88,6 +111,14 @@ pub struct ReadingBook {
 
     sidebar_visible: Option<LocalPopupType>,
 
+    notations: Rc<Vec<Notation>>,
+    bookmarks: Rc<Vec<Bookmark>>,
+    toc: Rc<Vec<TocItem>>,
+    jump_to: Rc<Mutex<Option<ReaderJump>>>,
+    /// The page overlay is hidden while highlighting so text can be selected.
+    is_highlighting: bool,
+    selection: Option<TextSelection>,
+
     display_toolbar: DisplayToolBars,
     timeout: Option<Timeout>,
 
@@ -137,6 +168,14 @@ impl Component for ReadingBook {
             auto_resize_cb: None,
 
             sidebar_visible: None,
+
+            notations: Rc::new(Vec::new()),
+            bookmarks: Rc::new(Vec::new()),
+            toc: Rc::new(Vec::new()),
+            jump_to: Rc::new(Mutex::new(None)),
+            is_highlighting: false,
+            selection: None,
+
             display_toolbar,
             timeout: None,
 
@@ -217,6 +256,147 @@ impl Component for ReadingBook {
                 self.sidebar_visible = None;
             }
 
+            Msg::ToggleHighlighting => {
+                self.is_highlighting = !self.is_highlighting;
+                self.selection = None;
+            }
+
+            Msg::CreateNotation(color) => {
+                let Some(selection) = self.selection.take() else {
+                    return false;
+                };
+
+                let file_id = ctx.props().id;
+
+                ctx.link().send_future(async move {
+                    Msg::RetrieveNotation(request::new_file_notation(file_id, api::NewNotationBody {
+                        anchor: selection.anchor,
+                        color,
+                        text: selection.text,
+                        note: None,
+                    }).await)
+                });
+            }
+
+            Msg::UpdateNotation(id, body) => {
+                let file_id = ctx.props().id;
+
+                ctx.link().send_future(async move {
+                    Msg::RetrieveNotation(request::update_file_notation(file_id, id, body).await)
+                });
+
+                return false;
+            }
+
+            Msg::DeleteNotation(id) => {
+                let file_id = ctx.props().id;
+
+                ctx.link().send_future(async move {
+                    match request::delete_file_notation(file_id, id).await.ok() {
+                        Ok(_) => Msg::NotationDeleted(id),
+                        Err(err) => {
+                            crate::display_error(err);
+                            Msg::NotationDeleted(NotationId::none())
+                        }
+                    }
+                });
+
+                return false;
+            }
+
+            Msg::RenameBookmark(id, label) => {
+                let file_id = ctx.props().id;
+
+                ctx.link().send_future(async move {
+                    Msg::RetrieveBookmark(request::update_file_bookmark(file_id, id, api::UpdateBookmarkBody { label }).await)
+                });
+
+                return false;
+            }
+
+            Msg::DeleteBookmark(id) => {
+                let file_id = ctx.props().id;
+
+                ctx.link().send_future(async move {
+                    match request::delete_file_bookmark(file_id, id).await.ok() {
+                        Ok(_) => Msg::BookmarkDeleted(id),
+                        Err(err) => {
+                            crate::display_error(err);
+                            Msg::BookmarkDeleted(BookmarkId::none())
+                        }
+                    }
+                });
+
+                return false;
+            }
+
+            Msg::JumpTo(jump) => {
+                *self.jump_to.lock().unwrap() = Some(jump);
+            }
+
+            Msg::RetrieveBookmarks(resp) => match resp.ok() {
+                Ok(items) => self.bookmarks = Rc::new(items),
+                Err(err) => crate::display_error(err),
+            },
+
+            Msg::RetrieveBookmark(resp) => match resp.ok() {
+                Ok(bookmark) => {
+                    let mut items = self.bookmarks.iter()
+                        .filter(|v| v.id != bookmark.id)
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    items.push(bookmark);
+
+                    self.bookmarks = Rc::new(items);
+                }
+
+                Err(err) => crate::display_error(err),
+            },
+
+            Msg::RetrieveToc(resp) => match resp.ok() {
+                Ok(items) => self.toc = Rc::new(items),
+                Err(err) => crate::display_error(err),
+            },
+
+            Msg::BookmarkDeleted(id) => {
+                self.bookmarks = Rc::new(
+                    self.bookmarks.iter()
+                        .filter(|v| v.id != id)
+                        .cloned()
+                        .collect()
+                );
+            }
+
+            Msg::RetrieveNotations(resp) => match resp.ok() {
+                Ok(items) => self.notations = Rc::new(items),
+                Err(err) => crate::display_error(err),
+            },
+
+            Msg::RetrieveNotation(resp) => match resp.ok() {
+                Ok(notation) => {
+                    let mut items = self.notations.iter()
+                        .filter(|v| v.id != notation.id)
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    items.push(notation);
+
+                    self.notations = Rc::new(items);
+                }
+
+                Err(err) => crate::display_error(err),
+            },
+
+            Msg::NotationDeleted(id) => {
+                self.notations = Rc::new(
+                    self.notations.iter()
+                        .filter(|v| v.id != id)
+                        .cloned()
+                        .collect()
+                );
+            }
+
             Msg::ShowPopup(type_of) => match self.sidebar_visible {
                 Some(v) if v == type_of => {
                     self.sidebar_visible = None;
@@ -251,6 +431,18 @@ impl Component for ReadingBook {
                         });
                     }
 
+                    ctx.link().send_future(async move {
+                        Msg::RetrieveNotations(request::get_file_notations(file_id).await)
+                    });
+
+                    ctx.link().send_future(async move {
+                        Msg::RetrieveBookmarks(request::get_file_bookmarks(file_id).await)
+                    });
+
+                    ctx.link().send_future(async move {
+                        Msg::RetrieveToc(request::get_file_toc(file_id).await)
+                    });
+
                     self.book = Some(Rc::new(resp.media));
                     *self.progress.lock().unwrap() = resp.progress;
                 }
@@ -282,6 +474,24 @@ impl Component for ReadingBook {
                             }
                         }
                     }
+
+                    ReaderEvent::AddBookmark(body) => {
+                        let file_id = ctx.props().id;
+
+                        ctx.link().send_future(async move {
+                            Msg::RetrieveBookmark(request::new_file_bookmark(file_id, body).await)
+                        });
+                    }
+
+                    ReaderEvent::TextSelection(selection) => {
+                        if !self.is_highlighting {
+                            return false;
+                        }
+
+                        self.selection = selection;
+
+                        return true;
+                    }
                 }
 
                 return false;
@@ -319,6 +529,38 @@ impl Component for ReadingBook {
                                         </Popup>
                                     },
 
+                                    LocalPopupType::Contents => html! {
+                                        <TableOfContents
+                                            items={ Rc::clone(&self.toc) }
+                                            on_jump={ ctx.link().callback(|(chapter, fragment)| Msg::JumpTo(ReaderJump::Fragment(chapter, fragment))) }
+                                        />
+                                    },
+
+                                    LocalPopupType::Search => html! {
+                                        <FileSearch
+                                            file_id={ ctx.props().id }
+                                            on_jump={ ctx.link().callback(|position| Msg::JumpTo(ReaderJump::Position(position))) }
+                                        />
+                                    },
+
+                                    LocalPopupType::Annotations => html! {
+                                        <FileNotations
+                                            notations={ Rc::clone(&self.notations) }
+                                            on_jump={ ctx.link().callback(|anchor: NotationAnchor| Msg::JumpTo(ReaderJump::Position(ReaderPosition { chapter: anchor.chapter, char_pos: anchor.start }))) }
+                                            on_update={ ctx.link().callback(|(id, body)| Msg::UpdateNotation(id, body)) }
+                                            on_delete={ ctx.link().callback(Msg::DeleteNotation) }
+                                        />
+                                    },
+
+                                    LocalPopupType::Bookmarks => html! {
+                                        <FileBookmarks
+                                            bookmarks={ Rc::clone(&self.bookmarks) }
+                                            on_jump={ ctx.link().callback(|position| Msg::JumpTo(ReaderJump::Position(position))) }
+                                            on_rename={ ctx.link().callback(|(id, label)| Msg::RenameBookmark(id, label)) }
+                                            on_delete={ ctx.link().callback(Msg::DeleteBookmark) }
+                                        />
+                                    },
+
                                     LocalPopupType::Settings => html! {
                                         <SettingsContainer
                                             scope={ ctx.link().clone() }
@@ -338,11 +580,36 @@ impl Component for ReadingBook {
                             book={ Rc::clone(book) }
                             chapters={ Rc::clone(&self.chapters) }
                             event={ ctx.link().callback(Msg::ReaderEvent) }
+                            notations={ Rc::clone(&self.notations) }
+                            bookmarks={ Rc::clone(&self.bookmarks) }
+                            jump_to={ Rc::clone(&self.jump_to) }
+                            allow_text_selection={ self.is_highlighting }
                         />
                     </div>
 
                     <div class={ classes!("tools", (self.reader_settings.default_full_screen && !self.display_toolbar.is_expanded()).then_some("hidden")) }>
                         <button class="btn btn-sm btn-secondary tool-item" title="Open/Close the Notebook" onclick={ ctx.link().callback(|_| Msg::ShowPopup(LocalPopupType::Notes)) }>{ "📝" }</button>
+                        <button class="btn btn-sm btn-secondary tool-item" title="Open/Close the Table of Contents" onclick={ ctx.link().callback(|_| Msg::ShowPopup(LocalPopupType::Contents)) }>{ "📑" }</button>
+                        <button class="btn btn-sm btn-secondary tool-item" title="Open/Close the Search" onclick={ ctx.link().callback(|_| Msg::ShowPopup(LocalPopupType::Search)) }>{ "🔍" }</button>
+                        <button class="btn btn-sm btn-secondary tool-item" title="Open/Close the Annotations" onclick={ ctx.link().callback(|_| Msg::ShowPopup(LocalPopupType::Annotations)) }>{ "💬" }</button>
+                        <button class="btn btn-sm btn-secondary tool-item" title="Open/Close the Bookmarks" onclick={ ctx.link().callback(|_| Msg::ShowPopup(LocalPopupType::Bookmarks)) }>{ "🔖" }</button>
+                        <button class={ classes!("btn", "btn-sm", "tool-item", if self.is_highlighting { "btn-primary" } else { "btn-secondary" }) } title="Start/Stop Highlighting" onclick={ ctx.link().callback(|_| Msg::ToggleHighlighting) }>{ "🖍️" }</button>
+
+                        {
+                            if self.selection.is_some() {
+                                html! {
+                                    for HighlightColor::get_all().into_iter().map(|color| html! {
+                                        <span
+                                            class={ classes!("tool-item", "annotation-color", color.name()) }
+                                            title={ format!("Highlight in {}", color.name()) }
+                                            onclick={ ctx.link().callback(move |_| Msg::CreateNotation(color)) }
+                                        ></span>
+                                    })
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
                         // <button class="btn btn-sm btn-secondary tool-item" title="Open/Close the Settings" onclick={ ctx.link().callback(|_| Msg::ShowPopup(LocalPopupType::Settings)) }>{ "⚙️" }</button>
                     </div>
                 </div>
@@ -1,6 +1,6 @@
 use common::api::WrappingResponse;
 use common::MemberId;
-use common_local::api;
+use common_local::{api, GroupPermissions, LibraryColl, LibraryId, Member};
 use gloo_utils::window;
 use wasm_bindgen::{JsCast, UnwrapThrowExt};
 use web_sys::HtmlInputElement;
@@ -11,6 +11,7 @@ use crate::request;
 pub enum Msg {
     // Request Results
     MembersResults(Box<WrappingResponse<api::ApiGetMembersListResponse>>),
+    LibrariesResults(WrappingResponse<api::ApiGetLibrariesResponse>),
 
     RequestUpdateOptions(api::UpdateMember),
     InviteMember { email: String },
@@ -20,6 +21,7 @@ pub enum Msg {
 
 pub struct AdminMembersPage {
     resp: Option<api::ApiGetMembersListResponse>,
+    libraries: Vec<LibraryColl>,
     visible_popup: Option<(usize, MemberId)>,
 }
 
@@ -30,6 +32,7 @@ impl Component for AdminMembersPage {
     fn create(_ctx: &Context<Self>) -> Self {
         Self {
             resp: None,
+            libraries: Vec::new(),
             visible_popup: None,
         }
     }
@@ -44,6 +47,11 @@ impl Component for AdminMembersPage {
                 Err(err) => crate::display_error(err),
             },
 
+            Msg::LibrariesResults(resp) => match resp.ok() {
+                Ok(resp) => self.libraries = resp.items,
+                Err(err) => crate::display_error(err),
+            },
+
             Msg::RequestUpdateOptions(options) => {
                 ctx.link().send_future(async move {
                     request::update_member(options).await;
@@ -98,7 +106,7 @@ impl Component for AdminMembersPage {
                     <table class="table table-dark table-striped">
                         <thead>
                             <tr>
-                                <td colspan="3">
+                                <td colspan="4">
                                     <h4>{ "Members" }</h4>
                                 </td>
                             </tr>
@@ -118,6 +126,9 @@ impl Component for AdminMembersPage {
                                                 <td>
                                                     <span class="title" title={ v.email.clone() }>{ v.name.clone() }</span>
                                                 </td>
+                                                <td>
+                                                    { self.render_permissions(v, resp, ctx) }
+                                                </td>
 
                                                 {
                                                     if v.permissions.is_owner() {
@@ -220,6 +231,106 @@ impl Component for AdminMembersPage {
         if first_render {
             ctx.link()
                 .send_future(async { Msg::MembersResults(Box::new(request::get_members().await)) });
+
+            ctx.link()
+                .send_future(async { Msg::LibrariesResults(request::get_libraries().await) });
+        }
+    }
+}
+
+impl AdminMembersPage {
+    fn render_permissions(&self, member: &Member, resp: &api::GetMembersListResponse, ctx: &Context<Self>) -> Html {
+        if member.permissions.is_owner() {
+            return html! {
+                <span class="label">{ "Owner" }</span>
+            };
+        }
+
+        let member_id = member.id;
+        let permissions = member.permissions;
+
+        let libraries = resp.libraries.iter()
+            .find(|v| v.0 == member_id)
+            .map(|v| v.1.clone())
+            .unwrap_or_default();
+
+        html! {
+            <>
+                {
+                    for GroupPermissions::get_capabilities().into_iter().map(|(flag, name)| {
+                        let libraries = libraries.clone();
+
+                        html! {
+                            <div class="form-check form-check-inline">
+                                <input class="form-check-input" type="checkbox"
+                                    checked={ permissions.contains_group(flag) }
+                                    onchange={ ctx.link().callback(move |event: Event| {
+                                        let mut permissions = permissions;
+                                        permissions.group.set(flag, event.target_unchecked_into::<HtmlInputElement>().checked());
+
+                                        Msg::RequestUpdateOptions(api::UpdateMember::Permissions {
+                                            id: member_id,
+                                            permissions,
+                                            libraries: libraries.clone(),
+                                        })
+                                    }) }
+                                />
+                                <label class="form-check-label">{ name }</label>
+                            </div>
+                        }
+                    })
+                }
+
+                {
+                    if permissions.contains_group(GroupPermissions::ALL_LIBRARIES) {
+                        html! {}
+                    } else {
+                        html! {
+                            <div>
+                                {
+                                    for self.libraries.iter().map(|library| {
+                                        let library_id = library.id;
+                                        let libraries = libraries.clone();
+
+                                        html! {
+                                            <div class="form-check form-check-inline">
+                                                <input class="form-check-input" type="checkbox"
+                                                    checked={ libraries.contains(&library_id) }
+                                                    onchange={ ctx.link().callback(move |event: Event| {
+                                                        Msg::RequestUpdateOptions(api::UpdateMember::Permissions {
+                                                            id: member_id,
+                                                            permissions,
+                                                            libraries: toggle_library(
+                                                                &libraries,
+                                                                library_id,
+                                                                event.target_unchecked_into::<HtmlInputElement>().checked(),
+                                                            ),
+                                                        })
+                                                    }) }
+                                                />
+                                                <label class="form-check-label">{ library.name.clone() }</label>
+                                            </div>
+                                        }
+                                    })
+                                }
+                            </div>
+                        }
+                    }
+                }
+            </>
         }
     }
 }
+
+fn toggle_library(libraries: &[LibraryId], library_id: LibraryId, enabled: bool) -> Vec<LibraryId> {
+    let mut libraries = libraries.iter()
+        .copied()
+        .filter(|v| *v != library_id)
+        .collect::<Vec<_>>();
+
+    if enabled {
+        libraries.push(library_id);
+    }
+
+    libraries
+}
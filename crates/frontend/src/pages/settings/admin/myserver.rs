@@ -1,6 +1,11 @@
-use common::api::WrappingResponse;
-use common_local::api;
-use web_sys::HtmlSelectElement;
+use chrono::Local;
+use common::api::{ApiErrorResponse, WrappingResponse};
+use common_local::{
+    api,
+    setup::{ConfigSchedule, ScheduledTask},
+    CronSchedule, LibraryColl, LibraryId,
+};
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 
 use crate::request;
@@ -11,6 +16,8 @@ pub enum Msg {
 
     // Events
     RequestUpdateOptions(bool, api::ModifyOptionsBody),
+
+    Ignore,
 }
 
 pub struct AdminMyServerPage {
@@ -43,6 +50,8 @@ impl Component for AdminMyServerPage {
                     Msg::OptionsResults(Box::new(request::get_options().await))
                 });
             }
+
+            Msg::Ignore => (),
         }
 
         true
@@ -98,6 +107,10 @@ impl Component for AdminMyServerPage {
                             }
                         }
                     }
+
+                    <br />
+
+                    { self.render_schedules(&config.schedules, &resp.libraries, ctx) }
                 </>
             }
         } else {
@@ -120,3 +133,126 @@ impl Component for AdminMyServerPage {
         }
     }
 }
+
+impl AdminMyServerPage {
+    fn render_schedules(
+        &self,
+        schedules: &[ConfigSchedule],
+        libraries: &[LibraryColl],
+        ctx: &Context<Self>,
+    ) -> Html {
+        let cron_ref = NodeRef::default();
+        let task_ref = NodeRef::default();
+        let library_ref = NodeRef::default();
+
+        let library_ids = libraries.iter().map(|v| v.id).collect::<Vec<_>>();
+
+        let get_library_name = |library_id: Option<LibraryId>| match library_id {
+            Some(id) => libraries
+                .iter()
+                .find(|v| v.id == id)
+                .map(|v| v.name.clone())
+                .unwrap_or_else(|| format!("Library {id}")),
+            None => String::from("All Libraries"),
+        };
+
+        html! {
+            <>
+                <h4>{ "Scheduled Tasks" }</h4>
+
+                <table class="table table-dark table-striped">
+                    <tbody>
+                        {
+                            for schedules.iter()
+                                .map(|schedule| {
+                                    let next_run = schedule.cron.parse::<CronSchedule>()
+                                        .ok()
+                                        .and_then(|v| v.next_after(&Local::now()))
+                                        .map(|v| v.format("%Y-%m-%d %H:%M").to_string())
+                                        .unwrap_or_else(|| String::from("Never"));
+
+                                    let library = match schedule.task {
+                                        ScheduledTask::LibraryScan { library_id }
+                                        | ScheduledTask::RefreshMetadata { library_id } => get_library_name(library_id),
+                                        ScheduledTask::CleanOrphanImages => String::new(),
+                                    };
+
+                                    let schedule = schedule.clone();
+
+                                    html! {
+                                        <tr>
+                                            <td><span class="label">{ schedule.cron.clone() }</span></td>
+                                            <td><span class="title">{ schedule.task.name() }</span></td>
+                                            <td>{ library }</td>
+                                            <td><span title="Next Run">{ next_run }</span></td>
+                                            <td>
+                                                <button class="btn btn-danger btn-sm" onclick={ ctx.link().callback(move |_| {
+                                                    Msg::RequestUpdateOptions(
+                                                        false,
+                                                        api::ModifyOptionsBody {
+                                                            schedule: Some(schedule.clone()),
+                                                            .. Default::default()
+                                                        }
+                                                    )
+                                                }) }>{ "Remove" }</button>
+                                            </td>
+                                        </tr>
+                                    }
+                                })
+                        }
+                    </tbody>
+                </table>
+
+                <div class="input-group mb-3">
+                    <input class="form-control" ref={ cron_ref.clone() } type="text" placeholder="Cron Expression (0 3 * * *)" />
+
+                    <select class="form-select" ref={ task_ref.clone() }>
+                        <option value="0" selected=true>{ "Library Scan" }</option>
+                        <option value="1">{ "Refresh Metadata" }</option>
+                        <option value="2">{ "Clean Orphan Images" }</option>
+                    </select>
+
+                    <select class="form-select" ref={ library_ref.clone() }>
+                        <option selected=true>{ "All Libraries" }</option>
+                        {
+                            for libraries.iter()
+                                .map(|v| html! {
+                                    <option>{ v.name.clone() }</option>
+                                })
+                        }
+                    </select>
+
+                    <button class="btn btn-primary" onclick={ ctx.link().callback(move |_| {
+                        let cron = cron_ref.cast::<HtmlInputElement>().unwrap().value();
+
+                        if let Err(e) = cron.parse::<CronSchedule>() {
+                            crate::display_error(ApiErrorResponse {
+                                description: format!("Invalid Schedule: {e}"),
+                            });
+
+                            return Msg::Ignore;
+                        }
+
+                        // The first option is every library.
+                        let library_index = library_ref.cast::<HtmlSelectElement>().unwrap().selected_index();
+                        let library_id = usize::try_from(library_index - 1).ok().and_then(|v| library_ids.get(v).copied());
+
+                        let task = match task_ref.cast::<HtmlSelectElement>().unwrap().selected_index() {
+                            0 => ScheduledTask::LibraryScan { library_id },
+                            1 => ScheduledTask::RefreshMetadata { library_id },
+                            _ => ScheduledTask::CleanOrphanImages,
+                        };
+
+                        Msg::RequestUpdateOptions(
+                            true,
+                            api::ModifyOptionsBody {
+                                schedule: Some(ConfigSchedule { cron, task }),
+                                .. Default::default()
+                            }
+                        )
+                    }) }>{ "Add" }</button>
+                </div>
+            </>
+        }
+    }
+}
@@ -1,17 +1,39 @@
 use std::rc::Rc;
 
-use common_local::ws::{TaskInfo, TaskType, WebsocketNotification};
+use common::api::WrappingResponse;
+use common_local::{
+    api,
+    ws::{TaskId, TaskInfo, WebsocketNotification},
+    TaskStatus,
+};
+use gloo_utils::window;
+use wasm_bindgen::UnwrapThrowExt;
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
 use yew_agent::{Bridge, Bridged};
 
-use crate::{services::WsEventBus, RUNNING_TASKS};
+use crate::{components::OwnerBarrier, request, services::WsEventBus, RUNNING_TASKS};
+
+pub enum Msg {
+    // Request Results
+    HistoryResults(Box<WrappingResponse<api::ApiGetTaskHistoryResponse>>),
+
+    Notification(WebsocketNotification),
+
+    CancelTask(TaskId),
+    ImportCalibre(String),
+
+    Ignore,
+}
 
 pub struct AdminTaskPage {
     _producer: Box<dyn Bridge<WsEventBus>>,
+
+    history: Option<api::ApiGetTaskHistoryResponse>,
 }
 
 impl Component for AdminTaskPage {
-    type Message = WebsocketNotification;
+    type Message = Msg;
     type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
@@ -19,17 +41,24 @@ impl Component for AdminTaskPage {
             _producer: {
                 let cb = {
                     let link = ctx.link().clone();
-                    move |e| link.send_message(e)
+                    move |e| link.send_message(Msg::Notification(e))
                 };
 
                 WsEventBus::bridge(Rc::new(cb))
             },
+
+            history: None,
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            WebsocketNotification::TaskStart { id, name } => {
+            Msg::HistoryResults(resp) => match resp.ok() {
+                Ok(resp) => self.history = Some(resp),
+                Err(err) => crate::display_error(err),
+            },
+
+            Msg::Notification(WebsocketNotification::TaskStart { id, name }) => {
                 RUNNING_TASKS.lock().unwrap().insert(
                     id,
                     TaskInfo {
@@ -39,11 +68,11 @@ impl Component for AdminTaskPage {
                 );
             }
 
-            WebsocketNotification::TaskUpdate {
+            Msg::Notification(WebsocketNotification::TaskUpdate {
                 id,
                 type_of,
                 inserting,
-            } => {
+            }) => {
                 if let Some(info) = RUNNING_TASKS.lock().unwrap().get_mut(&id) {
                     if inserting {
                         info.current = Some(type_of);
@@ -53,17 +82,48 @@ impl Component for AdminTaskPage {
                 }
             }
 
-            WebsocketNotification::TaskEnd(id) => {
+            Msg::Notification(WebsocketNotification::TaskEnd(id)) => {
                 RUNNING_TASKS.lock().unwrap().remove(&id);
+
+                ctx.link().send_future(async {
+                    Msg::HistoryResults(Box::new(request::get_task_history().await))
+                });
+            }
+
+            Msg::CancelTask(id) => {
+                ctx.link().send_future(async move {
+                    if let Err(err) = request::cancel_task(id).await.ok() {
+                        crate::display_error(err);
+                    }
+
+                    Msg::HistoryResults(Box::new(request::get_task_history().await))
+                });
+            }
+
+            Msg::ImportCalibre(path) => {
+                ctx.link().send_future(async move {
+                    let resp = request::run_task(api::RunTaskBody {
+                        import_calibre: Some(path),
+
+                        ..Default::default()
+                    })
+                    .await;
+
+                    if let Err(err) = resp.ok() {
+                        crate::display_error(err);
+                    }
+
+                    Msg::Ignore
+                });
             }
+
+            Msg::Ignore => (),
         }
 
         true
     }
 
-    fn view(&self, _ctx: &Context<Self>) -> Html {
-        // let member = get_member_self().unwrap();
-
+    fn view(&self, ctx: &Context<Self>) -> Html {
         let tasks = RUNNING_TASKS.lock().unwrap();
 
         html! {
@@ -81,18 +141,26 @@ impl Component for AdminTaskPage {
                                 }
                             } else {
                                 html! {
-                                    for tasks.values()
-                                        .map(|task| html! {
+                                    for tasks.iter()
+                                        .map(|(&id, task)| html! {
                                             <div>
                                                 <h4>{ task.name.clone() }</h4>
 
                                                 {
                                                     for task.current.clone()
                                                         .map(|type_of| html! {
-                                                            <p>{ render_type_of(type_of) }</p>
+                                                            <p>{ type_of.to_string() }</p>
                                                         })
                                                 }
 
+                                                <button class="btn btn-danger btn-sm" onclick={ ctx.link().callback(move |_| {
+                                                    if window().confirm_with_message("Are you sure you want to cancel this?").unwrap_throw() {
+                                                        Msg::CancelTask(id)
+                                                    } else {
+                                                        Msg::Ignore
+                                                    }
+                                                }) }>{ "Cancel" }</button>
+
                                                 <br />
                                             </div>
                                         })
@@ -100,18 +168,126 @@ impl Component for AdminTaskPage {
                             }
                         }
                     </div>
+
+                    <br />
+
+                    { self.render_calibre_import(ctx) }
+
+                    <br />
+
+                    { self.render_history(ctx) }
                 </div>
             </div>
         }
     }
+
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if first_render {
+            ctx.link().send_future(async {
+                Msg::HistoryResults(Box::new(request::get_task_history().await))
+            });
+        }
+    }
 }
 
-fn render_type_of(type_of: TaskType) -> String {
-    match type_of {
-        TaskType::UpdatingBook { id, subtitle } => {
-            subtitle.unwrap_or_else(|| format!("Updating {id:?}"))
+impl AdminTaskPage {
+    fn render_calibre_import(&self, ctx: &Context<Self>) -> Html {
+        let path_ref = NodeRef::default();
+
+        // It adds a directory to a library.
+        html! {
+            <OwnerBarrier>
+                <h4>{ "Import Calibre Library" }</h4>
+
+                <div class="input-group mb-3">
+                    <input class="form-control" ref={ path_ref.clone() } type="text" placeholder="Folder containing metadata.db" />
+
+                    <button class="btn btn-primary" onclick={ ctx.link().callback(move |_| {
+                        let path = path_ref.cast::<HtmlInputElement>().unwrap().value();
+
+                        if path.trim().is_empty() {
+                            Msg::Ignore
+                        } else {
+                            Msg::ImportCalibre(path)
+                        }
+                    }) }>{ "Import" }</button>
+                </div>
+            </OwnerBarrier>
         }
+    }
+
+    fn render_history(&self, ctx: &Context<Self>) -> Html {
+        let Some(history) = self.history.as_ref() else {
+            return html! {
+                <h4>{ "Loading..." }</h4>
+            };
+        };
 
-        TaskType::LibraryScan(file_name) => file_name,
+        html! {
+            <table class="table table-dark table-striped">
+                <thead>
+                    <tr>
+                        <td colspan="6">
+                            <h4>{ "History" }</h4>
+                        </td>
+                    </tr>
+                </thead>
+
+                <tbody>
+                    {
+                        for history.iter()
+                            .map(|v| {
+                                let id = v.id;
+
+                                html! {
+                                    <tr>
+                                        <td>
+                                            <span class="label">{ format!("{:?}", v.status) }</span>
+                                        </td>
+                                        <td>
+                                            <span class="title">{ v.name.clone() }</span>
+                                        </td>
+                                        <td>
+                                            <span title="Queued">{ v.queued_at.format("%Y-%m-%d %H:%M:%S").to_string() }</span>
+                                        </td>
+                                        <td>
+                                            {
+                                                match (v.started_at, v.finished_at) {
+                                                    (Some(started_at), Some(finished_at)) => format!("{}s", (finished_at - started_at).num_seconds()),
+                                                    (Some(_), None) => String::from("Running"),
+                                                    _ => String::new(),
+                                                }
+                                            }
+                                        </td>
+                                        <td>
+                                            {
+                                                if let Some(error) = v.error.clone() {
+                                                    html! { <span class="text-danger">{ error }</span> }
+                                                } else {
+                                                    html! { <span>{ v.progress.clone().unwrap_or_default() }</span> }
+                                                }
+                                            }
+                                        </td>
+
+                                        {
+                                            if v.status == TaskStatus::Queued {
+                                                html! {
+                                                    <td>
+                                                        <button class="btn btn-danger btn-sm" onclick={ ctx.link().callback(move |_| Msg::CancelTask(id)) }>{ "Cancel" }</button>
+                                                    </td>
+                                                }
+                                            } else {
+                                                html! {
+                                                    <td></td>
+                                                }
+                                            }
+                                        }
+                                    </tr>
+                                }
+                            })
+                    }
+                </tbody>
+            </table>
+        }
     }
 }
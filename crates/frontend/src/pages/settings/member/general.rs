@@ -1,7 +1,8 @@
 use std::rc::Rc;
 
 use common::api::WrappingResponse;
-use common_local::{MemberBasicPreferences, reader::ReaderColor, MemberPreferences};
+use common_local::{api::{ApiGetKoreaderSyncResponse, GetKoreaderSyncResponse}, MemberBasicPreferences, reader::ReaderColor, MemberPreferences};
+use gloo_utils::window;
 use wasm_bindgen::UnwrapThrowExt;
 use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
@@ -11,12 +12,17 @@ use crate::{request, components::reader::PageLoadType, AppState};
 pub enum Msg {
     // Request Results
     PrefsResult(WrappingResponse<MemberPreferences>),
+    KoreaderResult(WrappingResponse<ApiGetKoreaderSyncResponse>),
 
     // Events
     UpdateSettings(EditingType, Box<dyn Fn(&mut MemberBasicPreferences, serde_json::Value)>, serde_json::Value),
 
     ContextChanged(Rc<AppState>),
 
+    UpdateKoreaderPassword(String),
+    /// Removes the sync password when None.
+    SaveKoreader(Option<String>),
+
     Submit,
     Ignore,
 }
@@ -26,6 +32,9 @@ pub struct MemberGeneralPage {
     _listener: ContextHandle<Rc<AppState>>,
 
     preferences: MemberPreferences,
+
+    koreader: Option<GetKoreaderSyncResponse>,
+    koreader_password: String,
 }
 
 impl Component for MemberGeneralPage {
@@ -40,11 +49,18 @@ impl Component for MemberGeneralPage {
 
         let preferences = state.member.as_ref().unwrap().parse_preferences().unwrap_throw().unwrap_or_default();
 
+        ctx.link().send_future(async {
+            Msg::KoreaderResult(request::get_member_koreader().await)
+        });
+
         Self {
             state,
             _listener,
 
             preferences,
+
+            koreader: None,
+            koreader_password: String::new(),
         }
     }
 
@@ -57,6 +73,29 @@ impl Component for MemberGeneralPage {
                 Err(err) => crate::display_error(err),
             },
 
+            Msg::KoreaderResult(resp) => match resp.ok() {
+                Ok(resp) => self.koreader = Some(resp),
+                Err(err) => crate::display_error(err),
+            },
+
+            Msg::UpdateKoreaderPassword(value) => {
+                self.koreader_password = value;
+
+                return false;
+            }
+
+            Msg::SaveKoreader(password) => {
+                self.koreader_password.clear();
+
+                ctx.link().send_future(async move {
+                    if let Err(e) = request::update_member_koreader(password).await.ok() {
+                        crate::display_error(e);
+                    }
+
+                    Msg::KoreaderResult(request::get_member_koreader().await)
+                });
+            }
+
             Msg::UpdateSettings(type_of, func, json_value) => {
                 match type_of {
                     EditingType::Desktop => func(&mut self.preferences.desktop, json_value),
@@ -106,6 +145,10 @@ impl Component for MemberGeneralPage {
                     // TODO: Possibly something to do with it being "default settings"
 
                     <button class="btn btn-success" onclick={ ctx.link().callback(|_| Msg::Submit) }>{ "Submit" }</button>
+
+                    <h3>{ "KOReader Sync" }</h3>
+                    <hr/>
+                    { self.render_koreader(ctx) }
                 </div>
             </div>
         }
@@ -113,6 +156,36 @@ impl Component for MemberGeneralPage {
 }
 
 impl MemberGeneralPage {
+    fn render_koreader(&self, ctx: &Context<Self>) -> Html {
+        let Some(koreader) = self.koreader.as_ref() else {
+            return html! {};
+        };
+
+        let password = self.koreader_password.clone();
+
+        html! {
+            <>
+                <p>{ "Use a custom sync server in KOReader's Progress Sync settings." }</p>
+                <p>{ "Server: " }<code>{ format!("{}/koreader", window().location().origin().unwrap_throw()) }</code></p>
+                <p>{ "Username: " }<code>{ koreader.username.clone() }</code></p>
+
+                <div class="mb-3">
+                    <label class="form-label">{ "Sync Password" }</label>
+                    <input class="form-control" type="password"
+                        value={ self.koreader_password.clone() }
+                        onchange={ ctx.link().callback(|event: Event| Msg::UpdateKoreaderPassword(event.target_unchecked_into::<HtmlInputElement>().value())) }
+                    />
+                </div>
+
+                <button class="btn btn-success" onclick={ ctx.link().callback(move |_| Msg::SaveKoreader(Some(password.clone()))) }>{ "Save Sync Password" }</button>
+
+                if koreader.enabled {
+                    <button class="btn btn-danger" onclick={ ctx.link().callback(|_| Msg::SaveKoreader(None)) }>{ "Disable Sync" }</button>
+                }
+            </>
+        }
+    }
+
     fn render_group(editing: EditingType, prefs: &MemberBasicPreferences, ctx: &Context<Self>) -> Html {
         html! {
             <>
@@ -1,3 +1,5 @@
 mod general;
+mod tokens;
 
-pub use general::*;
\ No newline at end of file
+pub use general::*;
+pub use tokens::*;
\ No newline at end of file
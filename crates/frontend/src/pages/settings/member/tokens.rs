@@ -0,0 +1,244 @@
+use common::api::WrappingResponse;
+use common_local::{api, ApiToken, ApiTokenId, ApiTokenScope};
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::request;
+
+/// How many days a new token lasts.
+const EXPIRATIONS: [(&str, Option<u32>); 4] = [
+    ("Never", None),
+    ("30 Days", Some(30)),
+    ("90 Days", Some(90)),
+    ("1 Year", Some(365)),
+];
+
+pub enum Msg {
+    // Request Results
+    TokensResults(WrappingResponse<api::ApiGetApiTokenListResponse>),
+    CreatedResult(WrappingResponse<api::ApiPostApiTokenResponse>),
+
+    // Events
+    UpdateName(String),
+    UpdateScope(ApiTokenScope),
+    UpdateExpiration(Option<u32>),
+
+    Create,
+    Revoke(ApiTokenId),
+}
+
+pub struct MemberTokensPage {
+    tokens: Option<Vec<ApiToken>>,
+
+    /// The full token is only shown once after creating it.
+    created_secret: Option<String>,
+
+    new_token: api::NewApiTokenBody,
+}
+
+impl Component for MemberTokensPage {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_future(async {
+            Msg::TokensResults(request::get_member_tokens().await)
+        });
+
+        Self {
+            tokens: None,
+            created_secret: None,
+
+            new_token: api::NewApiTokenBody {
+                name: String::new(),
+                scope: ApiTokenScope::Read,
+                expires_in_days: None,
+            },
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::TokensResults(resp) => match resp.ok() {
+                Ok(resp) => self.tokens = Some(resp),
+                Err(err) => crate::display_error(err),
+            },
+
+            Msg::CreatedResult(resp) => match resp.ok() {
+                Ok(resp) => {
+                    self.created_secret = Some(resp.secret);
+                    self.new_token.name.clear();
+
+                    if let Some(tokens) = self.tokens.as_mut() {
+                        tokens.insert(0, resp.token);
+                    }
+                }
+
+                Err(err) => crate::display_error(err),
+            },
+
+            Msg::UpdateName(value) => {
+                self.new_token.name = value;
+
+                return false;
+            }
+
+            Msg::UpdateScope(value) => {
+                self.new_token.scope = value;
+
+                return false;
+            }
+
+            Msg::UpdateExpiration(value) => {
+                self.new_token.expires_in_days = value;
+
+                return false;
+            }
+
+            Msg::Create => {
+                let body = self.new_token.clone();
+
+                ctx.link().send_future(async move {
+                    Msg::CreatedResult(request::new_member_token(body).await)
+                });
+            }
+
+            Msg::Revoke(id) => {
+                ctx.link().send_future(async move {
+                    if let Err(e) = request::delete_member_token(id).await.ok() {
+                        crate::display_error(e);
+                    }
+
+                    Msg::TokensResults(request::get_member_tokens().await)
+                });
+            }
+        }
+
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <div class="view-container">
+                <div class="col-md-8 col-lg-6">
+                    <h2>{ "API Tokens" }</h2>
+                    <p>{ "Scripts and other clients send them in the " }<code>{ "Authorization: Bearer" }</code>{ " header instead of logging in." }</p>
+
+                    if let Some(secret) = self.created_secret.clone() {
+                        <div class="alert alert-success">
+                            <p>{ "Copy the token now. It won't be shown again." }</p>
+                            <code>{ secret }</code>
+                        </div>
+                    }
+
+                    <h3>{ "New Token" }</h3>
+                    <hr/>
+
+                    { self.render_new_token(ctx) }
+
+                    { self.render_tokens(ctx) }
+                </div>
+            </div>
+        }
+    }
+}
+
+impl MemberTokensPage {
+    fn render_new_token(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <>
+                <div class="mb-3">
+                    <label class="form-label">{ "Name" }</label>
+                    <input class="form-control" type="text"
+                        value={ self.new_token.name.clone() }
+                        onchange={ ctx.link().callback(|event: Event| Msg::UpdateName(event.target_unchecked_into::<HtmlInputElement>().value())) }
+                    />
+                </div>
+
+                <div class="mb-3">
+                    <label class="form-label">{ "Scope" }</label>
+                    <select class="form-select"
+                        onchange={ ctx.link().callback(|event: Event| {
+                            let index = event.target_unchecked_into::<HtmlSelectElement>().selected_index();
+
+                            Msg::UpdateScope(ApiTokenScope::get_all()[index as usize])
+                        }) }
+                    >
+                        {
+                            for ApiTokenScope::get_all().into_iter().map(|scope| html! {
+                                <option selected={ scope == self.new_token.scope }>{ scope.name() }</option>
+                            })
+                        }
+                    </select>
+                </div>
+
+                <div class="mb-3">
+                    <label class="form-label">{ "Expires" }</label>
+                    <select class="form-select"
+                        onchange={ ctx.link().callback(|event: Event| {
+                            let index = event.target_unchecked_into::<HtmlSelectElement>().selected_index();
+
+                            Msg::UpdateExpiration(EXPIRATIONS[index as usize].1)
+                        }) }
+                    >
+                        {
+                            for EXPIRATIONS.iter().map(|&(name, days)| html! {
+                                <option selected={ days == self.new_token.expires_in_days }>{ name }</option>
+                            })
+                        }
+                    </select>
+                </div>
+
+                <button class="btn btn-success" onclick={ ctx.link().callback(|_| Msg::Create) }>{ "Create Token" }</button>
+            </>
+        }
+    }
+
+    fn render_tokens(&self, ctx: &Context<Self>) -> Html {
+        let Some(tokens) = self.tokens.as_ref() else {
+            return html! { <h1>{ "Loading..." }</h1> };
+        };
+
+        let format_date = |value: Option<chrono::DateTime<chrono::Utc>>, default: &str| {
+            value
+                .map(|v| v.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| default.to_string())
+        };
+
+        html! {
+            <table class="table table-dark table-striped">
+                <thead>
+                    <tr>
+                        <th>{ "Name" }</th>
+                        <th>{ "Token" }</th>
+                        <th>{ "Scope" }</th>
+                        <th>{ "Expires" }</th>
+                        <th>{ "Last Used" }</th>
+                        <th></th>
+                    </tr>
+                </thead>
+
+                <tbody>
+                    {
+                        for tokens.iter().map(|token| {
+                            let id = token.id;
+
+                            html! {
+                                <tr>
+                                    <td>{ token.name.clone() }</td>
+                                    <td><code>{ format!("{}…", token.prefix) }</code></td>
+                                    <td>{ token.scope.name() }</td>
+                                    <td>{ format_date(token.expires_at, "Never") }</td>
+                                    <td>{ format_date(token.last_used_at, "Never") }</td>
+                                    <td>
+                                        <button class="btn btn-danger btn-sm" onclick={ ctx.link().callback(move |_| Msg::Revoke(id)) }>{ "Revoke" }</button>
+                                    </td>
+                                </tr>
+                            }
+                        })
+                    }
+                </tbody>
+            </table>
+        }
+    }
+}
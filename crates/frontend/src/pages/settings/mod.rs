@@ -1,3 +1,4 @@
+use common_local::GroupPermissions;
 use yew::{html, Html};
 use yew_router::Routable;
 
@@ -27,17 +28,23 @@ pub enum SettingsRoute {
     // General Routes
     #[at("/settings/general")]
     MemberGeneral,
+
+    #[at("/settings/tokens")]
+    MemberTokens,
 }
 
 impl SettingsRoute {
-    pub fn is_admin(&self) -> bool {
-        matches!(
-            self,
+    /// The permission needed to view the page. None if every member can.
+    pub fn required_permission(&self) -> Option<GroupPermissions> {
+        match self {
             Self::AdminLibraries |
-            Self::AdminMembers |
-            Self::AdminMyServer |
-            Self::AdminTasks
-        )
+            Self::AdminMyServer => Some(GroupPermissions::OWNER),
+            Self::AdminMembers => Some(GroupPermissions::MANAGE_MEMBERS),
+            Self::AdminTasks => Some(GroupPermissions::RUN_TASKS),
+
+            Self::MemberGeneral |
+            Self::MemberTokens => None,
+        }
     }
 }
 
@@ -51,5 +58,6 @@ pub fn switch_settings(route: SettingsRoute) -> Html {
 
         // Members
         SettingsRoute::MemberGeneral => html! { <MemberGeneralPage /> },
+        SettingsRoute::MemberTokens => html! { <MemberTokensPage /> },
     }
 }
\ No newline at end of file
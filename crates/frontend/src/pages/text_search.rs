@@ -0,0 +1,191 @@
+use common::api::WrappingResponse;
+use common_local::api::{self, BookTextMatch};
+use wasm_bindgen::UnwrapThrowExt;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::{request, BaseRoute};
+
+const PAGE_SIZE: usize = 25;
+
+pub enum Msg {
+    UpdateQuery(String),
+    Search,
+    LoadMore,
+
+    RetrieveResults(WrappingResponse<api::ApiGetBookTextSearchResponse>),
+}
+
+/// Searches the text of every book. The initial query is taken from the `query` parameter of the URL.
+pub struct TextSearchPage {
+    query: String,
+    is_searching: bool,
+
+    items: Option<Vec<BookTextMatch>>,
+    has_more: bool,
+}
+
+impl Component for TextSearchPage {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let search = gloo_utils::window().location().search().unwrap_throw();
+
+        let query = serde_qs::from_str::<api::BookTextSearchQuery>(search.trim_start_matches('?'))
+            .map(|v| v.query)
+            .unwrap_or_default();
+
+        if !query.trim().is_empty() {
+            ctx.link().send_message(Msg::Search);
+        }
+
+        Self {
+            query,
+            is_searching: false,
+
+            items: None,
+            has_more: false,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::UpdateQuery(value) => {
+                self.query = value;
+
+                return false;
+            }
+
+            Msg::Search => {
+                if self.is_searching || self.query.trim().is_empty() {
+                    return false;
+                }
+
+                self.items = None;
+
+                self.fetch(ctx);
+            }
+
+            Msg::LoadMore => {
+                if self.is_searching {
+                    return false;
+                }
+
+                self.fetch(ctx);
+            }
+
+            Msg::RetrieveResults(resp) => {
+                self.is_searching = false;
+
+                match resp.ok() {
+                    Ok(found) => {
+                        self.has_more = found.len() == PAGE_SIZE;
+
+                        self.items.get_or_insert_with(Vec::new).extend(found);
+                    }
+
+                    Err(err) => crate::display_error(err),
+                }
+            }
+        }
+
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <div class="view-container text-search">
+                <form class="input-group" onsubmit={ ctx.link().callback(|e: SubmitEvent| {
+                    e.prevent_default();
+                    Msg::Search
+                }) }>
+                    <input
+                        class="form-control"
+                        type="search"
+                        placeholder="Search inside books"
+                        value={ self.query.clone() }
+                        oninput={ ctx.link().callback(|e: InputEvent| Msg::UpdateQuery(e.target_unchecked_into::<HtmlInputElement>().value())) }
+                    />
+                    <button class="btn btn-primary" type="submit" disabled={ self.is_searching }>{ "Search" }</button>
+                </form>
+
+                <small class="text-muted">{ "Quote words to search for a phrase. End a word with * to match the start of words." }</small>
+
+                { self.render_results(ctx) }
+            </div>
+        }
+    }
+}
+
+impl TextSearchPage {
+    fn fetch(&mut self, ctx: &Context<Self>) {
+        self.is_searching = true;
+
+        let query = self.query.clone();
+        let offset = self.items.as_ref().map(|v| v.len());
+
+        ctx.link().send_future(async move {
+            Msg::RetrieveResults(request::search_book_text(query, None, offset, Some(PAGE_SIZE)).await)
+        });
+    }
+
+    fn render_results(&self, ctx: &Context<Self>) -> Html {
+        let Some(items) = self.items.as_ref() else {
+            if self.is_searching {
+                return html! {
+                    <h3>{ "Searching..." }</h3>
+                };
+            }
+
+            return html! {};
+        };
+
+        if items.is_empty() {
+            return html! {
+                <h3>{ "Nothing found." }</h3>
+            };
+        }
+
+        html! {
+            <div class="text-search-results">
+                {
+                    for items.iter().map(|item| html! {
+                        <div class="text-search-result">
+                            <Link<BaseRoute> to={ BaseRoute::ViewBook { book_id: item.book.id } } classes="link-light">
+                                <h5>{ item.book.title.clone() }</h5>
+                            </Link<BaseRoute>>
+
+                            <small class="text-muted">{ format!("Section {}", item.chapter + 1) }</small>
+
+                            <p>
+                                {
+                                    for item.snippet.iter().map(|part| if part.highlighted {
+                                        html! { <mark>{ part.text.clone() }</mark> }
+                                    } else {
+                                        html! { { part.text.clone() } }
+                                    })
+                                }
+                            </p>
+
+                            <Link<BaseRoute> to={ BaseRoute::ReadBook { book_id: item.file_id } } classes="btn btn-sm btn-secondary">
+                                { "Read" }
+                            </Link<BaseRoute>>
+                        </div>
+                    })
+                }
+
+                {
+                    if self.has_more {
+                        html! {
+                            <button class="btn btn-secondary" disabled={ self.is_searching } onclick={ ctx.link().callback(|_| Msg::LoadMore) }>{ "Load More" }</button>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+}
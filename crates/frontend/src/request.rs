@@ -16,7 +16,7 @@ use common_local::{
     filter::FilterContainer,
     setup::SetupConfig,
     ws::{TaskId, TaskInfo},
-    CollectionId, FileId, LibraryId, Progression, SearchType, MemberPreferences,
+    ApiTokenId, BookmarkId, CollectionId, FileId, LibraryId, NotationId, Progression, SearchType, MemberPreferences,
 };
 
 pub fn get_download_path(value: Either<BookId, FileId>) -> String {
@@ -77,6 +77,36 @@ pub async fn update_member_preferences(value: MemberPreferences) -> WrappingResp
         .unwrap_or_else(def)
 }
 
+pub async fn get_member_koreader() -> WrappingResponse<ApiGetKoreaderSyncResponse> {
+    fetch("GET", "/api/member/koreader", Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn update_member_koreader(password: Option<String>) -> WrappingResponse<String> {
+    fetch("POST", "/api/member/koreader", Some(&UpdateKoreaderSync { password }))
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn get_member_tokens() -> WrappingResponse<ApiGetApiTokenListResponse> {
+    fetch("GET", "/api/member/tokens", Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn new_member_token(value: NewApiTokenBody) -> WrappingResponse<ApiPostApiTokenResponse> {
+    fetch("POST", "/api/member/tokens", Some(&value))
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn delete_member_token(id: ApiTokenId) -> WrappingResponse<String> {
+    fetch("DELETE", &format!("/api/member/token/{id}"), Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
 
 // Collections
 
@@ -288,6 +318,28 @@ pub async fn get_books_preset(
         .unwrap_or_else(def)
 }
 
+pub async fn search_book_text(
+    query: String,
+    library: Option<LibraryId>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> WrappingResponse<ApiGetBookTextSearchResponse> {
+    let url = format!(
+        "/api/books/text?{}",
+        serde_qs::to_string(&BookTextSearchQuery {
+            query,
+            library,
+            offset,
+            limit,
+        })
+        .unwrap_throw()
+    );
+
+    fetch("GET", &url, Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
 pub async fn get_book_info(id: FileId) -> WrappingResponse<ApiGetFileByIdResponse> {
     fetch("GET", &format!("/api/file/{}", id), Option::<&()>::None)
         .await
@@ -308,6 +360,25 @@ pub async fn get_book_pages(
     .unwrap_or_else(def)
 }
 
+pub async fn get_file_toc(file_id: FileId) -> WrappingResponse<ApiGetFileTocByIdResponse> {
+    fetch("GET", &format!("/api/file/{file_id}/toc"), Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn search_file(file_id: FileId, query: String) -> WrappingResponse<ApiGetFileSearchByIdResponse> {
+    fetch(
+        "GET",
+        &format!(
+            "/api/file/{file_id}/search?{}",
+            serde_qs::to_string(&FileSearchQuery { query }).unwrap_or_default()
+        ),
+        Option::<&()>::None,
+    )
+    .await
+    .unwrap_or_else(def)
+}
+
 pub fn compile_book_resource_path(
     file_id: FileId,
     location: &Path,
@@ -396,6 +467,78 @@ pub async fn update_book_notes(book_id: FileId, data: String) -> WrappingRespons
         .unwrap_or_else(def)
 }
 
+// Notations
+
+pub async fn get_file_notations(file_id: FileId) -> WrappingResponse<ApiGetFileNotationsByIdResponse> {
+    fetch("GET", &format!("/api/file/{file_id}/notations"), Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn new_file_notation(
+    file_id: FileId,
+    body: NewNotationBody,
+) -> WrappingResponse<ApiPostFileNotationsByIdResponse> {
+    fetch("POST", &format!("/api/file/{file_id}/notations"), Some(&body))
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn update_file_notation(
+    file_id: FileId,
+    id: NotationId,
+    body: UpdateNotationBody,
+) -> WrappingResponse<ApiPostFileNotationByIdResponse> {
+    fetch("POST", &format!("/api/file/{file_id}/notations/{id}"), Some(&body))
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn delete_file_notation(
+    file_id: FileId,
+    id: NotationId,
+) -> WrappingResponse<ApiDeleteFileNotationByIdResponse> {
+    fetch("DELETE", &format!("/api/file/{file_id}/notations/{id}"), Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
+// Bookmarks
+
+pub async fn get_file_bookmarks(file_id: FileId) -> WrappingResponse<ApiGetFileBookmarksByIdResponse> {
+    fetch("GET", &format!("/api/file/{file_id}/bookmarks"), Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn new_file_bookmark(
+    file_id: FileId,
+    body: NewBookmarkBody,
+) -> WrappingResponse<ApiPostFileBookmarksByIdResponse> {
+    fetch("POST", &format!("/api/file/{file_id}/bookmarks"), Some(&body))
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn update_file_bookmark(
+    file_id: FileId,
+    id: BookmarkId,
+    body: UpdateBookmarkBody,
+) -> WrappingResponse<ApiPostFileBookmarkByIdResponse> {
+    fetch("POST", &format!("/api/file/{file_id}/bookmarks/{id}"), Some(&body))
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn delete_file_bookmark(
+    file_id: FileId,
+    id: BookmarkId,
+) -> WrappingResponse<ApiDeleteFileBookmarkByIdResponse> {
+    fetch("DELETE", &format!("/api/file/{file_id}/bookmarks/{id}"), Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
 // Image
 
 pub async fn get_posters_for(img_id_type: ImageIdType) -> WrappingResponse<GetPostersResponse> {
@@ -440,6 +583,18 @@ pub async fn get_tasks() -> WrappingResponse<Vec<(TaskId, TaskInfo)>> {
         .unwrap_or_else(def)
 }
 
+pub async fn cancel_task(id: TaskId) -> WrappingResponse<ApiDeleteTaskByIdResponse> {
+    fetch("DELETE", &format!("/api/task/{id}"), Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
+pub async fn get_task_history() -> WrappingResponse<ApiGetTaskHistoryResponse> {
+    fetch("GET", "/api/tasks/history", Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
 // Login In
 
 pub async fn login_with_password(email: String, password: String) -> WrappingResponse<String> {
@@ -467,6 +622,12 @@ pub async fn login_without_password(email: String) -> WrappingResponse<String> {
     .unwrap_or_else(def)
 }
 
+pub async fn get_oidc_provider() -> WrappingResponse<ApiGetOidcProviderResponse> {
+    fetch("GET", "/auth/oidc/provider", Option::<&()>::None)
+        .await
+        .unwrap_or_else(def)
+}
+
 // Directory
 
 pub async fn get_directory_contents(path: String) -> WrappingResponse<ApiGetDirectoryResponse> {